@@ -78,6 +78,7 @@ use near_primitives::views::{
 };
 use near_store::flat::{store_helper, FlatStorageReadyStatus, FlatStorageStatus};
 use near_store::get_genesis_state_roots;
+use near_store::retention;
 use near_store::{DBCol, ShardTries};
 use once_cell::sync::OnceCell;
 use rand::seq::SliceRandom;
@@ -1045,6 +1046,44 @@ impl Chain {
         chain_store_update.commit()
     }
 
+    /// Garbage collect the data of the columns which archival node keeps only
+    /// for the recent epochs, as configured by `column_num_epochs_to_keep`.
+    ///
+    /// At most `gc_blocks_limit` heights are processed for every column.
+    pub fn clear_retention_data(
+        &mut self,
+        retention_config: &retention::RetentionConfig,
+        gc_blocks_limit: NumBlocks,
+    ) -> Result<(), Error> {
+        if retention_config.is_empty() {
+            return Ok(());
+        }
+        let _d = DelayDetector::new(|| "GC".into());
+
+        let head = self.store.head()?;
+        let store = self.store.store().clone();
+        for &(col, num_epochs) in retention_config.columns() {
+            let stop_height =
+                retention::get_stop_height(head.height, self.epoch_length, num_epochs);
+            let tail = retention::get_tail(&store, col)?.unwrap_or(self.genesis.header().height());
+            let mut store_update = store.store_update();
+            let new_tail = retention::prune(
+                &store,
+                &mut store_update,
+                col,
+                tail,
+                stop_height,
+                gc_blocks_limit,
+            )?;
+            if new_tail != tail {
+                retention::set_tail(&mut store_update, col, new_tail)?;
+            }
+            store_update.commit()?;
+            retention::update_tail_metric(col, new_tail);
+        }
+        Ok(())
+    }
+
     pub fn clear_forks_data(
         &mut self,
         tries: ShardTries,
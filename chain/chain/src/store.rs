@@ -55,6 +55,8 @@ use crate::chunks_store::ReadOnlyChunksStore;
 use crate::types::{Block, BlockHeader, LatestKnown};
 use near_store::db::{StoreStatistics, STATE_SYNC_DUMP_KEY};
 use near_store::flat::store_helper;
+use near_store::metadata::DbKind;
+use near_store::state_history;
 use std::sync::Arc;
 
 /// lru cache size
@@ -2412,6 +2414,13 @@ impl<'a> ChainStoreUpdate<'a> {
         self.gc_col(DBCol::NextBlockHashes, block_hash.as_bytes());
         self.gc_col(DBCol::ChallengedBlocks, block_hash.as_bytes());
         self.gc_col(DBCol::BlocksToCatchup, block_hash.as_bytes());
+        // Archival nodes keep the whole state history in the hot storage, other nodes can't
+        // answer queries below the tail anyway.
+        if matches!(gc_mode, GCMode::Canonical(_))
+            && self.store().get_db_kind()? != Some(DbKind::Hot)
+        {
+            state_history::prune_block(self.store(), &mut store_update, &block_hash, height)?;
+        }
         let storage_key = KeyForStateChanges::for_block(&block_hash);
         let stored_state_changes: Vec<Box<[u8]>> = self
             .chain_store
@@ -2512,6 +2521,13 @@ impl<'a> ChainStoreUpdate<'a> {
         self.gc_col(DBCol::NextBlockHashes, block_hash.as_bytes());
         self.gc_col(DBCol::ChallengedBlocks, block_hash.as_bytes());
         self.gc_col(DBCol::BlocksToCatchup, block_hash.as_bytes());
+        // Archival nodes keep the whole state history in the hot storage, other nodes can't
+        // answer queries below the tail anyway.
+        if matches!(gc_mode, GCMode::Canonical(_))
+            && self.store().get_db_kind()? != Some(DbKind::Hot)
+        {
+            state_history::prune_block(self.store(), &mut store_update, &block_hash, height)?;
+        }
         let storage_key = KeyForStateChanges::for_block(&block_hash);
         let stored_state_changes: Vec<Box<[u8]>> = self
             .chain_store
@@ -2802,6 +2818,7 @@ impl<'a> ChainStoreUpdate<'a> {
             | DBCol::FlatStateDeltaMetadata
             | DBCol::FlatStorageStatus
             | DBCol::Misc
+            | DBCol::StateHistory
             => unreachable!(),
             #[cfg(feature = "new_epoch_sync")]
             DBCol::EpochSyncInfo => unreachable!(),
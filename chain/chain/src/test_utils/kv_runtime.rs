@@ -39,7 +39,7 @@ use near_primitives::types::{
 use near_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
 use near_primitives::views::{
     AccessKeyInfoView, AccessKeyList, CallResult, ContractCodeView, EpochValidatorInfo,
-    QueryRequest, QueryResponse, QueryResponseKind, ViewStateResult,
+    QueryRequest, QueryResponse, QueryResponseKind, TransactionSimulationView, ViewStateResult,
 };
 use near_store::{
     set_genesis_state_roots, DBCol, PartialStorage, ShardTries, Store, StoreUpdate, Trie,
@@ -1204,6 +1204,22 @@ impl RuntimeAdapter for KeyValueRuntime {
         unimplemented!();
     }
 
+    fn simulate_transaction(
+        &self,
+        _state_roots: &[StateRoot],
+        _block_height: BlockHeight,
+        _block_timestamp: u64,
+        _prev_block_hash: &CryptoHash,
+        _block_hash: &CryptoHash,
+        _epoch_id: &EpochId,
+        _gas_price: Balance,
+        _transaction: &SignedTransaction,
+        _verify_signature: bool,
+        _max_blocks: u64,
+    ) -> Result<Result<TransactionSimulationView, InvalidTxError>, Error> {
+        unimplemented!();
+    }
+
     fn query(
         &self,
         _shard_id: ShardUId,
@@ -1264,10 +1280,7 @@ impl RuntimeAdapter for KeyValueRuntime {
                 block_hash: *block_hash,
             }),
             QueryRequest::CallFunction { .. } => Ok(QueryResponse {
-                kind: QueryResponseKind::CallResult(CallResult {
-                    result: Default::default(),
-                    logs: Default::default(),
-                }),
+                kind: QueryResponseKind::CallResult(CallResult::default()),
                 block_height,
                 block_hash: *block_hash,
             }),
@@ -1,15 +1,26 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 use actix::Message;
 
+use near_chain_configs::TransactionPoolMode;
+use near_crypto::PublicKey;
 use near_pool::{InsertTransactionResult, PoolIteratorWrapper, TransactionPool};
 use near_primitives::{
     epoch_manager::RngSeed,
+    hash::CryptoHash,
     sharding::{EncodedShardChunk, PartialEncodedChunk, ShardChunk, ShardChunkHeader},
     transaction::SignedTransaction,
     types::{AccountId, ShardId},
+    views::{
+        TxPoolDropReason, TxPoolDroppedTransactionView, TxPoolGroupView, TxPoolShardView,
+        TxPoolTransactionView,
+    },
 };
 
+/// Number of recently dropped transactions remembered for each shard, to be able to tell the
+/// user why a transaction is not in the pool anymore.
+const RECENTLY_DROPPED_LIMIT: usize = 1000;
+
 #[derive(Message, Debug)]
 #[rtype(result = "()")]
 pub enum ShardsManagerResponse {
@@ -40,11 +51,27 @@ pub struct ShardedTransactionPool {
     /// If set, new transactions that bring the size of the pool over this limit will be rejected.
     /// The size is tracked and enforced separately for each shard.
     pool_size_limit: Option<u64>,
+
+    /// Defines how the pool of each shard orders, replaces and evicts transactions.
+    pool_mode: TransactionPoolMode,
+
+    /// The most recently dropped transactions of each shard, oldest first.
+    recently_dropped: HashMap<ShardId, VecDeque<TxPoolDroppedTransactionView>>,
 }
 
 impl ShardedTransactionPool {
-    pub fn new(rng_seed: RngSeed, pool_size_limit: Option<u64>) -> Self {
-        Self { tx_pools: HashMap::new(), rng_seed, pool_size_limit }
+    pub fn new(
+        rng_seed: RngSeed,
+        pool_size_limit: Option<u64>,
+        pool_mode: TransactionPoolMode,
+    ) -> Self {
+        Self {
+            tx_pools: HashMap::new(),
+            rng_seed,
+            pool_size_limit,
+            pool_mode,
+            recently_dropped: HashMap::new(),
+        }
     }
 
     pub fn get_pool_iterator(&mut self, shard_id: ShardId) -> Option<PoolIteratorWrapper<'_>> {
@@ -57,15 +84,155 @@ impl ShardedTransactionPool {
         shard_id: ShardId,
         tx: SignedTransaction,
     ) -> InsertTransactionResult {
-        self.pool_for_shard(shard_id).insert_transaction(tx)
+        let tx_hash = tx.get_hash();
+        let result = self.pool_for_shard(shard_id).insert_transaction(tx);
+        match &result {
+            InsertTransactionResult::Success | InsertTransactionResult::Duplicate => {}
+            InsertTransactionResult::NoSpaceLeft => {
+                self.record_dropped(shard_id, tx_hash, TxPoolDropReason::NoSpaceLeft);
+            }
+            InsertTransactionResult::Replaced { replaced_tx_hash, evicted_tx_hashes } => {
+                self.record_dropped(
+                    shard_id,
+                    *replaced_tx_hash,
+                    TxPoolDropReason::Replaced { by: tx_hash },
+                );
+                for evicted_tx_hash in evicted_tx_hashes {
+                    self.record_dropped(
+                        shard_id,
+                        *evicted_tx_hash,
+                        TxPoolDropReason::Evicted { by: tx_hash },
+                    );
+                }
+            }
+            InsertTransactionResult::Evicted { evicted_tx_hashes } => {
+                for evicted_tx_hash in evicted_tx_hashes {
+                    self.record_dropped(
+                        shard_id,
+                        *evicted_tx_hash,
+                        TxPoolDropReason::Evicted { by: tx_hash },
+                    );
+                }
+            }
+        }
+        result
     }
 
+    /// Removes transactions that were included into a chunk from the pool for a given shard.
     pub fn remove_transactions(&mut self, shard_id: ShardId, transactions: &[SignedTransaction]) {
-        if let Some(pool) = self.tx_pools.get_mut(&shard_id) {
-            pool.remove_transactions(transactions)
+        let Some(pool) = self.tx_pools.get_mut(&shard_id) else {
+            return;
+        };
+        let included_tx_hashes: Vec<CryptoHash> = transactions
+            .iter()
+            .map(|tx| tx.get_hash())
+            .filter(|tx_hash| pool.contains(tx_hash))
+            .collect();
+        pool.remove_transactions(transactions);
+        for tx_hash in included_tx_hashes {
+            self.record_dropped(shard_id, tx_hash, TxPoolDropReason::IncludedInChunk);
         }
     }
 
+    /// Records the transactions that were taken out of the pool while preparing a chunk but were
+    /// not included into it, because they turned out to be invalid or expired.
+    pub fn record_invalid_transactions(
+        &mut self,
+        shard_id: ShardId,
+        tx_hashes: impl IntoIterator<Item = CryptoHash>,
+    ) {
+        for tx_hash in tx_hashes {
+            self.record_dropped(shard_id, tx_hash, TxPoolDropReason::Invalid);
+        }
+    }
+
+    fn record_dropped(&mut self, shard_id: ShardId, hash: CryptoHash, reason: TxPoolDropReason) {
+        let recently_dropped = self.recently_dropped.entry(shard_id).or_default();
+        if recently_dropped.len() >= RECENTLY_DROPPED_LIMIT {
+            recently_dropped.pop_front();
+        }
+        recently_dropped.push_back(TxPoolDroppedTransactionView { hash, reason });
+    }
+
+    /// Returns the pooled transaction with the given hash from any shard.
+    pub fn get_transaction(&self, tx_hash: &CryptoHash) -> Option<&SignedTransaction> {
+        self.tx_pools.values().find_map(|pool| pool.get(tx_hash))
+    }
+
+    /// Describes the pooled and recently dropped transactions of every shard.
+    /// If set, `account_id` and `public_key` restrict the pooled transactions to the given
+    /// signer and key, and `transaction_hash` restricts both pooled and dropped transactions.
+    pub fn get_tx_pool_view(
+        &self,
+        account_id: Option<&AccountId>,
+        public_key: Option<&PublicKey>,
+        transaction_hash: Option<&CryptoHash>,
+    ) -> Vec<TxPoolShardView> {
+        let mut shard_ids: Vec<ShardId> =
+            self.tx_pools.keys().chain(self.recently_dropped.keys()).copied().collect();
+        shard_ids.sort();
+        shard_ids.dedup();
+        shard_ids
+            .into_iter()
+            .map(|shard_id| {
+                let pool = self.tx_pools.get(&shard_id);
+                let groups = pool
+                    .into_iter()
+                    .flat_map(|pool| pool.transaction_groups())
+                    .filter_map(|group| {
+                        let first = &group.first()?.transaction;
+                        if account_id.map_or(false, |account_id| &first.signer_id != account_id)
+                            || public_key.map_or(false, |key| &first.public_key != key)
+                        {
+                            return None;
+                        }
+                        let mut group: Vec<&SignedTransaction> = group.iter().collect();
+                        group.sort_by_key(|tx| tx.transaction.nonce);
+                        let transactions: Vec<TxPoolTransactionView> = group
+                            .iter()
+                            .filter(|tx| {
+                                transaction_hash.map_or(true, |hash| &tx.get_hash() == hash)
+                            })
+                            .map(|tx| TxPoolTransactionView {
+                                hash: tx.get_hash(),
+                                nonce: tx.transaction.nonce,
+                                receiver_id: tx.transaction.receiver_id.clone(),
+                                size: tx.get_size(),
+                                // Transactions with the same nonce are not ahead of each other.
+                                num_ahead: group.partition_point(|other| {
+                                    other.transaction.nonce < tx.transaction.nonce
+                                }),
+                            })
+                            .collect();
+                        if transactions.is_empty() {
+                            return None;
+                        }
+                        Some(TxPoolGroupView {
+                            signer_id: first.signer_id.clone(),
+                            public_key: first.public_key.clone(),
+                            transactions,
+                        })
+                    })
+                    .collect();
+                let recently_dropped = self
+                    .recently_dropped
+                    .get(&shard_id)
+                    .into_iter()
+                    .flatten()
+                    .filter(|dropped| transaction_hash.map_or(true, |hash| &dropped.hash == hash))
+                    .cloned()
+                    .collect();
+                TxPoolShardView {
+                    shard_id,
+                    num_transactions: pool.map_or(0, |pool| pool.len()),
+                    total_size: pool.map_or(0, |pool| pool.transaction_size()),
+                    groups,
+                    recently_dropped,
+                }
+            })
+            .collect()
+    }
+
     /// Computes a deterministic random seed for given `shard_id`.
     /// This seed is used to randomize the transaction pool.
     /// For better security we want the seed to different in each shard.
@@ -82,6 +249,7 @@ impl ShardedTransactionPool {
             TransactionPool::new(
                 Self::random_seed(&self.rng_seed, shard_id),
                 self.pool_size_limit,
+                self.pool_mode,
                 &shard_id.to_string(),
             )
         })
@@ -95,10 +263,12 @@ impl ShardedTransactionPool {
         transactions: &[SignedTransaction],
     ) -> usize {
         let mut reintroduced_count = 0;
-        let pool = self.pool_for_shard(shard_id);
         for tx in transactions {
-            reintroduced_count += match pool.insert_transaction(tx.clone()) {
-                InsertTransactionResult::Success | InsertTransactionResult::Duplicate => 1,
+            reintroduced_count += match self.insert_transaction(shard_id, tx.clone()) {
+                InsertTransactionResult::Success
+                | InsertTransactionResult::Duplicate
+                | InsertTransactionResult::Replaced { .. }
+                | InsertTransactionResult::Evicted { .. } => 1,
                 InsertTransactionResult::NoSpaceLeft => 0,
             }
         }
@@ -108,7 +278,14 @@ impl ShardedTransactionPool {
 
 #[cfg(test)]
 mod tests {
+    use near_chain_configs::TransactionPoolMode;
+    use near_crypto::{InMemorySigner, KeyType};
+    use near_pool::InsertTransactionResult;
     use near_primitives::epoch_manager::RngSeed;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::transaction::SignedTransaction;
+    use near_primitives::types::AccountId;
+    use near_primitives::views::{TxPoolDropReason, TxPoolDroppedTransactionView};
 
     use crate::client::ShardedTransactionPool;
 
@@ -132,4 +309,110 @@ mod tests {
         assert_ne!(seed256, seed1000000);
         assert_ne!(seed1000, seed1000000);
     }
+
+    #[test]
+    fn test_tx_pool_view() {
+        let signer_id: AccountId = "alice.near".parse().unwrap();
+        let signer = InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, "alice.near");
+        let transactions: Vec<SignedTransaction> = (1..=3)
+            .map(|nonce| {
+                SignedTransaction::send_money(
+                    nonce,
+                    signer_id.clone(),
+                    "bob.near".parse().unwrap(),
+                    &signer,
+                    1,
+                    CryptoHash::default(),
+                )
+            })
+            .collect();
+        let pool_size_limit = transactions[1].get_size() + transactions[2].get_size();
+        let mut pool = ShardedTransactionPool::new(
+            TEST_SEED,
+            Some(pool_size_limit),
+            TransactionPoolMode::Unordered,
+        );
+        for tx in transactions[1..].iter().rev() {
+            assert_eq!(pool.insert_transaction(0, tx.clone()), InsertTransactionResult::Success);
+        }
+        assert_eq!(
+            pool.insert_transaction(0, transactions[0].clone()),
+            InsertTransactionResult::NoSpaceLeft
+        );
+
+        let view = pool.get_tx_pool_view(None, None, None);
+        assert_eq!(view.len(), 1);
+        assert_eq!(view[0].num_transactions, 2);
+        assert_eq!(view[0].groups.len(), 1);
+        let group = &view[0].groups[0];
+        assert_eq!(group.signer_id, signer_id);
+        assert_eq!(
+            group.transactions.iter().map(|tx| (tx.nonce, tx.num_ahead)).collect::<Vec<_>>(),
+            vec![(2, 0), (3, 1)]
+        );
+        assert_eq!(
+            view[0].recently_dropped,
+            vec![TxPoolDroppedTransactionView {
+                hash: transactions[0].get_hash(),
+                reason: TxPoolDropReason::NoSpaceLeft,
+            }]
+        );
+
+        pool.remove_transactions(0, &transactions[1..2]);
+        let view = pool.get_tx_pool_view(None, None, Some(&transactions[1].get_hash()));
+        assert!(view[0].groups.is_empty());
+        assert_eq!(
+            view[0].recently_dropped,
+            vec![TxPoolDroppedTransactionView {
+                hash: transactions[1].get_hash(),
+                reason: TxPoolDropReason::IncludedInChunk,
+            }]
+        );
+
+        assert_eq!(pool.get_transaction(&transactions[2].get_hash()), Some(&transactions[2]));
+        assert_eq!(pool.get_transaction(&transactions[1].get_hash()), None);
+
+        let other_signer_id: AccountId = "bob.near".parse().unwrap();
+        let view = pool.get_tx_pool_view(Some(&other_signer_id), None, None);
+        assert!(view[0].groups.is_empty());
+        assert_eq!(view[0].num_transactions, 1);
+
+        pool.record_invalid_transactions(0, [transactions[2].get_hash()]);
+        let view = pool.get_tx_pool_view(None, None, Some(&transactions[2].get_hash()));
+        assert_eq!(
+            view[0].recently_dropped,
+            vec![TxPoolDroppedTransactionView {
+                hash: transactions[2].get_hash(),
+                reason: TxPoolDropReason::Invalid,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_tx_pool_view_same_nonce() {
+        let signer_id: AccountId = "alice.near".parse().unwrap();
+        let signer = InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, "alice.near");
+        let mut pool = ShardedTransactionPool::new(TEST_SEED, None, TransactionPoolMode::Unordered);
+        for (nonce, deposit) in [(1, 1), (1, 2), (2, 1)] {
+            let tx = SignedTransaction::send_money(
+                nonce,
+                signer_id.clone(),
+                "bob.near".parse().unwrap(),
+                &signer,
+                deposit,
+                CryptoHash::default(),
+            );
+            assert_eq!(pool.insert_transaction(0, tx), InsertTransactionResult::Success);
+        }
+
+        let view = pool.get_tx_pool_view(None, None, None);
+        assert_eq!(
+            view[0].groups[0]
+                .transactions
+                .iter()
+                .map(|tx| (tx.nonce, tx.num_ahead))
+                .collect::<Vec<_>>(),
+            vec![(1, 0), (1, 0), (2, 2)]
+        );
+    }
 }
@@ -98,6 +98,7 @@ use near_chain::near_chain_primitives::error::Error::DBNotFoundErr;
 use near_chain::types::EpochManagerAdapter;
 pub use near_chunks_primitives::Error;
 use near_epoch_manager::shard_tracker::ShardTracker;
+use near_network::reputation::ReputationEvent;
 use near_network::shards_manager::ShardsManagerRequestFromNetwork;
 use near_network::types::{
     AccountIdOrPeerTrackingShard, PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg,
@@ -242,6 +243,21 @@ impl RequestPool {
     }
 }
 
+/// Whether the error means that the chunk parts or receipts we received are invalid, rather than
+/// unexpected or not yet possible to process.
+fn is_invalid_data(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::InvalidPartMessage
+            | Error::InvalidChunkPartId
+            | Error::InvalidChunkShardId
+            | Error::InvalidMerkleProof
+            | Error::InvalidChunkSignature
+            | Error::InvalidChunk
+            | Error::ChainError(near_chain::Error::InvalidReceiptsProof)
+    )
+}
+
 pub struct ShardsManager {
     clock: time::Clock,
     me: Option<AccountId>,
@@ -1978,6 +1994,7 @@ impl ShardsManager {
             }
             ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse {
                 partial_encoded_chunk_response,
+                responder,
                 received_time,
             } => {
                 PARTIAL_ENCODED_CHUNK_RESPONSE_DELAY
@@ -1985,7 +2002,15 @@ impl ShardsManager {
                 if let Err(e) =
                     self.process_partial_encoded_chunk_response(partial_encoded_chunk_response)
                 {
-                    warn!(target: "chunks", "Error processing partial encoded chunk response: {:?}", e);
+                    warn!(target: "chunks", "Error processing partial encoded chunk response from {}: {:?}", responder, e);
+                    if is_invalid_data(&e) {
+                        self.peer_manager_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+                            NetworkRequests::ReportPeer {
+                                peer_id: responder,
+                                event: ReputationEvent::InvalidResponse,
+                            },
+                        ));
+                    }
                 }
             }
             ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkRequest {
@@ -2041,6 +2066,7 @@ mod test {
     use near_network::types::NetworkRequests;
     use near_primitives::block::Tip;
     use near_primitives::hash::{hash, CryptoHash};
+    use near_primitives::network::PeerId;
     use near_primitives::types::EpochId;
     use near_store::test_utils::create_test_store;
     use std::sync::Arc;
@@ -2211,6 +2237,51 @@ mod test {
         // TODO: add more test cases
     }
 
+    #[test]
+    fn test_invalid_chunk_response_reports_peer() {
+        let fixture = ChunkTestFixture::default();
+        let mut shards_manager = ShardsManager::new(
+            FakeClock::default().clock(),
+            Some(fixture.mock_shard_tracker.clone()),
+            Arc::new(fixture.epoch_manager.clone()),
+            fixture.shard_tracker.clone(),
+            fixture.mock_network.as_sender(),
+            fixture.mock_client_adapter.as_sender(),
+            fixture.chain_store.new_read_only_chunks_store(),
+            fixture.mock_chain_head.clone(),
+            fixture.mock_chain_head.clone(),
+        );
+        shards_manager.process_chunk_header_from_block(&fixture.mock_chunk_header).unwrap();
+        while fixture.mock_network.pop().is_some() {}
+
+        let responder = PeerId::random();
+        // Part id > num parts.
+        let mut parts = vec![fixture.mock_chunk_parts[0].clone()];
+        parts[0].part_ord = fixture.mock_chunk_parts.len() as u64;
+        shards_manager.handle_network_request(
+            ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse {
+                partial_encoded_chunk_response: PartialEncodedChunkResponseMsg {
+                    chunk_hash: fixture.mock_chunk_header.chunk_hash(),
+                    parts,
+                    receipts: vec![],
+                },
+                responder: responder.clone(),
+                received_time: std::time::Instant::now(),
+            },
+        );
+        let mut reported = vec![];
+        while let Some(request) = fixture.mock_network.pop() {
+            if let PeerManagerMessageRequest::NetworkRequests(NetworkRequests::ReportPeer {
+                peer_id,
+                event,
+            }) = request
+            {
+                reported.push((peer_id, event));
+            }
+        }
+        assert_eq!(reported, vec![(responder, ReputationEvent::InvalidResponse)]);
+    }
+
     #[test]
     fn test_chunk_forwarding_dedup() {
         // Tests that we only forward a chunk if it's the first time we receive it.
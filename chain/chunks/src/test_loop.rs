@@ -19,6 +19,7 @@ use near_network::{
 use near_primitives::{
     hash::CryptoHash,
     merkle::{self, MerklePath},
+    network::PeerId,
     sharding::{
         EncodedShardChunk, PartialEncodedChunk, PartialEncodedChunkV2, ReceiptProof,
         ReedSolomonWrapper, ShardChunkHeader,
@@ -96,6 +97,7 @@ pub fn route_shards_manager_network_messages<
                             (target_idx,
                             ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse {
                                 partial_encoded_chunk_response: response,
+                                responder: PeerId::random(),
                                 received_time: context.clock.now().into(), // TODO: use clock
                             }.into()),
                             network_delay,
@@ -5,7 +5,7 @@ use chrono::DateTime;
 use near_primitives::types::EpochId;
 use near_primitives::views::{
     CatchupStatusView, ChainProcessingInfo, EpochValidatorInfo, RequestedStatePartsView,
-    SyncStatusView,
+    SyncStatusView, TxPoolView,
 };
 use near_primitives::{
     block_header::ApprovalInner,
@@ -185,6 +185,8 @@ pub enum DebugStatus {
     ChainProcessingStatus,
     // The state parts already requested.
     RequestedStateParts,
+    // Transactions in the transaction pool.
+    TxPool,
 }
 
 impl actix::Message for DebugStatus {
@@ -206,4 +208,6 @@ pub enum DebugStatusResponse {
     ChainProcessingStatus(ChainProcessingInfo),
     // The state parts already requested.
     RequestedStateParts(Vec<RequestedStatePartsView>),
+    // Transactions in the transaction pool.
+    TxPool(TxPoolView),
 }
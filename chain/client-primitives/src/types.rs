@@ -4,10 +4,12 @@ use ansi_term::Style;
 use chrono::DateTime;
 use chrono::Utc;
 use near_chain_configs::{ClientConfig, ProtocolConfigView};
+use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
 use near_primitives::sharding::ChunkHash;
+use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{
     AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, ShardId,
     TransactionOrReceiptId,
@@ -18,7 +20,7 @@ use near_primitives::views::{
     FinalExecutionOutcomeViewEnum, GasPriceView, LightClientBlockLiteView, LightClientBlockView,
     MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView, ShardSyncDownloadView,
     SplitStorageInfoView, StateChangesKindsView, StateChangesRequestView, StateChangesView,
-    SyncStatusView,
+    SyncStatusView, TransactionSimulationView, TxPoolView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
 use std::collections::HashMap;
@@ -158,6 +160,7 @@ impl From<ShardSyncDownload> for ShardSyncDownloadView {
         ShardSyncDownloadView {
             downloads: download.downloads.iter().map(|x| x.into()).collect(),
             status: download.status.to_string(),
+            peers: vec![],
         }
     }
 }
@@ -546,6 +549,40 @@ pub enum QueryError {
     Unreachable { error_message: String },
 }
 
+/// Executes the transaction on top of the state after the referenced block
+/// without changing the state.
+#[derive(Debug)]
+pub struct SimulateTransaction {
+    pub block_reference: BlockReference,
+    pub signed_transaction: SignedTransaction,
+    /// Unsigned transactions can be simulated with the signature check disabled.
+    pub verify_signature: bool,
+    /// Maximum number of blocks to execute the receipts of the transaction in.
+    pub max_blocks: u64,
+}
+
+impl Message for SimulateTransaction {
+    type Result = Result<TransactionSimulationView, SimulateTransactionError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SimulateTransactionError {
+    #[error("There are no fully synchronized blocks on the node yet")]
+    NoSyncedBlocks,
+    #[error("Block either has never been observed on the node or has been garbage collected: {block_reference:?}")]
+    UnknownBlock { block_reference: BlockReference },
+    #[error(
+        "The data for block #{block_height} is garbage collected on this node, use an archival node to fetch historical data"
+    )]
+    GarbageCollectedBlock { block_height: BlockHeight, block_hash: CryptoHash },
+    #[error("The node does not track the shard ID {requested_shard_id}")]
+    UnavailableShard { requested_shard_id: ShardId },
+    #[error("Transaction is invalid: {error}")]
+    InvalidTransaction { error: InvalidTxError },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
 #[derive(Debug)]
 pub struct Status {
     pub is_health_check: bool,
@@ -790,6 +827,8 @@ pub enum GetStateChangesError {
     UnknownBlock { error_message: String },
     #[error("There are no fully synchronized blocks yet")]
     NotSyncedYet,
+    #[error("State changes of the block at height {block_height} have been deleted, the node keeps them starting from height {retention_tail}")]
+    PrunedData { block_height: BlockHeight, retention_tail: BlockHeight },
     // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
     // expected cases, we cannot statically guarantee that no other errors will be returned
     // in the future.
@@ -1080,6 +1119,73 @@ impl From<near_chain_primitives::Error> for GetClientConfigError {
     }
 }
 
+/// Lists the transactions in the transaction pool of the node.
+/// The optional fields narrow the list down to a given signer, access key or transaction.
+#[derive(Debug)]
+pub struct GetTxPool {
+    pub account_id: Option<AccountId>,
+    pub public_key: Option<near_crypto::PublicKey>,
+    pub transaction_hash: Option<CryptoHash>,
+}
+
+impl Message for GetTxPool {
+    type Result = Result<TxPoolView, GetTxPoolError>;
+}
+
+/// Looks up a transaction in the transaction pool of the node.
+#[derive(Debug)]
+pub struct GetPooledTransaction {
+    pub transaction_hash: CryptoHash,
+}
+
+impl Message for GetPooledTransaction {
+    type Result = Result<Option<SignedTransaction>, GetTxPoolError>;
+}
+
+/// The transaction pool is kept in memory, so reading it cannot fail.
+#[derive(thiserror::Error, Debug)]
+pub enum GetTxPoolError {}
+
+/// Published by the client whenever the head of the chain changes.
+#[derive(Debug)]
+pub struct BlockNotification {
+    /// The new head.
+    pub head: near_primitives::views::BlockHeaderView,
+}
+
+/// Subscribes to `BlockNotification`s. The receiver misses notifications if it lags behind by
+/// more than the capacity of the channel.
+#[derive(Debug)]
+pub struct SubscribeBlockNotifications {}
+
+impl Message for SubscribeBlockNotifications {
+    type Result = tokio::sync::broadcast::Receiver<Arc<BlockNotification>>;
+}
+
+/// Collects the data which the subscribers need to know about a new head.
+#[derive(Debug)]
+pub struct GetHeadNotification {
+    pub head: CryptoHash,
+    /// Height of the last final block the subscribers were notified about, if any.
+    /// Otherwise only the current last final block is included.
+    pub last_notified_final_height: Option<BlockHeight>,
+}
+
+#[derive(Debug)]
+pub struct HeadNotification {
+    pub head: near_primitives::views::BlockHeaderView,
+    /// Blocks that became final since `last_notified_final_height`, in the order of heights.
+    /// If there are too many, only the lowest ones are included and the others follow in the
+    /// next notifications.
+    pub new_final_blocks: Vec<near_primitives::views::BlockHeaderView>,
+    /// Outcomes of the receipts executed in the head, in the tracked shards.
+    pub receipt_outcomes: Vec<ExecutionOutcomeWithIdView>,
+}
+
+impl Message for GetHeadNotification {
+    type Result = Result<HeadNotification, String>;
+}
+
 #[derive(Debug)]
 pub struct GetSplitStorageInfo {}
 
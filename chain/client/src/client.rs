@@ -36,7 +36,7 @@ use near_chunks::logic::{
 use near_chunks::ShardsManager;
 use near_client_primitives::debug::ChunkProduction;
 use near_client_primitives::types::{
-    format_shard_sync_phase_per_shard, Error, ShardSyncDownload, ShardSyncStatus,
+    format_shard_sync_phase_per_shard, BlockNotification, Error, ShardSyncDownload, ShardSyncStatus,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
@@ -70,6 +70,8 @@ use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::version::PROTOCOL_VERSION;
 use near_primitives::views::{CatchupStatusView, DroppedReason};
 use near_store::metadata::DbKind;
+use near_store::retention::RetentionConfig;
+use near_store::state_history;
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -79,6 +81,11 @@ use tracing::{debug, error, info, trace, warn};
 const NUM_REBROADCAST_BLOCKS: usize = 30;
 const CHUNK_HEADERS_FOR_INCLUSION_CACHE_SIZE: usize = 2048;
 const NUM_EPOCH_CHUNK_PRODUCERS_TO_KEEP_IN_BLOCKLIST: usize = 1000;
+/// Number of block notifications buffered for every subscriber.
+const BLOCK_NOTIFICATIONS_CAPACITY: usize = 64;
+/// Maximum number of blocks added to the state history index after processing a block, so that
+/// catching up with the final head doesn't stall the client.
+const MAX_STATE_HISTORY_BLOCKS_PER_UPDATE: u64 = 100;
 
 /// The time we wait for the response to a Epoch Sync request before retrying
 // TODO #3488 set 30_000
@@ -161,6 +168,10 @@ pub struct Client {
     tier1_accounts_cache: Option<(EpochId, Arc<AccountKeys>)>,
     /// Used when it is needed to create flat storage in background for some shards.
     flat_storage_creator: Option<FlatStorageCreator>,
+    /// Notifies subscribers, e.g. WebSocket clients of the RPC, about new heads.
+    block_notifications: tokio::sync::broadcast::Sender<Arc<BlockNotification>>,
+    /// Columns with a limited retention, parsed from `config.gc.column_num_epochs_to_keep`.
+    retention_config: RetentionConfig,
 }
 
 impl Client {
@@ -233,8 +244,11 @@ impl Client {
             chain.store(),
             chain_config.background_migration_threads,
         )?;
-        let sharded_tx_pool =
-            ShardedTransactionPool::new(rng_seed, config.transaction_pool_size_limit);
+        let sharded_tx_pool = ShardedTransactionPool::new(
+            rng_seed,
+            config.transaction_pool_size_limit,
+            config.transaction_pool_mode,
+        );
         let sync_status = SyncStatus::AwaitingPeers;
         let genesis_block = chain.genesis_block();
         let epoch_sync = EpochSync::new(
@@ -285,6 +299,15 @@ impl Client {
             validator_signer.clone(),
             doomslug_threshold_mode,
         );
+        if config.save_state_history
+            && state_history::get_status(chain.store().store())
+                .map_err(near_chain::Error::from)?
+                .is_none()
+        {
+            warn!(target: "client", "State history index doesn't exist and won't be maintained. Run `neard database backfill-state-history` to create it.");
+        }
+        let retention_config =
+            RetentionConfig::new(&config.gc.column_num_epochs_to_keep).map_err(Error::Other)?;
         Ok(Self {
             #[cfg(feature = "test_features")]
             adv_produce_blocks: false,
@@ -327,6 +350,8 @@ impl Client {
             chunk_production_info: lru::LruCache::new(PRODUCTION_TIMES_CACHE_SIZE),
             tier1_accounts_cache: None,
             flat_storage_creator,
+            block_notifications: tokio::sync::broadcast::channel(BLOCK_NOTIFICATIONS_CAPACITY).0,
+            retention_config,
         })
     }
 
@@ -903,6 +928,8 @@ impl Client {
         let next_epoch_id = epoch_manager.get_epoch_id_from_prev_block(prev_block_header.hash())?;
         let protocol_version = epoch_manager.get_epoch_protocol_version(&next_epoch_id)?;
 
+        // Hashes of all the transactions taken out of the pool, to tell which ones were dropped.
+        let mut checked_tx_hashes = vec![];
         let transactions = if let Some(mut iter) = sharded_tx_pool.get_pool_iterator(shard_id) {
             let transaction_validity_period = chain.transaction_validity_period;
             runtime.prepare_transactions(
@@ -917,6 +944,7 @@ impl Client {
                 prev_block_header.height() + 1,
                 &mut iter,
                 &mut |tx: &SignedTransaction| -> bool {
+                    checked_tx_hashes.push(tx.get_hash());
                     chain
                         .store()
                         .check_transaction_validity_period(
@@ -938,6 +966,12 @@ impl Client {
             debug!(target: "client", "Reintroduced {} transactions out of {}",
                    reintroduced_count, transactions.len());
         }
+        let included_tx_hashes: HashSet<CryptoHash> =
+            transactions.iter().map(|tx| tx.get_hash()).collect();
+        sharded_tx_pool.record_invalid_transactions(
+            shard_id,
+            checked_tx_hashes.into_iter().filter(|tx_hash| !included_tx_hashes.contains(tx_hash)),
+        );
         Ok(transactions)
     }
 
@@ -1483,6 +1517,12 @@ impl Client {
             };
             self.chain.blocks_with_missing_chunks.prune_blocks_below_height(last_finalized_height);
 
+            if self.config.save_state_history {
+                if let Err(err) = self.update_state_history(last_finalized_height) {
+                    error!(target: "client", ?err, "Failed to update state history index");
+                }
+            }
+
             {
                 let _span = tracing::debug_span!(
                     target: "client",
@@ -1500,6 +1540,13 @@ impl Client {
             if let Err(err) = self.send_network_chain_info() {
                 error!(target: "client", ?err, "Failed to update network chain info");
             }
+
+            if self.block_notifications.receiver_count() > 0 {
+                // Fails only if all receivers were dropped in the meantime.
+                let _ = self
+                    .block_notifications
+                    .send(Arc::new(BlockNotification { head: block.header().clone().into() }));
+            }
         }
 
         if let Some(validator_signer) = self.validator_signer.clone() {
@@ -1521,6 +1568,36 @@ impl Client {
             .send(ShardsManagerRequestFromClient::CheckIncompleteChunks(*block.hash()));
     }
 
+    pub fn subscribe_block_notifications(
+        &self,
+    ) -> tokio::sync::broadcast::Receiver<Arc<BlockNotification>> {
+        self.block_notifications.subscribe()
+    }
+
+    /// Adds the blocks finalized since the last update to the state history index, if the index
+    /// exists.
+    fn update_state_history(&self, final_height: BlockHeight) -> Result<(), near_chain::Error> {
+        let store = self.chain.store().store();
+        let Some(status) = state_history::get_status(store)? else {
+            return Ok(());
+        };
+        if final_height <= status.head_height {
+            return Ok(());
+        }
+        // State changes of the blocks below the tail are garbage collected already.
+        if status.head_height + 1 < self.chain.tail()? {
+            return Err(near_chain::Error::Other(format!(
+                "state history index at height {} is behind the tail of the chain",
+                status.head_height
+            )));
+        }
+        let height = final_height.min(status.head_height + MAX_STATE_HISTORY_BLOCKS_PER_UPDATE);
+        let mut store_update = store.store_update();
+        state_history::record_blocks_up_to(store, &mut store_update, status, height)?;
+        store_update.commit()?;
+        Ok(())
+    }
+
     /// Reconcile the transaction pool after processing a block.
     /// returns true if it's ok to proceed to produce chunks
     /// returns false when handling a fork and there is no need to produce chunks
@@ -2054,6 +2131,15 @@ impl Client {
                         InsertTransactionResult::Success => {
                             trace!(target: "client", shard_id, tx=?tx.get_hash(), "Recorded a transaction.");
                         }
+                        InsertTransactionResult::Replaced {
+                            replaced_tx_hash,
+                            evicted_tx_hashes,
+                        } => {
+                            trace!(target: "client", shard_id, tx=?tx.get_hash(), ?replaced_tx_hash, ?evicted_tx_hashes, "Recorded a transaction in place of a transaction with the same nonce.");
+                        }
+                        InsertTransactionResult::Evicted { evicted_tx_hashes } => {
+                            trace!(target: "client", shard_id, tx=?tx.get_hash(), ?evicted_tx_hashes, "Recorded a transaction after evicting other transactions.");
+                        }
                         InsertTransactionResult::Duplicate => {
                             trace!(target: "client", shard_id, tx=?tx.get_hash(), "Duplicate transaction, not forwarding it.");
                             return Ok(ProcessTxResponse::ValidTx);
@@ -2330,8 +2416,11 @@ impl Client {
         }
 
         // An archival node with legacy storage or in the midst of migration to split
-        // storage should do the legacy clear_archive_data.
-        self.chain.clear_archive_data(self.config.gc.gc_blocks_limit)
+        // storage should do the legacy clear_archive_data. It also deletes the data
+        // of the columns with a limited retention, which with split storage is done
+        // by the cold store loop.
+        self.chain.clear_archive_data(self.config.gc.gc_blocks_limit)?;
+        self.chain.clear_retention_data(&self.retention_config, self.config.gc.gc_blocks_limit)
     }
 }
 
@@ -2448,11 +2537,18 @@ impl Client {
         // convert config tracked shards
         // runtime will track all shards if config tracked shards is not empty
         // https://github.com/near/nearcore/issues/4930
-        let tracked_shards = if self.config.tracked_shards.is_empty() {
-            vec![]
-        } else {
+        let tracked_shards = if !self.config.tracked_shards.is_empty() {
             let num_shards = self.epoch_manager.num_shards(&tip.epoch_id)?;
             (0..num_shards).collect()
+        } else if !self.config.tracked_shard_uids.is_empty() {
+            let num_shards = self.epoch_manager.num_shards(&tip.epoch_id)?;
+            (0..num_shards)
+                .filter(|&shard_id| {
+                    self.shard_tracker.care_about_shard(None, &tip.prev_block_hash, shard_id, true)
+                })
+                .collect()
+        } else {
+            vec![]
         };
         let tier1_accounts = self.get_tier1_accounts(&tip)?;
         let block = self.chain.get_block(&tip.last_block_hash)?;
@@ -16,7 +16,7 @@ use crate::info::{display_sync_status, InfoHelper};
 use crate::sync::state::{StateSync, StateSyncResult};
 use crate::sync_jobs_actor::{create_sync_job_scheduler, SyncJobsActor};
 use crate::{metrics, StatusResponse};
-use actix::{Actor, Addr, Arbiter, AsyncContext, Context, Handler};
+use actix::{Actor, Addr, Arbiter, AsyncContext, Context, Handler, MessageResult};
 use actix_rt::ArbiterHandle;
 use chrono::{DateTime, Utc};
 use near_async::messaging::{CanSend, Sender};
@@ -39,14 +39,16 @@ use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::ShardsManagerResponse;
 use near_chunks::logic::cares_about_shard_this_or_next_epoch;
 use near_client_primitives::types::{
-    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, NetworkInfoResponse,
-    StateSyncStatus, Status, StatusError, StatusSyncInfo, SyncStatus,
+    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, GetPooledTransaction, GetTxPool,
+    GetTxPoolError, NetworkInfoResponse, StateSyncStatus, Status, StatusError, StatusSyncInfo,
+    SubscribeBlockNotifications, SyncStatus,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
 use near_network::types::ReasonForBan;
 use near_network::types::{
-    NetworkInfo, NetworkRequests, PeerManagerAdapter, PeerManagerMessageRequest,
+    AuthorizationUpdate, NetworkInfo, NetworkRequests, PeerManagerAdapter,
+    PeerManagerMessageRequest,
 };
 use near_o11y::{handler_debug_span, OpenTelemetrySpanExt, WithSpanContext, WithSpanContextExt};
 use near_performance_metrics;
@@ -57,12 +59,13 @@ use near_primitives::epoch_manager::RngSeed;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::static_clock::StaticClock;
+use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::BlockHeight;
 use near_primitives::unwrap_or_return;
 use near_primitives::utils::{from_timestamp, MaybeValidated};
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{DetailedDebugStatus, ValidatorInfo};
+use near_primitives::views::{DetailedDebugStatus, TxPoolView, ValidatorInfo};
 #[cfg(feature = "test_features")]
 use near_store::DBCol;
 use near_telemetry::TelemetryActor;
@@ -1096,9 +1099,16 @@ impl ClientActor {
     /// min(time until the closest trigger, 1 second).
     fn check_triggers(&mut self, ctx: &mut Context<ClientActor>) -> Duration {
         if let Some(config_updater) = &mut self.config_updater {
-            config_updater.try_update(&|updateable_client_config| {
-                self.client.update_client_config(updateable_client_config)
-            });
+            config_updater.try_update(
+                &|updateable_client_config| {
+                    self.client.update_client_config(updateable_client_config)
+                },
+                &|updateable_network_config| {
+                    self.network_adapter.send(PeerManagerMessageRequest::UpdateAuthorization(
+                        AuthorizationUpdate::SetConfig(updateable_network_config.authorization),
+                    ))
+                },
+            );
         }
 
         // Check block height to trigger expected shutdown
@@ -1843,6 +1853,56 @@ impl Handler<WithSpanContext<GetClientConfig>> for ClientActor {
     }
 }
 
+impl Handler<WithSpanContext<GetTxPool>> for ClientActor {
+    type Result = Result<TxPoolView, GetTxPoolError>;
+
+    fn handle(&mut self, msg: WithSpanContext<GetTxPool>, _: &mut Context<Self>) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _d = delay_detector::DelayDetector::new(|| "client get tx pool".into());
+
+        let GetTxPool { account_id, public_key, transaction_hash } = msg;
+        Ok(TxPoolView {
+            shards: self.client.sharded_tx_pool.get_tx_pool_view(
+                account_id.as_ref(),
+                public_key.as_ref(),
+                transaction_hash.as_ref(),
+            ),
+        })
+    }
+}
+
+impl Handler<WithSpanContext<GetPooledTransaction>> for ClientActor {
+    type Result = Result<Option<SignedTransaction>, GetTxPoolError>;
+
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetPooledTransaction>,
+        _: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _d = delay_detector::DelayDetector::new(|| "client get pooled transaction".into());
+
+        Ok(self.client.sharded_tx_pool.get_transaction(&msg.transaction_hash).cloned())
+    }
+}
+
+impl Handler<WithSpanContext<SubscribeBlockNotifications>> for ClientActor {
+    type Result = MessageResult<WithSpanContext<SubscribeBlockNotifications>>;
+
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<SubscribeBlockNotifications>,
+        _: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+
+        MessageResult(self.client.subscribe_block_notifications())
+    }
+}
+
 /// Returns random seed sampled from the current thread
 pub fn random_seed_from_thread() -> RngSeed {
     let mut rng_seed: RngSeed = [0; 32];
@@ -35,7 +35,8 @@ use near_network::types::{ConnectedPeerInfo, NetworkInfo, PeerType};
 use near_primitives::sharding::ShardChunkHeader;
 use near_primitives::static_clock::StaticClock;
 use near_primitives::views::{
-    AccountDataView, KnownProducerView, NetworkInfoView, PeerInfoView, Tier1ProxyView,
+    AccountDataView, KnownProducerView, NetworkInfoView, PeerInfoView, SyncStatusView,
+    Tier1ProxyView, TxPoolView,
 };
 
 // Constants for debug requests.
@@ -156,7 +157,13 @@ impl Handler<WithSpanContext<DebugStatus>> for ClientActor {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
         match msg {
             DebugStatus::SyncStatus => {
-                Ok(DebugStatusResponse::SyncStatus(self.client.sync_status.clone().into()))
+                let mut sync_status: SyncStatusView = self.client.sync_status.clone().into();
+                if let SyncStatusView::StateSync(_, shards) = &mut sync_status {
+                    for (shard_id, shard) in shards.iter_mut() {
+                        shard.peers = self.client.state_sync.peers_view(*shard_id);
+                    }
+                }
+                Ok(DebugStatusResponse::SyncStatus(sync_status))
             }
             DebugStatus::TrackedShards => {
                 Ok(DebugStatusResponse::TrackedShards(self.get_tracked_shards_view()?))
@@ -179,6 +186,9 @@ impl Handler<WithSpanContext<DebugStatus>> for ClientActor {
             DebugStatus::ChainProcessingStatus => Ok(DebugStatusResponse::ChainProcessingStatus(
                 self.client.chain.get_chain_processing_info(),
             )),
+            DebugStatus::TxPool => Ok(DebugStatusResponse::TxPool(TxPoolView {
+                shards: self.client.sharded_tx_pool.get_tx_pool_view(None, None, None),
+            })),
         }
     }
 }
@@ -656,6 +666,11 @@ fn new_peer_info_view(chain: &Chain, connected_peer_info: &ConnectedPeerInfo) ->
         peer_id: full_peer_info.peer_info.id.public_key().clone(),
         received_bytes_per_sec: connected_peer_info.received_bytes_per_sec,
         sent_bytes_per_sec: connected_peer_info.sent_bytes_per_sec,
+        received_uncompressed_bytes_per_sec: connected_peer_info
+            .received_uncompressed_bytes_per_sec,
+        received_compressed_bytes_per_sec: connected_peer_info.received_compressed_bytes_per_sec,
+        sent_uncompressed_bytes_per_sec: connected_peer_info.sent_uncompressed_bytes_per_sec,
+        sent_compressed_bytes_per_sec: connected_peer_info.sent_compressed_bytes_per_sec,
         last_time_peer_requested_millis: connected_peer_info
             .last_time_peer_requested
             .elapsed()
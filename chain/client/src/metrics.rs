@@ -467,6 +467,15 @@ pub(crate) static STATE_SYNC_EXTERNAL_PARTS_FAILED: Lazy<IntCounterVec> = Lazy::
     .unwrap()
 });
 
+pub(crate) static STATE_SYNC_EXTERNAL_PARTS_CORRUPTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_state_sync_external_parts_corrupted_total",
+        "Number of parts retrieved from external storage that didn't match the manifest",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+
 pub(crate) static STATE_SYNC_EXTERNAL_PARTS_REQUEST_DELAY: Lazy<HistogramVec> = Lazy::new(|| {
     try_create_histogram_vec(
         "near_state_sync_external_parts_request_delay_sec",
@@ -3,12 +3,12 @@ use near_async::messaging::CanSend;
 use near_chain::Chain;
 use near_chain::{check_known, ChainStoreAccess};
 use near_client_primitives::types::SyncStatus;
+use near_network::reputation;
 use near_network::types::PeerManagerMessageRequest;
 use near_network::types::{HighestHeightPeerInfo, NetworkRequests, PeerManagerAdapter};
 use near_primitives::hash::CryptoHash;
 use near_primitives::static_clock::StaticClock;
 use near_primitives::types::{BlockHeight, BlockHeightDelta};
-use rand::seq::IteratorRandom;
 use tracing::{debug, warn};
 
 /// Maximum number of block requested at once in BlockSync
@@ -202,11 +202,16 @@ impl BlockSync {
             let (height, hash) = request;
             let request_from_archival = self.archive && height < gc_stop_height;
             let peer = if request_from_archival {
-                let archival_peer_iter = highest_height_peers.iter().filter(|p| p.archival);
-                archival_peer_iter.choose(&mut rand::thread_rng())
+                let archival_peers: Vec<_> =
+                    highest_height_peers.iter().filter(|p| p.archival).collect();
+                reputation::choose_weighted(&mut rand::thread_rng(), &archival_peers, |p| {
+                    p.reputation
+                })
+                .copied()
             } else {
-                let peer_iter = highest_height_peers.iter();
-                peer_iter.choose(&mut rand::thread_rng())
+                reputation::choose_weighted(&mut rand::thread_rng(), highest_height_peers, |p| {
+                    p.reputation
+                })
             };
 
             if let Some(peer) = peer {
@@ -293,6 +298,7 @@ mod test {
                 highest_block_hash: Default::default(),
                 tracked_shards: vec![],
                 archival: false,
+                reputation: 0,
             })
             .collect()
     }
@@ -1,8 +1,10 @@
 use crate::metrics;
 use futures::TryStreamExt;
-use near_primitives::types::{EpochId, ShardId};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{EpochId, ShardId, StateRoot};
+use once_cell::sync::Lazy;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
@@ -24,8 +26,20 @@ pub enum ExternalConnection {
         reqwest_client: Arc<reqwest::Client>,
         bucket: String,
     },
+    /// Read-only connection to a static file server, such as a CDN mirror of a bucket.
+    /// State parts are expected at `{base_url}/{location}`, and directory listings are expected
+    /// to be served as HTML pages linking the files, as done by nginx or Apache autoindex.
+    Http {
+        reqwest_client: Arc<reqwest::Client>,
+        base_url: String,
+    },
 }
 
+/// Number of attempts made by `ExternalConnection::Http` to fetch a file before giving up.
+const HTTP_NUM_ATTEMPTS: u32 = 3;
+/// Delay before the first retry of a failed HTTP request. Doubles with every next retry.
+const HTTP_INITIAL_RETRY_DELAY: Duration = Duration::from_millis(200);
+
 const GCS_ENCODE_SET: &percent_encoding::AsciiSet =
     &percent_encoding::NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
 
@@ -75,6 +89,12 @@ impl ExternalConnection {
                     }
                 }
             }
+            ExternalConnection::Http { reqwest_client, base_url } => {
+                let url = http_url(base_url, location);
+                let bytes = http_get_with_retries(reqwest_client, &url).await?;
+                tracing::debug!(target: "sync", %shard_id, location, num_bytes = bytes.len(), "HTTP state_part request finished");
+                Ok(bytes)
+            }
         }
     }
 
@@ -127,33 +147,131 @@ impl ExternalConnection {
                 tracing::debug!(target: "state_sync_dump", shard_id, part_length = state_part.len(), ?location, "Wrote a state part to GCS");
                 Ok(())
             }
+            ExternalConnection::Http { base_url, .. } => Err(anyhow::anyhow!(
+                "Can't upload {location} to {base_url}: HTTP external storage is read-only"
+            )),
         }
     }
 
-    fn extract_file_name_from_full_path(full_path: String) -> String {
-        return Self::extract_file_name_from_path_buf(PathBuf::from(full_path));
+    /// Downloads an arbitrary file from external storage.
+    pub async fn get_file(&self, location: &str) -> Result<Vec<u8>, anyhow::Error> {
+        match self {
+            ExternalConnection::S3 { bucket } => {
+                let response = bucket.get_object(location).await?;
+                if response.status_code() == 200 {
+                    Ok(response.bytes().to_vec())
+                } else {
+                    Err(anyhow::anyhow!(
+                        "Bad response status code {} for {}",
+                        response.status_code(),
+                        location
+                    ))
+                }
+            }
+            ExternalConnection::Filesystem { root_dir } => {
+                Ok(std::fs::read(root_dir.join(location))?)
+            }
+            ExternalConnection::GCS { gcs_client, bucket, .. } => {
+                Ok(gcs_client.object().download(bucket, location).await?)
+            }
+            ExternalConnection::Http { reqwest_client, base_url } => {
+                http_get_with_retries(reqwest_client, &http_url(base_url, location)).await
+            }
+        }
     }
 
-    fn extract_file_name_from_path_buf(path_buf: PathBuf) -> String {
-        return path_buf.file_name().unwrap().to_str().unwrap().to_string();
+    /// Uploads an arbitrary file to external storage, replacing the existing one.
+    pub async fn put_file(&self, data: &[u8], location: &str) -> Result<(), anyhow::Error> {
+        match self {
+            ExternalConnection::S3 { bucket } => {
+                bucket.put_object(&location, data).await?;
+            }
+            ExternalConnection::Filesystem { root_dir } => {
+                let path = root_dir.join(location);
+                if let Some(parent_dir) = path.parent() {
+                    std::fs::create_dir_all(parent_dir)?;
+                }
+                std::fs::write(&path, data)?;
+            }
+            ExternalConnection::GCS { gcs_client, bucket, .. } => {
+                gcs_client
+                    .object()
+                    .create(bucket, data.to_vec(), location, "application/octet-stream")
+                    .await?;
+            }
+            ExternalConnection::Http { base_url, .. } => anyhow::bail!(
+                "Can't upload {location} to {base_url}: HTTP external storage is read-only"
+            ),
+        }
+        tracing::debug!(target: "external", num_bytes = data.len(), location, "Uploaded a file");
+        Ok(())
     }
 
-    /// When using GCS external connection, this function requires credentials.
-    /// Thus, this function shouldn't be used for sync node that is expected to operate anonymously.
-    /// Only dump nodes should use this function.
-    pub async fn list_state_parts(
+    /// Uploads the file at `path` to external storage, replacing the existing one. The file is
+    /// streamed, not read into memory at once.
+    pub async fn put_file_from_path(
         &self,
-        shard_id: ShardId,
-        directory_path: &str,
-    ) -> Result<Vec<String>, anyhow::Error> {
-        let _timer = metrics::STATE_SYNC_DUMP_LIST_OBJECT_ELAPSED
-            .with_label_values(&[&shard_id.to_string()])
-            .start_timer();
+        path: &Path,
+        location: &str,
+    ) -> Result<(), anyhow::Error> {
         match self {
+            ExternalConnection::S3 { bucket } => {
+                let mut file = tokio::fs::File::open(path).await?;
+                let status = bucket.put_object_stream(&mut file, location).await?;
+                anyhow::ensure!(status == 200, "Bad response status code {status} for {location}");
+            }
+            ExternalConnection::Filesystem { root_dir } => {
+                let dest = root_dir.join(location);
+                if let Some(parent_dir) = dest.parent() {
+                    std::fs::create_dir_all(parent_dir)?;
+                }
+                tokio::fs::copy(path, &dest).await?;
+            }
+            ExternalConnection::GCS { gcs_client, bucket, .. } => {
+                let file = tokio::fs::File::open(path).await?;
+                let len = file.metadata().await?.len();
+                gcs_client
+                    .object()
+                    .create_streamed(
+                        bucket,
+                        tokio_util::io::ReaderStream::new(file),
+                        len,
+                        location,
+                        "application/octet-stream",
+                    )
+                    .await?;
+            }
+            ExternalConnection::Http { base_url, .. } => anyhow::bail!(
+                "Can't upload {location} to {base_url}: HTTP external storage is read-only"
+            ),
+        }
+        tracing::debug!(target: "external", ?path, location, "Uploaded a file");
+        Ok(())
+    }
+
+    /// Lists names of the files in the given directory of external storage.
+    /// Listing GCS buckets requires credentials.
+    pub async fn list_files(&self, directory_path: &str) -> Result<Vec<String>, anyhow::Error> {
+        match self {
+            ExternalConnection::Http { reqwest_client, base_url } => {
+                let url = format!("{}/", http_url(base_url, directory_path));
+                let index = http_get_with_retries(reqwest_client, &url).await?;
+                Ok(extract_links_from_html_index(&String::from_utf8_lossy(&index)))
+            }
+            ExternalConnection::Filesystem { root_dir } => {
+                let path = root_dir.join(directory_path);
+                if !path.exists() {
+                    return Ok(vec![]);
+                }
+                let mut file_names = vec![];
+                for file in std::fs::read_dir(&path)? {
+                    file_names.push(Self::extract_file_name_from_path_buf(file?.path()));
+                }
+                Ok(file_names)
+            }
             ExternalConnection::S3 { bucket } => {
                 let prefix = format!("{}/", directory_path);
-                let list_results = bucket.list(prefix.clone(), Some("/".to_string())).await?;
-                tracing::debug!(target: "state_sync_dump", shard_id, ?directory_path, "List state parts in s3");
+                let list_results = bucket.list(prefix, Some("/".to_string())).await?;
                 let mut file_names = vec![];
                 for res in list_results {
                     for obj in res.contents {
@@ -162,21 +280,8 @@ impl ExternalConnection {
                 }
                 Ok(file_names)
             }
-            ExternalConnection::Filesystem { root_dir } => {
-                let path = root_dir.join(directory_path);
-                tracing::debug!(target: "state_sync_dump", shard_id, ?path, "List state parts in local directory");
-                std::fs::create_dir_all(&path)?;
-                let mut file_names = vec![];
-                let files = std::fs::read_dir(&path)?;
-                for file in files {
-                    let file_name = Self::extract_file_name_from_path_buf(file?.path());
-                    file_names.push(file_name);
-                }
-                Ok(file_names)
-            }
             ExternalConnection::GCS { gcs_client, bucket, .. } => {
                 let prefix = format!("{}/", directory_path);
-                tracing::debug!(target: "state_sync_dump", shard_id, ?directory_path, "List state parts in GCS");
                 Ok(gcs_client
                     .object()
                     .list(
@@ -199,6 +304,194 @@ impl ExternalConnection {
             }
         }
     }
+
+    fn extract_file_name_from_full_path(full_path: String) -> String {
+        return Self::extract_file_name_from_path_buf(PathBuf::from(full_path));
+    }
+
+    fn extract_file_name_from_path_buf(path_buf: PathBuf) -> String {
+        return path_buf.file_name().unwrap().to_str().unwrap().to_string();
+    }
+
+    /// Lists the names of the state part files in the given directory, skipping other files such
+    /// as the manifest.
+    /// When using GCS external connection, this function requires credentials.
+    /// Thus, this function shouldn't be used for sync node that is expected to operate anonymously.
+    /// Only dump nodes should use this function.
+    pub async fn list_state_parts(
+        &self,
+        shard_id: ShardId,
+        directory_path: &str,
+    ) -> Result<Vec<String>, anyhow::Error> {
+        let _timer = metrics::STATE_SYNC_DUMP_LIST_OBJECT_ELAPSED
+            .with_label_values(&[&shard_id.to_string()])
+            .start_timer();
+        let mut file_names = match self {
+            ExternalConnection::S3 { .. } | ExternalConnection::GCS { .. } => {
+                tracing::debug!(target: "state_sync_dump", shard_id, ?directory_path, "List state parts in a bucket");
+                self.list_files(directory_path).await?
+            }
+            ExternalConnection::Filesystem { root_dir } => {
+                let path = root_dir.join(directory_path);
+                tracing::debug!(target: "state_sync_dump", shard_id, ?path, "List state parts in local directory");
+                std::fs::create_dir_all(&path)?;
+                let mut file_names = vec![];
+                let files = std::fs::read_dir(&path)?;
+                for file in files {
+                    let file_name = Self::extract_file_name_from_path_buf(file?.path());
+                    file_names.push(file_name);
+                }
+                file_names
+            }
+            ExternalConnection::Http { .. } => {
+                tracing::debug!(target: "state_sync_dump", shard_id, ?directory_path, "List state parts over HTTP");
+                self.list_files(directory_path).await?
+            }
+        };
+        file_names.retain(|file_name| is_part_filename(file_name));
+        Ok(file_names)
+    }
+}
+
+/// Whether an error returned by `ExternalConnection::get_part` means that the file doesn't exist,
+/// as opposed to a failure to fetch it, which may go away if retried.
+pub fn is_not_found_error(err: &anyhow::Error) -> bool {
+    if let Some(err) = err.downcast_ref::<std::io::Error>() {
+        return err.kind() == std::io::ErrorKind::NotFound;
+    }
+    if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+        return err.status() == Some(reqwest::StatusCode::NOT_FOUND);
+    }
+    matches!(err.downcast_ref::<s3::error::S3Error>(), Some(s3::error::S3Error::Http(404, _)))
+}
+
+fn http_url(base_url: &str, location: &str) -> String {
+    format!("{}/{}", base_url.trim_end_matches('/'), location.trim_start_matches('/'))
+}
+
+/// Fetches the given URL, retrying on connection errors, timeouts and server errors.
+/// Client errors, such as a missing file, and errors without a status, such as a failure to decode
+/// the body, are returned immediately.
+async fn http_get_with_retries(
+    reqwest_client: &reqwest::Client,
+    url: &str,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let mut delay = HTTP_INITIAL_RETRY_DELAY;
+    for attempt in 1..=HTTP_NUM_ATTEMPTS {
+        let result = match reqwest_client.get(url).send().await {
+            Ok(response) => match response.error_for_status() {
+                Ok(response) => response.bytes().await.map(|bytes| bytes.to_vec()),
+                Err(err) => Err(err),
+            },
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(bytes) => return Ok(bytes),
+            Err(err) => {
+                let retriable = err.is_timeout()
+                    || err.is_connect()
+                    || err.status().map_or(false, |status| {
+                        status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    });
+                if !retriable || attempt == HTTP_NUM_ATTEMPTS {
+                    tracing::debug!(target: "sync", url, attempt, error = ?err, "HTTP request failed");
+                    return Err(err.into());
+                }
+                tracing::debug!(target: "sync", url, attempt, error = ?err, ?delay, "HTTP request failed, retrying");
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+    }
+    unreachable!("the last attempt always returns")
+}
+
+static HREF_REGEX: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r#"href="([^"?#]+)""#).unwrap());
+
+/// Extracts names of all files linked from an HTML directory index page, skipping directories.
+fn extract_links_from_html_index(index: &str) -> Vec<String> {
+    let mut file_names: Vec<String> = HREF_REGEX
+        .captures_iter(index)
+        .filter_map(|captures| captures.get(1))
+        .map(|href| href.as_str())
+        .filter(|href| !href.ends_with('/'))
+        .map(|href| {
+            let file_name = href.rsplit('/').next().unwrap_or(href);
+            percent_encoding::percent_decode_str(file_name).decode_utf8_lossy().to_string()
+        })
+        .collect();
+    file_names.sort();
+    file_names.dedup();
+    file_names
+}
+
+/// Describes all state parts of a shard dumped for an epoch.
+/// Dumped by the state dump node once all parts are dumped, and used by syncing nodes to detect
+/// corrupted parts before applying them.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct StatePartsManifest {
+    /// State root that the state parts correspond to.
+    pub state_root: StateRoot,
+    pub num_parts: u64,
+    /// Ordered by `part_id`, one entry per part.
+    pub parts: Vec<StatePartsManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct StatePartsManifestEntry {
+    pub part_id: u64,
+    pub size: u64,
+    pub hash: CryptoHash,
+}
+
+impl StatePartsManifestEntry {
+    pub fn new(part_id: u64, state_part: &[u8]) -> Self {
+        Self { part_id, size: state_part.len() as u64, hash: CryptoHash::hash_bytes(state_part) }
+    }
+
+    /// Whether the given data is the state part described by this entry.
+    pub fn matches(&self, state_part: &[u8]) -> bool {
+        self.size == state_part.len() as u64 && self.hash == CryptoHash::hash_bytes(state_part)
+    }
+}
+
+impl StatePartsManifest {
+    pub fn from_bytes(data: &[u8]) -> Result<Self, anyhow::Error> {
+        let manifest: Self = serde_json::from_slice(data)?;
+        let is_complete = manifest.parts.len() as u64 == manifest.num_parts
+            && manifest.parts.iter().enumerate().all(|(idx, part)| part.part_id == idx as u64);
+        if !is_complete {
+            anyhow::bail!("Manifest doesn't list exactly {} parts", manifest.num_parts);
+        }
+        Ok(manifest)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec_pretty(self).unwrap()
+    }
+
+    /// Checks that the manifest describes the expected state, and returns the entry of the
+    /// given part.
+    pub fn get_part_entry(
+        &self,
+        state_root: &StateRoot,
+        part_id: u64,
+        num_parts: u64,
+    ) -> Result<&StatePartsManifestEntry, anyhow::Error> {
+        if &self.state_root != state_root || self.num_parts != num_parts {
+            anyhow::bail!(
+                "Manifest describes state root {} with {} parts, expected state root {} with {} parts",
+                self.state_root,
+                self.num_parts,
+                state_root,
+                num_parts
+            );
+        }
+        self.parts
+            .get(part_id as usize)
+            .ok_or_else(|| anyhow::anyhow!("Manifest doesn't list part {}", part_id))
+    }
 }
 
 /// Construct a location on the external storage.
@@ -226,6 +519,16 @@ pub fn external_storage_location_directory(
     location_prefix(chain_id, epoch_height, epoch_id, shard_id)
 }
 
+/// Location of the `StatePartsManifest` of the given shard and epoch.
+pub fn external_storage_manifest_location(
+    chain_id: &str,
+    epoch_id: &EpochId,
+    epoch_height: u64,
+    shard_id: u64,
+) -> String {
+    format!("{}/{}", location_prefix(chain_id, epoch_height, epoch_id, shard_id), MANIFEST_FILENAME)
+}
+
 pub fn location_prefix(
     chain_id: &str,
     epoch_height: u64,
@@ -242,6 +545,9 @@ pub fn part_filename(part_id: u64, num_parts: u64) -> String {
     format!("state_part_{:06}_of_{:06}", part_id, num_parts)
 }
 
+/// Name of the file listing all state parts of a shard, stored next to the parts.
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
 pub fn match_filename(s: &str) -> Option<regex::Captures> {
     let re = regex::Regex::new(r"^state_part_(\d{6})_of_(\d{6})$").unwrap();
     re.captures(s)
@@ -273,13 +579,17 @@ pub fn get_part_id_from_filename(s: &str) -> Option<u64> {
     None
 }
 
+/// Creates a bucket which sends requests without credentials, which is enough to read a public
+/// bucket. `endpoint` is the URL of an S3-compatible service to use instead of AWS.
 pub fn create_bucket_readonly(
     bucket: &str,
     region: &str,
+    endpoint: Option<&str>,
+    path_style: bool,
     timeout: Duration,
 ) -> Result<s3::Bucket, anyhow::Error> {
     let creds = s3::creds::Credentials::anonymous()?;
-    create_bucket(bucket, region, timeout, creds)
+    create_bucket(bucket, region, endpoint, path_style, timeout, creds)
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -288,9 +598,13 @@ struct S3CredentialsConfig {
     secret_key: String,
 }
 
+/// Creates a bucket which signs requests with the credentials from the file or, if no file is
+/// given, from the environment.
 pub fn create_bucket_readwrite(
     bucket: &str,
     region: &str,
+    endpoint: Option<&str>,
+    path_style: bool,
     timeout: Duration,
     credentials_file: Option<PathBuf>,
 ) -> Result<s3::Bucket, anyhow::Error> {
@@ -310,16 +624,27 @@ pub fn create_bucket_readwrite(
         }
         None => s3::creds::Credentials::default(),
     }?;
-    create_bucket(bucket, region, timeout, creds)
+    create_bucket(bucket, region, endpoint, path_style, timeout, creds)
 }
 
 fn create_bucket(
     bucket: &str,
     region: &str,
+    endpoint: Option<&str>,
+    path_style: bool,
     timeout: Duration,
     creds: s3::creds::Credentials,
 ) -> Result<s3::Bucket, anyhow::Error> {
-    let mut bucket = s3::Bucket::new(bucket, region.parse::<s3::Region>()?, creds)?;
+    let region = match endpoint {
+        Some(endpoint) => {
+            s3::Region::Custom { region: region.to_string(), endpoint: endpoint.to_string() }
+        }
+        None => region.parse::<s3::Region>()?,
+    };
+    let mut bucket = s3::Bucket::new(bucket, region, creds)?;
+    if path_style {
+        bucket.set_path_style();
+    }
     // Ensure requests finish in finite amount of time.
     bucket.set_request_timeout(Some(timeout));
     Ok(bucket)
@@ -328,10 +653,12 @@ fn create_bucket(
 #[cfg(test)]
 mod test {
     use crate::sync::external::{
-        get_num_parts_from_filename, get_part_id_from_filename, is_part_filename, part_filename,
-        ExternalConnection,
+        create_bucket_readonly, extract_links_from_html_index, get_num_parts_from_filename,
+        get_part_id_from_filename, is_not_found_error, is_part_filename, part_filename,
+        ExternalConnection, StatePartsManifest, StatePartsManifestEntry, MANIFEST_FILENAME,
     };
     use near_o11y::testonly::init_test_logger;
+    use near_primitives::hash::CryptoHash;
     use rand::distributions::{Alphanumeric, DistString};
 
     fn random_string(rand_len: usize) -> String {
@@ -351,6 +678,149 @@ mod test {
         assert_eq!(get_part_id_from_filename("123123"), None);
     }
 
+    #[test]
+    fn test_manifest() {
+        let parts: Vec<Vec<u8>> = vec![random_string(100).into(), random_string(200).into()];
+        let state_root = CryptoHash::hash_bytes(b"state_root");
+        let manifest = StatePartsManifest {
+            state_root,
+            num_parts: 2,
+            parts: parts
+                .iter()
+                .enumerate()
+                .map(|(part_id, part)| StatePartsManifestEntry::new(part_id as u64, part))
+                .collect(),
+        };
+        let manifest = StatePartsManifest::from_bytes(&manifest.to_bytes()).unwrap();
+
+        let entry = manifest.get_part_entry(&state_root, 1, 2).unwrap();
+        assert!(entry.matches(&parts[1]));
+        assert!(!entry.matches(&parts[0]));
+        let mut corrupted = parts[1].clone();
+        corrupted[0] ^= 1;
+        assert!(!entry.matches(&corrupted));
+
+        assert!(manifest.get_part_entry(&state_root, 2, 2).is_err());
+        assert!(manifest.get_part_entry(&state_root, 0, 3).is_err());
+        assert!(manifest.get_part_entry(&CryptoHash::default(), 0, 2).is_err());
+
+        // A manifest must list every part exactly once.
+        let incomplete = StatePartsManifest { num_parts: 3, ..manifest };
+        assert!(StatePartsManifest::from_bytes(&incomplete.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_extract_links_from_html_index() {
+        let index = format!(
+            r#"<html><body><pre><a href="../">../</a>
+<a href="{0}">{0}</a>
+<a href="/chain_id=test/shard_id=0/{1}">{1}</a>
+<a href="{1}">{1}</a>
+<a href="other_file">other_file</a></pre></body></html>"#,
+            part_filename(1, 2),
+            part_filename(0, 2),
+        );
+        assert_eq!(
+            extract_links_from_html_index(&index),
+            vec!["other_file".to_string(), part_filename(0, 2), part_filename(1, 2)]
+        );
+    }
+
+    #[test]
+    fn test_filesystem_list_state_parts() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let root_dir = tempfile::tempdir().unwrap();
+        let dir = "chain_id=test/epoch_height=1/epoch_id=test/shard_id=0";
+        let connection = ExternalConnection::Filesystem { root_dir: root_dir.path().to_path_buf() };
+
+        rt.block_on(async {
+            for location in [part_filename(0, 2), part_filename(1, 2), MANIFEST_FILENAME.into()] {
+                let location = format!("{}/{}", dir, location);
+                connection.put_state_part(b"data", 0, &location).await.unwrap();
+            }
+            let mut files = connection.list_state_parts(0, dir).await.unwrap();
+            files.sort();
+            assert_eq!(files, vec![part_filename(0, 2), part_filename(1, 2)]);
+
+            let missing_location = format!("{}/{}", dir, part_filename(0, 1));
+            let err = connection.get_part(0, &missing_location).await.unwrap_err();
+            assert!(is_not_found_error(&err), "{:?}", err);
+        });
+    }
+
+    /// Serves files from the given map over HTTP on a random local port.
+    /// The first request for every path is answered with `503 Service Unavailable` to exercise
+    /// retries.
+    async fn serve_files(files: std::collections::HashMap<String, Vec<u8>>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut seen_paths = std::collections::HashSet::new();
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 4096];
+                let n = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or_default().to_string();
+                let (status, body) = if seen_paths.insert(path.clone()) {
+                    ("503 Service Unavailable", vec![])
+                } else if let Some(body) = files.get(&path) {
+                    ("200 OK", body.clone())
+                } else {
+                    ("404 Not Found", vec![])
+                };
+                let header = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                stream.write_all(header.as_bytes()).await.unwrap();
+                stream.write_all(&body).await.unwrap();
+            }
+        });
+        format!("http://{}/state-parts/", addr)
+    }
+
+    #[test]
+    fn test_http_list_download() {
+        init_test_logger();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let dir = "chain_id=test/epoch_height=1/epoch_id=test/shard_id=0".to_string();
+        let filename = part_filename(0, 1);
+        let data: Vec<u8> = random_string(1000).into();
+        let index = format!(
+            r#"<html><body><a href="{0}">{0}</a><a href="{1}">{1}</a></body></html>"#,
+            filename, MANIFEST_FILENAME
+        );
+        let files = std::collections::HashMap::from([
+            (format!("/state-parts/{}/", dir), index.into_bytes()),
+            (format!("/state-parts/{}/{}", dir, filename), data.clone()),
+        ]);
+
+        rt.block_on(async {
+            let connection = ExternalConnection::Http {
+                reqwest_client: std::sync::Arc::new(reqwest::Client::default()),
+                base_url: serve_files(files).await,
+            };
+
+            let files = connection.list_state_parts(0, &dir).await.unwrap();
+            assert_eq!(files, vec![filename.clone()]);
+
+            let full_filename = format!("{}/{}", dir, filename);
+            let download_data = connection.get_part(0, &full_filename).await.unwrap();
+            assert_eq!(download_data, data);
+
+            let missing_filename = format!("{}/{}", dir, part_filename(1, 2));
+            let err = connection.get_part(0, &missing_filename).await.unwrap_err();
+            assert!(is_not_found_error(&err), "{:?}", err);
+
+            assert!(connection.put_state_part(&data, 0, &full_filename).await.is_err());
+        });
+    }
+
     /// This test should be ignored by default, as it requires gcloud credentials to run.
     /// Specify the path to service account json  in `SERVICE_ACCOUNT` variable to run the test.
     #[test]
@@ -359,8 +829,8 @@ mod test {
         init_test_logger();
         let rt = tokio::runtime::Runtime::new().unwrap();
 
-        // Generate random filename.
-        let filename = random_string(8);
+        // Generate random filename. Only state part files are listed.
+        let filename = part_filename(rand::random::<u64>() % 1000, 1000);
         tracing::debug!("Filename: {:?}", filename);
 
         // Define bucket.
@@ -405,4 +875,28 @@ mod test {
         let download_data = rt.block_on(async { connection.get_part(0, &full_filename).await });
         assert!(download_data.is_err(), "{:?}", download_data);
     }
+
+    #[test]
+    fn test_create_bucket_with_endpoint() {
+        let timeout = std::time::Duration::from_secs(5);
+        let bucket = create_bucket_readonly(
+            "state-parts",
+            "us-east-1",
+            Some("http://localhost:9000"),
+            true,
+            timeout,
+        )
+        .unwrap();
+        assert_eq!(bucket.url(), "http://localhost:9000/state-parts");
+
+        let bucket = create_bucket_readonly(
+            "state-parts",
+            "us-east-1",
+            Some("https://minio.local"),
+            false,
+            timeout,
+        )
+        .unwrap();
+        assert_eq!(bucket.url(), "https://state-parts.minio.local");
+    }
 }
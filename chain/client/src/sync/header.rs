@@ -5,6 +5,7 @@ use chrono::{DateTime, Duration, Utc};
 use near_async::messaging::CanSend;
 use near_chain::{Chain, ChainStoreAccess};
 use near_client_primitives::types::SyncStatus;
+use near_network::reputation;
 use near_network::types::PeerManagerMessageRequest;
 use near_network::types::{HighestHeightPeerInfo, NetworkRequests, PeerManagerAdapter};
 use near_primitives::block::Tip;
@@ -12,7 +13,6 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::static_clock::StaticClock;
 use near_primitives::types::BlockHeight;
 use near_primitives::utils::to_timestamp;
-use rand::seq::SliceRandom;
 use rand::thread_rng;
 use tracing::{debug, warn};
 
@@ -95,7 +95,12 @@ impl HeaderSync {
                 highest_height,
             };
             self.syncing_peer = None;
-            if let Some(peer) = highest_height_peers.choose(&mut thread_rng()).cloned() {
+            if let Some(peer) =
+                reputation::choose_weighted(&mut thread_rng(), highest_height_peers, |p| {
+                    p.reputation
+                })
+                .cloned()
+            {
                 if peer.highest_block_height > header_head.height {
                     self.syncing_peer = self.request_headers(chain, peer);
                 }
@@ -558,6 +563,7 @@ mod test {
                 highest_block_hash: Default::default(),
                 tracked_shards: vec![],
                 archival: false,
+                reputation: 0,
             });
             header_sync.syncing_peer.as_mut().unwrap().highest_block_height = highest_height;
         };
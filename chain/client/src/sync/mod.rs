@@ -3,3 +3,4 @@ pub mod epoch;
 pub mod external;
 pub mod header;
 pub mod state;
+pub mod state_parts;
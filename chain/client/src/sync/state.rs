@@ -12,7 +12,7 @@
 //! For downloading - the code is picking the potential target nodes (all direct peers that are tracking the shard
 //! (and are high enough) + validators from that epoch that were tracking the shard)
 //! Then for each part that we're missing, we're 'randomly' picking a target from whom we'll request it - but we make
-//! sure to not request more than MAX_STATE_PART_REQUESTS from each.
+//! sure to not request more parts from each than it can serve (see `StatePartsScheduler`).
 //!
 //! WARNING: with the current design, we're putting quite a load on the validators - as we request a lot of data from
 //!         them (if you assume that we have 100 validators and 30 peers - we send 100/130 of requests to validators).
@@ -22,12 +22,14 @@
 
 use crate::metrics;
 use crate::sync::external::{
-    create_bucket_readonly, external_storage_location, ExternalConnection,
+    create_bucket_readonly, create_bucket_readwrite, external_storage_location,
+    external_storage_manifest_location, is_not_found_error, ExternalConnection, StatePartsManifest,
 };
+use crate::sync::state_parts::StatePartsScheduler;
 use actix_rt::ArbiterHandle;
 use chrono::{DateTime, Duration, Utc};
 use futures::{future, FutureExt};
-use near_async::messaging::CanSendAsync;
+use near_async::messaging::{CanSend, CanSendAsync};
 use near_chain::chain::ApplyStatePartsRequest;
 use near_chain::near_chain_primitives;
 use near_chain::resharding::StateSplitRequest;
@@ -38,6 +40,7 @@ use near_client_primitives::types::{
     format_shard_sync_phase, DownloadStatus, ShardSyncDownload, ShardSyncStatus,
 };
 use near_epoch_manager::EpochManagerAdapter;
+use near_network::reputation::{self, ReputationEvent};
 use near_network::types::AccountOrPeerIdOrHash;
 use near_network::types::PeerManagerMessageRequest;
 use near_network::types::{
@@ -49,10 +52,10 @@ use near_primitives::state_part::PartId;
 use near_primitives::static_clock::StaticClock;
 use near_primitives::syncing::{get_num_state_parts, ShardStateSyncResponse};
 use near_primitives::types::{AccountId, EpochHeight, EpochId, ShardId, StateRoot};
-use rand::seq::SliceRandom;
+use near_primitives::views::StateSyncPeerView;
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::{thread_rng, Rng};
 use std::collections::HashMap;
-use std::ops::Add;
 use std::sync::atomic::Ordering;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::Arc;
@@ -60,14 +63,24 @@ use std::time::Duration as TimeDuration;
 use tokio::sync::{Semaphore, TryAcquireError};
 use tracing::{debug, info};
 
-/// Maximum number of state parts to request per peer on each round when node is trying to download the state.
-pub const MAX_STATE_PART_REQUEST: u64 = 16;
-/// Number of state parts already requested stored as pending.
-/// This number should not exceed MAX_STATE_PART_REQUEST times (number of peers in the network).
-pub const MAX_PENDING_PART: u64 = MAX_STATE_PART_REQUEST * 10000;
 /// Time limit per state dump iteration.
 /// A node must check external storage for parts to dump again once time is up.
 pub const STATE_DUMP_ITERATION_TIME_LIMIT_SECS: u64 = 300;
+/// Number of times a state part is downloaded from external storage before giving up,
+/// if the downloaded data doesn't match the manifest.
+const MAX_EXTERNAL_PART_ATTEMPTS: u32 = 3;
+/// Number of manifests of state parts in external storage to keep in memory.
+const MAX_CACHED_MANIFESTS: usize = 16;
+
+/// Manifests of state parts in external storage, keyed by their location.
+/// Every location has its own cell, so that a manifest is downloaded only once without blocking
+/// the downloads of the other manifests.
+/// `None` means that the manifest is missing, as in dumps made before manifests were introduced.
+type ManifestCache = Arc<
+    tokio::sync::Mutex<
+        lru::LruCache<String, Arc<tokio::sync::OnceCell<Option<Arc<StatePartsManifest>>>>>,
+    >,
+>;
 
 pub enum StateSyncResult {
     /// No shard has changed its status
@@ -79,21 +92,6 @@ pub enum StateSyncResult {
     Completed,
 }
 
-struct PendingRequestStatus {
-    /// Number of parts that are in progress (we requested them from a given peer but didn't get the answer yet).
-    missing_parts: usize,
-    wait_until: DateTime<Utc>,
-}
-
-impl PendingRequestStatus {
-    fn new(timeout: Duration) -> Self {
-        Self { missing_parts: 1, wait_until: StaticClock::utc().add(timeout) }
-    }
-    fn expired(&self) -> bool {
-        StaticClock::utc() > self.wait_until
-    }
-}
-
 /// Private to public API conversion.
 fn make_account_or_peer_id_or_hash(
     from: near_network::types::AccountOrPeerIdOrHash,
@@ -107,6 +105,21 @@ fn make_account_or_peer_id_or_hash(
     }
 }
 
+/// Reputation of the state sync target. Validators we don't know the peer id of have a neutral
+/// reputation.
+fn target_reputation(
+    target: &AccountOrPeerIdOrHash,
+    highest_height_peers: &[HighestHeightPeerInfo],
+) -> i64 {
+    match target {
+        AccountOrPeerIdOrHash::PeerId(peer_id) => highest_height_peers
+            .iter()
+            .find(|peer| &peer.peer_info.id == peer_id)
+            .map_or(0, |peer| peer.reputation),
+        _ => 0,
+    }
+}
+
 pub struct StateSyncGetPartResult {
     sync_hash: CryptoHash,
     shard_id: ShardId,
@@ -117,10 +130,8 @@ pub struct StateSyncGetPartResult {
 enum StateSyncInner {
     /// Request both the state header and state parts from the peers.
     Peers {
-        /// Which parts were requested from which peer and when.
-        last_part_id_requested: HashMap<(AccountOrPeerIdOrHash, ShardId), PendingRequestStatus>,
-        /// Map from which part we requested to whom.
-        requested_target: lru::LruCache<(u64, CryptoHash), AccountOrPeerIdOrHash>,
+        /// Decides which peers to request the parts from.
+        scheduler: StatePartsScheduler,
     },
     /// Requests the state header from peers but gets the state parts from an
     /// external storage.
@@ -131,6 +142,8 @@ enum StateSyncInner {
         semaphore: Arc<tokio::sync::Semaphore>,
         /// Connection to the external storage.
         external: ExternalConnection,
+        /// Manifests used to check the downloaded state parts.
+        manifests: ManifestCache,
     },
 }
 
@@ -171,8 +184,7 @@ impl StateSync {
     ) -> Self {
         let inner = match sync_config {
             SyncConfig::Peers => StateSyncInner::Peers {
-                last_part_id_requested: Default::default(),
-                requested_target: lru::LruCache::new(MAX_PENDING_PART as usize),
+                scheduler: StatePartsScheduler::new(Duration::from_std(timeout).unwrap()),
             },
             SyncConfig::ExternalStorage(ExternalStorageConfig {
                 location,
@@ -180,8 +192,26 @@ impl StateSync {
                 num_concurrent_requests_during_catchup,
             }) => {
                 let external = match location {
-                    ExternalStorageLocation::S3 { bucket, region, .. } => {
-                        let bucket = create_bucket_readonly(&bucket, &region, timeout);
+                    ExternalStorageLocation::S3 {
+                        bucket,
+                        region,
+                        endpoint,
+                        path_style,
+                        anonymous,
+                    } => {
+                        let endpoint = endpoint.as_deref();
+                        let bucket = if anonymous.unwrap_or(true) {
+                            create_bucket_readonly(bucket, region, endpoint, *path_style, timeout)
+                        } else {
+                            create_bucket_readwrite(
+                                bucket,
+                                region,
+                                endpoint,
+                                *path_style,
+                                timeout,
+                                None,
+                            )
+                        };
                         if let Err(err) = bucket {
                             panic!("Failed to create an S3 bucket: {}", err);
                         }
@@ -195,6 +225,16 @@ impl StateSync {
                         reqwest_client: Arc::new(reqwest::Client::default()),
                         bucket: bucket.clone(),
                     },
+                    ExternalStorageLocation::Http { base_url } => {
+                        let reqwest_client = reqwest::Client::builder().timeout(timeout).build();
+                        if let Err(err) = reqwest_client {
+                            panic!("Failed to create an HTTP client: {}", err);
+                        }
+                        ExternalConnection::Http {
+                            reqwest_client: Arc::new(reqwest_client.unwrap()),
+                            base_url: base_url.clone(),
+                        }
+                    }
                 };
                 let num_permits = if catchup {
                     *num_concurrent_requests_during_catchup
@@ -205,6 +245,9 @@ impl StateSync {
                     chain_id: chain_id.to_string(),
                     semaphore: Arc::new(tokio::sync::Semaphore::new(num_permits)),
                     external,
+                    manifests: Arc::new(tokio::sync::Mutex::new(lru::LruCache::new(
+                        MAX_CACHED_MANIFESTS,
+                    ))),
                 }
             }
         };
@@ -310,8 +353,12 @@ impl StateSync {
                         )?;
                 }
                 ShardSyncStatus::StateDownloadParts => {
-                    let res =
-                        self.sync_shards_download_parts_status(shard_id, shard_sync_download, now);
+                    let res = self.sync_shards_download_parts_status(
+                        shard_id,
+                        shard_sync_download,
+                        sync_hash,
+                        now,
+                    );
                     download_timeout = res.0;
                     run_shard_state_download = res.1;
                     update_sync_status |= res.2;
@@ -499,35 +546,6 @@ impl StateSync {
         }
     }
 
-    // Function called when our node receives the network response with a part.
-    pub fn received_requested_part(
-        &mut self,
-        part_id: u64,
-        shard_id: ShardId,
-        sync_hash: CryptoHash,
-    ) {
-        match &mut self.inner {
-            StateSyncInner::Peers { last_part_id_requested, requested_target } => {
-                let key = (part_id, sync_hash);
-                // Check that it came from the target that we requested it from.
-                if let Some(target) = requested_target.get(&key) {
-                    if last_part_id_requested.get_mut(&(target.clone(), shard_id)).map_or(
-                        false,
-                        |request| {
-                            request.missing_parts = request.missing_parts.saturating_sub(1);
-                            request.missing_parts == 0
-                        },
-                    ) {
-                        last_part_id_requested.remove(&(target.clone(), shard_id));
-                    }
-                }
-            }
-            StateSyncInner::PartsFromExternal { .. } => {
-                // Do nothing.
-            }
-        }
-    }
-
     /// Find possible targets to download state from.
     /// Candidates are validators at current epoch and peers at highest height.
     /// Only select candidates that we have no pending request currently ongoing.
@@ -574,29 +592,25 @@ impl StateSync {
                     None
                 }
             }));
-        Ok(self.select_peers(peers.collect(), shard_id)?)
+        let peers: Vec<_> = peers.collect();
+        if let StateSyncInner::Peers { scheduler } = &mut self.inner {
+            // Peers which disconnected are no longer among the candidates.
+            scheduler.retain_targets(shard_id, &peers);
+        }
+        Ok(self.select_peers(peers, shard_id)?)
     }
 
-    /// Avoids peers that already have outstanding requests for parts.
+    /// Avoids peers that can't take more requests for parts.
     fn select_peers(
         &mut self,
         peers: Vec<AccountOrPeerIdOrHash>,
         shard_id: ShardId,
     ) -> Result<Vec<AccountOrPeerIdOrHash>, near_chain::Error> {
-        let res = match &mut self.inner {
-            StateSyncInner::Peers {
-                last_part_id_requested,
-                requested_target: _requested_target,
-            } => {
-                last_part_id_requested.retain(|_, request| !request.expired());
-                peers
-                    .into_iter()
-                    .filter(|candidate| {
-                        // If we still have a pending request from this node - don't add another one.
-                        !last_part_id_requested.contains_key(&(candidate.clone(), shard_id))
-                    })
-                    .collect::<Vec<_>>()
-            }
+        let res = match &self.inner {
+            StateSyncInner::Peers { scheduler } => peers
+                .into_iter()
+                .filter(|candidate| scheduler.has_capacity(shard_id, candidate))
+                .collect::<Vec<_>>(),
             StateSyncInner::PartsFromExternal { .. } => peers,
         };
         Ok(res)
@@ -627,6 +641,10 @@ impl StateSync {
             // In most cases it means that all the targets are currently busy (that we have a pending request with them).
             return Ok(());
         }
+        let reputations: Vec<i64> = possible_targets
+            .iter()
+            .map(|target| target_reputation(target, highest_height_peers))
+            .collect();
 
         // Downloading strategy starts here
         match shard_sync_download.status {
@@ -635,6 +653,7 @@ impl StateSync {
                     shard_id,
                     sync_hash,
                     &possible_targets,
+                    &reputations,
                     shard_sync_download,
                 );
             }
@@ -643,6 +662,7 @@ impl StateSync {
                     shard_id,
                     sync_hash,
                     possible_targets,
+                    &reputations,
                     shard_sync_download,
                     chain,
                     state_parts_arbiter_handle,
@@ -654,15 +674,20 @@ impl StateSync {
         Ok(())
     }
 
-    /// Makes a StateRequestHeader header to one of the peers.
+    /// Makes a StateRequestHeader header to one of the peers, preferring the peers with a better
+    /// reputation.
     fn request_shard_header(
         &mut self,
         shard_id: ShardId,
         sync_hash: CryptoHash,
         possible_targets: &[AccountOrPeerIdOrHash],
+        reputations: &[i64],
         new_shard_sync_download: &mut ShardSyncDownload,
     ) {
-        let target = possible_targets.choose(&mut thread_rng()).cloned().unwrap();
+        let targets: Vec<_> = possible_targets.iter().zip(reputations).collect();
+        let target = reputation::choose_weighted(&mut thread_rng(), &targets, |(_, r)| **r)
+            .map(|(target, _)| (*target).clone())
+            .unwrap();
         assert!(new_shard_sync_download.downloads[0].run_me.load(Ordering::SeqCst));
         new_shard_sync_download.downloads[0].run_me.store(false, Ordering::SeqCst);
         new_shard_sync_download.downloads[0].state_requests_count += 1;
@@ -693,6 +718,7 @@ impl StateSync {
         shard_id: ShardId,
         sync_hash: CryptoHash,
         possible_targets: Vec<AccountOrPeerIdOrHash>,
+        reputations: &[i64],
         new_shard_sync_download: &mut ShardSyncDownload,
         chain: &Chain,
         state_parts_arbiter_handle: &ArbiterHandle,
@@ -700,29 +726,23 @@ impl StateSync {
         // Iterate over all parts that needs to be requested (i.e. download.run_me is true).
         // Parts are ordered such that its index match its part_id.
         match &mut self.inner {
-            StateSyncInner::Peers { last_part_id_requested, requested_target } => {
-                // We'll select all the 'highest' peers + validators as candidates (excluding those that gave us timeout in the past).
-                // And for each one of them, we'll ask for up to 16 (MAX_STATE_PART_REQUEST) parts.
-                let possible_targets_sampler =
-                    SamplerLimited::new(possible_targets, MAX_STATE_PART_REQUEST);
-
-                // For every part that needs to be requested it is selected one
-                // peer (target) randomly to request the part from.
-                // IMPORTANT: here we use 'zip' with possible_target_sampler -
-                // which is limited. So at any moment we'll not request more
-                // than possible_targets.len() * MAX_STATE_PART_REQUEST parts.
+            StateSyncInner::Peers { scheduler } => {
+                // We'll select all the 'highest' peers + validators as candidates, and spread the
+                // parts across them, preferring the candidates with a better reputation. Every
+                // candidate gets only as many parts at a time as it can serve, so we stop when all
+                // of them are busy.
+                scheduler.retain_sync_hash(sync_hash);
+                let now = StaticClock::utc();
+                let limits = possible_targets
+                    .iter()
+                    .map(|target| scheduler.free_slots(shard_id, target))
+                    .collect();
+                let weights = reputations.iter().map(|r| reputation::weight(*r)).collect();
+                let sampler = SamplerLimited::new(possible_targets, limits).with_weights(weights);
                 for ((part_id, download), target) in
-                    parts_to_fetch(new_shard_sync_download).zip(possible_targets_sampler)
+                    parts_to_fetch(new_shard_sync_download).zip(sampler)
                 {
-                    sent_request_part(
-                        target.clone(),
-                        part_id,
-                        shard_id,
-                        sync_hash,
-                        last_part_id_requested,
-                        requested_target,
-                        self.timeout,
-                    );
+                    scheduler.sent(sync_hash, shard_id, part_id, target.clone(), now);
                     request_part_from_peers(
                         part_id,
                         target,
@@ -733,7 +753,7 @@ impl StateSync {
                     );
                 }
             }
-            StateSyncInner::PartsFromExternal { chain_id, semaphore, external } => {
+            StateSyncInner::PartsFromExternal { chain_id, semaphore, external, manifests } => {
                 let sync_block_header = chain.get_block_header(&sync_hash).unwrap();
                 let epoch_id = sync_block_header.epoch_id();
                 let epoch_info = chain.epoch_manager.get_epoch_info(epoch_id).unwrap();
@@ -742,6 +762,7 @@ impl StateSync {
                 let shard_state_header = chain.get_state_header(shard_id, sync_hash).unwrap();
                 let state_num_parts =
                     get_num_state_parts(shard_state_header.state_root_node().memory_usage);
+                let state_root = shard_state_header.chunk_prev_state_root();
 
                 for (part_id, download) in parts_to_fetch(new_shard_sync_download) {
                     request_part_from_external_storage(
@@ -752,9 +773,11 @@ impl StateSync {
                         epoch_id,
                         epoch_height,
                         state_num_parts,
+                        state_root,
                         &chain_id.clone(),
                         semaphore.clone(),
                         external.clone(),
+                        manifests.clone(),
                         state_parts_arbiter_handle,
                         self.state_parts_mpsc_tx.clone(),
                     );
@@ -845,10 +868,6 @@ impl StateSync {
         state_response: ShardStateSyncResponse,
         chain: &mut Chain,
     ) {
-        if let Some(part_id) = state_response.part_id() {
-            // Mark that we have received this part (this will update info on pending parts from peers etc).
-            self.received_requested_part(part_id, shard_id, hash);
-        }
         match shard_sync_download.status {
             ShardSyncStatus::StateDownloadHeader => {
                 if let Some(header) = state_response.take_header() {
@@ -881,7 +900,7 @@ impl StateSync {
                         return;
                     }
                     if !shard_sync_download.downloads[part_id as usize].done {
-                        match chain.set_state_part(
+                        let valid = match chain.set_state_part(
                             shard_id,
                             hash,
                             PartId::new(part_id, num_parts),
@@ -889,12 +908,17 @@ impl StateSync {
                         ) {
                             Ok(()) => {
                                 shard_sync_download.downloads[part_id as usize].done = true;
+                                true
                             }
                             Err(err) => {
                                 tracing::error!(target: "sync", %shard_id, %hash, part_id, ?err, "State sync set_state_part error");
                                 shard_sync_download.downloads[part_id as usize].error = true;
+                                false
                             }
-                        }
+                        };
+                        self.received_part(hash, shard_id, part_id, data.len(), valid);
+                    } else if let StateSyncInner::Peers { scheduler } = &mut self.inner {
+                        scheduler.release(hash, shard_id, part_id);
                     }
                 }
             }
@@ -902,6 +926,41 @@ impl StateSync {
         }
     }
 
+    /// Records the part received from a peer, updating the stats and the reputation of the peer
+    /// which served it.
+    fn received_part(
+        &mut self,
+        sync_hash: CryptoHash,
+        shard_id: ShardId,
+        part_id: u64,
+        size: usize,
+        valid: bool,
+    ) {
+        let StateSyncInner::Peers { scheduler } = &mut self.inner else {
+            return;
+        };
+        let target =
+            scheduler.received(sync_hash, shard_id, part_id, size, valid, StaticClock::utc());
+        if let Some(AccountOrPeerIdOrHash::PeerId(peer_id)) = target {
+            let event = if valid {
+                ReputationEvent::StatePartServed
+            } else {
+                ReputationEvent::InvalidResponse
+            };
+            self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+                NetworkRequests::ReportPeer { peer_id, event },
+            ));
+        }
+    }
+
+    /// Per-peer statistics of the state parts download of the given shard.
+    pub fn peers_view(&self, shard_id: ShardId) -> Vec<StateSyncPeerView> {
+        match &self.inner {
+            StateSyncInner::Peers { scheduler } => scheduler.view(shard_id),
+            StateSyncInner::PartsFromExternal { .. } => vec![],
+        }
+    }
+
     /// Checks if the header is downloaded.
     /// If the download is complete, then moves forward to `StateDownloadParts`,
     /// otherwise retries the header request.
@@ -955,6 +1014,7 @@ impl StateSync {
         &mut self,
         shard_id: ShardId,
         shard_sync_download: &mut ShardSyncDownload,
+        sync_hash: CryptoHash,
         now: DateTime<Utc>,
     ) -> (bool, bool, bool) {
         // Step 2 - download all the parts (each part is usually around 1MB).
@@ -965,11 +1025,18 @@ impl StateSync {
         let mut parts_done = true;
         let num_parts = shard_sync_download.downloads.len();
         let mut num_parts_done = 0;
-        for part_download in shard_sync_download.downloads.iter_mut() {
+        for (part_id, part_download) in shard_sync_download.downloads.iter_mut().enumerate() {
             if !part_download.done {
                 parts_done = false;
                 let prev = part_download.prev_update_time;
-                let part_timeout = now - prev > self.timeout; // Retry parts that failed.
+                let part_timeout = match &mut self.inner {
+                    // Peers which turned out to be fast get a shorter timeout.
+                    StateSyncInner::Peers { scheduler } => {
+                        scheduler.check_timeout(sync_hash, shard_id, part_id as u64, now)
+                            || now - prev > self.timeout
+                    }
+                    StateSyncInner::PartsFromExternal { .. } => now - prev > self.timeout,
+                }; // Retry parts that failed.
                 if part_timeout || part_download.error {
                     download_timeout |= part_timeout;
                     if part_timeout || part_download.last_target.is_some() {
@@ -1002,6 +1069,9 @@ impl StateSync {
             .set(num_parts as i64);
         // If all parts are done - we can move towards scheduling.
         if parts_done {
+            if let StateSyncInner::Peers { scheduler } = &mut self.inner {
+                scheduler.finish_shard(shard_id);
+            }
             *shard_sync_download = ShardSyncDownload {
                 downloads: vec![],
                 status: ShardSyncStatus::StateDownloadScheduling,
@@ -1167,9 +1237,11 @@ fn request_part_from_external_storage(
     epoch_id: &EpochId,
     epoch_height: EpochHeight,
     num_parts: u64,
+    state_root: StateRoot,
     chain_id: &str,
     semaphore: Arc<Semaphore>,
     external: ExternalConnection,
+    manifests: ManifestCache,
     state_parts_arbiter_handle: &ArbiterHandle,
     state_parts_mpsc_tx: Sender<StateSyncGetPartResult>,
 ) {
@@ -1182,12 +1254,24 @@ fn request_part_from_external_storage(
 
     let location =
         external_storage_location(chain_id, epoch_id, epoch_height, shard_id, part_id, num_parts);
+    let manifest_location =
+        external_storage_manifest_location(chain_id, epoch_id, epoch_height, shard_id);
 
     match semaphore.try_acquire_owned() {
         Ok(permit) => {
             if state_parts_arbiter_handle.spawn({
                 async move {
-                    let result = external.get_part(shard_id, &location).await;
+                    let result = get_verified_part_from_external_storage(
+                        &external,
+                        &manifests,
+                        shard_id,
+                        &manifest_location,
+                        &location,
+                        part_id,
+                        num_parts,
+                        &state_root,
+                    )
+                    .await;
                     match state_parts_mpsc_tx.send(StateSyncGetPartResult {
                         sync_hash,
                         shard_id,
@@ -1216,6 +1300,87 @@ fn request_part_from_external_storage(
     }
 }
 
+/// Returns the manifest of the state parts, downloading it if it's not cached yet.
+/// Returns `None` if the manifest doesn't exist, because dumps made before manifests were
+/// introduced don't have one. Failures to download the manifest are returned and not cached, so
+/// that the next attempt downloads it again.
+async fn get_manifest_from_external_storage(
+    external: &ExternalConnection,
+    manifests: &ManifestCache,
+    shard_id: ShardId,
+    manifest_location: &str,
+) -> Result<Option<Arc<StatePartsManifest>>, anyhow::Error> {
+    // The lock is only held to find the cell of the location, not while downloading.
+    let cell = {
+        let mut manifests = manifests.lock().await;
+        match manifests.get(manifest_location) {
+            Some(cell) => cell.clone(),
+            None => {
+                let cell = Arc::new(tokio::sync::OnceCell::new());
+                manifests.put(manifest_location.to_string(), cell.clone());
+                cell
+            }
+        }
+    };
+    let manifest = cell
+        .get_or_try_init(|| async {
+            match external.get_part(shard_id, manifest_location).await {
+                Ok(data) => Ok(Some(Arc::new(StatePartsManifest::from_bytes(&data)?))),
+                Err(err) if is_not_found_error(&err) => {
+                    tracing::warn!(target: "sync", %shard_id, manifest_location, "The manifest of state parts doesn't exist, state parts will not be verified");
+                    Ok(None)
+                }
+                Err(err) => Err(err),
+            }
+        })
+        .await?;
+    Ok(manifest.clone())
+}
+
+/// Downloads a state part from external storage and checks it against the manifest.
+/// Parts that don't match the manifest are downloaded again, up to `MAX_EXTERNAL_PART_ATTEMPTS`
+/// times in total. If there is no manifest, the part is returned unverified.
+async fn get_verified_part_from_external_storage(
+    external: &ExternalConnection,
+    manifests: &ManifestCache,
+    shard_id: ShardId,
+    manifest_location: &str,
+    location: &str,
+    part_id: u64,
+    num_parts: u64,
+    state_root: &StateRoot,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let Some(manifest) =
+        get_manifest_from_external_storage(external, manifests, shard_id, manifest_location)
+            .await?
+    else {
+        return external.get_part(shard_id, location).await;
+    };
+    let entry = match manifest.get_part_entry(state_root, part_id, num_parts) {
+        Ok(entry) => entry,
+        Err(err) => {
+            // The manifest may have been dumped by a misbehaving node. Download it again next time.
+            manifests.lock().await.pop(manifest_location);
+            return Err(err);
+        }
+    };
+    for attempt in 1..=MAX_EXTERNAL_PART_ATTEMPTS {
+        let data = external.get_part(shard_id, location).await?;
+        if entry.matches(&data) {
+            return Ok(data);
+        }
+        metrics::STATE_SYNC_EXTERNAL_PARTS_CORRUPTED
+            .with_label_values(&[&shard_id.to_string()])
+            .inc();
+        tracing::warn!(target: "sync", %shard_id, part_id, attempt, num_bytes = data.len(), expected_num_bytes = entry.size, "State part doesn't match the manifest");
+    }
+    Err(anyhow::anyhow!(
+        "State part {} doesn't match the manifest after {} attempts",
+        part_id,
+        MAX_EXTERNAL_PART_ATTEMPTS
+    ))
+}
+
 /// Asynchronously requests a state part from a suitable peer.
 fn request_part_from_peers(
     part_id: u64,
@@ -1250,25 +1415,6 @@ fn request_part_from_peers(
     );
 }
 
-fn sent_request_part(
-    target: AccountOrPeerIdOrHash,
-    part_id: u64,
-    shard_id: ShardId,
-    sync_hash: CryptoHash,
-    last_part_id_requested: &mut HashMap<(AccountOrPeerIdOrHash, ShardId), PendingRequestStatus>,
-    requested_target: &mut lru::LruCache<(u64, CryptoHash), AccountOrPeerIdOrHash>,
-    timeout: Duration,
-) {
-    // FIXME: something is wrong - the index should have a shard_id too.
-    requested_target.put((part_id, sync_hash), target.clone());
-    last_part_id_requested
-        .entry((target, shard_id))
-        .and_modify(|pending_request| {
-            pending_request.missing_parts += 1;
-        })
-        .or_insert_with(|| PendingRequestStatus::new(timeout));
-}
-
 /// Works around how data requests to external storage are done.
 /// This function investigates if the response is valid and updates `done` and `error` appropriately.
 /// If the response is successful, then also writes the state part to the DB.
@@ -1326,38 +1472,53 @@ fn process_part_response(
 }
 
 /// Create an abstract collection of elements to be shuffled.
-/// Each element will appear in the shuffled output exactly `limit` times.
+/// Each element will appear in the shuffled output exactly as many times as its `limit`.
 /// Use it as an iterator to access the shuffled collection.
 ///
 /// ```rust,ignore
-/// let sampler = SamplerLimited::new(vec![1, 2, 3], 2);
+/// let sampler = SamplerLimited::new(vec![1, 2, 3], vec![2, 1, 0]);
 ///
 /// let res = sampler.collect::<Vec<_>>();
 ///
-/// assert!(res.len() == 6);
+/// assert!(res.len() == 3);
 /// assert!(res.iter().filter(|v| v == 1).count() == 2);
-/// assert!(res.iter().filter(|v| v == 2).count() == 2);
-/// assert!(res.iter().filter(|v| v == 3).count() == 2);
+/// assert!(res.iter().filter(|v| v == 2).count() == 1);
+/// assert!(res.iter().filter(|v| v == 3).count() == 0);
 /// ```
 ///
-/// Out of the 90 possible values of `res` in the code above on of them is:
+/// Out of the 3 possible values of `res` in the code above on of them is:
 ///
 /// ```
-/// vec![1, 2, 1, 3, 3, 2];
+/// vec![1, 2, 1];
 /// ```
 struct SamplerLimited<T> {
     data: Vec<T>,
     limit: Vec<u64>,
+    /// Relative probabilities of sampling the elements. Uniform if empty.
+    weights: Vec<f64>,
 }
 
 impl<T> SamplerLimited<T> {
-    fn new(data: Vec<T>, limit: u64) -> Self {
-        if limit == 0 {
-            Self { data: vec![], limit: vec![] }
-        } else {
-            let len = data.len();
-            Self { data, limit: vec![limit; len] }
+    fn new(data: Vec<T>, limit: Vec<u64>) -> Self {
+        assert_eq!(data.len(), limit.len());
+        Self { data, limit, weights: vec![] }
+    }
+
+    /// Samples the elements with the given relative probabilities, one weight per element.
+    fn with_weights(mut self, weights: Vec<f64>) -> Self {
+        if !self.data.is_empty() {
+            assert_eq!(self.data.len(), weights.len());
+            self.weights = weights;
         }
+        self
+    }
+
+    fn remove(&mut self, ix: usize) -> T {
+        if !self.weights.is_empty() {
+            self.weights.swap_remove(ix);
+        }
+        self.limit.swap_remove(ix);
+        self.data.swap_remove(ix)
     }
 }
 
@@ -1365,23 +1526,25 @@ impl<T: Clone> Iterator for SamplerLimited<T> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.limit.is_empty() {
-            None
-        } else {
+        loop {
+            if self.limit.is_empty() {
+                return None;
+            }
             let len = self.limit.len();
-            let ix = thread_rng().gen_range(0..len);
-            self.limit[ix] -= 1;
-
-            if self.limit[ix] == 0 {
-                if ix + 1 != len {
-                    self.limit[ix] = self.limit[len - 1];
-                    self.data.swap(ix, len - 1);
+            let ix = match WeightedIndex::new(&self.weights) {
+                Ok(dist) => dist.sample(&mut thread_rng()),
+                Err(_) => thread_rng().gen_range(0..len),
+            };
+            match self.limit[ix] {
+                // Elements which must not appear at all.
+                0 => {
+                    self.remove(ix);
+                }
+                1 => return Some(self.remove(ix)),
+                _ => {
+                    self.limit[ix] -= 1;
+                    return Some(self.data[ix].clone());
                 }
-
-                self.limit.pop();
-                self.data.pop()
-            } else {
-                Some(self.data[ix].clone())
             }
         }
     }
@@ -1524,4 +1687,19 @@ mod test {
             System::current().stop()
         });
     }
+
+    #[test]
+    fn test_sampler_limited() {
+        let res: Vec<_> = SamplerLimited::new(vec![1, 2, 3], vec![2, 1, 0]).collect();
+        assert_eq!(res.len(), 3);
+        assert_eq!(res.iter().filter(|v| **v == 1).count(), 2);
+        assert_eq!(res.iter().filter(|v| **v == 2).count(), 1);
+
+        // Elements with no weight are sampled last.
+        let res: Vec<_> =
+            SamplerLimited::new(vec![1, 2], vec![3, 1]).with_weights(vec![1., 0.]).collect();
+        assert_eq!(res, vec![1, 1, 1, 2]);
+
+        assert_eq!(SamplerLimited::<u64>::new(vec![], vec![]).next(), None);
+    }
 }
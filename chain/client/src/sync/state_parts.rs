@@ -0,0 +1,374 @@
+//! Scheduling of the state part requests sent to the peers.
+//!
+//! Parts of a shard are spread across all the targets which can serve them. Every target has a
+//! limit of the parts in flight, which grows with every part it serves and is halved when a
+//! request times out or the target serves an invalid part. This way the fast targets get more
+//! parts to serve than the slow ones, and a single slow target can't stall the sync. The timeout
+//! of a request follows the observed latency of the target.
+use chrono::{DateTime, Duration, Utc};
+use near_network::types::AccountOrPeerIdOrHash;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::ShardId;
+use near_primitives::views::StateSyncPeerView;
+use std::collections::HashMap;
+
+/// Number of parts requested from a target at the same time, before we know how fast it is.
+const INITIAL_PARTS_IN_FLIGHT: u64 = 4;
+/// Maximum number of parts requested from a target at the same time.
+pub const MAX_PARTS_IN_FLIGHT: u64 = 16;
+/// Requests don't time out sooner than this, however fast the target usually is.
+const MIN_PART_TIMEOUT_MS: i64 = 5_000;
+/// A request times out if it takes this many times longer than the average one.
+const TIMEOUT_LATENCY_FACTOR: f64 = 4.;
+/// Weight of the latest sample in the average latency and throughput.
+const SMOOTHING: f64 = 0.2;
+
+/// Download statistics of a single target.
+#[derive(Debug)]
+struct TargetStats {
+    in_flight: u64,
+    max_in_flight: u64,
+    parts_received: u64,
+    bytes_received: u64,
+    failures: u64,
+    /// Exponential moving average of the time it takes to receive a part.
+    avg_latency_ms: Option<f64>,
+    /// Exponential moving average of the download speed, in bytes per second.
+    throughput: Option<f64>,
+}
+
+impl TargetStats {
+    fn new() -> Self {
+        Self {
+            in_flight: 0,
+            max_in_flight: INITIAL_PARTS_IN_FLIGHT,
+            parts_received: 0,
+            bytes_received: 0,
+            failures: 0,
+            avg_latency_ms: None,
+            throughput: None,
+        }
+    }
+
+    fn success(&mut self, bytes: usize, latency: Duration) {
+        let latency_ms = latency.num_milliseconds().max(1) as f64;
+        let throughput = bytes as f64 * 1000. / latency_ms;
+        self.parts_received += 1;
+        self.bytes_received += bytes as u64;
+        self.avg_latency_ms = Some(smooth(self.avg_latency_ms, latency_ms));
+        self.throughput = Some(smooth(self.throughput, throughput));
+        self.max_in_flight = (self.max_in_flight + 1).min(MAX_PARTS_IN_FLIGHT);
+    }
+
+    fn failure(&mut self) {
+        self.failures += 1;
+        self.max_in_flight = (self.max_in_flight / 2).max(1);
+    }
+}
+
+fn smooth(avg: Option<f64>, sample: f64) -> f64 {
+    match avg {
+        None => sample,
+        Some(avg) => avg * (1. - SMOOTHING) + sample * SMOOTHING,
+    }
+}
+
+#[derive(Debug)]
+struct InFlightPart {
+    target: AccountOrPeerIdOrHash,
+    sent_at: DateTime<Utc>,
+}
+
+/// Decides which targets to request the state parts from.
+pub struct StatePartsScheduler {
+    /// Requests always time out after at most this.
+    max_timeout: Duration,
+    targets: HashMap<(ShardId, AccountOrPeerIdOrHash), TargetStats>,
+    in_flight: HashMap<(CryptoHash, ShardId, u64), InFlightPart>,
+}
+
+impl StatePartsScheduler {
+    pub fn new(max_timeout: Duration) -> Self {
+        Self { max_timeout, targets: HashMap::new(), in_flight: HashMap::new() }
+    }
+
+    /// Number of parts of the shard which may be requested from the target at the moment.
+    pub fn free_slots(&self, shard_id: ShardId, target: &AccountOrPeerIdOrHash) -> u64 {
+        match self.targets.get(&(shard_id, target.clone())) {
+            Some(stats) => stats.max_in_flight.saturating_sub(stats.in_flight),
+            None => INITIAL_PARTS_IN_FLIGHT,
+        }
+    }
+
+    fn stats_mut(&mut self, shard_id: ShardId, target: &AccountOrPeerIdOrHash) -> &mut TargetStats {
+        self.targets.entry((shard_id, target.clone())).or_insert_with(TargetStats::new)
+    }
+
+    /// Whether more parts of the shard may be requested from the target.
+    pub fn has_capacity(&self, shard_id: ShardId, target: &AccountOrPeerIdOrHash) -> bool {
+        self.free_slots(shard_id, target) > 0
+    }
+
+    /// Records that the part was requested from the target.
+    pub fn sent(
+        &mut self,
+        sync_hash: CryptoHash,
+        shard_id: ShardId,
+        part_id: u64,
+        target: AccountOrPeerIdOrHash,
+        now: DateTime<Utc>,
+    ) {
+        // The part may be requested again, e.g. if the previous target turned out to be unreachable.
+        self.release(sync_hash, shard_id, part_id);
+        self.stats_mut(shard_id, &target).in_flight += 1;
+        self.in_flight
+            .insert((sync_hash, shard_id, part_id), InFlightPart { target, sent_at: now });
+    }
+
+    /// Forgets the request of the part, without affecting the statistics of the target.
+    /// Returns the request, if the part was requested.
+    pub fn release(
+        &mut self,
+        sync_hash: CryptoHash,
+        shard_id: ShardId,
+        part_id: u64,
+    ) -> Option<InFlightPart> {
+        let part = self.in_flight.remove(&(sync_hash, shard_id, part_id))?;
+        let stats = self.stats_mut(shard_id, &part.target);
+        stats.in_flight = stats.in_flight.saturating_sub(1);
+        Some(part)
+    }
+
+    /// Records that the requested part arrived, and whether it was valid.
+    /// Returns the target which the part was requested from, if it was requested.
+    pub fn received(
+        &mut self,
+        sync_hash: CryptoHash,
+        shard_id: ShardId,
+        part_id: u64,
+        bytes: usize,
+        valid: bool,
+        now: DateTime<Utc>,
+    ) -> Option<AccountOrPeerIdOrHash> {
+        let part = self.release(sync_hash, shard_id, part_id)?;
+        let stats = self.stats_mut(shard_id, &part.target);
+        if valid {
+            stats.success(bytes, now - part.sent_at);
+        } else {
+            stats.failure();
+        }
+        Some(part.target)
+    }
+
+    /// How long to wait for a part from the target.
+    fn timeout(&self, shard_id: ShardId, target: &AccountOrPeerIdOrHash) -> Duration {
+        let avg_latency_ms =
+            self.targets.get(&(shard_id, target.clone())).and_then(|stats| stats.avg_latency_ms);
+        match avg_latency_ms {
+            Some(ms) => Duration::milliseconds(
+                ((ms * TIMEOUT_LATENCY_FACTOR) as i64).max(MIN_PART_TIMEOUT_MS),
+            )
+            .min(self.max_timeout),
+            None => self.max_timeout,
+        }
+    }
+
+    /// Checks whether the request of the part timed out, in which case the request is forgotten
+    /// and the target gets fewer parts to serve.
+    pub fn check_timeout(
+        &mut self,
+        sync_hash: CryptoHash,
+        shard_id: ShardId,
+        part_id: u64,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let Some(part) = self.in_flight.get(&(sync_hash, shard_id, part_id)) else {
+            return false;
+        };
+        if now - part.sent_at <= self.timeout(shard_id, &part.target) {
+            return false;
+        }
+        if let Some(part) = self.release(sync_hash, shard_id, part_id) {
+            self.stats_mut(shard_id, &part.target).failure();
+        }
+        true
+    }
+
+    /// Forgets the requests for other sync hashes, as their responses will be ignored.
+    pub fn retain_sync_hash(&mut self, sync_hash: CryptoHash) {
+        let stale: Vec<_> =
+            self.in_flight.keys().filter(|(hash, _, _)| hash != &sync_hash).cloned().collect();
+        for (hash, shard_id, part_id) in stale {
+            self.release(hash, shard_id, part_id);
+        }
+    }
+
+    /// Forgets the statistics of the idle targets of the shard which are no longer among the
+    /// `targets`, e.g. peers which disconnected.
+    pub fn retain_targets(&mut self, shard_id: ShardId, targets: &[AccountOrPeerIdOrHash]) {
+        self.targets.retain(|(id, target), stats| {
+            *id != shard_id || stats.in_flight > 0 || targets.contains(target)
+        });
+    }
+
+    /// Forgets the requests and the statistics of the targets of the shard, once all of its parts
+    /// are downloaded.
+    pub fn finish_shard(&mut self, shard_id: ShardId) {
+        self.in_flight.retain(|(_, id, _), _| *id != shard_id);
+        self.targets.retain(|(id, _), _| *id != shard_id);
+    }
+
+    /// Download statistics of the targets of the shard, the fastest first.
+    pub fn view(&self, shard_id: ShardId) -> Vec<StateSyncPeerView> {
+        let mut views: Vec<_> = (self.targets.iter())
+            .filter(|((id, _), _)| *id == shard_id)
+            .map(|((_, target), stats)| StateSyncPeerView {
+                target: format_target(target),
+                parts_in_flight: stats.in_flight,
+                max_parts_in_flight: stats.max_in_flight,
+                parts_received: stats.parts_received,
+                bytes_received: stats.bytes_received,
+                failures: stats.failures,
+                avg_latency_ms: stats.avg_latency_ms.map(|ms| ms as u64),
+                throughput_bytes_per_sec: stats.throughput.map_or(0, |t| t as u64),
+            })
+            .collect();
+        views.sort_by_key(|view| std::cmp::Reverse(view.throughput_bytes_per_sec));
+        views
+    }
+}
+
+fn format_target(target: &AccountOrPeerIdOrHash) -> String {
+    match target {
+        AccountOrPeerIdOrHash::AccountId(account_id) => account_id.to_string(),
+        AccountOrPeerIdOrHash::PeerId(peer_id) => peer_id.to_string(),
+        AccountOrPeerIdOrHash::Hash(hash) => hash.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, SecretKey};
+    use near_primitives::network::PeerId;
+
+    fn target(seed: &str) -> AccountOrPeerIdOrHash {
+        AccountOrPeerIdOrHash::PeerId(PeerId::new(
+            SecretKey::from_seed(KeyType::ED25519, seed).public_key(),
+        ))
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(chrono::NaiveDateTime::from_timestamp_opt(1, 0).unwrap(), Utc)
+    }
+
+    #[test]
+    fn limits_parts_in_flight() {
+        let sync_hash = CryptoHash::default();
+        let targets = [target("a"), target("b")];
+        let mut scheduler = StatePartsScheduler::new(Duration::seconds(60));
+        for part_id in 0..INITIAL_PARTS_IN_FLIGHT {
+            assert_eq!(INITIAL_PARTS_IN_FLIGHT - part_id, scheduler.free_slots(0, &targets[0]));
+            scheduler.sent(sync_hash, 0, part_id, targets[0].clone(), now());
+        }
+        // The first target got the initial number of parts and no more.
+        assert!(!scheduler.has_capacity(0, &targets[0]));
+        assert_eq!(INITIAL_PARTS_IN_FLIGHT, scheduler.free_slots(0, &targets[1]));
+        // Other shards are scheduled independently.
+        assert!(scheduler.has_capacity(1, &targets[0]));
+        scheduler.release(sync_hash, 0, 0);
+        assert_eq!(1, scheduler.free_slots(0, &targets[0]));
+    }
+
+    #[test]
+    fn adapts_to_throughput() {
+        let sync_hash = CryptoHash::default();
+        let fast = target("fast");
+        let slow = target("slow");
+        let mut scheduler = StatePartsScheduler::new(Duration::seconds(60));
+        for part_id in 0..4 {
+            scheduler.sent(sync_hash, 0, part_id, fast.clone(), now());
+            let received = now() + Duration::milliseconds(100);
+            assert_eq!(
+                Some(fast.clone()),
+                scheduler.received(sync_hash, 0, part_id, 1000, true, received)
+            );
+        }
+        scheduler.sent(sync_hash, 0, 4, slow.clone(), now());
+        // The timeout of the fast target follows its latency, the slow one waits for the maximum.
+        assert!(scheduler.check_timeout(sync_hash, 0, 4, now() + Duration::seconds(61)));
+        scheduler.sent(sync_hash, 0, 5, fast.clone(), now());
+        assert!(!scheduler.check_timeout(sync_hash, 0, 5, now() + Duration::seconds(4)));
+        assert!(scheduler.check_timeout(sync_hash, 0, 5, now() + Duration::seconds(6)));
+        // A timed out request is forgotten, a late response doesn't count.
+        assert_eq!(None, scheduler.received(sync_hash, 0, 5, 1000, true, now()));
+
+        let view = scheduler.view(0);
+        assert_eq!(2, view.len());
+        assert_eq!(format_target(&fast), view[0].target);
+        assert_eq!(4, view[0].parts_received);
+        assert_eq!(10_000, view[0].throughput_bytes_per_sec);
+        assert_eq!(1, view[0].failures);
+        // Grew to 8 by the served parts, halved by the failure.
+        assert_eq!(4, view[0].max_parts_in_flight);
+        assert_eq!(0, view[0].parts_in_flight);
+        assert_eq!(2, view[1].max_parts_in_flight);
+    }
+
+    #[test]
+    fn invalid_part() {
+        let sync_hash = CryptoHash::default();
+        let t = target("a");
+        let mut scheduler = StatePartsScheduler::new(Duration::seconds(60));
+        scheduler.sent(sync_hash, 0, 0, t.clone(), now());
+        assert_eq!(Some(t.clone()), scheduler.received(sync_hash, 0, 0, 1000, false, now()));
+        let view = scheduler.view(0);
+        assert_eq!(1, view[0].failures);
+        assert_eq!(0, view[0].parts_received);
+        assert_eq!(INITIAL_PARTS_IN_FLIGHT / 2, view[0].max_parts_in_flight);
+    }
+
+    #[test]
+    fn retain_sync_hash() {
+        let old_hash = CryptoHash::hash_bytes(b"old");
+        let new_hash = CryptoHash::hash_bytes(b"new");
+        let t = target("a");
+        let mut scheduler = StatePartsScheduler::new(Duration::seconds(60));
+        scheduler.sent(old_hash, 0, 0, t.clone(), now());
+        scheduler.sent(new_hash, 0, 0, t.clone(), now());
+        // Requesting a part again replaces the previous request.
+        scheduler.sent(new_hash, 0, 0, t.clone(), now());
+        assert_eq!(2, scheduler.view(0)[0].parts_in_flight);
+        scheduler.retain_sync_hash(new_hash);
+        assert_eq!(1, scheduler.view(0)[0].parts_in_flight);
+        assert_eq!(0, scheduler.view(0)[0].failures);
+    }
+
+    #[test]
+    fn forgets_targets() {
+        let sync_hash = CryptoHash::default();
+        let (busy, idle, alive) = (target("busy"), target("idle"), target("alive"));
+        let mut scheduler = StatePartsScheduler::new(Duration::seconds(60));
+        scheduler.sent(sync_hash, 0, 0, busy.clone(), now());
+        scheduler.sent(sync_hash, 0, 1, idle.clone(), now());
+        scheduler.received(sync_hash, 0, 1, 1000, true, now());
+        scheduler.sent(sync_hash, 0, 2, alive.clone(), now());
+        scheduler.received(sync_hash, 0, 2, 1000, true, now());
+        scheduler.sent(sync_hash, 1, 0, idle.clone(), now());
+        scheduler.received(sync_hash, 1, 0, 1000, true, now());
+
+        // Idle targets which disappeared are forgotten, the busy ones wait for their parts.
+        scheduler.retain_targets(0, &[alive.clone()]);
+        let mut view: Vec<_> = scheduler.view(0).into_iter().map(|view| view.target).collect();
+        view.sort();
+        let mut expected = vec![format_target(&busy), format_target(&alive)];
+        expected.sort();
+        assert_eq!(expected, view);
+        assert_eq!(1, scheduler.view(1).len());
+
+        scheduler.finish_shard(0);
+        assert!(scheduler.view(0).is_empty());
+        assert!(!scheduler.check_timeout(sync_hash, 0, 0, now() + Duration::seconds(61)));
+        assert_eq!(1, scheduler.view(1).len());
+    }
+}
@@ -743,6 +743,10 @@ pub fn setup_mock_all_validators(
                                 },
                                 received_bytes_per_sec: 0,
                                 sent_bytes_per_sec: 0,
+                                received_uncompressed_bytes_per_sec: 0,
+                                received_compressed_bytes_per_sec: 0,
+                                sent_uncompressed_bytes_per_sec: 0,
+                                sent_compressed_bytes_per_sec: 0,
                                 last_time_peer_requested: near_async::time::Instant::now(),
                                 last_time_received_message: near_async::time::Instant::now(),
                                 connection_established_time: near_async::time::Instant::now(),
@@ -817,7 +821,7 @@ pub fn setup_mock_all_validators(
                                 route_back,
                                 drop_chunks,
                                 |c| {
-                                    c.send(ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse { partial_encoded_chunk_response: response.clone(), received_time: Instant::now() });
+                                    c.send(ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse { partial_encoded_chunk_response: response.clone(), responder: PeerId::random(), received_time: Instant::now() });
                                 },
                             );
                         }
@@ -1062,6 +1066,7 @@ pub fn setup_mock_all_validators(
                         }
                         NetworkRequests::ForwardTx(_, _)
                         | NetworkRequests::BanPeer { .. }
+                        | NetworkRequests::ReportPeer { .. }
                         | NetworkRequests::TxStatus(_, _, _)
                         | NetworkRequests::Challenge(_) => {}
                     };
@@ -1592,6 +1597,11 @@ impl TestEnvBuilder {
     ///
     /// Note that in order to track *NO* shards, just don't override shard_trackers.
     pub fn track_all_shards(self) -> Self {
+        self.tracked_config(TrackedConfig::AllShards)
+    }
+
+    /// Constructs ShardTracker with the given config for each instance.
+    pub fn tracked_config(self, tracked_config: TrackedConfig) -> Self {
         let ret = self.ensure_epoch_managers();
         let shard_trackers = ret
             .epoch_managers
@@ -1599,7 +1609,7 @@ impl TestEnvBuilder {
             .unwrap()
             .iter()
             .map(|epoch_manager| {
-                ShardTracker::new(TrackedConfig::AllShards, epoch_manager.clone().into_adapter())
+                ShardTracker::new(tracked_config.clone(), epoch_manager.clone().into_adapter())
             })
             .collect();
         ret.shard_trackers(shard_trackers)
@@ -1949,6 +1959,7 @@ impl TestEnv {
                 self.shards_manager_adapters[id].send(
                     ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse {
                         partial_encoded_chunk_response: response,
+                        responder: PeerId::random(),
                         received_time: Instant::now(),
                     },
                 );
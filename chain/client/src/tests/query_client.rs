@@ -7,12 +7,13 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::adapter::{BlockResponse, ProcessTxRequest, ProcessTxResponse, StateRequestHeader};
-use crate::test_utils::{setup_mock_all_validators, setup_no_network, setup_only_view};
+use crate::test_utils::{setup_mock_all_validators, setup_no_network, setup_only_view, TestEnv};
 use crate::{
-    GetBlock, GetBlockWithMerkleTree, GetExecutionOutcomesForBlock, Query, QueryError, Status,
-    TxStatus,
+    GetBlock, GetBlockWithMerkleTree, GetExecutionOutcomesForBlock, GetStateChangesInBlock, Query,
+    QueryError, Status, TxStatus, ViewClientActor,
 };
 use near_actix_test_utils::run_actix;
+use near_chain::ChainGenesis;
 use near_chain_configs::DEFAULT_GC_NUM_EPOCHS_TO_KEEP;
 use near_crypto::{InMemorySigner, KeyType};
 use near_network::test_utils::MockPeerManagerAdapter;
@@ -21,15 +22,23 @@ use near_network::types::{
     NetworkRequests, NetworkResponses, PeerManagerMessageRequest, PeerManagerMessageResponse,
 };
 
+use borsh::BorshSerialize;
 use chrono::Utc;
+use near_client_primitives::types::GetStateChangesError;
 use near_o11y::testonly::init_test_logger;
-use near_o11y::WithSpanContextExt;
+use near_o11y::{WithSpanContext, WithSpanContextExt};
+use near_primitives::account::Account;
 use near_primitives::block::{Block, BlockHeader};
+use near_primitives::hash::CryptoHash;
 use near_primitives::transaction::SignedTransaction;
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{BlockId, BlockReference, EpochId};
 use near_primitives::utils::to_timestamp;
 use near_primitives::version::PROTOCOL_VERSION;
 use near_primitives::views::{QueryRequest, QueryResponseKind};
+use near_store::retention;
+use near_store::state_history::{self, StateHistoryStatus};
+use near_store::DBCol;
 use num_rational::Ratio;
 
 /// Query account from view client
@@ -390,3 +399,134 @@ fn test_garbage_collection() {
         near_network::test_utils::wait_or_panic(block_prod_time * target_height * 2 + 2000);
     })
 }
+
+/// Starts a view client saving the state history, whose index disagrees with the state about the
+/// accounts at genesis, to tell where the answers come from.
+fn start_state_history_view_client(env: &TestEnv) -> actix::Addr<ViewClientActor> {
+    let client = &env.clients[0];
+    let account = Account::new(42, 0, CryptoHash::default(), 100);
+    let mut store_update = client.chain.store().store().store_update();
+    state_history::set_value(
+        &mut store_update,
+        &TrieKey::Account { account_id: "test0".parse().unwrap() }.to_vec(),
+        0,
+        Some(&account.try_to_vec().unwrap()),
+    )
+    .unwrap();
+    state_history::set_status(
+        &mut store_update,
+        &StateHistoryStatus { start_height: 0, head_height: 0 },
+    )
+    .unwrap();
+    store_update.commit().unwrap();
+
+    let mut config = client.config.clone();
+    config.save_state_history = true;
+    crate::start_view_client(
+        None,
+        env.chain_genesis.clone(),
+        client.epoch_manager.clone(),
+        client.shard_tracker.clone(),
+        client.runtime_adapter.clone(),
+        Arc::new(MockPeerManagerAdapter::default()).into(),
+        config,
+        crate::adversarial::Controls::default(),
+    )
+}
+
+fn view_account_at_genesis(account_id: &str) -> WithSpanContext<Query> {
+    Query::new(
+        BlockReference::BlockId(BlockId::Height(0)),
+        QueryRequest::ViewAccount { account_id: account_id.parse().unwrap() },
+    )
+    .with_span_context()
+}
+
+/// Queries about the blocks covered by the state history index are answered from the index.
+#[test]
+fn query_state_history() {
+    init_test_logger();
+    run_actix(async {
+        let env = TestEnv::builder(ChainGenesis::test()).track_all_shards().build();
+        let view_client = start_state_history_view_client(&env);
+        actix::spawn(async move {
+            let response =
+                view_client.send(view_account_at_genesis("test0")).await.unwrap().unwrap();
+            match response.kind {
+                QueryResponseKind::ViewAccount(view) => assert_eq!(view.amount, 42),
+                kind => panic!("Invalid response {:?}", kind),
+            }
+            // Accounts without values in the index don't exist, whatever the state says.
+            let response = view_client.send(view_account_at_genesis("test1")).await.unwrap();
+            assert!(matches!(response, Err(QueryError::UnknownAccount { .. })), "{:?}", response);
+            System::current().stop();
+        });
+    });
+}
+
+/// Queries about the shards the node doesn't track aren't answered from the state history index,
+/// which doesn't have their changes.
+#[test]
+fn query_state_history_untracked_shard() {
+    init_test_logger();
+    run_actix(async {
+        let env = TestEnv::builder(ChainGenesis::test()).build();
+        let view_client = start_state_history_view_client(&env);
+        actix::spawn(async move {
+            for account_id in ["test0", "test1"] {
+                let response = view_client.send(view_account_at_genesis(account_id)).await.unwrap();
+                // The genesis state of all the shards is available, so the answers come from it.
+                match response.unwrap().kind {
+                    QueryResponseKind::ViewAccount(view) => assert_ne!(view.amount, 42),
+                    kind => panic!("Invalid response {:?}", kind),
+                }
+            }
+            System::current().stop();
+        });
+    });
+}
+
+/// Queries about the state changes deleted by the retention of archival nodes fail with a
+/// dedicated error.
+#[test]
+fn query_pruned_state_changes() {
+    init_test_logger();
+    run_actix(async {
+        let mut env = TestEnv::builder(ChainGenesis::test()).archive(true).build();
+        for height in 1..=3 {
+            env.produce_block(0, height);
+        }
+        let client = &env.clients[0];
+        let block_hash =
+            |height| *client.chain.get_block_by_height(height).unwrap().header().hash();
+        let (pruned_hash, retained_hash) = (block_hash(1), block_hash(2));
+        let mut store_update = client.chain.store().store().store_update();
+        retention::set_tail(&mut store_update, DBCol::StateChanges, 2).unwrap();
+        store_update.commit().unwrap();
+
+        let view_client = crate::start_view_client(
+            None,
+            env.chain_genesis.clone(),
+            client.epoch_manager.clone(),
+            client.shard_tracker.clone(),
+            client.runtime_adapter.clone(),
+            Arc::new(MockPeerManagerAdapter::default()).into(),
+            client.config.clone(),
+            crate::adversarial::Controls::default(),
+        );
+        actix::spawn(async move {
+            let query = |block_hash| GetStateChangesInBlock { block_hash }.with_span_context();
+            let response = view_client.send(query(pruned_hash)).await.unwrap();
+            assert!(
+                matches!(
+                    response,
+                    Err(GetStateChangesError::PrunedData { block_height: 1, retention_tail: 2 })
+                ),
+                "{:?}",
+                response
+            );
+            view_client.send(query(retained_hash)).await.unwrap().unwrap();
+            System::current().stop();
+        });
+    });
+}
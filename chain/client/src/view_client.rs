@@ -10,6 +10,7 @@ use crate::{
     GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered,
 };
 use actix::{Actor, Addr, Handler, SyncArbiter, SyncContext};
+use borsh::BorshDeserialize;
 use near_async::messaging::CanSend;
 use near_chain::types::{RuntimeAdapter, Tip};
 use near_chain::{
@@ -20,12 +21,12 @@ use near_chain_primitives::error::EpochErrorResultToChainError;
 use near_client_primitives::types::{
     Error, GetBlock, GetBlockError, GetBlockProof, GetBlockProofError, GetBlockProofResponse,
     GetBlockWithMerkleTree, GetChunkError, GetExecutionOutcome, GetExecutionOutcomeError,
-    GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError, GetMaintenanceWindows,
-    GetMaintenanceWindowsError, GetNextLightClientBlockError, GetProtocolConfig,
-    GetProtocolConfigError, GetReceipt, GetReceiptError, GetSplitStorageInfo,
+    GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError, GetHeadNotification,
+    GetMaintenanceWindows, GetMaintenanceWindowsError, GetNextLightClientBlockError,
+    GetProtocolConfig, GetProtocolConfigError, GetReceipt, GetReceiptError, GetSplitStorageInfo,
     GetSplitStorageInfoError, GetStateChangesError, GetStateChangesWithCauseInBlock,
-    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
-    TxStatus, TxStatusError,
+    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, HeadNotification,
+    Query, QueryError, SimulateTransaction, SimulateTransactionError, TxStatus, TxStatusError,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
@@ -35,6 +36,7 @@ use near_network::types::{
 };
 use near_o11y::{handler_debug_span, OpenTelemetrySpanExt, WithSpanContext, WithSpanContextExt};
 use near_performance_metrics_macros::perf;
+use near_primitives::account::{AccessKey, Account};
 use near_primitives::block::{Block, BlockHeader};
 use near_primitives::epoch_manager::epoch_info::EpochInfo;
 use near_primitives::hash::CryptoHash;
@@ -47,6 +49,7 @@ use near_primitives::syncing::{
     ShardStateSyncResponse, ShardStateSyncResponseHeader, ShardStateSyncResponseV1,
     ShardStateSyncResponseV2,
 };
+use near_primitives::trie_key::{trie_key_parsers, TrieKey};
 use near_primitives::types::{
     AccountId, BlockHeight, BlockId, BlockReference, EpochReference, Finality, MaybeBlockId,
     ShardId, SyncCheckpoint, TransactionOrReceiptId, ValidatorInfoIdentifier,
@@ -55,12 +58,13 @@ use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
     BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
     FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, GasPriceView, LightClientBlockView,
-    MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView, SplitStorageInfoView,
-    StateChangesKindsView, StateChangesView,
+    MaintenanceWindowsView, QueryRequest, QueryResponse, QueryResponseKind, ReceiptView,
+    SplitStorageInfoView, StateChangesKindsView, StateChangesView, StateItem,
+    TransactionSimulationView, ViewStateResult,
 };
-use near_store::{DBCol, COLD_HEAD_KEY, FINAL_HEAD_KEY, HEAD_KEY};
+use near_store::{retention, state_history, DBCol, COLD_HEAD_KEY, FINAL_HEAD_KEY, HEAD_KEY};
 use std::cmp::Ordering;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::Hash;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
@@ -72,6 +76,10 @@ const QUERY_REQUEST_LIMIT: usize = 500;
 const REQUEST_WAIT_TIME: u64 = 1000;
 
 const POISONED_LOCK_ERR: &str = "The lock was poisoned.";
+/// Max number of final blocks included in a single head notification, to bound the work done when
+/// finality jumps far ahead, e.g. after the node catches up. The remaining final blocks are
+/// included in the following notifications.
+const MAX_NEW_FINAL_BLOCKS_PER_NOTIFICATION: usize = 100;
 
 /// Request and response manager across all instances of ViewClientActor.
 pub struct ViewClientRequestManager {
@@ -330,6 +338,13 @@ impl ViewClientActor {
             .epoch_manager
             .account_id_to_shard_id(account_id, header.epoch_id())
             .map_err(|err| QueryError::InternalError { error_message: err.to_string() })?;
+
+        if self.config.save_state_history {
+            if let Some(response) = self.query_state_history(&header, shard_id, &msg.request)? {
+                return Ok(response);
+            }
+        }
+
         let shard_uid = self
             .epoch_manager
             .shard_id_to_uid(shard_id, header.epoch_id())
@@ -417,6 +432,168 @@ impl ViewClientActor {
         }
     }
 
+    fn handle_simulate_transaction(
+        &mut self,
+        msg: SimulateTransaction,
+    ) -> Result<TransactionSimulationView, SimulateTransactionError> {
+        let header = match self.get_block_header_by_reference(&msg.block_reference) {
+            Ok(Some(header)) => header,
+            Ok(None) => return Err(SimulateTransactionError::NoSyncedBlocks),
+            Err(near_chain::near_chain_primitives::Error::DBNotFoundErr(_)) => {
+                return Err(SimulateTransactionError::UnknownBlock {
+                    block_reference: msg.block_reference,
+                })
+            }
+            Err(err) => {
+                return Err(SimulateTransactionError::InternalError {
+                    error_message: err.to_string(),
+                })
+            }
+        };
+        let internal_error = |err: &dyn std::fmt::Display| {
+            SimulateTransactionError::InternalError { error_message: err.to_string() }
+        };
+
+        // Receipts may touch any shard, so the state of all of them is needed.
+        let shard_layout = self
+            .epoch_manager
+            .get_shard_layout(header.epoch_id())
+            .map_err(|e| internal_error(&e))?;
+        let mut state_roots = vec![];
+        for shard_uid in shard_layout.get_shard_uids() {
+            let chunk_extra = match self.chain.get_chunk_extra(header.hash(), &shard_uid) {
+                Ok(chunk_extra) => chunk_extra,
+                Err(near_chain::near_chain_primitives::Error::DBNotFoundErr(_)) => {
+                    let tip = self.chain.head().map_err(|e| internal_error(&e))?;
+                    let gc_stop_height = self.runtime.get_gc_stop_height(&tip.last_block_hash);
+                    return Err(if !self.config.archive && header.height() < gc_stop_height {
+                        SimulateTransactionError::GarbageCollectedBlock {
+                            block_height: header.height(),
+                            block_hash: *header.hash(),
+                        }
+                    } else {
+                        SimulateTransactionError::UnavailableShard {
+                            requested_shard_id: shard_uid.shard_id(),
+                        }
+                    });
+                }
+                Err(err) => return Err(internal_error(&err)),
+            };
+            state_roots.push(*chunk_extra.state_root());
+        }
+
+        match self.runtime.simulate_transaction(
+            &state_roots,
+            header.height(),
+            header.raw_timestamp(),
+            header.prev_hash(),
+            header.hash(),
+            header.epoch_id(),
+            header.gas_price(),
+            &msg.signed_transaction,
+            msg.verify_signature,
+            msg.max_blocks,
+        ) {
+            Ok(Ok(simulation)) => Ok(simulation),
+            Ok(Err(error)) => Err(SimulateTransactionError::InvalidTransaction { error }),
+            Err(err) => Err(internal_error(&err)),
+        }
+    }
+
+    /// Answers the query from the state history index, if the index covers the block and the
+    /// request doesn't need the trie.
+    fn query_state_history(
+        &self,
+        header: &BlockHeader,
+        shard_id: ShardId,
+        request: &QueryRequest,
+    ) -> Result<Option<QueryResponse>, QueryError> {
+        let internal_error =
+            |err: std::io::Error| QueryError::InternalError { error_message: err.to_string() };
+        let store = self.chain.store().store();
+        let block_height = header.height();
+        let block_hash = *header.hash();
+        match state_history::get_status(store).map_err(internal_error)? {
+            Some(status) if status.contains(block_height) => {}
+            _ => return Ok(None),
+        }
+        // The index follows the canonical chain only.
+        match self.chain.get_block_hash_by_height(block_height) {
+            Ok(hash) if hash == block_hash => {}
+            _ => return Ok(None),
+        }
+        // The index only has the changes of the tracked shards, in which the accounts of the other
+        // shards would look missing. Those are left to the trie, which reports them as unavailable.
+        if !self.shard_tracker.care_about_shard(
+            self.validator_account_id.as_ref(),
+            header.prev_hash(),
+            shard_id,
+            true,
+        ) {
+            return Ok(None);
+        }
+
+        let get_account = |account_id: &AccountId| -> Result<Account, QueryError> {
+            let key = TrieKey::Account { account_id: account_id.clone() }.to_vec();
+            match state_history::get_value(store, &key, block_height).map_err(internal_error)? {
+                Some(value) => Account::try_from_slice(&value).map_err(internal_error),
+                None => Err(QueryError::UnknownAccount {
+                    requested_account_id: account_id.clone(),
+                    block_height,
+                    block_hash,
+                }),
+            }
+        };
+        let kind = match request {
+            QueryRequest::ViewAccount { account_id } => {
+                QueryResponseKind::ViewAccount(get_account(account_id)?.into())
+            }
+            QueryRequest::ViewAccessKey { account_id, public_key } => {
+                let key = TrieKey::AccessKey {
+                    account_id: account_id.clone(),
+                    public_key: public_key.clone(),
+                }
+                .to_vec();
+                match state_history::get_value(store, &key, block_height).map_err(internal_error)? {
+                    Some(value) => QueryResponseKind::AccessKey(
+                        AccessKey::try_from_slice(&value).map_err(internal_error)?.into(),
+                    ),
+                    None => {
+                        return Err(QueryError::UnknownAccessKey {
+                            public_key: public_key.clone(),
+                            block_height,
+                            block_hash,
+                        })
+                    }
+                }
+            }
+            QueryRequest::ViewState { account_id, prefix, include_proof: false } => {
+                let account = get_account(account_id)?;
+                // Leave the accounts which may exceed the limit on the size of the viewable state
+                // to the trie viewer, which checks it precisely.
+                if let Some(limit) = self.config.trie_viewer_state_size_limit {
+                    if account.storage_usage() > limit {
+                        return Ok(None);
+                    }
+                }
+                let prefix: &[u8] = prefix.as_ref();
+                let query = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix);
+                let acc_sep_len = query.len() - prefix.len();
+                let values = state_history::get_values_by_prefix(store, &query, block_height)
+                    .map_err(internal_error)?
+                    .into_iter()
+                    .map(|(key, value)| StateItem {
+                        key: key[acc_sep_len..].to_vec().into(),
+                        value: value.into(),
+                    })
+                    .collect();
+                QueryResponseKind::ViewState(ViewStateResult { values, proof: vec![] })
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(QueryResponse { kind, block_height, block_hash }))
+    }
+
     fn get_tx_status(
         &mut self,
         tx_hash: CryptoHash,
@@ -510,6 +687,25 @@ impl ViewClientActor {
             .map_err(|e| e.into())
     }
 
+    /// Returns an error if the state changes of the block have been deleted because
+    /// the node keeps them only for the recent epochs.
+    fn check_state_changes_retained(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<(), GetStateChangesError> {
+        let retention_tail =
+            retention::get_tail(self.chain.store().store(), DBCol::StateChanges)
+                .map_err(|err| GetStateChangesError::IOError { error_message: err.to_string() })?;
+        let Some(retention_tail) = retention_tail else {
+            return Ok(());
+        };
+        let block_height = self.chain.get_block_header(block_hash)?.height();
+        if block_height < retention_tail {
+            return Err(GetStateChangesError::PrunedData { block_height, retention_tail });
+        }
+        Ok(())
+    }
+
     fn check_state_sync_request(&self) -> bool {
         let mut cache = self.state_request_cache.lock().expect(POISONED_LOCK_ERR);
         let now = StaticClock::instant();
@@ -546,6 +742,24 @@ impl Handler<WithSpanContext<Query>> for ViewClientActor {
     }
 }
 
+impl Handler<WithSpanContext<SimulateTransaction>> for ViewClientActor {
+    type Result = Result<TransactionSimulationView, SimulateTransactionError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<SimulateTransaction>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["SimulateTransaction"])
+            .start_timer();
+        self.handle_simulate_transaction(msg)
+    }
+}
+
 /// Handles retrieving block from the chain.
 impl Handler<WithSpanContext<GetBlock>> for ViewClientActor {
     type Result = Result<BlockView, GetBlockError>;
@@ -748,6 +962,7 @@ impl Handler<WithSpanContext<GetStateChangesInBlock>> for ViewClientActor {
         let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
             .with_label_values(&["GetStateChangesInBlock"])
             .start_timer();
+        self.check_state_changes_retained(&msg.block_hash)?;
         Ok(self
             .chain
             .store()
@@ -772,6 +987,7 @@ impl Handler<WithSpanContext<GetStateChanges>> for ViewClientActor {
         tracing::debug!(target: "client", ?msg);
         let _timer =
             metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["GetStateChanges"]).start_timer();
+        self.check_state_changes_retained(&msg.block_hash)?;
         Ok(self
             .chain
             .store()
@@ -797,6 +1013,7 @@ impl Handler<WithSpanContext<GetStateChangesWithCauseInBlock>> for ViewClientAct
         let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
             .with_label_values(&["GetStateChangesWithCauseInBlock"])
             .start_timer();
+        self.check_state_changes_retained(&msg.block_hash)?;
         Ok(self
             .chain
             .store()
@@ -823,6 +1040,7 @@ impl Handler<WithSpanContext<GetStateChangesWithCauseInBlockForTrackedShards>> f
         let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
             .with_label_values(&["GetStateChangesWithCauseInBlockForTrackedShards"])
             .start_timer();
+        self.check_state_changes_retained(&msg.block_hash)?;
         let state_changes_with_cause_in_block =
             self.chain.store().get_state_changes_with_cause_in_block(&msg.block_hash)?;
 
@@ -1020,6 +1238,76 @@ impl Handler<WithSpanContext<GetExecutionOutcomesForBlock>> for ViewClientActor
     }
 }
 
+impl Handler<WithSpanContext<GetHeadNotification>> for ViewClientActor {
+    type Result = Result<HeadNotification, String>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetHeadNotification>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetHeadNotification"])
+            .start_timer();
+        let block = self.chain.get_block(&msg.head).map_err(|e| e.to_string())?;
+
+        let mut new_final_blocks = vec![];
+        let last_final_block = *block.header().last_final_block();
+        if last_final_block != CryptoHash::default() {
+            let last_final_header =
+                self.chain.get_block_header(&last_final_block).map_err(|e| e.to_string())?;
+            match msg.last_notified_final_height {
+                // All the blocks of the canonical chain up to the last final block are final.
+                // Those beyond the limit are included in the next notifications.
+                Some(last_notified_height) => {
+                    for height in last_notified_height + 1..=last_final_header.height() {
+                        if new_final_blocks.len() >= MAX_NEW_FINAL_BLOCKS_PER_NOTIFICATION {
+                            break;
+                        }
+                        match self.chain.get_block_header_by_height(height) {
+                            Ok(header) => new_final_blocks.push(header.into()),
+                            // Skipped height.
+                            Err(near_chain::Error::DBNotFoundErr(_)) => {}
+                            Err(err) => return Err(err.to_string()),
+                        }
+                    }
+                }
+                None => new_final_blocks.push(last_final_header.into()),
+            }
+        }
+
+        // Outcomes of the block include the conversions of the transactions from its new chunks.
+        let mut tx_hashes = HashSet::new();
+        for chunk_header in block.chunks().iter() {
+            if chunk_header.height_included() != block.header().height() {
+                continue;
+            }
+            if let Ok(chunk) = self.chain.get_chunk(&chunk_header.chunk_hash()) {
+                tx_hashes.extend(chunk.transactions().iter().map(|tx| tx.get_hash()));
+            }
+        }
+        let receipt_outcomes = self
+            .chain
+            .store()
+            .get_block_execution_outcomes(block.hash())
+            .map_err(|e| e.to_string())?
+            .into_values()
+            .flatten()
+            .filter(|outcome| !tx_hashes.contains(&outcome.outcome_with_id.id))
+            .map(Into::into)
+            .collect();
+
+        Ok(HeadNotification {
+            head: block.header().clone().into(),
+            new_final_blocks,
+            receipt_outcomes,
+        })
+    }
+}
+
 impl Handler<WithSpanContext<GetReceipt>> for ViewClientActor {
     type Result = Result<Option<ReceiptView>, GetReceiptError>;
 
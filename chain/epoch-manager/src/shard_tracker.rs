@@ -5,13 +5,15 @@ use near_cache::SyncLruCache;
 use near_chain_configs::ClientConfig;
 use near_primitives::errors::EpochError;
 use near_primitives::hash::CryptoHash;
-use near_primitives::shard_layout::account_id_to_shard_id;
+use near_primitives::shard_layout::{account_id_to_shard_id, ShardLayout, ShardUId, ShardVersion};
 use near_primitives::types::{AccountId, EpochId, ShardId};
 
 #[derive(Clone)]
 pub enum TrackedConfig {
     Accounts(Vec<AccountId>),
     AllShards,
+    // Tracks the given shards and the shards they split into.
+    Shards(Vec<ShardUId>),
     // Rotates between sets of shards to track.
     Schedule(Vec<Vec<ShardId>>),
 }
@@ -24,6 +26,8 @@ impl TrackedConfig {
     pub fn from_config(config: &ClientConfig) -> Self {
         if !config.tracked_shards.is_empty() {
             TrackedConfig::AllShards
+        } else if !config.tracked_shard_uids.is_empty() {
+            TrackedConfig::Shards(config.tracked_shard_uids.clone())
         } else if !config.tracked_shard_schedule.is_empty() {
             TrackedConfig::Schedule(config.tracked_shard_schedule.clone())
         } else {
@@ -35,13 +39,17 @@ impl TrackedConfig {
 // bit mask for which shard to track
 type BitMask = Vec<bool>;
 
-/// Tracker that tracks shard ids and accounts. Right now, it only supports two modes
+/// Tracker that tracks shard ids and accounts. It supports the following modes
 /// TrackedConfig::Accounts(accounts): track the shards where `accounts` belong to
 /// TrackedConfig::AllShards: track all shards
+/// TrackedConfig::Shards(shard_uids): track `shard_uids` and, after any number of reshardings,
+/// the shards split from them
+/// TrackedConfig::Schedule(schedule): track a different set of shards every epoch
 #[derive(Clone)]
 pub struct ShardTracker {
     tracked_config: TrackedConfig,
-    /// Stores shard tracking information by epoch, only useful if TrackedState == Accounts
+    /// Stores shard tracking information by epoch, only useful if TrackedState is Accounts or
+    /// Shards
     tracking_shards_cache: Arc<SyncLruCache<EpochId, BitMask>>,
     epoch_manager: Arc<dyn EpochManagerAdapter>,
 }
@@ -80,6 +88,14 @@ impl ShardTracker {
                 });
                 Ok(tracking_mask.get(shard_id as usize).copied().unwrap_or(false))
             }
+            TrackedConfig::Shards(tracked_shards) => {
+                let shard_layout = self.epoch_manager.get_shard_layout(epoch_id)?;
+                let tracking_mask =
+                    self.tracking_shards_cache.get_or_try_put(epoch_id.clone(), |epoch_id| {
+                        self.get_tracked_shards_mask(tracked_shards, &shard_layout, epoch_id)
+                    })?;
+                Ok(tracking_mask.get(shard_id as usize).copied().unwrap_or(false))
+            }
             TrackedConfig::AllShards => Ok(true),
             TrackedConfig::Schedule(schedule) => {
                 assert_ne!(schedule.len(), 0);
@@ -92,6 +108,70 @@ impl ShardTracker {
         }
     }
 
+    /// Returns which shards of the epoch are in `tracked_shards` or descend from one of them.
+    fn get_tracked_shards_mask(
+        &self,
+        tracked_shards: &[ShardUId],
+        shard_layout: &ShardLayout,
+        epoch_id: &EpochId,
+    ) -> Result<BitMask, EpochError> {
+        let min_version = tracked_shards.iter().map(|shard_uid| shard_uid.version).min();
+        let prev_shard_layouts = self.get_prev_shard_layouts(epoch_id, min_version)?;
+        let tracks_shard = |shard_id| {
+            // The parent of a shard belongs to the layout preceding the shard's layout.
+            let mut shard_uid = ShardUId::from_shard_id_and_layout(shard_id, shard_layout);
+            for layout in std::iter::once(shard_layout).chain(&prev_shard_layouts) {
+                if tracked_shards.contains(&shard_uid) {
+                    return true;
+                }
+                match layout.get_parent_shard_uid(shard_uid.shard_id()) {
+                    Some(parent_shard_uid) => shard_uid = parent_shard_uid,
+                    None => return false,
+                }
+            }
+            tracked_shards.contains(&shard_uid)
+        };
+        Ok((0..shard_layout.num_shards()).map(tracks_shard).collect())
+    }
+
+    /// Returns the shard layouts that preceded the layout of the given epoch, from the most recent
+    /// one, down to the first one whose version is at most `min_version`.
+    fn get_prev_shard_layouts(
+        &self,
+        epoch_id: &EpochId,
+        min_version: Option<ShardVersion>,
+    ) -> Result<Vec<ShardLayout>, EpochError> {
+        let mut shard_layouts = vec![];
+        let mut version = self.epoch_manager.get_shard_layout(epoch_id)?.version();
+        let reached_min_version =
+            |version: ShardVersion| min_version.map_or(true, |min_version| version <= min_version);
+        if reached_min_version(version) || epoch_id == &EpochId::default() {
+            return Ok(shard_layouts);
+        }
+        // The id of an epoch is the hash of the last block of the epoch before the previous one,
+        // so the next epoch of that block is the previous epoch.
+        let mut block_hash = epoch_id.0;
+        let mut prev_epoch_id = self.epoch_manager.get_next_epoch_id(&block_hash)?;
+        loop {
+            let shard_layout = self.epoch_manager.get_shard_layout(&prev_epoch_id)?;
+            if shard_layout.version() != version {
+                version = shard_layout.version();
+                shard_layouts.push(shard_layout);
+                if reached_min_version(version) {
+                    break;
+                }
+            }
+            if block_hash == CryptoHash::default() {
+                break;
+            }
+            prev_epoch_id = self.epoch_manager.get_epoch_id(&block_hash)?;
+            let epoch_first_block =
+                *self.epoch_manager.get_block_info(&block_hash)?.epoch_first_block();
+            block_hash = *self.epoch_manager.get_block_info(&epoch_first_block)?.prev_hash();
+        }
+        Ok(shard_layouts)
+    }
+
     fn tracks_shard(&self, shard_id: ShardId, prev_hash: &CryptoHash) -> Result<bool, EpochError> {
         let epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(prev_hash)?;
         self.tracks_shard_at_epoch(shard_id, &epoch_id)
@@ -341,6 +421,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_track_shards() {
+        let num_shards = 4;
+        let epoch_manager = get_epoch_manager(PROTOCOL_VERSION, num_shards, false);
+        let tracked_shards = vec!["s0.v0".parse().unwrap(), "s3.v0".parse().unwrap()];
+        let tracker =
+            ShardTracker::new(TrackedConfig::Shards(tracked_shards), Arc::new(epoch_manager));
+        let total_tracked_shards = HashSet::from([0, 3]);
+
+        assert_eq!(
+            get_all_shards_care_about(&tracker, num_shards, &CryptoHash::default()),
+            total_tracked_shards
+        );
+        assert_eq!(
+            get_all_shards_will_care_about(&tracker, num_shards, &CryptoHash::default()),
+            total_tracked_shards
+        );
+    }
+
     #[test]
     fn test_track_schedule() {
         // Creates a ShardTracker that changes every epoch tracked shards.
@@ -454,4 +553,141 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_track_shards_resharding() {
+        let simple_nightshade_version = SimpleNightshade.protocol_version();
+        let epoch_manager = get_epoch_manager(simple_nightshade_version - 1, 1, true);
+        // Tracking the only shard of the old layout tracks all the shards it splits into,
+        // while a shard of the new layout is only tracked after resharding.
+        let parent_tracker = ShardTracker::new(
+            TrackedConfig::Shards(vec!["s0.v0".parse().unwrap()]),
+            Arc::new(epoch_manager.clone()),
+        );
+        let child_tracker = ShardTracker::new(
+            TrackedConfig::Shards(vec!["s2.v1".parse().unwrap()]),
+            Arc::new(epoch_manager.clone()),
+        );
+
+        let h = hash_range(8);
+        {
+            let mut epoch_manager = epoch_manager.write();
+            record_block(
+                &mut epoch_manager,
+                CryptoHash::default(),
+                h[0],
+                0,
+                vec![],
+                simple_nightshade_version,
+            );
+            for i in 1..8 {
+                record_block(
+                    &mut epoch_manager,
+                    h[i - 1],
+                    h[i],
+                    i as u64,
+                    vec![],
+                    simple_nightshade_version,
+                );
+            }
+        }
+
+        let mut layout_changed = false;
+        for i in 1..8 {
+            let epoch_id = epoch_manager.get_epoch_id_from_prev_block(&h[i - 1]).unwrap();
+            let shard_layout = epoch_manager.get_shard_layout(&epoch_id).unwrap();
+            let next_epoch_id = epoch_manager.get_next_epoch_id_from_prev_block(&h[i - 1]).unwrap();
+            let next_shard_layout = epoch_manager.get_shard_layout(&next_epoch_id).unwrap();
+            layout_changed |= shard_layout.version() != next_shard_layout.version();
+            let child_shards = |shard_layout: &ShardLayout| -> HashSet<ShardId> {
+                if shard_layout.version() == 1 {
+                    HashSet::from([2])
+                } else {
+                    HashSet::new()
+                }
+            };
+
+            assert_eq!(
+                get_all_shards_care_about(&parent_tracker, shard_layout.num_shards(), &h[i - 1]),
+                (0..shard_layout.num_shards()).collect()
+            );
+            assert_eq!(
+                get_all_shards_will_care_about(
+                    &parent_tracker,
+                    next_shard_layout.num_shards(),
+                    &h[i - 1]
+                ),
+                (0..next_shard_layout.num_shards()).collect()
+            );
+            assert_eq!(
+                get_all_shards_care_about(&child_tracker, shard_layout.num_shards(), &h[i - 1]),
+                child_shards(&shard_layout)
+            );
+            assert_eq!(
+                get_all_shards_will_care_about(
+                    &child_tracker,
+                    next_shard_layout.num_shards(),
+                    &h[i - 1]
+                ),
+                child_shards(&next_shard_layout)
+            );
+        }
+        assert!(layout_changed);
+    }
+
+    #[cfg(feature = "protocol_feature_simple_nightshade_v2")]
+    #[test]
+    fn test_track_shards_two_reshardings() {
+        use near_primitives::version::ProtocolFeature::SimpleNightshadeV2;
+
+        let simple_nightshade_version = SimpleNightshade.protocol_version();
+        let simple_nightshade_v2_version = SimpleNightshadeV2.protocol_version();
+        let epoch_manager = get_epoch_manager(simple_nightshade_version - 1, 1, true);
+        // Shards of both older layouts keep being tracked through their descendants.
+        let v0_tracker = ShardTracker::new(
+            TrackedConfig::Shards(vec!["s0.v0".parse().unwrap()]),
+            Arc::new(epoch_manager.clone()),
+        );
+        let v1_tracker = ShardTracker::new(
+            TrackedConfig::Shards(vec!["s3.v1".parse().unwrap()]),
+            Arc::new(epoch_manager.clone()),
+        );
+
+        let h = hash_range(16);
+        {
+            let mut epoch_manager = epoch_manager.write();
+            for i in 0..16 {
+                record_block(
+                    &mut epoch_manager,
+                    if i > 0 { h[i - 1] } else { CryptoHash::default() },
+                    h[i],
+                    i as u64,
+                    vec![],
+                    if i < 6 { simple_nightshade_version } else { simple_nightshade_v2_version },
+                );
+            }
+        }
+
+        let mut versions = HashSet::new();
+        for i in 1..16 {
+            let epoch_id = epoch_manager.get_epoch_id_from_prev_block(&h[i - 1]).unwrap();
+            let shard_layout = epoch_manager.get_shard_layout(&epoch_id).unwrap();
+            versions.insert(shard_layout.version());
+            let v1_descendants = match shard_layout.version() {
+                0 => HashSet::new(),
+                1 => HashSet::from([3]),
+                _ => HashSet::from([3, 4]),
+            };
+
+            assert_eq!(
+                get_all_shards_care_about(&v0_tracker, shard_layout.num_shards(), &h[i - 1]),
+                (0..shard_layout.num_shards()).collect()
+            );
+            assert_eq!(
+                get_all_shards_care_about(&v1_tracker, shard_layout.num_shards(), &h[i - 1]),
+                v1_descendants
+            );
+        }
+        assert_eq!(versions, HashSet::from([0, 1, 2]));
+    }
 }
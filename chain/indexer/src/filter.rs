@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+
+use near_indexer_primitives::StreamerMessage;
+use near_primitives::shard_layout::{account_id_to_shard_id, ShardLayout};
+use near_primitives::types::{AccountId, ShardId};
+use near_primitives::views::{
+    ActionView, ExecutionStatusView, ReceiptEnumView, ReceiptView, StateChangeValueView,
+};
+
+/// Kind of an action, one per variant of `near_primitives::action::Action`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum ActionKind {
+    CreateAccount,
+    DeployContract,
+    FunctionCall,
+    Transfer,
+    Stake,
+    AddKey,
+    DeleteKey,
+    DeleteAccount,
+    Delegate,
+}
+
+impl From<&ActionView> for ActionKind {
+    fn from(action: &ActionView) -> Self {
+        match action {
+            ActionView::CreateAccount => ActionKind::CreateAccount,
+            ActionView::DeployContract { .. } => ActionKind::DeployContract,
+            ActionView::FunctionCall { .. } => ActionKind::FunctionCall,
+            ActionView::Transfer { .. } => ActionKind::Transfer,
+            ActionView::Stake { .. } => ActionKind::Stake,
+            ActionView::AddKey { .. } => ActionKind::AddKey,
+            ActionView::DeleteKey { .. } => ActionKind::DeleteKey,
+            ActionView::DeleteAccount { .. } => ActionKind::DeleteAccount,
+            ActionView::Delegate { .. } => ActionKind::Delegate,
+        }
+    }
+}
+
+/// Which execution outcomes to stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum OutcomeStatusFilter {
+    #[default]
+    All,
+    /// Only outcomes with `SuccessValue` or `SuccessReceiptId` status.
+    Successful,
+    /// Only outcomes with `Failure` status.
+    Failed,
+}
+
+/// Declarative filter applied by the Indexer to every `StreamerMessage`.
+///
+/// Transactions, receipts and execution outcomes are streamed only if they satisfy all of the
+/// conditions, and an empty list doesn't restrict anything. Account patterns are globs where `*`
+/// matches any sequence of characters and `?` matches a single character, e.g. `*.poolv1.near`.
+/// Blocks are streamed even if nothing in them matches, so that consumers can follow the chain.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct IndexerFilter {
+    /// Patterns of receiver accounts. State changes are streamed only for matching accounts.
+    pub receiver_ids: Vec<String>,
+    /// Patterns of signer accounts. For data receipts the predecessor is used as the signer.
+    pub signer_ids: Vec<String>,
+    /// At least one action must be of one of these kinds. Data receipts have no actions.
+    pub action_kinds: Vec<ActionKind>,
+    /// Applies to transactions and receipts with execution outcomes.
+    /// Receipts included in chunks don't have outcomes yet and are not affected.
+    pub outcome_status: OutcomeStatusFilter,
+}
+
+impl IndexerFilter {
+    fn is_empty(&self) -> bool {
+        self.receiver_ids.is_empty()
+            && self.signer_ids.is_empty()
+            && self.action_kinds.is_empty()
+            && self.outcome_status == OutcomeStatusFilter::All
+    }
+
+    /// Returns the shards which may contain matching data, or `None` if any shard may.
+    ///
+    /// That can be determined only if the patterns are exact account IDs, because transactions
+    /// are included in chunks of the signer's shard, and receipts in chunks of the receiver's
+    /// shard.
+    pub(crate) fn shards_to_fetch(&self, shard_layout: &ShardLayout) -> Option<HashSet<ShardId>> {
+        let mut shards = exact_accounts_shards(&self.signer_ids, shard_layout)?;
+        shards.extend(exact_accounts_shards(&self.receiver_ids, shard_layout)?);
+        Some(shards)
+    }
+
+    /// Removes everything that doesn't match the filter from the message.
+    pub(crate) fn apply(&self, message: &mut StreamerMessage) {
+        if self.is_empty() {
+            return;
+        }
+        for shard in message.shards.iter_mut() {
+            if let Some(chunk) = shard.chunk.as_mut() {
+                chunk.transactions.retain(|tx| {
+                    let tx_view = &tx.transaction;
+                    matches_any(&self.receiver_ids, &tx_view.receiver_id)
+                        && matches_any(&self.signer_ids, &tx_view.signer_id)
+                        && self.matches_actions(&tx_view.actions)
+                        && self.matches_status(&tx.outcome.execution_outcome.outcome.status)
+                });
+                chunk.receipts.retain(|receipt| self.matches_receipt(receipt));
+            }
+            shard.receipt_execution_outcomes.retain(|outcome| {
+                self.matches_receipt(&outcome.receipt)
+                    && self.matches_status(&outcome.execution_outcome.outcome.status)
+            });
+            shard.state_changes.retain(|state_change| {
+                matches_any(&self.receiver_ids, state_change_account_id(&state_change.value))
+            });
+        }
+    }
+
+    fn matches_receipt(&self, receipt: &ReceiptView) -> bool {
+        let (signer_id, actions) = match &receipt.receipt {
+            ReceiptEnumView::Action { signer_id, actions, .. } => (signer_id, actions.as_slice()),
+            ReceiptEnumView::Data { .. } => (&receipt.predecessor_id, [].as_slice()),
+        };
+        matches_any(&self.receiver_ids, &receipt.receiver_id)
+            && matches_any(&self.signer_ids, signer_id)
+            && self.matches_actions(actions)
+    }
+
+    fn matches_actions(&self, actions: &[ActionView]) -> bool {
+        self.action_kinds.is_empty()
+            || actions.iter().any(|action| self.action_kinds.contains(&ActionKind::from(action)))
+    }
+
+    fn matches_status(&self, status: &ExecutionStatusView) -> bool {
+        match self.outcome_status {
+            OutcomeStatusFilter::All => true,
+            OutcomeStatusFilter::Successful => matches!(
+                status,
+                ExecutionStatusView::SuccessValue(_) | ExecutionStatusView::SuccessReceiptId(_)
+            ),
+            OutcomeStatusFilter::Failed => matches!(status, ExecutionStatusView::Failure(_)),
+        }
+    }
+}
+
+/// Returns shards of the accounts, or `None` if the list is empty or contains wildcards.
+fn exact_accounts_shards(
+    patterns: &[String],
+    shard_layout: &ShardLayout,
+) -> Option<HashSet<ShardId>> {
+    if patterns.is_empty() {
+        return None;
+    }
+    patterns
+        .iter()
+        .map(|pattern| {
+            let account_id = pattern.parse::<AccountId>().ok()?;
+            Some(account_id_to_shard_id(&account_id, shard_layout))
+        })
+        .collect()
+}
+
+fn state_change_account_id(value: &StateChangeValueView) -> &AccountId {
+    match value {
+        StateChangeValueView::AccountUpdate { account_id, .. }
+        | StateChangeValueView::AccountDeletion { account_id }
+        | StateChangeValueView::AccessKeyUpdate { account_id, .. }
+        | StateChangeValueView::AccessKeyDeletion { account_id, .. }
+        | StateChangeValueView::DataUpdate { account_id, .. }
+        | StateChangeValueView::DataDeletion { account_id, .. }
+        | StateChangeValueView::ContractCodeUpdate { account_id, .. }
+        | StateChangeValueView::ContractCodeDeletion { account_id } => account_id,
+    }
+}
+
+fn matches_any(patterns: &[String], account_id: &AccountId) -> bool {
+    patterns.is_empty() || patterns.iter().any(|pattern| glob_matches(pattern, account_id.as_str()))
+}
+
+/// Matches the text against a pattern where `*` matches any sequence of characters and `?`
+/// matches a single character.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let text = text.as_bytes();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and of the text it was matched against.
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last `*` match one more character.
+            backtrack = Some((star_p, star_t + 1));
+            p = star_p + 1;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_matches, ActionKind, IndexerFilter, OutcomeStatusFilter};
+    use near_primitives::shard_layout::ShardLayout;
+
+    #[test]
+    fn test_deserialize() {
+        let filter: IndexerFilter = serde_json::from_str(
+            r#"{"receiver_ids": ["*.poolv1.near"], "action_kinds": ["Stake", "Transfer"], "outcome_status": "Failed"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            filter,
+            IndexerFilter {
+                receiver_ids: vec!["*.poolv1.near".to_string()],
+                signer_ids: vec![],
+                action_kinds: vec![ActionKind::Stake, ActionKind::Transfer],
+                outcome_status: OutcomeStatusFilter::Failed,
+            }
+        );
+        assert_eq!(serde_json::from_str::<IndexerFilter>("{}").unwrap(), IndexerFilter::default());
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("alice.near", "alice.near"));
+        assert!(!glob_matches("alice.near", "bob.near"));
+        assert!(glob_matches("*.near", "alice.near"));
+        assert!(!glob_matches("*.near", "alice.testnet"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("a*e.*", "alice.near"));
+        assert!(glob_matches("?lice.near", "alice.near"));
+        assert!(!glob_matches("?alice.near", "alice.near"));
+        assert!(glob_matches("*.poolv1.near", "a.b.poolv1.near"));
+        assert!(!glob_matches("*.poolv1.near", "poolv1.near"));
+    }
+
+    #[test]
+    fn test_shards_to_fetch() {
+        let shard_layout =
+            ShardLayout::v1(vec!["aurora".parse().unwrap(), "hhh".parse().unwrap()], None, 1);
+        let filter = |receiver_ids: &[&str], signer_ids: &[&str]| IndexerFilter {
+            receiver_ids: receiver_ids.iter().map(|s| s.to_string()).collect(),
+            signer_ids: signer_ids.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(filter(&[], &[]).shards_to_fetch(&shard_layout), None);
+        // Transactions to the receiver may come from any shard.
+        assert_eq!(filter(&["aurora"], &[]).shards_to_fetch(&shard_layout), None);
+        assert_eq!(filter(&["aurora"], &["*.near"]).shards_to_fetch(&shard_layout), None);
+        assert_eq!(
+            filter(&["aurora"], &["aaa", "zzz"]).shards_to_fetch(&shard_layout),
+            Some([0, 1, 2].into_iter().collect())
+        );
+        assert_eq!(
+            filter(&["aurora"], &["aurora"]).shards_to_fetch(&shard_layout),
+            Some([1].into_iter().collect())
+        );
+    }
+}
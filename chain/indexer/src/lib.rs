@@ -5,6 +5,7 @@ use tokio::sync::mpsc;
 
 use near_chain_configs::GenesisValidationMode;
 pub use near_primitives;
+use near_primitives::epoch_manager::AllEpochConfig;
 use near_primitives::types::Gas;
 pub use nearcore::{get_default_home, init_configs, NearConfig};
 
@@ -14,6 +15,11 @@ pub use near_indexer_primitives::{
     StreamerMessage,
 };
 
+pub use filter::{ActionKind, IndexerFilter, OutcomeStatusFilter};
+pub use sink::{JsonLinesSink, Sink, SqliteSink};
+
+mod filter;
+mod sink;
 mod streamer;
 
 pub const INDEXER: &str = "indexer";
@@ -51,20 +57,22 @@ pub struct InitConfigArgs {
 }
 
 /// Enum to define a mode of syncing for NEAR Indexer
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum SyncModeEnum {
     /// Real-time syncing, always taking the latest finalized block to stream
     LatestSynced,
     /// Starts syncing from the block NEAR Indexer was interrupted last time
+    #[default]
     FromInterruption,
     /// Specific block height to start syncing from
     BlockHeight(u64),
 }
 
 /// Enum to define whether await for node to be fully synced or stream while syncing (useful for indexing from genesis)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum AwaitForNodeSyncedEnum {
     /// Don't stream until the node is fully synced
+    #[default]
     WaitForFullSync,
     /// Stream while node is syncing
     StreamWhileSyncing,
@@ -81,6 +89,22 @@ pub struct IndexerConfig {
     pub await_for_node_synced: AwaitForNodeSyncedEnum,
     /// Tells whether to validate the genesis file before starting
     pub validate_genesis: bool,
+    /// Filter of the streamed data. Use `IndexerFilter::default()` to stream everything
+    pub filter: IndexerFilter,
+}
+
+impl Default for IndexerConfig {
+    /// Streams everything from the default home directory, resuming after interruptions once
+    /// the node is synced.
+    fn default() -> Self {
+        Self {
+            home_dir: get_default_home(),
+            sync_mode: SyncModeEnum::default(),
+            await_for_node_synced: AwaitForNodeSyncedEnum::default(),
+            validate_genesis: true,
+            filter: IndexerFilter::default(),
+        }
+    }
 }
 
 /// This is the core component, which handles `nearcore` and internal `streamer`.
@@ -110,7 +134,8 @@ impl Indexer {
                 .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
 
         assert!(
-            !&near_config.client_config.tracked_shards.is_empty(),
+            !&near_config.client_config.tracked_shards.is_empty()
+                || !near_config.client_config.tracked_shard_uids.is_empty(),
             "Indexer should track at least one shard. \n\
             Tip: You may want to update {} with `\"tracked_shards\": [0]`
             ",
@@ -131,11 +156,36 @@ impl Indexer {
             self.indexer_config.clone(),
             self.near_config.config.store.clone(),
             self.near_config.config.archive,
-            sender,
+            self.all_epoch_config(),
+            streamer::MessageDestination::Channel(sender),
         ));
         receiver
     }
 
+    /// Boots up `near_indexer::streamer` writing the messages to the given sink instead of a
+    /// channel. The height of a block is checkpointed only after the sink confirms its message is
+    /// durable, so with `SyncModeEnum::FromInterruption` every message is delivered at least once
+    /// across restarts.
+    pub fn stream_to_sink(&self, sink: impl Sink + 'static) {
+        actix::spawn(streamer::start(
+            self.view_client.clone(),
+            self.client.clone(),
+            self.indexer_config.clone(),
+            self.near_config.config.store.clone(),
+            self.near_config.config.archive,
+            self.all_epoch_config(),
+            streamer::MessageDestination::Sink(std::sync::Arc::new(std::sync::Mutex::new(
+                Box::new(sink),
+            ))),
+        ));
+    }
+
+    /// Used by the streamer to determine the shard layout of a block.
+    fn all_epoch_config(&self) -> AllEpochConfig {
+        let genesis_config = &self.near_config.genesis.config;
+        AllEpochConfig::new(genesis_config.use_production_config(), genesis_config.into())
+    }
+
     /// Expose neard config
     pub fn near_config(&self) -> &nearcore::NearConfig {
         &self.near_config
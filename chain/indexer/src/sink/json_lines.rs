@@ -0,0 +1,112 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+use near_indexer_primitives::StreamerMessage;
+
+use super::Sink;
+
+/// Appends messages as newline-delimited JSON to files in a directory.
+///
+/// Files are named after the height of the first block written to them, e.g.
+/// `blocks_000000001234.jsonl`. Once a file grows over `max_file_size` bytes, the next message
+/// starts a new file.
+pub struct JsonLinesSink {
+    dir: PathBuf,
+    max_file_size: u64,
+    /// The file being written to and its size.
+    current: Option<(std::fs::File, u64)>,
+}
+
+impl JsonLinesSink {
+    pub fn new(dir: impl Into<PathBuf>, max_file_size: u64) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+        Ok(Self { dir, max_file_size, current: None })
+    }
+
+    fn file_path(&self, height: near_primitives::types::BlockHeight) -> PathBuf {
+        self.dir.join(format!("blocks_{:012}.jsonl", height))
+    }
+}
+
+impl Sink for JsonLinesSink {
+    fn write(&mut self, message: &StreamerMessage) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+
+        if self.current.as_ref().map_or(true, |(_, size)| *size >= self.max_file_size) {
+            let path = self.file_path(message.block.header.height);
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            let size = file.metadata()?.len();
+            self.current = Some((file, size));
+        }
+        let (file, size) = self.current.as_mut().unwrap();
+        if let Err(err) = file.write_all(&line).and_then(|()| file.sync_data()) {
+            // The file may end with a partial line now. Start a new file on retry.
+            self.current = None;
+            return Err(err.into());
+        }
+        *size += line.len() as u64;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonLinesSink;
+    use crate::sink::{test_message, Sink};
+    use near_indexer_primitives::StreamerMessage;
+
+    #[test]
+    fn test_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        // Every file fits a single message.
+        let mut sink = JsonLinesSink::new(dir.path(), 1).unwrap();
+        for height in 1..=3 {
+            sink.write(&test_message(height)).unwrap();
+        }
+
+        let mut files: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                "blocks_000000000001.jsonl",
+                "blocks_000000000002.jsonl",
+                "blocks_000000000003.jsonl"
+            ]
+        );
+
+        let data = std::fs::read_to_string(dir.path().join(&files[1])).unwrap();
+        let lines: Vec<_> = data.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let message: StreamerMessage = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(message.block.header.height, 2);
+    }
+
+    #[test]
+    fn test_no_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sink = JsonLinesSink::new(dir.path(), u64::MAX).unwrap();
+        for height in 1..=3 {
+            sink.write(&test_message(height)).unwrap();
+        }
+
+        let data = std::fs::read_to_string(dir.path().join("blocks_000000000001.jsonl")).unwrap();
+        let heights: Vec<_> = data
+            .lines()
+            .map(|line| serde_json::from_str::<StreamerMessage>(line).unwrap().block.header.height)
+            .collect();
+        assert_eq!(heights, vec![1, 2, 3]);
+    }
+}
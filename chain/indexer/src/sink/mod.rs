@@ -0,0 +1,40 @@
+use near_indexer_primitives::StreamerMessage;
+
+pub use self::json_lines::JsonLinesSink;
+pub use self::sqlite::SqliteSink;
+
+mod json_lines;
+mod sqlite;
+
+/// Destination of `StreamerMessage`s produced by the Indexer.
+///
+/// The Indexer passes messages to the sink one by one, in the order of block heights, and
+/// persists a checkpoint of the block height only after `write` returns `Ok`. After a restart
+/// with `SyncModeEnum::FromInterruption` the streaming resumes from the checkpoint, so a sink
+/// may receive the same message more than once and should handle it idempotently.
+pub trait Sink: Send {
+    /// Writes the message and returns once it's durable.
+    /// Failed writes are retried with the same message.
+    /// Called on a thread of the blocking thread pool, so it's fine to block on I/O.
+    fn write(&mut self, message: &StreamerMessage) -> anyhow::Result<()>;
+}
+
+#[cfg(test)]
+pub(crate) fn test_message(height: near_primitives::types::BlockHeight) -> StreamerMessage {
+    let block = near_primitives::block::Block::genesis(
+        near_primitives::version::PROTOCOL_VERSION,
+        vec![],
+        near_primitives::static_clock::StaticClock::utc(),
+        height,
+        100,
+        1_000_000,
+        Default::default(),
+    );
+    StreamerMessage {
+        block: near_primitives::views::BlockView::from_author_block(
+            "test.near".parse().unwrap(),
+            block,
+        ),
+        shards: vec![],
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use anyhow::Context;
+use rusqlite::{params, Connection};
+
+use near_indexer_primitives::StreamerMessage;
+
+use super::Sink;
+
+/// Stores every message as a JSON document in the `streamer_messages` table of an SQLite
+/// database, keyed by block height.
+///
+/// Writing a message of an already stored block replaces it, which makes repeated delivery of
+/// messages after a restart harmless.
+pub struct SqliteSink {
+    conn: Connection,
+}
+
+impl SqliteSink {
+    /// Opens an existing SQLite database or creates it.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open SQLite database {}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS streamer_messages (
+                block_height INTEGER PRIMARY KEY,
+                block_hash TEXT NOT NULL,
+                message TEXT NOT NULL
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+}
+
+impl Sink for SqliteSink {
+    fn write(&mut self, message: &StreamerMessage) -> anyhow::Result<()> {
+        let json = serde_json::to_string(message)?;
+        // A single statement runs in its own transaction, which is durable once committed.
+        self.conn.execute(
+            "INSERT OR REPLACE INTO streamer_messages (block_height, block_hash, message) VALUES (?1, ?2, ?3)",
+            params![
+                message.block.header.height as i64,
+                message.block.header.hash.to_string(),
+                json
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SqliteSink;
+    use crate::sink::{test_message, Sink};
+
+    #[test]
+    fn test_write_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("indexer.sqlite");
+        let mut sink = SqliteSink::open(&path).unwrap();
+        for height in [1, 2, 2, 3] {
+            sink.write(&test_message(height)).unwrap();
+        }
+        drop(sink);
+
+        let sink = SqliteSink::open(&path).unwrap();
+        let mut stmt = sink
+            .conn
+            .prepare("SELECT block_height FROM streamer_messages ORDER BY block_height")
+            .unwrap();
+        let heights = stmt
+            .query_map([], |row| row.get::<_, i64>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(heights, vec![1, 2, 3]);
+    }
+}
@@ -1,6 +1,6 @@
 //! Streamer watches the network and collects all the blocks and related chunks
 //! into one struct and pushes in in to the given queue
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use actix::Addr;
 use futures::stream::StreamExt;
@@ -83,9 +83,11 @@ pub(crate) async fn fetch_state_changes(
 
 /// Fetch all ExecutionOutcomeWithId for current block
 /// Returns a HashMap where the key is shard id IndexerExecutionOutcomeWithOptionalReceipt
+/// If `shards` is given, outcomes of other shards are skipped.
 pub(crate) async fn fetch_outcomes(
     client: &Addr<near_client::ViewClientActor>,
     block_hash: CryptoHash,
+    shards: Option<&HashSet<types::ShardId>>,
 ) -> Result<
     HashMap<near_primitives::types::ShardId, Vec<IndexerExecutionOutcomeWithOptionalReceipt>>,
     FailedToFetchData,
@@ -100,6 +102,9 @@ pub(crate) async fn fetch_outcomes(
         Vec<IndexerExecutionOutcomeWithOptionalReceipt>,
     > = HashMap::new();
     for (shard_id, shard_outcomes) in outcomes {
+        if shards.map_or(false, |shards| !shards.contains(&shard_id)) {
+            continue;
+        }
         let mut outcomes_with_receipts: Vec<IndexerExecutionOutcomeWithOptionalReceipt> = vec![];
         for outcome in shard_outcomes {
             let receipt = match fetch_receipt_by_id(&client, outcome.id).await {
@@ -149,14 +154,17 @@ async fn fetch_single_chunk(
 
 /// Fetches all chunks belonging to given block.
 /// Includes transactions and receipts in custom struct (to provide more info).
+/// If `shards` is given, chunks of other shards are skipped.
 pub(crate) async fn fetch_block_chunks(
     client: &Addr<near_client::ViewClientActor>,
     block: &views::BlockView,
+    shards: Option<&HashSet<types::ShardId>>,
 ) -> Result<Vec<views::ChunkView>, FailedToFetchData> {
     let mut futures: futures::stream::FuturesUnordered<_> = block
         .chunks
         .iter()
         .filter(|chunk| chunk.height_included == block.header.height)
+        .filter(|chunk| shards.map_or(true, |shards| shards.contains(&chunk.shard_id)))
         .map(|chunk| fetch_single_chunk(&client, chunk.chunk_hash))
         .collect();
     let mut chunks = Vec::<views::ChunkView>::with_capacity(futures.len());
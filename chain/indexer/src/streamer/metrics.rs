@@ -36,6 +36,14 @@ pub(crate) static NUM_STREAMER_MESSAGES_SENT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+pub(crate) static NUM_SINK_WRITE_FAILURES: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_indexer_num_sink_write_failures",
+        "Number of failed attempts to write a Streamer message to a sink",
+    )
+    .unwrap()
+});
+
 pub(crate) static BUILD_STREAMER_MESSAGE_TIME: Lazy<Histogram> = Lazy::new(|| {
     try_create_histogram(
         "near_indexer_build_streamer_message_time",
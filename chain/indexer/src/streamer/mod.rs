@@ -1,3 +1,4 @@
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use actix::Addr;
@@ -6,16 +7,19 @@ use node_runtime::config::RuntimeConfig;
 use rocksdb::DB;
 use tokio::sync::mpsc;
 use tokio::time;
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
 
 use near_indexer_primitives::{
     IndexerChunkView, IndexerExecutionOutcomeWithOptionalReceipt,
     IndexerExecutionOutcomeWithReceipt, IndexerShard, IndexerTransactionWithOutcome,
     StreamerMessage,
 };
+use near_primitives::epoch_manager::AllEpochConfig;
 use near_primitives::hash::CryptoHash;
 use near_primitives::views;
 
+use crate::filter::IndexerFilter;
+use crate::sink::Sink;
 use crate::{AwaitForNodeSyncedEnum, IndexerConfig};
 
 use self::errors::FailedToFetchData;
@@ -33,6 +37,60 @@ mod metrics;
 mod utils;
 
 const INTERVAL: Duration = Duration::from_millis(500);
+/// Delay before retrying a failed write to a sink.
+const SINK_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where the Streamer delivers the messages to.
+pub(crate) enum MessageDestination {
+    /// The messages are considered delivered once sent to the channel.
+    Channel(mpsc::Sender<StreamerMessage>),
+    /// The messages are considered delivered once the sink confirms that they are durable.
+    /// Writes run on the blocking thread pool, so that a slow disk doesn't stall the runtime
+    /// shared with the node.
+    Sink(Arc<Mutex<Box<dyn Sink>>>),
+}
+
+impl MessageDestination {
+    /// Returns once the message is delivered, or `false` if the destination can't accept
+    /// messages anymore.
+    async fn deliver(&mut self, streamer_message: StreamerMessage) -> bool {
+        match self {
+            MessageDestination::Channel(sender) => sender.send(streamer_message).await.is_ok(),
+            MessageDestination::Sink(sink) => {
+                let height = streamer_message.block.header.height;
+                let streamer_message = Arc::new(streamer_message);
+                // Later blocks must not be delivered before this one, so retry until it succeeds.
+                loop {
+                    let sink = sink.clone();
+                    let message = streamer_message.clone();
+                    let result =
+                        tokio::task::spawn_blocking(move || sink.lock().unwrap().write(&message))
+                            .await;
+                    match result {
+                        Ok(Ok(())) => return true,
+                        Ok(Err(err)) => {
+                            metrics::NUM_SINK_WRITE_FAILURES.inc();
+                            warn!(
+                                target: INDEXER,
+                                "Failed to write block #{} to the sink, retrying: {:#}",
+                                height,
+                                err
+                            );
+                            time::sleep(SINK_RETRY_INTERVAL).await;
+                        }
+                        Err(err) => {
+                            error!(
+                                target: INDEXER,
+                                "Writing block #{} to the sink panicked: {}", height, err
+                            );
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
 
 /// Blocks #47317863 and #47317864 with restored receipts.
 const PROBLEMATIC_BLOCKS: [CryptoHash; 2] = [
@@ -66,16 +124,23 @@ fn test_problematic_blocks_hash() {
 
 /// This function supposed to return the entire `StreamerMessage`.
 /// It fetches the block and all related parts (chunks, outcomes, state changes etc.)
-/// and returns everything together in one struct
+/// and returns everything together in one struct, trimmed according to the `filter`.
+/// Chunks and outcomes of shards that can't contain data matching the filter are not fetched.
 #[async_recursion]
 async fn build_streamer_message(
     client: &Addr<near_client::ViewClientActor>,
     block: views::BlockView,
+    filter: &IndexerFilter,
+    all_epoch_config: &AllEpochConfig,
 ) -> Result<StreamerMessage, FailedToFetchData> {
     let _timer = metrics::BUILD_STREAMER_MESSAGE_TIME.start_timer();
-    let chunks = fetch_block_chunks(&client, &block).await?;
-
     let protocol_config_view = fetch_protocol_config(&client, block.header.hash).await?;
+    let shard_layout =
+        all_epoch_config.for_protocol_version(protocol_config_view.protocol_version).shard_layout;
+    let shards_to_fetch = filter.shards_to_fetch(&shard_layout);
+
+    let chunks = fetch_block_chunks(&client, &block, shards_to_fetch.as_ref()).await?;
+
     let num_shards = protocol_config_view.num_block_producer_seats_per_shard.len()
         as near_primitives::types::NumShards;
 
@@ -83,7 +148,8 @@ async fn build_streamer_message(
         near_primitives::runtime::config_store::RuntimeConfigStore::new(None);
     let runtime_config = runtime_config_store.get_config(protocol_config_view.protocol_version);
 
-    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash).await?;
+    let mut shards_outcomes =
+        fetch_outcomes(&client, block.header.hash, shards_to_fetch.as_ref()).await?;
     let mut state_changes = fetch_state_changes(
         &client,
         block.header.hash,
@@ -235,7 +301,9 @@ async fn build_streamer_message(
         )
     }
 
-    Ok(StreamerMessage { block, shards: indexer_shards })
+    let mut streamer_message = StreamerMessage { block, shards: indexer_shards };
+    filter.apply(&mut streamer_message);
+    Ok(streamer_message)
 }
 
 /// Function that tries to find specific local receipt by it's ID and returns it
@@ -246,9 +314,9 @@ async fn find_local_receipt_by_id_in_block(
     block: views::BlockView,
     receipt_id: near_primitives::hash::CryptoHash,
 ) -> Result<Option<views::ReceiptView>, FailedToFetchData> {
-    let chunks = fetch_block_chunks(&client, &block).await?;
+    let chunks = fetch_block_chunks(&client, &block, None).await?;
 
-    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash).await?;
+    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash, None).await?;
 
     for chunk in chunks {
         let views::ChunkView { header, transactions, .. } = chunk;
@@ -287,13 +355,17 @@ async fn find_local_receipt_by_id_in_block(
 /// compares to already fetched block height and in case it differs fetches new block of given height.
 ///
 /// We have to pass `client: Addr<near_client::ClientActor>` and `view_client: Addr<near_client::ViewClientActor>`.
+///
+/// The height of a block is saved as the checkpoint to resume from only after its message is
+/// delivered to `destination`.
 pub(crate) async fn start(
     view_client: Addr<near_client::ViewClientActor>,
     client: Addr<near_client::ClientActor>,
     indexer_config: IndexerConfig,
     store_config: near_store::StoreConfig,
     archive: bool,
-    blocks_sink: mpsc::Sender<StreamerMessage>,
+    all_epoch_config: AllEpochConfig,
+    mut destination: MessageDestination,
 ) {
     info!(target: INDEXER, "Starting Streamer...");
     let indexer_db_path =
@@ -354,12 +426,18 @@ pub(crate) async fn start(
         for block_height in start_syncing_block_height..=latest_block_height {
             metrics::CURRENT_BLOCK_HEIGHT.set(block_height as i64);
             if let Ok(block) = fetch_block_by_height(&view_client, block_height).await {
-                let response = build_streamer_message(&view_client, block).await;
+                let response = build_streamer_message(
+                    &view_client,
+                    block,
+                    &indexer_config.filter,
+                    &all_epoch_config,
+                )
+                .await;
 
                 match response {
                     Ok(streamer_message) => {
                         debug!(target: INDEXER, "{:#?}", &streamer_message);
-                        if blocks_sink.send(streamer_message).await.is_err() {
+                        if !destination.deliver(streamer_message).await {
                             info!(
                                 target: INDEXER,
                                 "Unable to send StreamerMessage to listener, listener doesn't listen. terminating..."
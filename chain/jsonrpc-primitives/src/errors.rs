@@ -28,6 +28,7 @@ pub enum RpcErrorKind {
     RequestValidationError(RpcRequestValidationErrorKind),
     HandlerError(Value),
     InternalError(Value),
+    TooManyRequests { method_name: String },
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
@@ -35,6 +36,8 @@ pub enum RpcErrorKind {
 pub enum RpcRequestValidationErrorKind {
     MethodNotFound { method_name: String },
     ParseError { error_message: String },
+    BatchTooLarge { max_batch_size: usize },
+    EmptyBatch,
 }
 
 /// A general Server Error
@@ -137,6 +140,43 @@ impl RpcError {
         }
     }
 
+    /// Create an error for a request rejected by a rate limit of the client.
+    pub fn too_many_requests(method_name: String) -> Self {
+        RpcError {
+            code: -32_000,
+            message: "Too many requests".to_owned(),
+            data: Some(Value::String(method_name.clone())),
+            error_struct: Some(RpcErrorKind::TooManyRequests { method_name }),
+        }
+    }
+
+    /// Create an error for a batch with more requests than allowed.
+    pub fn batch_too_large(max_batch_size: usize) -> Self {
+        RpcError {
+            code: -32_600,
+            message: "Invalid request".to_owned(),
+            data: Some(Value::String(format!(
+                "Batch can't contain more than {} requests",
+                max_batch_size
+            ))),
+            error_struct: Some(RpcErrorKind::RequestValidationError(
+                RpcRequestValidationErrorKind::BatchTooLarge { max_batch_size },
+            )),
+        }
+    }
+
+    /// Create an error for a batch without requests.
+    pub fn empty_batch() -> Self {
+        RpcError {
+            code: -32_600,
+            message: "Invalid request".to_owned(),
+            data: Some(Value::String("JSON RPC batch can't be empty".to_owned())),
+            error_struct: Some(RpcErrorKind::RequestValidationError(
+                RpcRequestValidationErrorKind::EmptyBatch,
+            )),
+        }
+    }
+
     /// Create a method not found error.
     pub fn method_not_found(method: String) -> Self {
         RpcError {
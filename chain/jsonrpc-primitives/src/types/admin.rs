@@ -0,0 +1,54 @@
+use near_primitives::network::PeerId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcBanPeerRequest {
+    /// Peers to ban: an IP, IP:port, IP range in the CIDR notation or a peer id.
+    pub rule: String,
+    /// For how long the peers are banned, in seconds.
+    pub duration_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcUnbanPeerRequest {
+    /// Rule of a previous `admin_ban_peer` request.
+    pub rule: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcUpdateAuthorizationResponse {
+    /// Connected peers which were disconnected because they became denied.
+    pub disconnected_peers: Vec<PeerId>,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcAdminError {
+    #[error("Invalid rule: {error_message}")]
+    InvalidRule { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcAdminError> for crate::errors::RpcError {
+    fn from(error: RpcAdminError) -> Self {
+        let error_data = match &error {
+            RpcAdminError::InvalidRule { .. } | RpcAdminError::InternalError { .. } => {
+                Some(Value::String(error.to_string()))
+            }
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcAdminError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -34,6 +34,11 @@ pub enum RpcStateChangesError {
     },
     #[error("There are no fully synchronized blocks yet")]
     NotSyncedYet,
+    #[error("State changes of the block at height {block_height} have been deleted, the node keeps them starting from height {retention_tail}")]
+    PrunedData {
+        block_height: near_primitives::types::BlockHeight,
+        retention_tail: near_primitives::types::BlockHeight,
+    },
     #[error("The node reached its limits. Try again later. More details: {error_message}")]
     InternalError { error_message: String },
 }
@@ -52,3 +57,28 @@ impl From<RpcStateChangesError> for crate::errors::RpcError {
         Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RpcStateChangesError;
+    use crate::errors::{RpcError, RpcErrorKind};
+
+    #[test]
+    fn test_pruned_data_error() {
+        let error = RpcError::from(RpcStateChangesError::PrunedData {
+            block_height: 10,
+            retention_tail: 20,
+        });
+        assert_eq!(error.code, -32_000);
+        match error.error_struct {
+            Some(RpcErrorKind::HandlerError(cause)) => assert_eq!(
+                cause,
+                serde_json::json!({
+                    "name": "PRUNED_DATA",
+                    "info": {"block_height": 10, "retention_tail": 20},
+                })
+            ),
+            error => panic!("Unexpected error {:?}", error),
+        }
+    }
+}
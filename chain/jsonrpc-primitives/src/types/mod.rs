@@ -1,3 +1,4 @@
+pub mod admin;
 pub mod blocks;
 pub mod changes;
 pub mod chunks;
@@ -11,7 +12,10 @@ pub mod network_info;
 pub mod query;
 pub mod receipts;
 pub mod sandbox;
+pub mod simulate_transaction;
 pub mod split_storage;
 pub mod status;
+pub mod subscriptions;
 pub mod transactions;
+pub mod tx_pool;
 pub mod validator;
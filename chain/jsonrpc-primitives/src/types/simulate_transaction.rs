@@ -0,0 +1,72 @@
+use serde_json::Value;
+
+#[derive(Debug, Clone)]
+pub struct RpcSimulateTransactionRequest {
+    pub block_reference: near_primitives::types::BlockReference,
+    pub signed_transaction: near_primitives::transaction::SignedTransaction,
+    /// Disabled for unsigned transactions.
+    pub verify_signature: bool,
+    pub max_blocks: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcSimulateTransactionResponse {
+    #[serde(flatten)]
+    pub simulation: near_primitives::views::TransactionSimulationView,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSimulateTransactionError {
+    #[error("There are no fully synchronized blocks on the node yet")]
+    NoSyncedBlocks,
+    #[error("Block either has never been observed on the node or has been garbage collected: {block_reference:?}")]
+    UnknownBlock { block_reference: near_primitives::types::BlockReference },
+    #[error(
+        "The data for block #{block_height} is garbage collected on this node, use an archival node to fetch historical data"
+    )]
+    GarbageCollectedBlock {
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error("The node does not track the shard ID {requested_shard_id}")]
+    UnavailableShard { requested_shard_id: near_primitives::types::ShardId },
+    #[error("An error happened during transaction execution: {context:?}")]
+    InvalidTransaction {
+        #[serde(skip_serializing)]
+        context: near_primitives::errors::InvalidTxError,
+    },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSimulateTransactionError> for crate::errors::RpcError {
+    fn from(error: RpcSimulateTransactionError) -> Self {
+        let error_data = match &error {
+            RpcSimulateTransactionError::InvalidTransaction { context } => {
+                if let Ok(value) =
+                    serde_json::to_value(crate::errors::ServerError::TxExecutionError(
+                        near_primitives::errors::TxExecutionError::InvalidTxError(context.clone()),
+                    ))
+                {
+                    value
+                } else {
+                    Value::String(error.to_string())
+                }
+            }
+            _ => Value::String(error.to_string()),
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSimulateTransactionError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(Some(error_data), error_data_value)
+    }
+}
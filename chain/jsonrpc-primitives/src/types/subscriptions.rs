@@ -0,0 +1,103 @@
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::AccountId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Identifier of a subscription, unique within a WebSocket connection.
+pub type SubscriptionId = u64;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionFinality {
+    #[default]
+    Optimistic,
+    Final,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RpcSubscribeRequest {
+    /// Notifies with the header of every new head, or of every new final block.
+    NewHeads {
+        #[serde(default)]
+        finality: SubscriptionFinality,
+    },
+    /// Notifies with the outcome of every receipt executed by the account in a new head.
+    ReceiptOutcomes { account_id: AccountId },
+    /// Notifies once with the outcome of the transaction when it reaches a final execution
+    /// status, i.e. all its receipts are executed. The status is checked whenever a new block
+    /// becomes final, and the subscription ends after the notification.
+    TransactionStatus { tx_hash: CryptoHash, sender_account_id: AccountId },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcSubscribeResponse {
+    pub subscription: SubscriptionId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcUnsubscribeRequest {
+    pub subscription: SubscriptionId,
+}
+
+/// Params of the `subscription` notifications sent to the subscribers.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcSubscriptionNotification {
+    pub subscription: SubscriptionId,
+    pub result: Value,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSubscriptionError {
+    #[error("Subscription {subscription} doesn't exist")]
+    UnknownSubscription { subscription: SubscriptionId },
+    #[error("Subscriptions are available only over WebSocket")]
+    WebSocketRequired,
+    #[error("Too many subscriptions, at most {limit} are allowed per connection")]
+    TooManySubscriptions { limit: usize },
+}
+
+impl From<RpcSubscriptionError> for crate::errors::RpcError {
+    fn from(error: RpcSubscriptionError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSubscriptionError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RpcSubscribeRequest, SubscriptionFinality};
+
+    #[test]
+    fn test_parse_subscribe_request() {
+        let parse = |value| serde_json::from_value::<RpcSubscribeRequest>(value).unwrap();
+        assert_eq!(
+            parse(serde_json::json!({"kind": "new_heads"})),
+            RpcSubscribeRequest::NewHeads { finality: SubscriptionFinality::Optimistic }
+        );
+        assert_eq!(
+            parse(serde_json::json!({"kind": "new_heads", "finality": "final"})),
+            RpcSubscribeRequest::NewHeads { finality: SubscriptionFinality::Final }
+        );
+        assert_eq!(
+            parse(serde_json::json!({"kind": "receipt_outcomes", "account_id": "alice.near"})),
+            RpcSubscribeRequest::ReceiptOutcomes { account_id: "alice.near".parse().unwrap() }
+        );
+        assert!(serde_json::from_value::<RpcSubscribeRequest>(
+            serde_json::json!({"kind": "new_heads", "finality": "near-final"})
+        )
+        .is_err());
+    }
+}
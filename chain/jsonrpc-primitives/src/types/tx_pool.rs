@@ -0,0 +1,51 @@
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::AccountId;
+use near_primitives::views::TxPoolView;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RpcTxPoolRequest {
+    /// If set, only transactions signed by this account are listed.
+    #[serde(default)]
+    pub account_id: Option<AccountId>,
+    /// If set, only transactions signed with this access key are listed.
+    #[serde(default)]
+    pub public_key: Option<near_crypto::PublicKey>,
+    /// If set, only the transaction with this hash is listed, either pooled or dropped.
+    #[serde(default)]
+    pub transaction_hash: Option<CryptoHash>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcTxPoolResponse {
+    #[serde(flatten)]
+    pub tx_pool: TxPoolView,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcTxPoolError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcTxPoolError> for crate::errors::RpcError {
+    fn from(error: RpcTxPoolError) -> Self {
+        let error_data = match &error {
+            RpcTxPoolError::InternalError { .. } => Some(Value::String(error.to_string())),
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcTxPoolError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -252,6 +252,24 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_protocol_config", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_tx_pool(
+        &self,
+        request: near_jsonrpc_primitives::types::tx_pool::RpcTxPoolRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::tx_pool::RpcTxPoolResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_tx_pool", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_simulate_tx(
+        &self,
+        request: serde_json::Value,
+    ) -> RpcRequest<
+        near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionResponse,
+    > {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_simulate_tx", request)
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_split_storage_info(
         &self,
@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use actix::Addr;
 use futures::{future, future::LocalBoxFuture, FutureExt, TryFutureExt};
+use near_async::messaging::Sender;
+use near_async::time;
 use near_chain_configs::GenesisConfig;
 use near_client::test_utils::setup_no_network_with_validity_period_and_no_epoch_sync;
 use near_client::ViewClientActor;
@@ -10,7 +12,10 @@ use near_jsonrpc_primitives::{
     message::{from_slice, Message},
     types::entity_debug::DummyEntityDebugHandler,
 };
+use near_network::config::NetworkConfig;
 use near_network::tcp;
+use near_network::PeerManagerActor;
+use near_primitives::block::GenesisId;
 use near_primitives::types::NumBlocks;
 use once_cell::sync::Lazy;
 use serde_json::json;
@@ -56,6 +61,46 @@ pub fn start_all_with_validity_period_and_no_epoch_sync(
     (actor_handles.view_client_actor, addr)
 }
 
+/// Starts a node with the admin methods enabled, and a peer manager without any peers.
+pub fn start_all_with_admin_rpc(node_type: NodeType) -> tcp::ListenerAddr {
+    let actor_handles = setup_no_network_with_validity_period_and_no_epoch_sync(
+        vec!["test1".parse().unwrap(), "test2".parse().unwrap()],
+        if let NodeType::Validator = node_type {
+            "test1".parse().unwrap()
+        } else {
+            "other".parse().unwrap()
+        },
+        true,
+        100,
+        false,
+    );
+    let peer_manager = PeerManagerActor::spawn(
+        time::Clock::real(),
+        near_store::db::TestDB::new(),
+        NetworkConfig::from_seed("test1", tcp::ListenerAddr::reserve_for_test()),
+        Arc::new(near_client::adapter::Adapter::new(
+            actor_handles.client_actor.clone(),
+            actor_handles.view_client_actor.clone(),
+        )),
+        Sender::noop(),
+        GenesisId::default(),
+    )
+    .unwrap();
+
+    let addr = tcp::ListenerAddr::reserve_for_test();
+    let mut config = RpcConfig::new(addr);
+    config.enable_admin_rpc = true;
+    start_http(
+        config,
+        TEST_GENESIS_CONFIG.clone(),
+        actor_handles.client_actor,
+        actor_handles.view_client_actor,
+        Some(peer_manager),
+        Arc::new(DummyEntityDebugHandler {}),
+    );
+    addr
+}
+
 #[macro_export]
 macro_rules! test_with_client {
     ($node_type:expr, $client:ident, $block:expr) => {
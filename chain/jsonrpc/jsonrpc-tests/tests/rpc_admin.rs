@@ -0,0 +1,104 @@
+use actix::System;
+use serde_json::{json, Value};
+
+use near_actix_test_utils::run_actix;
+use near_crypto::{KeyType, SecretKey};
+use near_jsonrpc_primitives::errors::RpcErrorKind;
+use near_jsonrpc_primitives::types::admin::RpcUpdateAuthorizationResponse;
+use near_o11y::testonly::init_test_logger;
+
+use near_jsonrpc_tests as test_utils;
+
+/// The admin methods are not available unless enabled in the config.
+#[test]
+fn test_admin_rpc_disabled() {
+    init_test_logger();
+
+    run_actix(async {
+        let (_view_client_addr, addr) = test_utils::start_all(test_utils::NodeType::NonValidator);
+
+        actix::spawn(async move {
+            let client = awc::Client::new();
+            let server_addr = format!("http://{}", addr);
+            for method in ["admin_ban_peer", "admin_unban_peer"] {
+                let error = test_utils::call_method::<Value>(
+                    &client,
+                    &server_addr,
+                    method,
+                    json!({"rule": "192.0.2.4"}),
+                )
+                .await
+                .unwrap_err();
+                assert_eq!(error.code, -32_601, "{method}: {error:?}");
+            }
+            System::current().stop();
+        });
+    });
+}
+
+#[test]
+fn test_admin_ban_and_unban_peer() {
+    init_test_logger();
+
+    run_actix(async {
+        let addr = test_utils::start_all_with_admin_rpc(test_utils::NodeType::NonValidator);
+
+        actix::spawn(async move {
+            let client = awc::Client::new();
+            let server_addr = format!("http://{}", addr);
+            let peer_id = SecretKey::from_seed(KeyType::ED25519, "peer").public_key();
+            for rule in [peer_id.to_string(), "192.0.2.0/24".to_string()] {
+                let response: RpcUpdateAuthorizationResponse = test_utils::call_method(
+                    &client,
+                    &server_addr,
+                    "admin_ban_peer",
+                    json!({"rule": rule, "duration_secs": 3600}),
+                )
+                .await
+                .unwrap();
+                // The node has no peers to disconnect.
+                assert!(response.disconnected_peers.is_empty());
+
+                let response: RpcUpdateAuthorizationResponse = test_utils::call_method(
+                    &client,
+                    &server_addr,
+                    "admin_unban_peer",
+                    json!({"rule": rule}),
+                )
+                .await
+                .unwrap();
+                assert!(response.disconnected_peers.is_empty());
+            }
+            System::current().stop();
+        });
+    });
+}
+
+#[test]
+fn test_admin_ban_peer_invalid_rule() {
+    init_test_logger();
+
+    run_actix(async {
+        let addr = test_utils::start_all_with_admin_rpc(test_utils::NodeType::NonValidator);
+
+        actix::spawn(async move {
+            let client = awc::Client::new();
+            let server_addr = format!("http://{}", addr);
+            for (method, params) in [
+                ("admin_ban_peer", json!({"rule": "192.0.2.*", "duration_secs": 3600})),
+                ("admin_unban_peer", json!({"rule": "192.0.2.0/33"})),
+            ] {
+                let error = test_utils::call_method::<Value>(&client, &server_addr, method, params)
+                    .await
+                    .unwrap_err();
+                match error.error_struct {
+                    Some(RpcErrorKind::HandlerError(cause)) => {
+                        assert_eq!(cause["name"], "INVALID_RULE", "{method}")
+                    }
+                    error => panic!("{method}: unexpected error {error:?}"),
+                }
+            }
+            System::current().stop();
+        });
+    });
+}
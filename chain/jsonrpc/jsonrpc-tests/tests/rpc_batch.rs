@@ -0,0 +1,109 @@
+use actix::System;
+use serde_json::{json, Value};
+
+use near_actix_test_utils::run_actix;
+use near_jsonrpc_primitives::errors::RpcError;
+use near_jsonrpc_primitives::message::{from_slice, Message};
+use near_o11y::testonly::init_test_logger;
+
+use near_jsonrpc_tests as test_utils;
+
+/// Posts a raw JSON-RPC payload, returns the HTTP status and the response message if any.
+async fn post(addr: &str, payload: Value) -> (awc::http::StatusCode, Option<Message>) {
+    let mut response =
+        awc::Client::new().post(format!("http://{}", addr)).send_json(&payload).await.unwrap();
+    let body = response.body().await.unwrap();
+    let message = if body.is_empty() { None } else { Some(from_slice(&body).unwrap()) };
+    (response.status(), message)
+}
+
+fn status_request(id: u64) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "method": "status", "params": []})
+}
+
+/// Only the requests of a batch get a response, notifications are not answered.
+#[test]
+fn test_batch_with_notifications() {
+    init_test_logger();
+
+    run_actix(async {
+        let (_view_client_addr, addr) = test_utils::start_all(test_utils::NodeType::NonValidator);
+
+        actix::spawn(async move {
+            let addr = addr.to_string();
+            let notification = json!({"jsonrpc": "2.0", "method": "status", "params": []});
+            let (status, message) =
+                post(&addr, json!([status_request(1), notification.clone(), status_request(2)]))
+                    .await;
+            assert!(status.is_success());
+            match message.unwrap() {
+                Message::Batch(responses) => {
+                    let ids: Vec<Value> = responses
+                        .into_iter()
+                        .map(|response| match response {
+                            Message::Response(response) => {
+                                assert!(response.result.is_ok());
+                                response.id
+                            }
+                            message => panic!("Unexpected message {:?}", message),
+                        })
+                        .collect();
+                    assert_eq!(ids, vec![json!(1), json!(2)]);
+                }
+                message => panic!("Unexpected message {:?}", message),
+            }
+
+            let (status, message) = post(&addr, json!([notification])).await;
+            assert_eq!(status, awc::http::StatusCode::NO_CONTENT);
+            assert!(message.is_none());
+
+            System::current().stop();
+        });
+    });
+}
+
+#[test]
+fn test_empty_batch() {
+    init_test_logger();
+
+    run_actix(async {
+        let (_view_client_addr, addr) = test_utils::start_all(test_utils::NodeType::NonValidator);
+
+        actix::spawn(async move {
+            let (_status, message) = post(&addr.to_string(), json!([])).await;
+            match message.unwrap() {
+                Message::Response(response) => {
+                    assert_eq!(response.id, Value::Null);
+                    assert_eq!(response.result.unwrap_err().code, -32_600);
+                }
+                message => panic!("Unexpected message {:?}", message),
+            }
+            System::current().stop();
+        });
+    });
+}
+
+#[test]
+fn test_batch_too_large() {
+    init_test_logger();
+
+    run_actix(async {
+        let (_view_client_addr, addr) = test_utils::start_all(test_utils::NodeType::NonValidator);
+
+        actix::spawn(async move {
+            let max_batch_size = near_jsonrpc::RpcLimitsConfig::default().max_batch_size;
+            let batch: Vec<Value> = (0..max_batch_size as u64 + 1).map(status_request).collect();
+            let (_status, message) = post(&addr.to_string(), Value::Array(batch)).await;
+            match message.unwrap() {
+                Message::Response(response) => {
+                    let error = response.result.unwrap_err();
+                    let expected = RpcError::batch_too_large(max_batch_size);
+                    assert_eq!(error.code, expected.code);
+                    assert_eq!(error.data, expected.data);
+                }
+                message => panic!("Unexpected message {:?}", message),
+            }
+            System::current().stop();
+        });
+    });
+}
@@ -324,6 +324,10 @@ fn test_query_call_function() {
                     account_id: "test".parse().unwrap(),
                     method_name: "method".to_string(),
                     args: vec![].into(),
+                    signer_id: None,
+                    predecessor_id: None,
+                    attached_deposit: None,
+                    gas: None,
                 },
             })
             .await
@@ -0,0 +1,121 @@
+use actix::System;
+use awc::ws;
+use futures::{SinkExt, Stream, StreamExt};
+use serde_json::{json, Value};
+
+use near_actix_test_utils::run_actix;
+use near_jsonrpc_primitives::message::{from_slice, Message};
+use near_o11y::testonly::init_test_logger;
+
+use near_jsonrpc_tests as test_utils;
+
+async fn send<S>(connection: &mut S, request: Value)
+where
+    S: futures::Sink<ws::Message> + Unpin,
+    S::Error: std::fmt::Debug,
+{
+    connection.send(ws::Message::Text(request.to_string().into())).await.unwrap();
+}
+
+/// Returns the next JSON-RPC message, skipping control frames.
+async fn next_message<S>(connection: &mut S) -> Message
+where
+    S: Stream<Item = Result<ws::Frame, ws::ProtocolError>> + Unpin,
+{
+    loop {
+        match connection.next().await.unwrap().unwrap() {
+            ws::Frame::Text(text) => return from_slice(&text).unwrap(),
+            ws::Frame::Ping(_) | ws::Frame::Pong(_) => {}
+            frame => panic!("Unexpected frame {:?}", frame),
+        }
+    }
+}
+
+/// Subscribes to new heads, receives a few of them and unsubscribes.
+#[test]
+fn test_subscribe_new_heads() {
+    init_test_logger();
+
+    run_actix(async {
+        let (_view_client_addr, addr) = test_utils::start_all(test_utils::NodeType::Validator);
+
+        actix::spawn(async move {
+            let (_response, mut connection) =
+                awc::Client::new().ws(format!("ws://{}/ws", addr)).connect().await.unwrap();
+
+            send(
+                &mut connection,
+                json!({"jsonrpc": "2.0", "id": 1, "method": "subscribe", "params": {"kind": "new_heads"}}),
+            )
+            .await;
+            let subscription = match next_message(&mut connection).await {
+                Message::Response(response) => {
+                    assert_eq!(response.id, json!(1));
+                    response.result.unwrap()["subscription"].clone()
+                }
+                message => panic!("Unexpected message {:?}", message),
+            };
+
+            let mut last_height = None;
+            for _ in 0..3 {
+                match next_message(&mut connection).await {
+                    Message::Notification(notification) => {
+                        assert_eq!(notification.method, "subscription");
+                        assert_eq!(notification.params["subscription"], subscription);
+                        let height = notification.params["result"]["height"].as_u64().unwrap();
+                        assert!(last_height.map_or(true, |last_height| height > last_height));
+                        last_height = Some(height);
+                    }
+                    message => panic!("Unexpected message {:?}", message),
+                }
+            }
+
+            send(
+                &mut connection,
+                json!({"jsonrpc": "2.0", "id": 2, "method": "unsubscribe", "params": {"subscription": subscription}}),
+            )
+            .await;
+            // Notifications sent before the request was processed may still arrive.
+            loop {
+                match next_message(&mut connection).await {
+                    Message::Notification(_) => {}
+                    Message::Response(response) => {
+                        assert_eq!(response.id, json!(2));
+                        assert_eq!(response.result.unwrap(), json!(true));
+                        break;
+                    }
+                    message => panic!("Unexpected message {:?}", message),
+                }
+            }
+
+            // Nothing is sent after unsubscribing, so the next message answers the next request.
+            send(
+                &mut connection,
+                json!({"jsonrpc": "2.0", "id": 3, "method": "status", "params": []}),
+            )
+            .await;
+            match next_message(&mut connection).await {
+                Message::Response(response) => {
+                    assert_eq!(response.id, json!(3));
+                    assert!(response.result.is_ok());
+                }
+                message => panic!("Unexpected message {:?}", message),
+            }
+
+            send(
+                &mut connection,
+                json!({"jsonrpc": "2.0", "id": 4, "method": "unsubscribe", "params": {"subscription": subscription}}),
+            )
+            .await;
+            match next_message(&mut connection).await {
+                Message::Response(response) => {
+                    assert_eq!(response.id, json!(4));
+                    assert!(response.result.is_err());
+                }
+                message => panic!("Unexpected message {:?}", message),
+            }
+
+            System::current().stop();
+        });
+    });
+}
@@ -224,3 +224,25 @@ fn test_check_invalid_tx() {
         }
     });
 }
+
+#[test]
+fn test_tx_pool_filtered_by_unknown_account() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let request = near_jsonrpc_primitives::types::tx_pool::RpcTxPoolRequest {
+            account_id: Some("unknown.near".parse().unwrap()),
+            ..Default::default()
+        };
+        let response = client.EXPERIMENTAL_tx_pool(request).await.unwrap();
+        assert!(response.tx_pool.shards.iter().all(|shard| shard.groups.is_empty()));
+    });
+}
+
+#[test]
+fn test_simulate_tx_without_transaction() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let request = serde_json::json!({ "finality": "final", "max_blocks": 10 });
+        let err = client.EXPERIMENTAL_simulate_tx(request).await.unwrap_err();
+        let s = serde_json::to_string(&err.data.unwrap()).unwrap();
+        assert!(s.contains("Exactly one of signed_tx_base64 and tx_base64"), "{}", s);
+    });
+}
@@ -38,6 +38,16 @@ function computeTraffic(bytes_received, bytes_sent) {
     return "⬇ " + convertBps(bytes_received) + "<br>⬆ " + convertBps(bytes_sent);
 }
 
+// Traffic of the compressed messages, and how much of it compression saved.
+function computeCompression(peer) {
+    let saved = (uncompressed, compressed) =>
+        uncompressed > 0 ? " (" + Math.round(100 * (1 - compressed / uncompressed)) + "% saved)" : "";
+    return "⬇ " + convertBps(peer.received_compressed_bytes_per_sec)
+        + saved(peer.received_uncompressed_bytes_per_sec, peer.received_compressed_bytes_per_sec)
+        + "<br>⬆ " + convertBps(peer.sent_compressed_bytes_per_sec)
+        + saved(peer.sent_uncompressed_bytes_per_sec, peer.sent_compressed_bytes_per_sec);
+}
+
 function add_debug_port_link(peer_network_addr) {
     // Each node running in a machine is assigned ports 24567 + peer_num and 3030 + peer_num, whereby peer_num is a whole number
     // peer_rpc_address is not shared between peer nodes. Hence, it cannot be programmatically fetched.
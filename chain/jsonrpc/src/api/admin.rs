@@ -0,0 +1,32 @@
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::admin::{
+    RpcAdminError, RpcBanPeerRequest, RpcUnbanPeerRequest,
+};
+use near_network::authorization::ParseRuleError;
+use serde_json::Value;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcBanPeerRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcUnbanPeerRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcAdminError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<ParseRuleError> for RpcAdminError {
+    fn rpc_from(error: ParseRuleError) -> Self {
+        Self::InvalidRule { error_message: error.to_string() }
+    }
+}
@@ -53,6 +53,9 @@ impl RpcFrom<GetStateChangesError> for RpcStateChangesError {
                 Self::UnknownBlock { error_message }
             }
             GetStateChangesError::NotSyncedYet => Self::NotSyncedYet,
+            GetStateChangesError::PrunedData { block_height, retention_tail } => {
+                Self::PrunedData { block_height, retention_tail }
+            }
             GetStateChangesError::Unreachable { ref error_message } => {
                 tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
                 crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
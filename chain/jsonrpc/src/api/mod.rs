@@ -3,6 +3,7 @@ use serde_json::Value;
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::errors::{RpcError, ServerError};
 
+mod admin;
 mod blocks;
 mod changes;
 mod chunks;
@@ -15,9 +16,12 @@ mod network_info;
 mod query;
 mod receipts;
 mod sandbox;
+mod simulate_transaction;
 mod split_storage;
 mod status;
+mod subscriptions;
 mod transactions;
+mod tx_pool;
 mod validator;
 
 pub(crate) trait RpcRequest: Sized {
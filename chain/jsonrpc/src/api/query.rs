@@ -77,6 +77,10 @@ fn parse_path_data(path: String, data: String) -> Result<RpcQueryRequest, RpcPar
                 account_id,
                 method_name: method_name.to_string(),
                 args: parse_data()?.into(),
+                signer_id: None,
+                predecessor_id: None,
+                attached_deposit: None,
+                gas: None,
             },
             None => return Err(RpcParseError("Method name is missing".to_string())),
         },
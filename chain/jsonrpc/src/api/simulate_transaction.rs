@@ -0,0 +1,104 @@
+use serde_json::Value;
+use serde_with::base64::Base64;
+use serde_with::serde_as;
+
+use near_client_primitives::types::SimulateTransactionError;
+use near_crypto::{KeyType, Signature};
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::simulate_transaction::{
+    RpcSimulateTransactionError, RpcSimulateTransactionRequest,
+};
+use near_primitives::borsh::BorshDeserialize;
+use near_primitives::transaction::{SignedTransaction, Transaction};
+use near_primitives::types::{BlockReference, Finality};
+
+use super::{Params, RpcFrom, RpcRequest};
+
+/// Number of simulated blocks when the request doesn't specify `max_blocks`.
+const DEFAULT_MAX_BLOCKS: u64 = 20;
+/// Simulation holds all the changes in memory, so it can't run for too long.
+const MAX_BLOCKS_LIMIT: u64 = 100;
+
+impl RpcRequest for RpcSimulateTransactionRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        #[serde_as]
+        #[derive(serde::Deserialize)]
+        struct Payload {
+            #[serde(flatten)]
+            block_reference: Option<BlockReference>,
+            #[serde_as(as = "Option<Base64>")]
+            #[serde(default)]
+            signed_tx_base64: Option<Vec<u8>>,
+            /// Borsh-serialized `Transaction` without a signature.
+            #[serde_as(as = "Option<Base64>")]
+            #[serde(default)]
+            tx_base64: Option<Vec<u8>>,
+            #[serde(default)]
+            max_blocks: Option<u64>,
+        }
+
+        let payload = Params::<Payload>::parse(value)?;
+        let decode_err =
+            |err: std::io::Error| RpcParseError(format!("Failed to decode transaction: {}", err));
+        let (signed_transaction, verify_signature) =
+            match (payload.signed_tx_base64, payload.tx_base64) {
+                (Some(bytes), None) => {
+                    (SignedTransaction::try_from_slice(&bytes).map_err(decode_err)?, true)
+                }
+                (None, Some(bytes)) => {
+                    let transaction = Transaction::try_from_slice(&bytes).map_err(decode_err)?;
+                    (SignedTransaction::new(Signature::empty(KeyType::ED25519), transaction), false)
+                }
+                _ => {
+                    return Err(RpcParseError(
+                        "Exactly one of signed_tx_base64 and tx_base64 must be provided"
+                            .to_string(),
+                    ))
+                }
+            };
+        let max_blocks = payload.max_blocks.unwrap_or(DEFAULT_MAX_BLOCKS);
+        if max_blocks == 0 || max_blocks > MAX_BLOCKS_LIMIT {
+            return Err(RpcParseError(format!(
+                "max_blocks must be between 1 and {}",
+                MAX_BLOCKS_LIMIT
+            )));
+        }
+        Ok(Self {
+            block_reference: payload
+                .block_reference
+                .unwrap_or(BlockReference::Finality(Finality::Final)),
+            signed_transaction,
+            verify_signature,
+            max_blocks,
+        })
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcSimulateTransactionError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<SimulateTransactionError> for RpcSimulateTransactionError {
+    fn rpc_from(error: SimulateTransactionError) -> Self {
+        match error {
+            SimulateTransactionError::NoSyncedBlocks => Self::NoSyncedBlocks,
+            SimulateTransactionError::UnknownBlock { block_reference } => {
+                Self::UnknownBlock { block_reference }
+            }
+            SimulateTransactionError::GarbageCollectedBlock { block_height, block_hash } => {
+                Self::GarbageCollectedBlock { block_height, block_hash }
+            }
+            SimulateTransactionError::UnavailableShard { requested_shard_id } => {
+                Self::UnavailableShard { requested_shard_id }
+            }
+            SimulateTransactionError::InvalidTransaction { error } => {
+                Self::InvalidTransaction { context: error }
+            }
+            SimulateTransactionError::InternalError { error_message } => {
+                Self::InternalError { error_message }
+            }
+        }
+    }
+}
@@ -49,6 +49,9 @@ impl RpcFrom<near_client_primitives::debug::DebugStatusResponse>
                     x,
                 )
             }
+            near_client_primitives::debug::DebugStatusResponse::TxPool(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::TxPool(x)
+            }
         }
     }
 }
@@ -0,0 +1,17 @@
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::subscriptions::{RpcSubscribeRequest, RpcUnsubscribeRequest};
+use serde_json::Value;
+
+use super::{Params, RpcRequest};
+
+impl RpcRequest for RpcSubscribeRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcUnsubscribeRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
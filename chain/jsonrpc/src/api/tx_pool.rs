@@ -0,0 +1,24 @@
+use near_client_primitives::types::GetTxPoolError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::tx_pool::{RpcTxPoolError, RpcTxPoolRequest};
+use serde_json::Value;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcTxPoolRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcTxPoolError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetTxPoolError> for RpcTxPoolError {
+    fn rpc_from(error: GetTxPoolError) -> Self {
+        match error {}
+    }
+}
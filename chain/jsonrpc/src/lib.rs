@@ -14,10 +14,10 @@ use near_client::{
     ClientActor, DebugStatus, GetBlock, GetBlockProof, GetChunk, GetClientConfig,
     GetExecutionOutcome, GetGasPrice, GetMaintenanceWindows, GetNetworkInfo,
     GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
-    GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered, ProcessTxRequest,
+    GetStateChangesInBlock, GetTxPool, GetValidatorInfo, GetValidatorOrdered, ProcessTxRequest,
     ProcessTxResponse, Query, Status, TxStatus, ViewClientActor,
 };
-use near_client_primitives::types::GetSplitStorageInfo;
+use near_client_primitives::types::{GetSplitStorageInfo, SimulateTransaction};
 pub use near_jsonrpc_client as client;
 use near_jsonrpc_primitives::errors::RpcError;
 use near_jsonrpc_primitives::message::{Message, Request};
@@ -25,7 +25,11 @@ use near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse;
 use near_jsonrpc_primitives::types::entity_debug::{EntityDebugHandler, EntityQuery};
 use near_jsonrpc_primitives::types::query::RpcQueryRequest;
 use near_jsonrpc_primitives::types::split_storage::RpcSplitStorageInfoResponse;
+use near_jsonrpc_primitives::types::subscriptions::RpcSubscriptionError;
 use near_network::tcp;
+use near_network::types::{
+    AuthorizationUpdate, PeerManagerMessageRequest, PeerManagerMessageResponse,
+};
 use near_network::PeerManagerActor;
 use near_o11y::metrics::{prometheus, Encoder, TextEncoder};
 use near_o11y::{WithSpanContext, WithSpanContextExt};
@@ -34,6 +38,8 @@ use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{AccountId, BlockHeight};
 use near_primitives::views::{FinalExecutionOutcomeViewEnum, QueryRequest};
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -42,6 +48,11 @@ use tracing::{error, info};
 
 mod api;
 mod metrics;
+mod rate_limit;
+mod subscriptions;
+
+use rate_limit::RateLimiter;
+pub use rate_limit::{RpcRateLimit, ANY_METHOD};
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
 pub struct RpcPollingConfig {
@@ -62,18 +73,44 @@ impl Default for RpcPollingConfig {
 pub struct RpcLimitsConfig {
     /// Maximum byte size of the json payload.
     pub json_payload_max_size: usize,
+    /// Maximum number of requests in a batch.
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+    /// Limits of the requests of every client IP address, by method name. The limit under `*`
+    /// applies to the methods without a limit of their own. Every request of a batch counts.
+    /// Clients are identified by the address of the connection, so all clients behind the same
+    /// proxy share the limits unless the proxy is listed in `trusted_proxies`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub rate_limits: HashMap<String, RpcRateLimit>,
+    /// Addresses of the reverse proxies in front of the RPC server. Requests coming through them
+    /// are rate limited by the client address from the `X-Forwarded-For` header instead.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_proxies: Vec<IpAddr>,
 }
 
 impl Default for RpcLimitsConfig {
     fn default() -> Self {
-        Self { json_payload_max_size: 10 * 1024 * 1024 }
+        Self {
+            json_payload_max_size: 10 * 1024 * 1024,
+            max_batch_size: default_max_batch_size(),
+            rate_limits: HashMap::new(),
+            trusted_proxies: vec![],
+        }
     }
 }
 
+fn default_max_batch_size() -> usize {
+    100
+}
+
 fn default_enable_debug_rpc() -> bool {
     false
 }
 
+fn default_enable_admin_rpc() -> bool {
+    false
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct RpcConfig {
     pub addr: tcp::ListenerAddr,
@@ -87,6 +124,10 @@ pub struct RpcConfig {
     // We disable it by default, as some of those endpoints might be quite CPU heavy.
     #[serde(default = "default_enable_debug_rpc")]
     pub enable_debug_rpc: bool,
+    // If true, enable the RPC methods which change the state of the node, like `admin_ban_peer`.
+    // Make sure the RPC is not reachable by untrusted clients before enabling them.
+    #[serde(default = "default_enable_admin_rpc")]
+    pub enable_admin_rpc: bool,
     // For node developers only: if specified, the HTML files used to serve the debug pages will
     // be read from this directory, instead of the contents compiled into the binary. This allows
     // for quick iterative development.
@@ -102,6 +143,7 @@ impl Default for RpcConfig {
             polling_config: Default::default(),
             limits_config: Default::default(),
             enable_debug_rpc: false,
+            enable_admin_rpc: false,
             experimental_debug_pages_src_path: None,
         }
     }
@@ -221,26 +263,95 @@ struct JsonRpcHandler {
     polling_config: RpcPollingConfig,
     genesis_config: GenesisConfig,
     enable_debug_rpc: bool,
+    enable_admin_rpc: bool,
     debug_pages_src_path: Option<PathBuf>,
     entity_debug_handler: Arc<dyn EntityDebugHandler>,
+    max_batch_size: usize,
+    rate_limiter: Arc<RateLimiter>,
+    trusted_proxies: Vec<IpAddr>,
+    /// Notifications about new heads for the WebSocket subscriptions.
+    head_notifications:
+        tokio::sync::broadcast::Sender<Arc<near_client_primitives::types::HeadNotification>>,
 }
 
 impl JsonRpcHandler {
-    pub async fn process(&self, message: Message) -> Result<Message, HttpError> {
+    /// Processes a request or a batch of requests from the client with the given IP address.
+    /// Rate limits apply only to the requests from known addresses.
+    /// Returns `None` if there is nothing to respond, i.e. the batch consists of notifications.
+    pub async fn process(
+        &self,
+        message: Message,
+        client: Option<IpAddr>,
+    ) -> Result<Option<Message>, HttpError> {
         let id = message.id();
-        match message {
+        let response = match message {
             Message::Request(request) => {
-                Ok(Message::response(id, self.process_request(request).await))
+                Message::response(id, self.process_request(request, client).await)
+            }
+            Message::Batch(messages) => {
+                if messages.is_empty() {
+                    return Ok(Some(Message::error(RpcError::empty_batch())));
+                }
+                if messages.len() > self.max_batch_size {
+                    return Ok(Some(Message::error(RpcError::batch_too_large(
+                        self.max_batch_size,
+                    ))));
+                }
+                let responses =
+                    futures::future::join_all(messages.into_iter().map(|message| async move {
+                        match message {
+                            Message::Request(request) => {
+                                let id = request.id.clone();
+                                Some(Message::response(
+                                    id,
+                                    self.process_request(request, client).await,
+                                ))
+                            }
+                            // Notifications are processed, but never answered.
+                            Message::Notification(notification) => {
+                                let _ =
+                                    self.process_request(notification.into_request(), client).await;
+                                None
+                            }
+                            _ => Some(Message::error(RpcError::parse_error(
+                                "JSON RPC Request format was expected".to_owned(),
+                            ))),
+                        }
+                    }))
+                    .await;
+                let responses: Vec<Message> = responses.into_iter().flatten().collect();
+                if responses.is_empty() {
+                    return Ok(None);
+                }
+                Message::Batch(responses)
             }
-            _ => Ok(Message::error(RpcError::parse_error(
+            _ => Message::error(RpcError::parse_error(
                 "JSON RPC Request format was expected".to_owned(),
-            ))),
+            )),
+        };
+        Ok(Some(response))
+    }
+
+    /// Returns an error if the client exceeded its rate limit of the method.
+    fn check_rate_limit(&self, client: Option<IpAddr>, method: &str) -> Result<(), RpcError> {
+        let Some(client) = client else {
+            return Ok(());
+        };
+        if let Err(limit_name) = self.rate_limiter.try_acquire(client, method) {
+            metrics::RPC_RATE_LIMITED_COUNT.with_label_values(&[limit_name]).inc();
+            return Err(RpcError::too_many_requests(method.to_owned()));
         }
+        Ok(())
     }
 
     // `process_request` increments affected metrics but the request processing is done by
     // `process_request_internal`.
-    async fn process_request(&self, request: Request) -> Result<Value, RpcError> {
+    async fn process_request(
+        &self,
+        request: Request,
+        client: Option<IpAddr>,
+    ) -> Result<Value, RpcError> {
+        self.check_rate_limit(client, &request.method)?;
         let timer = Instant::now();
         let (metrics_name, response) = self.process_request_internal(request).await;
 
@@ -310,6 +421,12 @@ impl JsonRpcHandler {
     ) -> Result<Result<Value, RpcError>, Request> {
         Ok(match request.method.as_ref() {
             // Handlers ordered alphabetically
+            "admin_ban_peer" if self.enable_admin_rpc => {
+                process_method_call(request, |params| self.admin_ban_peer(params)).await
+            }
+            "admin_unban_peer" if self.enable_admin_rpc => {
+                process_method_call(request, |params| self.admin_unban_peer(params)).await
+            }
             "block" => process_method_call(request, |params| self.block(params)).await,
             "broadcast_tx_async" => {
                 process_method_call(request, |params| async {
@@ -335,6 +452,7 @@ impl JsonRpcHandler {
             }
             "network_info" => process_method_call(request, |_params: ()| self.network_info()).await,
             "status" => process_method_call(request, |_params: ()| self.status()).await,
+            "subscribe" | "unsubscribe" => Err(RpcSubscriptionError::WebSocketRequired.into()),
             "tx" => {
                 process_method_call(request, |params| self.tx_status_common(params, false)).await
             }
@@ -372,6 +490,9 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_receipt" => {
                 process_method_call(request, |params| self.receipt(params)).await
             }
+            "EXPERIMENTAL_tx_pool" => {
+                process_method_call(request, |params| self.tx_pool(params)).await
+            }
             "EXPERIMENTAL_tx_status" => {
                 process_method_call(request, |params| self.tx_status_common(params, true)).await
             }
@@ -384,6 +505,9 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_split_storage_info" => {
                 process_method_call(request, |params| self.split_storage_info(params)).await
             }
+            "EXPERIMENTAL_simulate_tx" => {
+                process_method_call(request, |params| self.simulate_tx(params)).await
+            }
             #[cfg(feature = "sandbox")]
             "sandbox_patch_state" => {
                 process_method_call(request, |params| self.sandbox_patch_state(params)).await
@@ -717,6 +841,51 @@ impl JsonRpcHandler {
         }
     }
 
+    async fn tx_pool(
+        &self,
+        request_data: near_jsonrpc_primitives::types::tx_pool::RpcTxPoolRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::tx_pool::RpcTxPoolResponse,
+        near_jsonrpc_primitives::types::tx_pool::RpcTxPoolError,
+    > {
+        let near_jsonrpc_primitives::types::tx_pool::RpcTxPoolRequest {
+            account_id,
+            public_key,
+            transaction_hash,
+        } = request_data;
+        let tx_pool =
+            self.client_send(GetTxPool { account_id, public_key, transaction_hash }).await?;
+        Ok(near_jsonrpc_primitives::types::tx_pool::RpcTxPoolResponse { tx_pool })
+    }
+
+    /// Executes the transaction and its receipts on top of the state at the
+    /// given block without changing the state or broadcasting the transaction.
+    async fn simulate_tx(
+        &self,
+        request_data: near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionResponse,
+        near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionError,
+    > {
+        let near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionRequest {
+            block_reference,
+            signed_transaction,
+            verify_signature,
+            max_blocks,
+        } = request_data;
+        let simulation = self
+            .view_client_send(SimulateTransaction {
+                block_reference,
+                signed_transaction,
+                verify_signature,
+                max_blocks,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionResponse {
+            simulation,
+        })
+    }
+
     async fn send_tx_commit(
         &self,
         request_data: near_jsonrpc_primitives::types::transactions::RpcBroadcastTransactionRequest,
@@ -829,6 +998,7 @@ impl JsonRpcHandler {
                     "/debug/api/requested_state_parts" => {
                         self.client_send(DebugStatus::RequestedStateParts).await?.rpc_into()
                     }
+                    "/debug/api/tx_pool" => self.client_send(DebugStatus::TxPool).await?.rpc_into(),
                     "/debug/api/peer_store" => self
                         .peer_manager_send(near_network::debug::GetDebugStatus::PeerStore)
                         .await?
@@ -1132,6 +1302,55 @@ impl JsonRpcHandler {
         Ok(near_jsonrpc_primitives::types::client_config::RpcClientConfigResponse { client_config })
     }
 
+    async fn admin_ban_peer(
+        &self,
+        request_data: near_jsonrpc_primitives::types::admin::RpcBanPeerRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::admin::RpcUpdateAuthorizationResponse,
+        near_jsonrpc_primitives::types::admin::RpcAdminError,
+    > {
+        let rule = request_data.rule.parse().map_err(RpcFrom::rpc_from)?;
+        let duration = near_async::time::Duration::seconds(
+            i64::try_from(request_data.duration_secs).unwrap_or(i64::MAX),
+        );
+        self.update_authorization(AuthorizationUpdate::Ban(rule, duration)).await
+    }
+
+    async fn admin_unban_peer(
+        &self,
+        request_data: near_jsonrpc_primitives::types::admin::RpcUnbanPeerRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::admin::RpcUpdateAuthorizationResponse,
+        near_jsonrpc_primitives::types::admin::RpcAdminError,
+    > {
+        let rule = request_data.rule.parse().map_err(RpcFrom::rpc_from)?;
+        self.update_authorization(AuthorizationUpdate::Unban(rule)).await
+    }
+
+    async fn update_authorization(
+        &self,
+        update: AuthorizationUpdate,
+    ) -> Result<
+        near_jsonrpc_primitives::types::admin::RpcUpdateAuthorizationResponse,
+        near_jsonrpc_primitives::types::admin::RpcAdminError,
+    > {
+        match self
+            .peer_manager_send(
+                PeerManagerMessageRequest::UpdateAuthorization(update).with_span_context(),
+            )
+            .await?
+        {
+            PeerManagerMessageResponse::UpdateAuthorization(disconnected_peers) => {
+                Ok(near_jsonrpc_primitives::types::admin::RpcUpdateAuthorizationResponse {
+                    disconnected_peers,
+                })
+            }
+            response => Err(near_jsonrpc_primitives::types::admin::RpcAdminError::InternalError {
+                error_message: format!("unexpected response {response:?}"),
+            }),
+        }
+    }
+
     pub async fn split_storage_info(
         &self,
         _request_data: near_jsonrpc_primitives::types::split_storage::RpcSplitStorageInfoRequest,
@@ -1353,13 +1572,36 @@ impl JsonRpcHandler {
     }
 }
 
+/// Returns the IP address of the client which sent the request, used for rate limiting.
+/// Requests from trusted proxies are attributed to the client address they add last to the
+/// `X-Forwarded-For` header, as other addresses in it may be set by the client.
+fn client_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+    let forwarded = req
+        .headers()
+        .get_all("X-Forwarded-For")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|address| address.trim().parse::<IpAddr>().ok())
+        .filter(|address| !trusted_proxies.contains(address))
+        .last();
+    Some(forwarded.unwrap_or(peer))
+}
+
 fn rpc_handler(
+    req: HttpRequest,
     message: web::Json<Message>,
     handler: web::Data<JsonRpcHandler>,
 ) -> impl Future<Output = Result<HttpResponse, HttpError>> {
+    let client = client_ip(&req, &handler.trusted_proxies);
     let response = async move {
-        let message = handler.process(message.0).await?;
-        Ok(HttpResponse::Ok().json(&message))
+        match handler.process(message.0, client).await? {
+            Some(message) => Ok(HttpResponse::Ok().json(&message)),
+            None => Ok(HttpResponse::NoContent().finish()),
+        }
     };
     response.boxed()
 }
@@ -1518,6 +1760,8 @@ async fn display_debug_html(
         "sync.css" => Some(debug_page_string!("sync.css", handler)),
         "validator" => Some(debug_page_string!("validator.html", handler)),
         "validator.css" => Some(debug_page_string!("validator.css", handler)),
+        "tx_pool" => Some(debug_page_string!("tx_pool.html", handler)),
+        "tx_pool.css" => Some(debug_page_string!("tx_pool.css", handler)),
         _ => None,
     };
 
@@ -1555,12 +1799,22 @@ pub fn start_http(
         polling_config,
         limits_config,
         enable_debug_rpc,
+        enable_admin_rpc,
         experimental_debug_pages_src_path: debug_pages_src_path,
     } = config;
     let prometheus_addr = prometheus_addr.filter(|it| it != &addr.to_string());
     let cors_allowed_origins_clone = cors_allowed_origins.clone();
     info!(target:"network", "Starting http server at {}", addr);
     let mut servers = Vec::new();
+    // Shared by the workers, so that the limits apply to the server as a whole.
+    let rate_limiter = Arc::new(RateLimiter::new(&limits_config.rate_limits));
+    let head_notifications =
+        tokio::sync::broadcast::channel(subscriptions::HEAD_NOTIFICATIONS_CAPACITY).0;
+    tokio::spawn(subscriptions::run_head_notifier(
+        client_addr.clone(),
+        view_client_addr.clone(),
+        head_notifications.clone(),
+    ));
     let listener = HttpServer::new(move || {
         App::new()
             .wrap(get_cors(&cors_allowed_origins))
@@ -1571,12 +1825,18 @@ pub fn start_http(
                 polling_config,
                 genesis_config: genesis_config.clone(),
                 enable_debug_rpc,
+                enable_admin_rpc,
                 debug_pages_src_path: debug_pages_src_path.clone().map(Into::into),
                 entity_debug_handler: entity_debug_handler.clone(),
+                max_batch_size: limits_config.max_batch_size,
+                rate_limiter: rate_limiter.clone(),
+                trusted_proxies: limits_config.trusted_proxies.clone(),
+                head_notifications: head_notifications.clone(),
             }))
             .app_data(web::JsonConfig::default().limit(limits_config.json_payload_max_size))
             .wrap(middleware::Logger::default())
             .service(web::resource("/").route(web::post().to(rpc_handler)))
+            .service(web::resource("/ws").route(web::get().to(subscriptions::ws_handler)))
             .service(
                 web::resource("/status")
                     .route(web::get().to(status_handler))
@@ -55,3 +55,11 @@ pub static RPC_UNREACHABLE_ERROR_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static RPC_RATE_LIMITED_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_rpc_rate_limited_requests_total",
+        "Total count of RPC requests rejected by rate limits, by the exceeded limit",
+        &["limit"],
+    )
+    .unwrap()
+});
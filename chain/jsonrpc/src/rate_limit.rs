@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use near_primitives::static_clock::StaticClock;
+
+/// Name under which the limit of the methods without a limit of their own is configured.
+pub const ANY_METHOD: &str = "*";
+
+/// How often the state of the clients which didn't use their limits recently is dropped.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Token bucket limit of the requests of a single client.
+///
+/// After a period of inactivity the client may send up to `burst` requests at once. Under
+/// constant load `qps` requests per second are let through.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RpcRateLimit {
+    pub burst: u64,
+    pub qps: f64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: &RpcRateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.qps).min(limit.burst as f64);
+        self.updated = now;
+    }
+}
+
+struct Buckets {
+    /// Buckets by client and index of the limit.
+    buckets: HashMap<(IpAddr, usize), Bucket>,
+    last_cleanup: Instant,
+}
+
+/// Applies the rate limits of `RpcLimitsConfig` to the clients, identified by IP addresses.
+pub(crate) struct RateLimiter {
+    limits: Vec<(String, RpcRateLimit)>,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limits: &HashMap<String, RpcRateLimit>) -> Self {
+        Self {
+            limits: limits.iter().map(|(method, limit)| (method.clone(), *limit)).collect(),
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                last_cleanup: StaticClock::instant(),
+            }),
+        }
+    }
+
+    /// Takes a token from the bucket of the client for the method. If the bucket is empty and
+    /// the request should be rejected, returns the name of the exceeded limit.
+    pub(crate) fn try_acquire(&self, client: IpAddr, method: &str) -> Result<(), &str> {
+        let limit_index = match self
+            .limits
+            .iter()
+            .position(|(name, _)| name == method)
+            .or_else(|| self.limits.iter().position(|(name, _)| name == ANY_METHOD))
+        {
+            Some(limit_index) => limit_index,
+            None => return Ok(()),
+        };
+        let (limit_name, limit) = &self.limits[limit_index];
+        let now = StaticClock::instant();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if now.saturating_duration_since(buckets.last_cleanup) >= CLEANUP_INTERVAL {
+            // Full buckets are equivalent to missing ones.
+            let limits = &self.limits;
+            buckets.buckets.retain(|(_, index), bucket| {
+                let limit = &limits[*index].1;
+                bucket.refill(limit, now);
+                bucket.tokens < limit.burst as f64
+            });
+            buckets.last_cleanup = now;
+        }
+        let bucket = buckets
+            .buckets
+            .entry((client, limit_index))
+            .or_insert_with(|| Bucket { tokens: limit.burst as f64, updated: now });
+        bucket.refill(limit, now);
+        if bucket.tokens < 1.0 {
+            return Err(limit_name);
+        }
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateLimiter, RpcRateLimit, ANY_METHOD};
+    use std::collections::HashMap;
+    use std::net::IpAddr;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limits = HashMap::from([
+            ("query".to_string(), RpcRateLimit { burst: 3, qps: 0.001 }),
+            (ANY_METHOD.to_string(), RpcRateLimit { burst: 1, qps: 0.001 }),
+        ]);
+        let limiter = RateLimiter::new(&limits);
+        let alice: IpAddr = "10.0.0.1".parse().unwrap();
+        let bob: IpAddr = "10.0.0.2".parse().unwrap();
+
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(alice, "query").is_ok());
+        }
+        assert_eq!(limiter.try_acquire(alice, "query"), Err("query"));
+        // Limits are separate for every client and every method.
+        assert!(limiter.try_acquire(bob, "query").is_ok());
+        assert!(limiter.try_acquire(alice, "block").is_ok());
+        assert_eq!(limiter.try_acquire(alice, "block"), Err(ANY_METHOD));
+        // Methods without a limit of their own share the default one.
+        assert_eq!(limiter.try_acquire(alice, "chunk"), Err(ANY_METHOD));
+        assert!(limiter.try_acquire(bob, "chunk").is_ok());
+    }
+
+    #[test]
+    fn test_no_limits() {
+        let limiter = RateLimiter::new(&HashMap::new());
+        for _ in 0..100 {
+            assert!(limiter.try_acquire("10.0.0.1".parse().unwrap(), "query").is_ok());
+        }
+    }
+}
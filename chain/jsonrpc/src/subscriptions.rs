@@ -0,0 +1,379 @@
+//! WebSocket endpoint of the JSON-RPC server.
+//!
+//! Besides regular requests, the endpoint serves `subscribe` and `unsubscribe` requests.
+//! Notifications of a subscription are sent as `subscription` JSON-RPC notifications with the
+//! subscription id and the result in params. Subscriptions are fed by the head notifications
+//! built by a single `run_head_notifier` task, which follows the new heads published by the
+//! client and reads the rest of the data with the view client.
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use actix::Addr;
+use actix_http::ws;
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::{web, Error as HttpError, HttpRequest, HttpResponse};
+use bytes::{Bytes, BytesMut};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use near_client::{
+    ClientActor, GetHeadNotification, SubscribeBlockNotifications, TxStatus, TxStatusError,
+    ViewClientActor,
+};
+use near_client_primitives::types::HeadNotification;
+use near_jsonrpc_primitives::errors::RpcError;
+use near_jsonrpc_primitives::message::{self, Message};
+use near_jsonrpc_primitives::types::subscriptions::{
+    RpcSubscribeRequest, RpcSubscribeResponse, RpcSubscriptionError, RpcSubscriptionNotification,
+    RpcUnsubscribeRequest, SubscriptionFinality, SubscriptionId,
+};
+use near_o11y::WithSpanContextExt;
+use near_primitives::views::{FinalExecutionOutcomeViewEnum, FinalExecutionStatus};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::api::RpcRequest;
+use crate::{serialize_response, JsonRpcHandler};
+
+/// Maximum number of active subscriptions of a single connection.
+const MAX_SUBSCRIPTIONS: usize = 64;
+/// Number of frames buffered for sending to the peer.
+const OUTGOING_FRAMES_CAPACITY: usize = 64;
+/// Number of head notifications buffered for every WebSocket session.
+pub(crate) const HEAD_NOTIFICATIONS_CAPACITY: usize = 64;
+
+/// Follows the new heads of the client and publishes the head notifications to the WebSocket
+/// sessions. The data is read by the view client once per head, however many sessions there are.
+pub(crate) async fn run_head_notifier(
+    client_addr: Addr<ClientActor>,
+    view_client_addr: Addr<ViewClientActor>,
+    sender: broadcast::Sender<Arc<HeadNotification>>,
+) {
+    let mut receiver = match client_addr
+        .send(SubscribeBlockNotifications {}.with_span_context())
+        .await
+    {
+        Ok(receiver) => receiver,
+        Err(err) => {
+            tracing::warn!(target: "jsonrpc", ?err, "Failed to subscribe to block notifications");
+            return;
+        }
+    };
+    let mut last_notified_final_height = None;
+    loop {
+        let head = match receiver.recv().await {
+            Ok(notification) => notification.head.hash,
+            // Missed heads don't matter: optimistic subscribers only care about the latest head,
+            // and the final blocks are collected from the last notified one.
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::debug!(target: "jsonrpc", skipped, "Head notifier skipped block notifications");
+                continue;
+            }
+            // The client is shutting down.
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        if sender.receiver_count() == 0 {
+            // Nobody is subscribed, so there are no final blocks to catch up with later.
+            last_notified_final_height = None;
+            continue;
+        }
+        let msg = GetHeadNotification { head, last_notified_final_height };
+        match view_client_addr.send(msg.with_span_context()).await {
+            Ok(Ok(notification)) => {
+                if let Some(header) = notification.new_final_blocks.last() {
+                    last_notified_final_height = Some(header.height);
+                }
+                // Fails only if all sessions were dropped in the meantime.
+                let _ = sender.send(Arc::new(notification));
+            }
+            Ok(Err(err)) => {
+                tracing::debug!(target: "jsonrpc", ?err, %head, "Failed to build head notification");
+            }
+            Err(err) => {
+                tracing::warn!(target: "jsonrpc", ?err, %head, "Failed to build head notification");
+            }
+        }
+    }
+}
+
+pub(crate) async fn ws_handler(
+    req: HttpRequest,
+    payload: web::Payload,
+    handler: web::Data<JsonRpcHandler>,
+) -> Result<HttpResponse, HttpError> {
+    ws::verify_handshake(req.head())?;
+    // The presence of the key is checked by `verify_handshake`.
+    let key = ws::hash_key(req.headers().get(header::SEC_WEBSOCKET_KEY).unwrap().as_bytes());
+    let (sender, receiver) = mpsc::channel(OUTGOING_FRAMES_CAPACITY);
+    let client = crate::client_ip(&req, &handler.trusted_proxies);
+    let session = WsSession {
+        handler: handler.into_inner(),
+        client,
+        subscriptions: HashMap::new(),
+        next_subscription_id: 0,
+        notifications: None,
+        codec: ws::Codec::new(),
+        sender,
+    };
+    actix_web::rt::spawn(session.run(payload));
+    Ok(HttpResponse::SwitchingProtocols()
+        .upgrade("websocket")
+        // The hashed key is base64, which is a valid header value.
+        .insert_header((header::SEC_WEBSOCKET_ACCEPT, HeaderValue::from_bytes(&key).unwrap()))
+        .streaming(receiver.map(Ok::<Bytes, std::convert::Infallible>)))
+}
+
+/// A WebSocket connection. Requests of a connection are processed one by one.
+struct WsSession {
+    handler: Arc<JsonRpcHandler>,
+    /// IP address of the peer, used for rate limiting.
+    client: Option<IpAddr>,
+    subscriptions: HashMap<SubscriptionId, RpcSubscribeRequest>,
+    next_subscription_id: SubscriptionId,
+    /// Head notifications, received while the session has subscriptions.
+    notifications: Option<broadcast::Receiver<Arc<HeadNotification>>>,
+    codec: ws::Codec,
+    /// Frames to be sent to the peer. Closed once the peer disconnects.
+    sender: mpsc::Sender<Bytes>,
+}
+
+impl WsSession {
+    async fn run(mut self, mut payload: web::Payload) {
+        let mut buf = BytesMut::new();
+        'session: while !self.sender.is_closed() {
+            tokio::select! {
+                chunk = payload.next() => {
+                    match chunk {
+                        Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+                        Some(Err(err)) => {
+                            tracing::debug!(target: "jsonrpc", ?err, "WebSocket connection failed");
+                            break 'session;
+                        }
+                        None => break 'session,
+                    }
+                    loop {
+                        match self.codec.decode(&mut buf) {
+                            Ok(Some(frame)) => {
+                                if !self.on_frame(frame).await {
+                                    break 'session;
+                                }
+                            }
+                            Ok(None) => break,
+                            Err(err) => {
+                                tracing::debug!(target: "jsonrpc", ?err, "WebSocket protocol error");
+                                break 'session;
+                            }
+                        }
+                    }
+                }
+                notification = next_notification(&mut self.notifications) => {
+                    match notification {
+                        Some(notification) => self.on_head_notification(&notification).await,
+                        // The client is shutting down.
+                        None => break 'session,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns whether the session should go on.
+    async fn on_frame(&mut self, frame: ws::Frame) -> bool {
+        match frame {
+            ws::Frame::Text(text) => self.on_text(&text).await,
+            ws::Frame::Binary(_) | ws::Frame::Continuation(_) => {
+                self.send_message(Message::error(RpcError::parse_error(
+                    "Only unfragmented text messages are supported".to_owned(),
+                )))
+                .await
+            }
+            ws::Frame::Ping(data) => self.send(ws::Message::Pong(data)).await,
+            ws::Frame::Pong(_) => {}
+            ws::Frame::Close(reason) => {
+                self.send(ws::Message::Close(reason)).await;
+                return false;
+            }
+        }
+        true
+    }
+
+    async fn on_text(&mut self, text: &[u8]) {
+        let message = match message::from_slice(text) {
+            Ok(message) => message,
+            Err(broken) => return self.send_message(broken.reply()).await,
+        };
+        let response = match message {
+            Message::Request(request) if request.method == "subscribe" => {
+                let result = match self.handler.check_rate_limit(self.client, &request.method) {
+                    Ok(()) => self.subscribe(request.params),
+                    Err(err) => Err(err),
+                };
+                Message::response(request.id, result)
+            }
+            Message::Request(request) if request.method == "unsubscribe" => {
+                Message::response(request.id, self.unsubscribe(request.params))
+            }
+            message => {
+                let id = message.id();
+                match self.handler.process(message, self.client).await {
+                    Ok(Some(response)) => response,
+                    Ok(None) => return,
+                    Err(err) => Message::response(
+                        id,
+                        Err(RpcError::new_internal_error(None, err.to_string())),
+                    ),
+                }
+            }
+        };
+        self.send_message(response).await;
+    }
+
+    fn subscribe(&mut self, params: Value) -> Result<Value, RpcError> {
+        let request = RpcSubscribeRequest::parse(params)?;
+        if self.subscriptions.len() >= MAX_SUBSCRIPTIONS {
+            return Err(
+                RpcSubscriptionError::TooManySubscriptions { limit: MAX_SUBSCRIPTIONS }.into()
+            );
+        }
+        if self.notifications.is_none() {
+            self.notifications = Some(self.handler.head_notifications.subscribe());
+        }
+        let subscription = self.next_subscription_id;
+        self.next_subscription_id += 1;
+        self.subscriptions.insert(subscription, request);
+        serialize_response(RpcSubscribeResponse { subscription })
+    }
+
+    fn unsubscribe(&mut self, params: Value) -> Result<Value, RpcError> {
+        let RpcUnsubscribeRequest { subscription } = RpcUnsubscribeRequest::parse(params)?;
+        match self.remove_subscription(subscription) {
+            Some(_) => serialize_response(true),
+            None => Err(RpcSubscriptionError::UnknownSubscription { subscription }.into()),
+        }
+    }
+
+    /// Removes the subscription, and stops receiving head notifications after the last one.
+    fn remove_subscription(&mut self, subscription: SubscriptionId) -> Option<RpcSubscribeRequest> {
+        let request = self.subscriptions.remove(&subscription);
+        if self.subscriptions.is_empty() {
+            self.notifications = None;
+        }
+        request
+    }
+
+    async fn on_head_notification(&mut self, notification: &HeadNotification) {
+        let mut notifications = vec![];
+        let mut tx_status_checks = vec![];
+        for (&subscription, request) in &self.subscriptions {
+            match request {
+                RpcSubscribeRequest::NewHeads { finality: SubscriptionFinality::Optimistic } => {
+                    notifications.push((subscription, serialize_response(&notification.head)));
+                }
+                RpcSubscribeRequest::NewHeads { finality: SubscriptionFinality::Final } => {
+                    for header in &notification.new_final_blocks {
+                        notifications.push((subscription, serialize_response(header)));
+                    }
+                }
+                RpcSubscribeRequest::ReceiptOutcomes { account_id } => {
+                    for outcome in notification
+                        .receipt_outcomes
+                        .iter()
+                        .filter(|outcome| &outcome.outcome.executor_id == account_id)
+                    {
+                        notifications.push((subscription, serialize_response(outcome)));
+                    }
+                }
+                RpcSubscribeRequest::TransactionStatus { tx_hash, sender_account_id } => {
+                    if !notification.new_final_blocks.is_empty() {
+                        tx_status_checks.push((
+                            subscription,
+                            TxStatus {
+                                tx_hash: *tx_hash,
+                                signer_account_id: sender_account_id.clone(),
+                                fetch_receipt: false,
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+
+        for (subscription, msg) in tx_status_checks {
+            let tx_hash = msg.tx_hash;
+            match self.handler.view_client_addr.send(msg.with_span_context()).await {
+                Ok(Ok(Some(outcome))) if is_final(&outcome) => {
+                    notifications.push((subscription, serialize_response(outcome)));
+                    self.remove_subscription(subscription);
+                }
+                // The transaction is not known or not executed yet.
+                Ok(Ok(_)) | Ok(Err(TxStatusError::MissingTransaction(_))) => {}
+                Ok(Err(err)) => {
+                    tracing::debug!(target: "jsonrpc", ?err, %tx_hash, "Failed to fetch transaction status");
+                }
+                Err(err) => {
+                    tracing::warn!(target: "jsonrpc", ?err, %tx_hash, "Failed to fetch transaction status");
+                }
+            }
+        }
+
+        for (subscription, result) in notifications {
+            let params = result.and_then(|result| {
+                serialize_response(RpcSubscriptionNotification { subscription, result })
+            });
+            match params {
+                Ok(params) => {
+                    self.send_message(Message::notification("subscription".to_owned(), params))
+                        .await
+                }
+                Err(err) => {
+                    tracing::error!(target: "jsonrpc", ?err, "Failed to serialize subscription notification")
+                }
+            }
+        }
+    }
+
+    async fn send_message(&mut self, message: Message) {
+        let text: String = message.into();
+        self.send(ws::Message::Text(text.into())).await;
+    }
+
+    async fn send(&mut self, message: ws::Message) {
+        let mut buf = BytesMut::new();
+        if let Err(err) = self.codec.encode(message, &mut buf) {
+            tracing::error!(target: "jsonrpc", ?err, "Failed to encode WebSocket message");
+            return;
+        }
+        // Fails only if the peer disconnected, which ends the session.
+        let _ = self.sender.send(buf.freeze()).await;
+    }
+}
+
+/// Waits for the next head notification, skipping the ones missed because of lagging behind.
+/// Never completes if the session isn't subscribed to head notifications.
+async fn next_notification(
+    receiver: &mut Option<broadcast::Receiver<Arc<HeadNotification>>>,
+) -> Option<Arc<HeadNotification>> {
+    let Some(receiver) = receiver else {
+        return std::future::pending().await;
+    };
+    loop {
+        match receiver.recv().await {
+            Ok(notification) => return Some(notification),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::debug!(target: "jsonrpc", skipped, "WebSocket session skipped head notifications");
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+fn is_final(outcome: &FinalExecutionOutcomeViewEnum) -> bool {
+    let status = match outcome {
+        FinalExecutionOutcomeViewEnum::FinalExecutionOutcome(outcome) => &outcome.status,
+        FinalExecutionOutcomeViewEnum::FinalExecutionOutcomeWithReceipt(outcome) => {
+            &outcome.final_outcome.status
+        }
+    };
+    matches!(status, FinalExecutionStatus::SuccessValue(_) | FinalExecutionStatus::Failure(_))
+}
@@ -0,0 +1,435 @@
+//! Rules deciding which peers the node may talk to.
+//!
+//! Peers matching any `deny` rule, and none of the `allow` rules, may neither connect to the node
+//! nor be connected to. On top of the config, peers may be banned temporarily, e.g. by an
+//! operator through the admin JSON-RPC. Temporary bans apply to the allowed peers as well.
+//!
+//! The whitelisted nodes are not subject to the limit of inbound connections.
+//!
+//! The rules may be updated while the node is running, in which case the connected peers which
+//! become denied are disconnected.
+use crate::network_protocol::PeerInfo;
+use near_async::time;
+use near_crypto::PublicKey;
+use near_primitives::network::PeerId;
+use near_primitives::types::AccountId;
+use std::collections::HashMap;
+use std::fmt;
+use std::net;
+
+/// Only IPv6 addresses are stored.  IPv4 addresses are mapped to IPv6 before being added.
+///
+/// Without the mapping, we could deny an IPv4 and still interact with that address if
+/// it is presented as IPv6.
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub enum Rule {
+    /// Addresses whose first `prefix_len` bits are the same as of `ip`. The remaining bits of
+    /// `ip` are zero. A single IP is a range with prefix of length 128.
+    IpRange {
+        ip: net::Ipv6Addr,
+        prefix_len: u8,
+    },
+    IpPort(net::Ipv6Addr, u16),
+    PeerId(PeerId),
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum ParseRuleError {
+    #[error("expected an IP, IP:port, IP/prefix_len or a peer id, got {0:?}")]
+    Format(String),
+    #[error("prefix length {0} is too long for the IP")]
+    PrefixLength(u8),
+    #[error(
+        "expected a peer id with an IP:port or Host:port, and optionally an account id, got {0:?}"
+    )]
+    WhitelistNode(String),
+}
+
+fn to_ipv6(ip: net::IpAddr) -> net::Ipv6Addr {
+    match ip {
+        net::IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        net::IpAddr::V6(ip) => ip,
+    }
+}
+
+fn mask(ip: net::Ipv6Addr, prefix_len: u8) -> net::Ipv6Addr {
+    let mask = u128::MAX.checked_shl(128 - u32::from(prefix_len)).unwrap_or(0);
+    net::Ipv6Addr::from(u128::from(ip) & mask)
+}
+
+impl Rule {
+    pub fn from_ip(ip: net::IpAddr) -> Rule {
+        Rule::IpRange { ip: to_ipv6(ip), prefix_len: 128 }
+    }
+
+    pub fn from_addr(addr: net::SocketAddr) -> Rule {
+        Rule::IpPort(to_ipv6(addr.ip()), addr.port())
+    }
+
+    /// Range of the addresses starting with the first `prefix_len` bits of `ip`. For IPv4,
+    /// `prefix_len` is at most 32.
+    pub fn ip_range(ip: net::IpAddr, prefix_len: u8) -> Result<Rule, ParseRuleError> {
+        let prefix_len = match ip {
+            net::IpAddr::V4(_) if prefix_len <= 32 => prefix_len + 96,
+            net::IpAddr::V6(_) if prefix_len <= 128 => prefix_len,
+            _ => return Err(ParseRuleError::PrefixLength(prefix_len)),
+        };
+        Ok(Rule::IpRange { ip: mask(to_ipv6(ip), prefix_len), prefix_len })
+    }
+
+    /// Whether the peer with the given id, listening at the given address, matches the rule.
+    pub fn matches(&self, peer_id: &PeerId, addr: Option<net::SocketAddr>) -> bool {
+        match self {
+            Rule::IpRange { ip, prefix_len } => {
+                addr.map_or(false, |addr| mask(to_ipv6(addr.ip()), *prefix_len) == *ip)
+            }
+            Rule::IpPort(ip, port) => {
+                addr.map_or(false, |addr| to_ipv6(addr.ip()) == *ip && addr.port() == *port)
+            }
+            Rule::PeerId(id) => id == peer_id,
+        }
+    }
+}
+
+impl std::str::FromStr for Rule {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format_err = || ParseRuleError::Format(s.to_string());
+        if let Some((ip, prefix_len)) = s.split_once('/') {
+            let ip = ip.parse::<net::IpAddr>().map_err(|_| format_err())?;
+            let prefix_len = prefix_len.parse::<u8>().map_err(|_| format_err())?;
+            return Rule::ip_range(ip, prefix_len);
+        }
+        if let Ok(ip) = s.parse::<net::IpAddr>() {
+            return Ok(Rule::from_ip(ip));
+        }
+        if let Ok(addr) = s.parse::<net::SocketAddr>() {
+            return Ok(Rule::from_addr(addr));
+        }
+        if let Ok(key) = s.parse::<PublicKey>() {
+            return Ok(Rule::PeerId(PeerId::new(key)));
+        }
+        Err(format_err())
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let canonical = |ip: &net::Ipv6Addr| -> net::IpAddr {
+            ip.to_ipv4_mapped().map_or(net::IpAddr::V6(*ip), net::IpAddr::V4)
+        };
+        match self {
+            Rule::IpRange { ip, prefix_len: 128 } => write!(f, "{}", canonical(ip)),
+            Rule::IpRange { ip, prefix_len } => match ip.to_ipv4_mapped() {
+                Some(ip) if *prefix_len >= 96 => write!(f, "{}/{}", ip, prefix_len - 96),
+                _ => write!(f, "{}/{}", ip, prefix_len),
+            },
+            Rule::IpPort(ip, port) => write!(f, "{}", net::SocketAddr::new(canonical(ip), *port)),
+            Rule::PeerId(peer_id) => write!(f, "{}", peer_id),
+        }
+    }
+}
+
+impl serde::Serialize for Rule {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+impl<'a> serde::Deserialize<'a> for Rule {
+    fn deserialize<D: serde::Deserializer<'a>>(d: D) -> Result<Self, D::Error> {
+        <String as serde::Deserialize>::deserialize(d)?
+            .parse()
+            .map_err(<D::Error as serde::de::Error>::custom)
+    }
+}
+
+/// Node allowed to connect even if the limit of inbound connections has been reached. Written like
+/// a boot node, `peer_id@ip:port`, optionally followed by `@account_id`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WhitelistNode {
+    id: PeerId,
+    addr: net::SocketAddr,
+    account_id: Option<AccountId>,
+}
+
+impl WhitelistNode {
+    pub fn from_peer_info(peer_info: &PeerInfo) -> Result<Self, ParseRuleError> {
+        Ok(Self {
+            id: peer_info.id.clone(),
+            addr: peer_info
+                .addr
+                .ok_or_else(|| ParseRuleError::WhitelistNode(peer_info.to_string()))?,
+            account_id: peer_info.account_id.clone(),
+        })
+    }
+
+    /// Whether the node is the given peer. The peer needs to present the same account id,
+    /// unless the entry doesn't specify one.
+    pub fn matches(&self, peer_info: &PeerInfo) -> bool {
+        self.id == peer_info.id
+            && Some(self.addr) == peer_info.addr
+            && (self.account_id.is_none() || self.account_id == peer_info.account_id)
+    }
+}
+
+impl std::str::FromStr for WhitelistNode {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let peer_info: PeerInfo =
+            s.parse().map_err(|_| ParseRuleError::WhitelistNode(s.to_string()))?;
+        Self::from_peer_info(&peer_info)
+    }
+}
+
+impl fmt::Display for WhitelistNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.id, self.addr)?;
+        if let Some(account_id) = &self.account_id {
+            write!(f, "@{}", account_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl serde::Serialize for WhitelistNode {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+impl<'a> serde::Deserialize<'a> for WhitelistNode {
+    fn deserialize<D: serde::Deserializer<'a>>(d: D) -> Result<Self, D::Error> {
+        <String as serde::Deserialize>::deserialize(d)?
+            .parse()
+            .map_err(<D::Error as serde::de::Error>::custom)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuthorizationConfig {
+    /// Peers matching any of these rules are never denied by the `deny` rules.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<Rule>,
+    /// Nodes will not accept or try to establish connection to peers matching any of these
+    /// rules.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<Rule>,
+    /// Inbound connections from these nodes are accepted even if the limit of inbound
+    /// connections has been reached.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub whitelist: Vec<WhitelistNode>,
+}
+
+impl AuthorizationConfig {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty() && self.whitelist.is_empty()
+    }
+}
+
+/// Authorization config together with the temporary bans.
+#[derive(Default)]
+pub(crate) struct Authorization {
+    config: AuthorizationConfig,
+    /// Rules banned until the given time.
+    bans: HashMap<Rule, time::Utc>,
+}
+
+impl Authorization {
+    pub fn new(config: AuthorizationConfig) -> Self {
+        Self { config, bans: HashMap::new() }
+    }
+
+    pub fn set_config(&mut self, config: AuthorizationConfig) {
+        self.config = config;
+    }
+
+    /// Bans the peers matching the rule until the given time. Overrides the previous ban
+    /// for the same rule, if any.
+    pub fn ban(&mut self, rule: Rule, until: time::Utc) {
+        self.bans.insert(rule, until);
+    }
+
+    /// Lifts the temporary ban for the rule. Returns whether the rule was banned.
+    pub fn unban(&mut self, rule: &Rule) -> bool {
+        self.bans.remove(rule).is_some()
+    }
+
+    /// Removes the bans which have expired.
+    pub fn remove_expired(&mut self, now: time::Utc) {
+        self.bans.retain(|_, until| now < *until);
+    }
+
+    pub fn is_denied(
+        &self,
+        now: time::Utc,
+        peer_id: &PeerId,
+        addr: Option<net::SocketAddr>,
+    ) -> bool {
+        if self.bans.iter().any(|(rule, until)| now < *until && rule.matches(peer_id, addr)) {
+            return true;
+        }
+        !self.config.allow.iter().any(|rule| rule.matches(peer_id, addr))
+            && self.config.deny.iter().any(|rule| rule.matches(peer_id, addr))
+    }
+
+    pub fn is_whitelisted(&self, peer_info: &PeerInfo) -> bool {
+        self.config.whitelist.iter().any(|node| node.matches(peer_info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LO4: net::IpAddr = net::IpAddr::V4(net::Ipv4Addr::LOCALHOST);
+    const LO6: net::IpAddr = net::IpAddr::V6(net::Ipv6Addr::LOCALHOST);
+
+    fn parse(value: &str) -> Option<Rule> {
+        value.parse().ok()
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        assert_eq!(None, parse("foo"));
+        assert_eq!(None, parse("192.0.2.*"));
+        assert_eq!(None, parse("192.0.2.0/33"));
+        assert_eq!(None, parse("::/129"));
+        assert_eq!(None, parse("192.0.2.4.5"));
+        assert_eq!(None, parse("192.0.2.4:424242"));
+
+        assert_eq!(parse("::ffff:192.0.2.4").unwrap(), parse("192.0.2.4").unwrap());
+        assert_eq!(parse("[::ffff:192.0.2.4]:0").unwrap(), parse("192.0.2.4:0").unwrap());
+        assert_eq!(parse("[::ffff:192.0.2.4]:42").unwrap(), parse("192.0.2.4:42").unwrap());
+        assert_eq!(parse("::ffff:192.0.2.0/120").unwrap(), parse("192.0.2.0/24").unwrap());
+        assert_eq!(parse("192.0.2.4/24").unwrap(), parse("192.0.2.0/24").unwrap());
+        assert_eq!(parse("192.0.2.4/32").unwrap(), parse("192.0.2.4").unwrap());
+
+        assert_eq!(Rule::from_ip(LO6), parse("::1").unwrap());
+        assert_eq!(Rule::from_addr(net::SocketAddr::new(LO6, 42)), parse("[::1]:42").unwrap());
+
+        assert_eq!(Rule::from_ip(LO4), parse("::ffff:127.0.0.1").unwrap());
+        assert_eq!(
+            Rule::from_addr(net::SocketAddr::new(LO4, 42)),
+            parse("[::ffff:127.0.0.1]:42").unwrap()
+        );
+
+        let peer_id = PeerId::random();
+        assert_eq!(Rule::PeerId(peer_id.clone()), parse(&peer_id.to_string()).unwrap());
+
+        for value in
+            ["192.0.2.4", "192.0.2.0/24", "2001:db8::/32", "::1", "192.0.2.4:42", "[::1]:42"]
+        {
+            assert_eq!(value, parse(value).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        use std::net::*;
+
+        let peer_id = PeerId::random();
+        let ip: IpAddr = Ipv4Addr::new(192, 0, 2, 4).into();
+        let mapped_ip = IpAddr::V6("::ffff:192.0.2.4".parse().unwrap());
+        let mapped_lo4 = IpAddr::V6("::ffff:127.0.0.1".parse().unwrap());
+
+        let denied = |rules: &[Rule], addr: SocketAddr| {
+            rules.iter().any(|rule| rule.matches(&peer_id, Some(addr)))
+        };
+        let rules = [
+            Rule::from_ip(LO4),
+            Rule::from_addr(SocketAddr::new(ip, 42)),
+            Rule::from_addr(SocketAddr::new(LO6, 42)),
+        ];
+        assert!(denied(&rules, SocketAddr::new(LO4, 42)));
+        assert!(denied(&rules, SocketAddr::new(LO4, 8080)));
+        assert!(denied(&rules, SocketAddr::new(ip, 42)));
+        assert!(!denied(&rules, SocketAddr::new(ip, 8080)));
+        assert!(denied(&rules, SocketAddr::new(LO6, 42)));
+        assert!(!denied(&rules, SocketAddr::new(LO6, 8080)));
+        assert!(denied(&rules, SocketAddr::new(mapped_lo4, 42)));
+        assert!(denied(&rules, SocketAddr::new(mapped_lo4, 8080)));
+        assert!(denied(&rules, SocketAddr::new(mapped_ip, 42)));
+        assert!(!denied(&rules, SocketAddr::new(mapped_ip, 8080)));
+
+        let rules = [parse("192.0.2.0/24").unwrap(), parse("2001:db8::/32").unwrap()];
+        assert!(denied(&rules, SocketAddr::new(ip, 42)));
+        assert!(denied(&rules, SocketAddr::new(mapped_ip, 42)));
+        assert!(denied(&rules, "[2001:db8:1::1]:42".parse().unwrap()));
+        assert!(!denied(&rules, "192.0.3.4:42".parse().unwrap()));
+        assert!(!denied(&rules, "[2001:db9::1]:42".parse().unwrap()));
+        assert!(!denied(&rules, SocketAddr::new(LO4, 42)));
+
+        let rule = Rule::PeerId(peer_id.clone());
+        assert!(rule.matches(&peer_id, None));
+        assert!(!rule.matches(&PeerId::random(), Some(SocketAddr::new(ip, 42))));
+        assert!(!Rule::from_ip(ip).matches(&peer_id, None));
+    }
+
+    #[test]
+    fn test_authorization() {
+        let clock = time::FakeClock::default();
+        let peer_id = PeerId::random();
+        let addr: net::SocketAddr = "192.0.2.4:42".parse().unwrap();
+        let other_addr: net::SocketAddr = "192.0.3.4:42".parse().unwrap();
+
+        let mut auth = Authorization::new(AuthorizationConfig {
+            allow: vec![parse("192.0.2.4").unwrap()],
+            deny: vec![parse("192.0.0.0/16").unwrap()],
+            ..Default::default()
+        });
+        assert!(!auth.is_denied(clock.now_utc(), &peer_id, Some(addr)));
+        assert!(auth.is_denied(clock.now_utc(), &peer_id, Some(other_addr)));
+
+        // Temporary bans apply to the allowed peers too, until they expire.
+        auth.ban(Rule::PeerId(peer_id.clone()), clock.now_utc() + time::Duration::seconds(10));
+        assert!(auth.is_denied(clock.now_utc(), &peer_id, Some(addr)));
+        clock.advance(time::Duration::seconds(10));
+        assert!(!auth.is_denied(clock.now_utc(), &peer_id, Some(addr)));
+        auth.remove_expired(clock.now_utc());
+        assert!(!auth.unban(&Rule::PeerId(peer_id.clone())));
+
+        auth.ban(Rule::from_addr(addr), clock.now_utc() + time::Duration::seconds(10));
+        assert!(auth.is_denied(clock.now_utc(), &peer_id, Some(addr)));
+        assert!(auth.unban(&Rule::from_addr(addr)));
+        assert!(!auth.is_denied(clock.now_utc(), &peer_id, Some(addr)));
+
+        auth.set_config(AuthorizationConfig::default());
+        assert!(!auth.is_denied(clock.now_utc(), &peer_id, Some(other_addr)));
+    }
+
+    #[test]
+    fn test_whitelist() {
+        let peer_id = PeerId::random();
+        let addr: net::SocketAddr = "192.0.2.4:42".parse().unwrap();
+        let peer_info = |addr: Option<net::SocketAddr>, account_id: Option<&str>| PeerInfo {
+            id: peer_id.clone(),
+            addr,
+            account_id: account_id.map(|account_id| account_id.parse().unwrap()),
+        };
+
+        assert!(format!("{peer_id}").parse::<WhitelistNode>().is_err());
+        let node: WhitelistNode = format!("{peer_id}@{addr}").parse().unwrap();
+        assert_eq!(node.to_string(), format!("{peer_id}@{addr}"));
+        let node_with_account: WhitelistNode =
+            format!("{peer_id}@{addr}@test.near").parse().unwrap();
+        assert_eq!(node_with_account.to_string(), format!("{peer_id}@{addr}@test.near"));
+
+        let auth =
+            Authorization::new(AuthorizationConfig { whitelist: vec![node], ..Default::default() });
+        assert!(auth.is_whitelisted(&peer_info(Some(addr), None)));
+        assert!(auth.is_whitelisted(&peer_info(Some(addr), Some("other.near"))));
+        assert!(!auth.is_whitelisted(&peer_info(None, None)));
+        assert!(!auth.is_whitelisted(&peer_info(Some("192.0.2.4:43".parse().unwrap()), None)));
+
+        let auth = Authorization::new(AuthorizationConfig {
+            whitelist: vec![node_with_account],
+            ..Default::default()
+        });
+        assert!(auth.is_whitelisted(&peer_info(Some(addr), Some("test.near"))));
+        assert!(!auth.is_whitelisted(&peer_info(Some(addr), Some("other.near"))));
+        assert!(!auth.is_whitelisted(&peer_info(Some(addr), None)));
+    }
+}
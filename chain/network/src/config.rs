@@ -1,7 +1,6 @@
-use crate::blacklist;
+use crate::authorization::AuthorizationConfig;
 use crate::concurrency::rate;
 use crate::network_protocol::PeerAddr;
-use crate::network_protocol::PeerInfo;
 use crate::peer_manager::peer_manager_actor::Event;
 use crate::peer_manager::peer_store;
 use crate::sink::Sink;
@@ -88,6 +87,33 @@ pub struct Tier1 {
     pub enable_outbound: bool,
 }
 
+/// Part of the network config which may be updated while the node is running.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UpdateableNetworkConfig {
+    pub authorization: AuthorizationConfig,
+}
+
+impl UpdateableNetworkConfig {
+    pub fn new(cfg: &crate::config_json::Config) -> anyhow::Result<Self> {
+        // Entries of the legacy `blacklist` are the same as the `deny` rules, and the legacy
+        // `whitelist_nodes` are the same as the `whitelist`.
+        let mut authorization = cfg.authorization.clone();
+        for entry in &cfg.blacklist {
+            authorization
+                .deny
+                .push(entry.parse().with_context(|| format!("failed to parse blacklist {entry}"))?);
+        }
+        for entry in cfg.whitelist_nodes.split(',').filter(|entry| !entry.is_empty()) {
+            authorization.whitelist.push(
+                entry
+                    .parse()
+                    .with_context(|| format!("failed to parse whitelist_nodes {entry}"))?,
+            );
+        }
+        Ok(Self { authorization })
+    }
+}
+
 /// Validated configuration for the peer-to-peer manager.
 #[derive(Clone)]
 pub struct NetworkConfig {
@@ -96,7 +122,6 @@ pub struct NetworkConfig {
     pub validator: Option<ValidatorConfig>,
 
     pub peer_store: peer_store::Config,
-    pub whitelist_nodes: Vec<PeerInfo>,
     pub handshake_timeout: time::Duration,
 
     /// Whether to re-establish connection to known reliable peers from previous neard run(s).
@@ -153,6 +178,14 @@ pub struct NetworkConfig {
     pub routing_table_update_rate_limit: rate::Limit,
     /// Config of the TIER1 network.
     pub tier1: Option<Tier1>,
+    /// Whether to encrypt the connections with the peers which enable it too.
+    /// See `crate::peer::encryption`.
+    pub encrypted_transport: bool,
+    /// Whether to close the connections which can't be encrypted.
+    pub require_encrypted_transport: bool,
+    /// Compressible messages at least this large are sent compressed to the peers accepting it.
+    /// See `crate::network_protocol::compression`.
+    pub message_compression_threshold: Option<usize>,
 
     // Whether to ignore tombstones some time after startup.
     //
@@ -272,32 +305,12 @@ impl NetworkConfig {
                         .collect::<Result<_, _>>()
                         .context("boot_nodes")?
                 },
-                blacklist: cfg
-                    .blacklist
-                    .iter()
-                    .map(|e| e.parse())
-                    .collect::<Result<_, _>>()
-                    .context("failed to parse blacklist")?,
+                authorization: UpdateableNetworkConfig::new(&cfg)?.authorization,
                 peer_states_cache_size: cfg.peer_states_cache_size,
                 connect_only_to_boot_nodes: cfg.experimental.connect_only_to_boot_nodes,
                 ban_window: cfg.ban_window.try_into()?,
                 peer_expiration_duration: cfg.peer_expiration_duration.try_into()?,
             },
-            whitelist_nodes: if cfg.whitelist_nodes.is_empty() {
-                vec![]
-            } else {
-                cfg.whitelist_nodes
-                    .split(',')
-                    .map(|peer| match peer.parse::<PeerInfo>() {
-                        Ok(peer) if peer.addr.is_none() => anyhow::bail!(
-                            "whitelist_nodes are required to specify both PeerId and IP:port"
-                        ),
-                        Ok(peer) => Ok(peer),
-                        Err(err) => Err(err.into()),
-                    })
-                    .collect::<anyhow::Result<_>>()
-                    .context("whitelist_nodes")?
-            },
             connect_to_reliable_peers_on_startup: true,
             handshake_timeout: cfg.handshake_timeout.try_into()?,
             monitor_peers_max_period: cfg.monitor_peers_max_period.try_into()?,
@@ -326,6 +339,9 @@ impl NetworkConfig {
                 enable_inbound: cfg.experimental.tier1_enable_inbound,
                 enable_outbound: cfg.experimental.tier1_enable_outbound,
             }),
+            encrypted_transport: cfg.encrypted_transport,
+            require_encrypted_transport: cfg.require_encrypted_transport,
+            message_compression_threshold: cfg.message_compression_threshold,
             inbound_disabled: cfg.experimental.inbound_disabled,
             skip_tombstones: if cfg.experimental.skip_sending_tombstones_seconds > 0 {
                 Some(time::Duration::seconds(cfg.experimental.skip_sending_tombstones_seconds))
@@ -358,13 +374,12 @@ impl NetworkConfig {
             validator: Some(validator),
             peer_store: peer_store::Config {
                 boot_nodes: vec![],
-                blacklist: blacklist::Blacklist::default(),
+                authorization: AuthorizationConfig::default(),
                 peer_states_cache_size: 1000,
                 ban_window: time::Duration::seconds(1),
                 peer_expiration_duration: time::Duration::seconds(60 * 60),
                 connect_only_to_boot_nodes: false,
             },
-            whitelist_nodes: vec![],
             handshake_timeout: time::Duration::seconds(5),
             connect_to_reliable_peers_on_startup: true,
             monitor_peers_max_period: time::Duration::seconds(100),
@@ -396,6 +411,9 @@ impl NetworkConfig {
                 enable_inbound: true,
                 enable_outbound: true,
             }),
+            encrypted_transport: false,
+            require_encrypted_transport: false,
+            message_compression_threshold: Some(64 * 1024),
             skip_tombstones: None,
             event_sink: Sink::null(),
         }
@@ -440,6 +458,13 @@ impl NetworkConfig {
             );
         }
 
+        if self.encrypted_transport && self.node_key.key_type() != KeyType::ED25519 {
+            anyhow::bail!("encrypted_transport requires an ED25519 node key");
+        }
+        if self.require_encrypted_transport && !self.encrypted_transport {
+            anyhow::bail!("require_encrypted_transport requires encrypted_transport");
+        }
+
         self.accounts_data_broadcast_rate_limit
             .validate()
             .context("accounts_Data_broadcast_rate_limit")?;
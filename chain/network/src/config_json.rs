@@ -1,3 +1,4 @@
+use crate::authorization::AuthorizationConfig;
 use crate::network_protocol::PeerAddr;
 use crate::stun;
 use std::time::Duration;
@@ -61,6 +62,11 @@ fn default_skip_tombstones() -> i64 {
     0
 }
 
+/// Messages smaller than this are not worth the CPU time of compressing them.
+fn default_message_compression_threshold() -> Option<usize> {
+    Some(64 * 1024)
+}
+
 /// This is a list of public STUN servers provided by Google,
 /// which are known to have good availability. To avoid trusting
 /// a centralized entity (and DNS used for domain resolution),
@@ -91,6 +97,7 @@ pub struct Config {
     /// Examples:
     ///   ed25519:86EtEy7epneKyrcJwSWP7zsisTkfDRH5CFVszt4qiQYw@31.192.22.209:24567
     ///   ed25519:86EtEy7epneKyrcJwSWP7zsisTkfDRH5CFVszt4qiQYw@nearnode.com:24567
+    /// Entries are added to the `whitelist` of `authorization`.
     #[serde(default)]
     pub whitelist_nodes: String,
     /// Maximum number of active peers. Hard limit.
@@ -124,8 +131,16 @@ pub struct Config {
     pub ban_window: Duration,
     /// List of addresses that will not be accepted as valid neighbors.
     /// It can be IP:Port or IP (to blacklist all connections coming from this address).
+    /// Entries are added to the `deny` rules of `authorization`.
     #[serde(default)]
     pub blacklist: Vec<String>,
+    /// Rules deciding which peers may connect to the node and be connected to. Peers matching
+    /// a `deny` rule and none of the `allow` rules are rejected. A rule is an IP, IP:Port,
+    /// IP range in the CIDR notation or a peer id. Inbound connections from the `whitelist` nodes
+    /// are accepted even if the limit of inbound connections has been reached. Can be updated
+    /// while the node is running.
+    #[serde(default)]
+    pub authorization: AuthorizationConfig,
     /// Time to persist Accounts Id in the router without removing them in seconds.
     #[serde(default = "default_ttl_account_id_router")]
     pub ttl_account_id_router: Duration,
@@ -188,6 +203,20 @@ pub struct Config {
     /// such a case.
     #[serde(default = "default_trusted_stun_servers")]
     pub trusted_stun_servers: Vec<stun::ServerAddr>,
+    /// Encrypt and authenticate the connections with the peers which also enable this option.
+    /// Connections with other peers stay unencrypted.
+    #[serde(default)]
+    pub encrypted_transport: bool,
+    /// Close the connections with the peers which don't encrypt them, instead of falling back to
+    /// an unencrypted connection. Protects against on-path attackers which strip the encryption
+    /// offer from the handshakes, but leaves only the peers with `encrypted_transport` reachable.
+    #[serde(default)]
+    pub require_encrypted_transport: bool,
+    /// Blocks, headers, chunks and state parts at least this many bytes large are compressed
+    /// with zstd when sent to the peers which accept compressed messages.
+    /// `null` disables compression of the sent messages.
+    #[serde(default = "default_message_compression_threshold")]
+    pub message_compression_threshold: Option<usize>,
     // Experimental part of the JSON config. Regular users/validators should not have to set any values there.
     // Field names in here can change/disappear at any moment without warning.
     #[serde(default)]
@@ -298,6 +327,7 @@ impl Default for Config {
             peer_states_cache_size: default_peer_states_cache_size(),
             ban_window: Duration::from_secs(3 * 60 * 60),
             blacklist: vec![],
+            authorization: AuthorizationConfig::default(),
             ttl_account_id_router: default_ttl_account_id_router(),
             peer_stats_period: default_peer_stats_period(),
             monitor_peers_max_period: default_monitor_peers_max_period(),
@@ -305,6 +335,9 @@ impl Default for Config {
             public_addrs: vec![],
             allow_private_ip_in_public_addrs: false,
             trusted_stun_servers: default_trusted_stun_servers(),
+            encrypted_transport: false,
+            require_encrypted_transport: false,
+            message_compression_threshold: default_message_compression_threshold(),
             experimental: Default::default(),
         }
     }
@@ -11,13 +11,14 @@ mod store;
 mod stun;
 
 pub mod actix;
-pub mod blacklist;
+pub mod authorization;
 pub mod client;
 pub mod concurrency;
 pub mod config;
 pub mod config_json;
 pub mod debug;
 pub mod raw;
+pub mod reputation;
 pub mod routing;
 pub mod shards_manager;
 pub mod tcp;
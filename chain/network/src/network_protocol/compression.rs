@@ -0,0 +1,50 @@
+//! Compression of large messages.
+//!
+//! Peers which accept compressed messages say so in their `Handshake`. Messages of the types
+//! which are large and compress well are sent to such peers as `CompressedPeerMessage`, if their
+//! proto encoding is at least as large as the configured threshold and compression makes them
+//! smaller. Other messages, and all messages sent to the other peers, stay uncompressed.
+use crate::network_protocol::{PeerMessage, RoutedMessageBody};
+use crate::peer::stream::NETWORK_MESSAGE_MAX_SIZE_BYTES;
+use std::io;
+use std::io::Read as _;
+
+/// zstd compression level. Higher levels save little on blocks and state parts, while taking
+/// a lot more CPU time.
+const LEVEL: i32 = 3;
+
+/// Whether messages of the type are worth compressing.
+pub(crate) fn is_compressible(msg: &PeerMessage) -> bool {
+    match msg {
+        PeerMessage::Block(_)
+        | PeerMessage::BlockHeaders(_)
+        | PeerMessage::VersionedStateResponse(_) => true,
+        PeerMessage::Routed(msg) => matches!(
+            msg.msg.body,
+            RoutedMessageBody::VersionedPartialEncodedChunk(_)
+                | RoutedMessageBody::PartialEncodedChunkResponse(_)
+                | RoutedMessageBody::PartialEncodedChunkForward(_)
+                | RoutedMessageBody::StateResponse(_)
+        ),
+        _ => false,
+    }
+}
+
+pub(crate) fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+    zstd::bulk::compress(data, LEVEL)
+}
+
+/// Decompresses a message. Fails if the decompressed message would be larger than a frame may be.
+pub(crate) fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decompressed = vec![];
+    zstd::stream::read::Decoder::new(data)?
+        .take(NETWORK_MESSAGE_MAX_SIZE_BYTES as u64 + 1)
+        .read_to_end(&mut decompressed)?;
+    if decompressed.len() > NETWORK_MESSAGE_MAX_SIZE_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("decompressed message larger than {NETWORK_MESSAGE_MAX_SIZE_BYTES} bytes"),
+        ));
+    }
+    Ok(decompressed)
+}
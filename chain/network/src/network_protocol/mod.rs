@@ -2,6 +2,7 @@
 #[path = "borsh.rs"]
 mod borsh_;
 mod borsh_conv;
+mod compression;
 mod edge;
 mod peer;
 mod proto_conv;
@@ -22,6 +23,7 @@ pub use _proto::network as proto;
 use crate::network_protocol::proto_conv::trace_context::{
     extract_span_context, inject_trace_context,
 };
+use crate::peer::encryption::SessionPublicKey;
 use borsh::{BorshDeserialize as _, BorshSerialize as _};
 use near_async::time;
 use near_crypto::PublicKey;
@@ -333,6 +335,10 @@ pub struct Handshake {
     pub(crate) partial_edge_info: PartialEdgeInfo,
     /// Account owned by the sender.
     pub(crate) owned_account: Option<SignedOwnedAccount>,
+    /// Ephemeral key of the sender, if it wants to encrypt the connection.
+    pub(crate) session_key: Option<SessionPublicKey>,
+    /// Whether the sender accepts compressed messages.
+    pub(crate) accepts_compression: bool,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, strum::IntoStaticStr)]
@@ -435,6 +441,10 @@ pub enum ParsePeerMessageError {
     ProtoDecode(#[source] protobuf::Error),
     #[error("ProtoConv")]
     ProtoConv(#[source] proto_conv::ParsePeerMessageError),
+    #[error("Decompress")]
+    Decompress(#[source] std::io::Error),
+    #[error("CompressionNotNegotiated")]
+    CompressionNotNegotiated,
 }
 
 impl PeerMessage {
@@ -452,23 +462,83 @@ impl PeerMessage {
         }
     }
 
+    /// Serializes a message in the `Proto` encoding, like `serialize`. The message is compressed
+    /// if it is of a type worth compressing, at least `threshold` bytes large, and compression
+    /// makes it smaller. Returns the serialized message and, if it was compressed, its size
+    /// before compression.
+    pub(crate) fn serialize_compressed(&self, threshold: usize) -> (Vec<u8>, Option<usize>) {
+        let bytes = self.serialize(Encoding::Proto);
+        if bytes.len() < threshold || !compression::is_compressible(self) {
+            return (bytes, None);
+        }
+        let zstd_payload = match compression::compress(&bytes) {
+            Ok(zstd_payload) => zstd_payload,
+            Err(err) => {
+                tracing::warn!(target: "network", ?err, "Failed to compress a message");
+                return (bytes, None);
+            }
+        };
+        let compressed = proto::PeerMessage {
+            message_type: Some(proto::peer_message::Message_type::Compressed(
+                proto::CompressedPeerMessage { zstd_payload, ..Default::default() },
+            )),
+            ..Default::default()
+        }
+        .write_to_bytes()
+        .unwrap();
+        if compressed.len() >= bytes.len() {
+            return (bytes, None);
+        }
+        (compressed, Some(bytes.len()))
+    }
+
     pub(crate) fn deserialize(
         enc: Encoding,
         data: &[u8],
     ) -> Result<PeerMessage, ParsePeerMessageError> {
+        Ok(Self::deserialize_compressed(enc, data, true)?.0)
+    }
+
+    /// Deserializes a message in the given encoding, decompressing it if needed. Returns the
+    /// message and, if it was compressed, its size after decompression.
+    /// Compressed messages are rejected without being decompressed unless `accept_compressed`.
+    pub(crate) fn deserialize_compressed(
+        enc: Encoding,
+        data: &[u8],
+        accept_compressed: bool,
+    ) -> Result<(PeerMessage, Option<usize>), ParsePeerMessageError> {
         let span = tracing::trace_span!(target: "network", "deserialize").entered();
         Ok(match enc {
-            Encoding::Borsh => (&borsh_::PeerMessage::try_from_slice(data)
-                .map_err(ParsePeerMessageError::BorshDecode)?)
-                .try_into()
-                .map_err(ParsePeerMessageError::BorshConv)?,
+            Encoding::Borsh => (
+                (&borsh_::PeerMessage::try_from_slice(data)
+                    .map_err(ParsePeerMessageError::BorshDecode)?)
+                    .try_into()
+                    .map_err(ParsePeerMessageError::BorshConv)?,
+                None,
+            ),
             Encoding::Proto => {
-                let proto_msg: proto::PeerMessage = proto::PeerMessage::parse_from_bytes(data)
+                let mut proto_msg: proto::PeerMessage = proto::PeerMessage::parse_from_bytes(data)
                     .map_err(ParsePeerMessageError::ProtoDecode)?;
+                let mut decompressed_size = None;
+                if let Some(proto::peer_message::Message_type::Compressed(compressed)) =
+                    &proto_msg.message_type
+                {
+                    if !accept_compressed {
+                        return Err(ParsePeerMessageError::CompressionNotNegotiated);
+                    }
+                    let data = compression::decompress(&compressed.zstd_payload)
+                        .map_err(ParsePeerMessageError::Decompress)?;
+                    decompressed_size = Some(data.len());
+                    proto_msg = proto::PeerMessage::parse_from_bytes(&data)
+                        .map_err(ParsePeerMessageError::ProtoDecode)?;
+                }
                 if let Ok(extracted_span_context) = extract_span_context(&proto_msg.trace_context) {
                     span.clone().or_current().add_link(extracted_span_context);
                 }
-                (&proto_msg).try_into().map_err(|err| ParsePeerMessageError::ProtoConv(err))?
+                (
+                    (&proto_msg).try_into().map_err(|err| ParsePeerMessageError::ProtoConv(err))?,
+                    decompressed_size,
+                )
             }
         })
     }
@@ -4,6 +4,7 @@ use super::*;
 use crate::network_protocol::proto;
 use crate::network_protocol::{Handshake, HandshakeFailureReason};
 use crate::network_protocol::{PeerChainInfoV2, PeerInfo};
+use crate::peer::encryption::SessionPublicKey;
 use near_primitives::block::GenesisId;
 use protobuf::MessageField as MF;
 
@@ -77,6 +78,8 @@ pub enum ParseHandshakeError {
     PartialEdgeInfo(ParseRequiredError<ParsePartialEdgeInfoError>),
     #[error("owned_account {0}")]
     OwnedAccount(ParseSignedOwnedAccountError),
+    #[error("session_key: invalid length {0}")]
+    SessionKey(usize),
 }
 
 impl From<&Handshake> for proto::Handshake {
@@ -90,6 +93,8 @@ impl From<&Handshake> for proto::Handshake {
             sender_chain_info: MF::some((&x.sender_chain_info).into()),
             partial_edge_info: MF::some((&x.partial_edge_info).into()),
             owned_account: x.owned_account.as_ref().map(Into::into).into(),
+            session_key: x.session_key.map(|key| key.0.to_vec()).unwrap_or_default(),
+            accepts_compression: x.accepts_compression,
             ..Self::default()
         }
     }
@@ -120,6 +125,17 @@ impl TryFrom<&proto::Handshake> for Handshake {
                 .map_err(Self::Error::PartialEdgeInfo)?,
             owned_account: try_from_optional(&p.owned_account)
                 .map_err(Self::Error::OwnedAccount)?,
+            session_key: if p.session_key.is_empty() {
+                None
+            } else {
+                Some(SessionPublicKey(
+                    p.session_key
+                        .as_slice()
+                        .try_into()
+                        .map_err(|_| Self::Error::SessionKey(p.session_key.len()))?,
+                ))
+            },
+            accepts_compression: p.accepts_compression,
         })
     }
 }
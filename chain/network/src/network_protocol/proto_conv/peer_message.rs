@@ -317,6 +317,8 @@ pub enum ParsePeerMessageError {
     SyncAccountsData(ParseVecError<ParseSignedAccountDataError>),
     #[error("state_response: {0}")]
     StateResponse(ParseRequiredError<ParseStateInfoError>),
+    #[error("compressed message has to be decompressed first")]
+    Compressed,
 }
 
 impl TryFrom<&proto::PeerMessage> for PeerMessage {
@@ -415,6 +417,7 @@ impl TryFrom<&proto::PeerMessage> for PeerMessage {
             ProtoMT::StateResponse(t) => PeerMessage::VersionedStateResponse(
                 try_from_required(&t.state_response_info).map_err(Self::Error::StateResponse)?,
             ),
+            ProtoMT::Compressed(_) => return Err(Self::Error::Compressed),
         })
     }
 }
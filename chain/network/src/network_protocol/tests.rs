@@ -5,6 +5,7 @@ use crate::testonly::make_rng;
 use crate::types::{Disconnect, HandshakeFailureReason, PeerMessage};
 use crate::types::{PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg};
 use anyhow::{bail, Context as _};
+use assert_matches::assert_matches;
 use itertools::Itertools as _;
 use near_async::time;
 use rand::Rng as _;
@@ -170,3 +171,106 @@ fn serialize_deserialize() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn serialize_deserialize_compressed() {
+    let mut rng = make_rng(71957381);
+    let mut clock = time::FakeClock::default();
+    let chain = data::Chain::make(&mut clock, &mut rng, 12);
+    let state_part =
+        PeerMessage::VersionedStateResponse(StateResponseInfo::V2(StateResponseInfoV2 {
+            shard_id: 0,
+            sync_hash: *chain.blocks[5].hash(),
+            state_response: ShardStateSyncResponse::V2(
+                near_primitives::syncing::ShardStateSyncResponseV2 {
+                    header: None,
+                    part: Some((0, vec![7; 100_000])),
+                },
+            ),
+        }));
+    let uncompressed_len = state_part.serialize(Encoding::Proto).len();
+
+    // Compressible messages above the threshold are compressed.
+    let (bytes, got_len) = state_part.serialize_compressed(1000);
+    assert_eq!(got_len, Some(uncompressed_len));
+    assert!(bytes.len() < uncompressed_len / 10);
+    assert_eq!(
+        PeerMessage::deserialize_compressed(Encoding::Proto, &bytes, true).unwrap(),
+        (state_part.clone(), Some(uncompressed_len))
+    );
+    // Compressed messages are never mistaken for borsh.
+    assert!(PeerMessage::deserialize(Encoding::Borsh, &bytes).is_err());
+
+    // Small messages and messages of other types are not.
+    assert_eq!(state_part.serialize_compressed(uncompressed_len + 1).1, None);
+    let tx = PeerMessage::Transaction(data::make_signed_transaction(&mut rng));
+    assert_eq!(tx.serialize_compressed(0), (tx.serialize(Encoding::Proto), None));
+
+    // Other compressible messages either get smaller or stay uncompressed.
+    for m in [
+        PeerMessage::BlockHeaders(chain.get_block_headers()),
+        PeerMessage::Block(chain.blocks[5].clone()),
+    ] {
+        let (bytes, got_len) = m.serialize_compressed(0);
+        let (m2, got_len2) =
+            PeerMessage::deserialize_compressed(Encoding::Proto, &bytes, true).unwrap();
+        assert_eq!(m, m2);
+        assert_eq!(got_len, got_len2);
+        if got_len.is_some() {
+            assert!(bytes.len() < m.serialize(Encoding::Proto).len());
+        }
+    }
+}
+
+#[test]
+fn nested_compression_is_rejected() {
+    use protobuf::Message as _;
+
+    let (inner, _) =
+        PeerMessage::VersionedStateResponse(StateResponseInfo::V2(StateResponseInfoV2 {
+            shard_id: 0,
+            sync_hash: CryptoHash::default(),
+            state_response: ShardStateSyncResponse::V2(
+                near_primitives::syncing::ShardStateSyncResponseV2 {
+                    header: None,
+                    part: Some((0, vec![7; 100_000])),
+                },
+            ),
+        }))
+        .serialize_compressed(0);
+    let outer = proto::PeerMessage {
+        message_type: Some(proto::peer_message::Message_type::Compressed(
+            proto::CompressedPeerMessage {
+                zstd_payload: compression::compress(&inner).unwrap(),
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    }
+    .write_to_bytes()
+    .unwrap();
+    assert!(PeerMessage::deserialize(Encoding::Proto, &outer).is_err());
+}
+
+#[test]
+fn unnegotiated_compression_is_rejected_before_decompression() {
+    use protobuf::Message as _;
+
+    // The payload is not valid zstd, so decompressing it would fail with `Decompress`.
+    let bytes = proto::PeerMessage {
+        message_type: Some(proto::peer_message::Message_type::Compressed(
+            proto::CompressedPeerMessage { zstd_payload: vec![7; 1000], ..Default::default() },
+        )),
+        ..Default::default()
+    }
+    .write_to_bytes()
+    .unwrap();
+    assert_matches!(
+        PeerMessage::deserialize_compressed(Encoding::Proto, &bytes, false),
+        Err(ParsePeerMessageError::CompressionNotNegotiated)
+    );
+    assert_matches!(
+        PeerMessage::deserialize_compressed(Encoding::Proto, &bytes, true),
+        Err(ParsePeerMessageError::Decompress(_))
+    );
+}
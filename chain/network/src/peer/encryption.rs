@@ -0,0 +1,284 @@
+//! Encryption of the frames of a peer connection.
+//!
+//! Peers which have `encrypted_transport` enabled send an ephemeral X25519 key in their
+//! `Handshake`, and encrypt the connection if the other peer sent one as well. Old peers ignore
+//! the unknown field, so no protocol version is needed to negotiate it.
+//! Session keys are derived as in the Noise KK pattern: from the Diffie-Hellman of both ephemeral
+//! keys, and of each ephemeral key with the static key of the other peer. The static key of
+//! a peer is its ED25519 node key, i.e. the key behind its `PeerId`, in the X25519 form.
+//! Therefore only the owners of both node keys can derive the session keys, even if the
+//! handshakes were rewritten on the way, and the ephemeral keys need no signatures.
+//! The ephemeral key may still be stripped from the handshake, which makes the peers fall back to
+//! an unencrypted connection, unless they set `require_encrypted_transport`.
+//!
+//! All frames sent after the handshakes are sealed with ChaCha20-Poly1305, using a separate key
+//! for each direction and the number of the frame as the nonce.
+use curve25519_dalek::constants::X25519_BASEPOINT;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
+use near_crypto::{PublicKey, SecretKey};
+use rand::RngCore as _;
+use ring::aead;
+use sha2::Digest as _;
+
+/// Name of the handshake pattern and the primitives, mixed into the session keys.
+const PROTOCOL_NAME: &[u8] = b"near_KK_25519_ChaChaPoly_SHA256";
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+    #[error("only ED25519 node keys are supported")]
+    UnsupportedKeyType,
+    #[error("invalid ED25519 public key")]
+    InvalidPublicKey,
+    #[error("Diffie-Hellman with a point of small order")]
+    SmallOrderPoint,
+    #[error("frame counter exhausted")]
+    CounterExhausted,
+    #[error("failed to decrypt the frame")]
+    Decryption,
+}
+
+/// Public part of the ephemeral key which a peer sends in its `Handshake`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct SessionPublicKey(pub [u8; 32]);
+
+/// Ephemeral key of a single connection.
+pub(crate) struct EphemeralSecret {
+    scalar: Scalar,
+    public_key: SessionPublicKey,
+}
+
+impl EphemeralSecret {
+    pub fn new() -> Self {
+        let mut bytes = [0; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let scalar = clamp(bytes);
+        Self { public_key: SessionPublicKey((X25519_BASEPOINT * scalar).to_bytes()), scalar }
+    }
+
+    pub fn public_key(&self) -> SessionPublicKey {
+        self.public_key
+    }
+}
+
+/// Keys of both directions of a connection.
+pub(crate) struct SessionKeys {
+    pub send: SealingKey,
+    pub recv: OpeningKey,
+}
+
+/// Derives the session keys of a connection. The initiator is the peer which opened the
+/// connection.
+pub(crate) fn derive_session_keys(
+    node_key: &SecretKey,
+    ephemeral: &EphemeralSecret,
+    peer_key: &PublicKey,
+    peer_ephemeral: &SessionPublicKey,
+    initiator: bool,
+) -> Result<SessionKeys, Error> {
+    let static_secret = static_secret(node_key)?;
+    let peer_static = static_public_key(peer_key)?;
+    let peer_ephemeral_point = MontgomeryPoint(peer_ephemeral.0);
+
+    let ee = dh(&ephemeral.scalar, &peer_ephemeral_point)?;
+    // `es` is the Diffie-Hellman of the initiator's ephemeral key and the responder's static key,
+    // `se` the other way round.
+    let (es, se) = if initiator {
+        (dh(&ephemeral.scalar, &peer_static)?, dh(&static_secret, &peer_ephemeral_point)?)
+    } else {
+        (dh(&static_secret, &peer_ephemeral_point)?, dh(&ephemeral.scalar, &peer_static)?)
+    };
+
+    let my_key = node_key.public_key();
+    let my_ephemeral = ephemeral.public_key();
+    let (initiator_keys, responder_keys) = if initiator {
+        ((&my_key, &my_ephemeral), (peer_key, peer_ephemeral))
+    } else {
+        ((peer_key, peer_ephemeral), (&my_key, &my_ephemeral))
+    };
+    let transcript = sha2::Sha256::new()
+        .chain_update(PROTOCOL_NAME)
+        .chain_update(initiator_keys.0.key_data())
+        .chain_update(initiator_keys.1 .0)
+        .chain_update(responder_keys.0.key_data())
+        .chain_update(responder_keys.1 .0)
+        .finalize();
+
+    let hkdf = hkdf::Hkdf::<sha2::Sha256>::new(Some(&transcript), &[ee, es, se].concat());
+    let mut initiator_key = [0; 32];
+    let mut responder_key = [0; 32];
+    // Expanding fails only if the requested output is too long.
+    hkdf.expand(b"initiator", &mut initiator_key).unwrap();
+    hkdf.expand(b"responder", &mut responder_key).unwrap();
+    let (send, recv) =
+        if initiator { (initiator_key, responder_key) } else { (responder_key, initiator_key) };
+    Ok(SessionKeys { send: SealingKey(Cipher::new(&send)), recv: OpeningKey(Cipher::new(&recv)) })
+}
+
+/// Key sealing the frames sent to the peer.
+pub(crate) struct SealingKey(Cipher);
+
+impl SealingKey {
+    pub fn seal(&mut self, mut frame: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let nonce = self.0.next_nonce()?;
+        // Sealing fails only if the frame is too large for the cipher, which is way above
+        // the frame size limit.
+        self.0.key.seal_in_place_append_tag(nonce, aead::Aad::empty(), &mut frame).unwrap();
+        Ok(frame)
+    }
+}
+
+/// Key opening the frames received from the peer.
+pub(crate) struct OpeningKey(Cipher);
+
+impl OpeningKey {
+    pub fn open(&mut self, mut frame: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let nonce = self.0.next_nonce()?;
+        let len = self
+            .0
+            .key
+            .open_in_place(nonce, aead::Aad::empty(), &mut frame)
+            .map_err(|_| Error::Decryption)?
+            .len();
+        frame.truncate(len);
+        Ok(frame)
+    }
+}
+
+struct Cipher {
+    key: aead::LessSafeKey,
+    /// Number of the next frame.
+    counter: u64,
+}
+
+impl Cipher {
+    fn new(key: &[u8; 32]) -> Self {
+        let key = aead::UnboundKey::new(&aead::CHACHA20_POLY1305, key).unwrap();
+        Self { key: aead::LessSafeKey::new(key), counter: 0 }
+    }
+
+    fn next_nonce(&mut self) -> Result<aead::Nonce, Error> {
+        let mut nonce = [0; aead::NONCE_LEN];
+        nonce[4..].copy_from_slice(&self.counter.to_le_bytes());
+        self.counter = self.counter.checked_add(1).ok_or(Error::CounterExhausted)?;
+        Ok(aead::Nonce::assume_unique_for_key(nonce))
+    }
+}
+
+fn clamp(mut bytes: [u8; 32]) -> Scalar {
+    bytes[0] &= 248;
+    bytes[31] &= 127;
+    bytes[31] |= 64;
+    Scalar::from_bits(bytes)
+}
+
+/// X25519 form of the ED25519 node key, i.e. the clamped scalar derived from its seed.
+fn static_secret(node_key: &SecretKey) -> Result<Scalar, Error> {
+    let SecretKey::ED25519(key) = node_key else {
+        return Err(Error::UnsupportedKeyType);
+    };
+    let hash = sha2::Sha512::digest(&key.0[..32]);
+    Ok(clamp(hash[..32].try_into().unwrap()))
+}
+
+/// X25519 form of the ED25519 public key of a peer.
+fn static_public_key(peer_key: &PublicKey) -> Result<MontgomeryPoint, Error> {
+    let PublicKey::ED25519(key) = peer_key else {
+        return Err(Error::UnsupportedKeyType);
+    };
+    let point = CompressedEdwardsY(key.0).decompress().ok_or(Error::InvalidPublicKey)?;
+    Ok(point.to_montgomery())
+}
+
+fn dh(scalar: &Scalar, point: &MontgomeryPoint) -> Result<[u8; 32], Error> {
+    let shared = (point * scalar).to_bytes();
+    // Points of small order give a shared secret known in advance.
+    if shared == [0; 32] {
+        return Err(Error::SmallOrderPoint);
+    }
+    Ok(shared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{derive_session_keys, EphemeralSecret, SessionKeys, SessionPublicKey};
+    use near_crypto::{KeyType, SecretKey};
+
+    fn handshake(
+        initiator_key: &SecretKey,
+        responder_key: &SecretKey,
+        responder_peer_key: &SecretKey,
+    ) -> (SessionKeys, SessionKeys) {
+        let initiator_ephemeral = EphemeralSecret::new();
+        let responder_ephemeral = EphemeralSecret::new();
+        let initiator = derive_session_keys(
+            initiator_key,
+            &initiator_ephemeral,
+            &responder_peer_key.public_key(),
+            &responder_ephemeral.public_key(),
+            true,
+        )
+        .unwrap();
+        let responder = derive_session_keys(
+            responder_key,
+            &responder_ephemeral,
+            &initiator_key.public_key(),
+            &initiator_ephemeral.public_key(),
+            false,
+        )
+        .unwrap();
+        (initiator, responder)
+    }
+
+    #[test]
+    fn test_session() {
+        let alice = SecretKey::from_seed(KeyType::ED25519, "alice");
+        let bob = SecretKey::from_seed(KeyType::ED25519, "bob");
+        let (mut alice_keys, mut bob_keys) = handshake(&alice, &bob, &bob);
+
+        for frame in [b"hello".to_vec(), vec![], vec![7; 10000]] {
+            let sealed = alice_keys.send.seal(frame.clone()).unwrap();
+            assert_eq!(sealed.len(), frame.len() + ring::aead::MAX_TAG_LEN);
+            assert_eq!(bob_keys.recv.open(sealed).unwrap(), frame);
+            let sealed = bob_keys.send.seal(frame.clone()).unwrap();
+            assert_eq!(alice_keys.recv.open(sealed).unwrap(), frame);
+        }
+
+        // A tampered frame is rejected.
+        let mut sealed = alice_keys.send.seal(b"hello".to_vec()).unwrap();
+        sealed[0] ^= 1;
+        assert!(bob_keys.recv.open(sealed).is_err());
+
+        // So is a replayed or reordered one.
+        let (mut alice_keys, mut bob_keys) = handshake(&alice, &bob, &bob);
+        alice_keys.send.seal(b"first".to_vec()).unwrap();
+        let second = alice_keys.send.seal(b"second".to_vec()).unwrap();
+        assert!(bob_keys.recv.open(second).is_err());
+    }
+
+    #[test]
+    fn test_impersonation() {
+        let alice = SecretKey::from_seed(KeyType::ED25519, "alice");
+        let bob = SecretKey::from_seed(KeyType::ED25519, "bob");
+        let mallory = SecretKey::from_seed(KeyType::ED25519, "mallory");
+        // Alice connects to Bob, but Mallory answers in Bob's name with her own node key.
+        let (mut alice_keys, mut mallory_keys) = handshake(&alice, &mallory, &bob);
+        let sealed = alice_keys.send.seal(b"hello".to_vec()).unwrap();
+        assert!(mallory_keys.recv.open(sealed).is_err());
+    }
+
+    #[test]
+    fn test_small_order_point() {
+        let alice = SecretKey::from_seed(KeyType::ED25519, "alice");
+        let bob = SecretKey::from_seed(KeyType::ED25519, "bob");
+        let result = derive_session_keys(
+            &alice,
+            &EphemeralSecret::new(),
+            &bob.public_key(),
+            &SessionPublicKey([0; 32]),
+            true,
+        );
+        assert!(result.is_err());
+    }
+}
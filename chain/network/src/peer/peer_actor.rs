@@ -7,6 +7,7 @@ use crate::network_protocol::{
     PartialEdgeInfo, PeerChainInfoV2, PeerIdOrHash, PeerInfo, PeersRequest, PeersResponse,
     RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate, StateResponseInfo, SyncAccountsData,
 };
+use crate::peer::encryption;
 use crate::peer::stream;
 use crate::peer::tracker::Tracker;
 use crate::peer_manager::connection;
@@ -14,6 +15,7 @@ use crate::peer_manager::network_state::{NetworkState, PRUNE_EDGES_AFTER};
 use crate::peer_manager::peer_manager_actor::Event;
 use crate::peer_manager::peer_manager_actor::MAX_TIER2_PEERS;
 use crate::private_actix::{RegisterPeerError, SendMessage};
+use crate::reputation::RequestKey;
 use crate::routing::edge::verify_nonce;
 use crate::routing::NetworkTopologyChange;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
@@ -29,6 +31,7 @@ use near_async::time;
 use near_crypto::Signature;
 use near_o11y::{handler_debug_span, log_assert, OpenTelemetrySpanExt, WithSpanContext};
 use near_performance_metrics_macros::perf;
+use near_primitives::checked_feature;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::EpochId;
@@ -111,6 +114,10 @@ pub(crate) enum ClosingReason {
     TooLargeClockSkew,
     #[error("owned_account.peer_id doesn't match handshake.sender_peer_id")]
     OwnedAccountMismatch,
+    #[error("Received a frame which failed to decrypt")]
+    DecryptionFailed,
+    #[error("Encryption is required, but the peer can't encrypt the connection")]
+    UnencryptedConnection,
     #[error("PeerActor stopped NOT via PeerActor::stop()")]
     Unknown,
 }
@@ -132,6 +139,8 @@ impl ClosingReason {
             ClosingReason::DisconnectMessage => false, // graceful disconnect
             ClosingReason::TooLargeClockSkew => true, // reconnect will fail for the same reason
             ClosingReason::OwnedAccountMismatch => true, // misbehaving peer
+            ClosingReason::DecryptionFailed => false, // connection issue
+            ClosingReason::UnencryptedConnection => true, // reconnect will fail for the same reason
             ClosingReason::Unknown => false,        // only happens in tests
         }
     }
@@ -166,6 +175,14 @@ pub(crate) struct PeerActor {
     /// Whether the PeerActor should skip protobuf support detection and use
     /// a given encoding right away.
     force_encoding: Option<Encoding>,
+    /// Ephemeral key of the connection. Present if encryption is enabled in the config.
+    session_secret: Option<encryption::EphemeralSecret>,
+    /// Key opening the received frames. Present once the connection is encrypted.
+    opening_key: Option<encryption::OpeningKey>,
+    /// Whether we've agreed on compression with the peer: it advertised it in its Handshake and
+    /// its protocol version supports it. Compressed messages are only sent to and accepted from
+    /// such peers.
+    peer_accepts_compression: bool,
 
     /// Peer status.
     peer_status: PeerStatus,
@@ -190,6 +207,8 @@ struct HandshakeSpec {
     tier: tcp::Tier,
     protocol_version: ProtocolVersion,
     partial_edge_info: PartialEdgeInfo,
+    /// Whether to offer (outbound) or accept (inbound) encryption of the connection.
+    encrypted: bool,
 }
 
 type HandshakeSignalSender = tokio::sync::oneshot::Sender<std::convert::Infallible>;
@@ -274,6 +293,7 @@ impl PeerActor {
                     protocol_version: PROTOCOL_VERSION,
                     tier: *tier,
                     peer_id: peer_id.clone(),
+                    encrypted: network_state.config.encrypted_transport,
                 },
             },
         };
@@ -318,6 +338,12 @@ impl PeerActor {
                     routed_message_cache: LruCache::new(ROUTED_MESSAGE_CACHE_SIZE),
                     protocol_buffers_supported: false,
                     force_encoding,
+                    session_secret: network_state
+                        .config
+                        .encrypted_transport
+                        .then(encryption::EphemeralSecret::new),
+                    opening_key: None,
+                    peer_accepts_compression: false,
                     peer_info: match &stream_type {
                         tcp::StreamType::Inbound => None,
                         tcp::StreamType::Outbound { peer_id, .. } => Some(PeerInfo {
@@ -351,15 +377,26 @@ impl PeerActor {
         }
     }
 
-    fn parse_message(&mut self, msg: &[u8]) -> Result<PeerMessage, ParsePeerMessageError> {
+    /// Parses a received message. Returns the message and, if it was compressed, its size after
+    /// decompression.
+    /// We only accept compressed messages from the peers we've agreed on compression with, so that
+    /// nobody can make us decompress data we didn't ask for. Other compressed messages are rejected
+    /// before decompression.
+    fn parse_message(
+        &mut self,
+        msg: &[u8],
+    ) -> Result<(PeerMessage, Option<usize>), ParsePeerMessageError> {
+        let accept_compressed = self.peer_accepts_compression;
         if let Some(e) = self.encoding() {
-            return PeerMessage::deserialize(e, msg);
+            return PeerMessage::deserialize_compressed(e, msg, accept_compressed);
         }
-        if let Ok(msg) = PeerMessage::deserialize(Encoding::Proto, msg) {
+        if let Ok(msg) =
+            PeerMessage::deserialize_compressed(Encoding::Proto, msg, accept_compressed)
+        {
             self.protocol_buffers_supported = true;
             return Ok(msg);
         }
-        return PeerMessage::deserialize(Encoding::Borsh, msg);
+        return PeerMessage::deserialize_compressed(Encoding::Borsh, msg, accept_compressed);
     }
 
     fn send_message_or_log(&self, msg: &PeerMessage) {
@@ -402,9 +439,28 @@ impl PeerActor {
             _ => (),
         };
 
-        let bytes = msg.serialize(enc);
+        let (bytes, uncompressed_len) =
+            match (enc, self.network_state.config.message_compression_threshold) {
+                (Encoding::Proto, Some(threshold)) if self.peer_accepts_compression => {
+                    msg.serialize_compressed(threshold)
+                }
+                _ => (msg.serialize(enc), None),
+            };
         self.tracker.lock().increment_sent(&self.clock, bytes.len() as u64);
         let bytes_len = bytes.len();
+        if let Some(uncompressed_len) = uncompressed_len {
+            self.tracker.lock().increment_sent_compressed(
+                &self.clock,
+                uncompressed_len as u64,
+                bytes_len as u64,
+            );
+            metrics::PEER_MESSAGE_COMPRESSION_BYTES
+                .with_label_values(&["sent", "uncompressed"])
+                .inc_by(uncompressed_len as u64);
+            metrics::PEER_MESSAGE_COMPRESSION_BYTES
+                .with_label_values(&["sent", "compressed"])
+                .inc_by(bytes_len as u64);
+        }
         tracing::trace!(target: "network", msg_len = bytes_len);
         self.framed.send(stream::Frame(bytes));
         metrics::PEER_DATA_SENT_BYTES.inc_by(bytes_len as u64);
@@ -443,12 +499,52 @@ impl PeerActor {
                 }
                 .sign(vc.signer.as_ref())
             }),
+            // Borsh encoding doesn't support encryption.
+            session_key: match &self.session_secret {
+                Some(secret) if spec.encrypted && self.encoding() != Some(Encoding::Borsh) => {
+                    Some(secret.public_key())
+                }
+                _ => None,
+            },
+            accepts_compression: supports_compression(spec.protocol_version),
         };
+        let encrypted = handshake.session_key.is_some();
         let msg = match spec.tier {
             tcp::Tier::T1 => PeerMessage::Tier1Handshake(handshake),
             tcp::Tier::T2 => PeerMessage::Tier2Handshake(handshake),
         };
-        self.send_message_or_log(&msg);
+        // There is no point in sending the Handshake in both encodings then.
+        if encrypted {
+            self.send_message_with_encoding(&msg, Encoding::Proto);
+        } else {
+            self.send_message_or_log(&msg);
+        }
+    }
+
+    /// Derives the session keys of the connection if both peers want to encrypt it.
+    fn derive_session_keys(
+        &self,
+        handshake: &Handshake,
+    ) -> Result<Option<encryption::SessionKeys>, encryption::Error> {
+        let (Some(secret), Some(peer_session_key)) = (&self.session_secret, &handshake.session_key)
+        else {
+            return Ok(None);
+        };
+        encryption::derive_session_keys(
+            &self.network_state.config.node_key,
+            secret,
+            handshake.sender_peer_id.public_key(),
+            peer_session_key,
+            self.peer_type == PeerType::Outbound,
+        )
+        .map(Some)
+    }
+
+    /// Encrypts the frames sent from now on and decrypts the frames received from now on.
+    fn start_encryption(&mut self, keys: encryption::SessionKeys) {
+        tracing::debug!(target: "network", "Encrypting the connection with {}", self.peer_info);
+        self.framed.start_encryption(keys.send);
+        self.opening_key = Some(keys.recv);
     }
 
     fn stop(&mut self, ctx: &mut actix::Context<PeerActor>, reason: ClosingReason) {
@@ -582,6 +678,39 @@ impl PeerActor {
             }
         }
 
+        let mut session_keys = match self.derive_session_keys(&handshake) {
+            Ok(session_keys) => session_keys,
+            Err(err) => {
+                tracing::warn!(target: "network", %err, "Failed to derive session keys. Disconnecting peer {}", handshake.sender_peer_id);
+                self.stop(ctx, ClosingReason::HandshakeFailed);
+                return;
+            }
+        };
+        if session_keys.is_none() && self.network_state.config.encrypted_transport {
+            // Handshakes in Borsh can't carry the session key, it is used by old peers only.
+            // The encoding is known to be Proto once a message in Proto was received.
+            let reason = match self.encoding() {
+                Some(Encoding::Proto) => "the peer didn't offer a session key",
+                _ => "the peer uses Borsh encoding",
+            };
+            if self.network_state.config.require_encrypted_transport {
+                tracing::info!(target: "network", "Can't encrypt the connection with {}, {reason}. Disconnecting.", handshake.sender_peer_id);
+                self.stop(ctx, ClosingReason::UnencryptedConnection);
+                return;
+            }
+            tracing::debug!(target: "network", "Can't encrypt the connection with {}, {reason}", handshake.sender_peer_id);
+        }
+        // The outbound peer has already sent its Handshake, so it encrypts the frames from now
+        // on. The inbound peer starts once it sends its Handshake back.
+        if self.peer_type == PeerType::Outbound {
+            if let Some(keys) = session_keys.take() {
+                self.start_encryption(keys);
+            }
+        }
+
+        self.peer_accepts_compression =
+            handshake.accepts_compression && supports_compression(handshake.protocol_version);
+
         // Merge partial edges.
         let nonce = handshake.partial_edge_info.nonce;
         let partial_edge_info = match cs {
@@ -602,7 +731,7 @@ impl PeerActor {
 
         // TODO(gprusak): not enabling a port for listening is also a valid setup.
         // In that case peer_info.addr should be None (same as now), however
-        // we still should do the check against the PeerStore::blacklist.
+        // we still should do the check against the authorization rules.
         // Currently PeerManager is rejecting connections with peer_info.addr == None
         // preemptively.
         let peer_info = PeerInfo {
@@ -647,12 +776,37 @@ impl PeerActor {
             wrap_future(async move {
                 loop {
                     interval.tick(&clock).await;
-                    let sent = tracker.lock().sent_bytes.minute_stats(&clock);
-                    let received = tracker.lock().received_bytes.minute_stats(&clock);
+                    let mut guard = tracker.lock();
+                    let tracker = &mut *guard;
+                    let sent = tracker.sent_bytes.minute_stats(&clock);
+                    let received = tracker.received_bytes.minute_stats(&clock);
                     conn.stats
                         .received_bytes_per_sec
                         .store(received.bytes_per_min / 60, Ordering::Relaxed);
                     conn.stats.sent_bytes_per_sec.store(sent.bytes_per_min / 60, Ordering::Relaxed);
+                    for (stats, per_sec) in [
+                        (
+                            &mut tracker.sent_uncompressed_bytes,
+                            &conn.stats.sent_uncompressed_bytes_per_sec,
+                        ),
+                        (
+                            &mut tracker.sent_compressed_bytes,
+                            &conn.stats.sent_compressed_bytes_per_sec,
+                        ),
+                        (
+                            &mut tracker.received_uncompressed_bytes,
+                            &conn.stats.received_uncompressed_bytes_per_sec,
+                        ),
+                        (
+                            &mut tracker.received_compressed_bytes,
+                            &conn.stats.received_compressed_bytes_per_sec,
+                        ),
+                    ] {
+                        per_sec.store(
+                            stats.minute_stats(&clock).bytes_per_min / 60,
+                            Ordering::Relaxed,
+                        );
+                    }
                 }
             })
         });
@@ -685,7 +839,11 @@ impl PeerActor {
                                 tier,
                                 protocol_version: handshake.protocol_version,
                                 partial_edge_info: partial_edge_info,
+                                encrypted: session_keys.is_some(),
                             });
+                            if let Some(keys) = session_keys {
+                                act.start_encryption(keys);
+                            }
                         }
                         // TIER1 is strictly reserved for BFT consensensus messages,
                         // so all kinds of periodical syncs happen only on TIER2 connections.
@@ -903,6 +1061,7 @@ impl PeerActor {
         clock: &time::Clock,
         network_state: &NetworkState,
         peer_id: PeerId,
+        author: PeerId,
         msg_hash: CryptoHash,
         body: RoutedMessageBody,
     ) -> Result<Option<RoutedMessageBody>, ReasonForBan> {
@@ -942,6 +1101,7 @@ impl PeerActor {
                 network_state.shards_manager_adapter.send(
                     ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse {
                         partial_encoded_chunk_response: response,
+                        responder: author,
                         received_time: clock.now().into(),
                     },
                 );
@@ -1005,6 +1165,25 @@ impl PeerActor {
             }
             _ => false,
         };
+        // Measure how fast the peer responds to our requests.
+        let response = match &msg {
+            PeerMessage::Block(block) => {
+                Some((conn.peer_info.id.clone(), RequestKey::Block(*block.hash())))
+            }
+            PeerMessage::BlockHeaders(_) => {
+                Some((conn.peer_info.id.clone(), RequestKey::BlockHeaders))
+            }
+            PeerMessage::Routed(msg) => match &msg.msg.body {
+                RoutedMessageBody::PartialEncodedChunkResponse(response) => {
+                    Some((msg.msg.author.clone(), RequestKey::Chunk(response.chunk_hash.clone())))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some((peer_id, key)) = response {
+            self.network_state.response_received(&self.clock, peer_id, key);
+        }
         let clock = self.clock.clone();
         let network_state = self.network_state.clone();
         let peer_id = conn.peer_info.id.clone();
@@ -1012,7 +1191,7 @@ impl PeerActor {
             Ok(match msg {
                 PeerMessage::Routed(msg) => {
                     let msg_hash = msg.hash();
-                    Self::receive_routed_message(&clock, &network_state, peer_id, msg_hash, msg.msg.body).await?.map(
+                    Self::receive_routed_message(&clock, &network_state, peer_id, msg.msg.author, msg_hash, msg.msg.body).await?.map(
                         |body| {
                             PeerMessage::Routed(network_state.sign_message(
                                 &clock,
@@ -1517,6 +1696,7 @@ impl actix::Handler<stream::Error> for PeerActor {
             }
             // It is expected in a sense that the peer might be just slow.
             stream::Error::Send(stream::SendError::QueueOverflow { .. }) => true,
+            stream::Error::Send(stream::SendError::Encryption(_)) => false,
             stream::Error::Recv(stream::RecvError::IO(err))
             | stream::Error::Send(stream::SendError::IO(err)) => match err.kind() {
                 // Connection has been closed.
@@ -1565,8 +1745,35 @@ impl actix::Handler<stream::Frame> for PeerActor {
             self.tracker.lock().increment_received(&self.clock, msg.len() as u64);
         }
 
+        let msg = match &mut self.opening_key {
+            Some(key) => match key.open(msg) {
+                Ok(msg) => msg,
+                Err(err) => {
+                    tracing::warn!(target: "network", %err, "Received invalid encrypted frame from {}. Disconnecting.", self.peer_info);
+                    self.stop(ctx, ClosingReason::DecryptionFailed);
+                    return;
+                }
+            },
+            None => msg,
+        };
+
         let mut peer_msg = match self.parse_message(&msg) {
-            Ok(msg) => msg,
+            Ok((peer_msg, uncompressed_len)) => {
+                if let Some(uncompressed_len) = uncompressed_len {
+                    self.tracker.lock().increment_received_compressed(
+                        &self.clock,
+                        uncompressed_len as u64,
+                        msg.len() as u64,
+                    );
+                    metrics::PEER_MESSAGE_COMPRESSION_BYTES
+                        .with_label_values(&["received", "uncompressed"])
+                        .inc_by(uncompressed_len as u64);
+                    metrics::PEER_MESSAGE_COMPRESSION_BYTES
+                        .with_label_values(&["received", "compressed"])
+                        .inc_by(msg.len() as u64);
+                }
+                peer_msg
+            }
             Err(err) => {
                 tracing::debug!(target: "network", "Received invalid data {} from {}: {}", near_fmt::AbbrBytes(&msg), self.peer_info, err);
                 return;
@@ -1657,6 +1864,15 @@ impl actix::Handler<WithSpanContext<Stop>> for PeerActor {
 
 type InboundHandshakePermit = tokio::sync::OwnedSemaphorePermit;
 
+/// Whether peers running the given protocol version may compress their messages.
+fn supports_compression(protocol_version: ProtocolVersion) -> bool {
+    checked_feature!(
+        "protocol_feature_peer_message_compression",
+        PeerMessageCompression,
+        protocol_version
+    )
+}
+
 #[derive(Debug)]
 enum ConnectingStatus {
     Inbound(InboundHandshakePermit),
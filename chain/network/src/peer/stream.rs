@@ -1,3 +1,4 @@
+use crate::peer::encryption;
 use crate::peer_manager::connection;
 use crate::stats::metrics;
 use crate::tcp;
@@ -13,7 +14,7 @@ use tokio::io::AsyncWriteExt as _;
 
 /// Maximum size of network message in encoded format.
 /// We encode length as `u32`, and therefore maximum size can't be larger than `u32::MAX`.
-const NETWORK_MESSAGE_MAX_SIZE_BYTES: usize = 512 * MIB as usize;
+pub(crate) const NETWORK_MESSAGE_MAX_SIZE_BYTES: usize = 512 * MIB as usize;
 /// Maximum capacity of write buffer in bytes.
 const MAX_WRITE_BUFFER_CAPACITY_BYTES: usize = GIB as usize;
 
@@ -24,6 +25,8 @@ type WriteHalf = tokio::io::WriteHalf<tokio::net::TcpStream>;
 pub(crate) enum SendError {
     #[error("IO error: {0}")]
     IO(#[source] io::Error),
+    #[error("encryption: {0}")]
+    Encryption(#[source] encryption::Error),
     #[error("queue is full, got {got_bytes}B, max capacity is {want_max_bytes}")]
     QueueOverflow { got_bytes: usize, want_max_bytes: usize },
 }
@@ -40,6 +43,13 @@ pub(crate) enum RecvError {
 #[rtype(result = "()")]
 pub(crate) struct Frame(pub Vec<u8>);
 
+/// Item of the send queue.
+enum Outgoing {
+    Frame(Frame),
+    /// Frames queued after this item are encrypted with the key.
+    StartEncryption(encryption::SealingKey),
+}
+
 /// Stream critical error.
 /// Actor is responsible for calling ctx.stop() after receiving stream::Error.
 /// Actor might receive more than 1 stream::Error, but should call ctx.stop() just after the
@@ -57,7 +67,7 @@ pub(crate) enum Error {
 }
 
 pub(crate) struct FramedStream<Actor: actix::Actor> {
-    queue_send: tokio::sync::mpsc::UnboundedSender<Frame>,
+    queue_send: tokio::sync::mpsc::UnboundedSender<Outgoing>,
     stats: Arc<connection::Stats>,
     send_buf_size_metric: Arc<metrics::IntGaugeGuard>,
     addr: actix::Addr<Actor>,
@@ -86,7 +96,7 @@ where
             let m = send_buf_size_metric.clone();
             async move {
                 if let Err(err) = Self::run_send_loop(tcp_send, queue_recv, stats, m).await {
-                    addr.do_send(Error::Send(SendError::IO(err)));
+                    addr.do_send(Error::Send(err));
                 }
             }
         }));
@@ -125,7 +135,12 @@ where
                 want_max_bytes: MAX_WRITE_BUFFER_CAPACITY_BYTES,
             }));
         }
-        let _ = self.queue_send.send(frame);
+        let _ = self.queue_send.send(Outgoing::Frame(frame));
+    }
+
+    /// Encrypts the frames sent after this call with the key.
+    pub fn start_encryption(&self, key: encryption::SealingKey) {
+        let _ = self.queue_send.send(Outgoing::StartEncryption(key));
     }
 
     /// Event loop receiving and processing messages.
@@ -177,28 +192,39 @@ where
     }
     async fn run_send_loop(
         tcp_send: WriteHalf,
-        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Frame>,
+        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Outgoing>,
         stats: Arc<connection::Stats>,
         buf_size_metric: Arc<metrics::IntGaugeGuard>,
-    ) -> io::Result<()> {
+    ) -> Result<(), SendError> {
         const WRITE_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut writer = tokio::io::BufWriter::with_capacity(WRITE_BUFFER_CAPACITY, tcp_send);
-        while let Some(Frame(mut msg)) = queue_recv.recv().await {
+        let mut sealing_key: Option<encryption::SealingKey> = None;
+        while let Some(mut item) = queue_recv.recv().await {
             // Try writing a batch of messages and flush once at the end.
             loop {
-                // TODO(gprusak): sending a too large message should probably be treated as a bug,
-                // since dropping messages may lead to hard-to-debug high-level issues.
-                if msg.len() > NETWORK_MESSAGE_MAX_SIZE_BYTES {
-                    metrics::MessageDropped::InputTooLong.inc_unknown_msg();
-                } else {
-                    writer.write_u32_le(msg.len() as u32).await?;
-                    writer.write_all(&msg[..]).await?;
+                match item {
+                    Outgoing::Frame(Frame(msg)) => {
+                        let msg_len = msg.len();
+                        let msg = match &mut sealing_key {
+                            Some(key) => key.seal(msg).map_err(SendError::Encryption)?,
+                            None => msg,
+                        };
+                        // TODO(gprusak): sending a too large message should probably be treated as a bug,
+                        // since dropping messages may lead to hard-to-debug high-level issues.
+                        if msg.len() > NETWORK_MESSAGE_MAX_SIZE_BYTES {
+                            metrics::MessageDropped::InputTooLong.inc_unknown_msg();
+                        } else {
+                            writer.write_u32_le(msg.len() as u32).await.map_err(SendError::IO)?;
+                            writer.write_all(&msg[..]).await.map_err(SendError::IO)?;
+                        }
+                        stats.messages_to_send.fetch_sub(1, Ordering::Release);
+                        stats.bytes_to_send.fetch_sub(msg_len as u64, Ordering::Release);
+                        buf_size_metric.sub(msg_len as i64);
+                    }
+                    Outgoing::StartEncryption(key) => sealing_key = Some(key),
                 }
-                stats.messages_to_send.fetch_sub(1, Ordering::Release);
-                stats.bytes_to_send.fetch_sub(msg.len() as u64, Ordering::Release);
-                buf_size_metric.sub(msg.len() as i64);
-                msg = match queue_recv.try_recv() {
-                    Ok(Frame(it)) => it,
+                item = match queue_recv.try_recv() {
+                    Ok(it) => it,
                     Err(_) => break,
                 };
             }
@@ -208,7 +234,7 @@ where
             // and added to the queue at a rate similar to flush latency. To fix that
             // we would need to put writer.flush() and queue_recv.recv() into a tokio::select
             // and make sure that both are cancellation-safe.
-            writer.flush().await?;
+            writer.flush().await.map_err(SendError::IO)?;
         }
         Ok(())
     }
@@ -108,7 +108,6 @@ impl PeerHandle {
             cfg.chain.genesis_id.clone(),
             fc.clone(),
             fc.as_sender(),
-            vec![],
         ));
         let actix = ActixSystem::spawn({
             let clock = clock.clone();
@@ -3,6 +3,8 @@ use crate::network_protocol::{
     Encoding, Handshake, HandshakeFailureReason, PartialEdgeInfo, PeerMessage, PeersRequest,
     PeersResponse, RoutedMessageBody,
 };
+use crate::peer::encryption;
+use crate::peer::peer_actor::ClosingReason;
 use crate::peer::testonly::{Event, PeerConfig, PeerHandle};
 use crate::peer_manager::peer_manager_actor::Event as PME;
 use crate::tcp;
@@ -19,23 +21,32 @@ use std::sync::Arc;
 async fn test_peer_communication(
     outbound_encoding: Option<Encoding>,
     inbound_encoding: Option<Encoding>,
+    outbound_encrypted: bool,
+    inbound_encrypted: bool,
+    compress_all: bool,
 ) -> anyhow::Result<()> {
-    tracing::info!("test_peer_communication({outbound_encoding:?},{inbound_encoding:?})");
+    tracing::info!("test_peer_communication({outbound_encoding:?},{inbound_encoding:?},{outbound_encrypted},{inbound_encrypted},{compress_all})");
 
     let mut rng = make_rng(89028037453);
     let mut clock = time::FakeClock::default();
 
     let chain = Arc::new(data::Chain::make(&mut clock, &mut rng, 12));
-    let inbound_cfg = PeerConfig {
+    let mut inbound_cfg = PeerConfig {
         chain: chain.clone(),
         network: chain.make_config(&mut rng),
         force_encoding: inbound_encoding,
     };
-    let outbound_cfg = PeerConfig {
+    inbound_cfg.network.encrypted_transport = inbound_encrypted;
+    let mut outbound_cfg = PeerConfig {
         chain: chain.clone(),
         network: chain.make_config(&mut rng),
         force_encoding: outbound_encoding,
     };
+    outbound_cfg.network.encrypted_transport = outbound_encrypted;
+    if compress_all {
+        inbound_cfg.network.message_compression_threshold = Some(0);
+        outbound_cfg.network.message_compression_threshold = Some(0);
+    }
     let (outbound_stream, inbound_stream) =
         tcp::Stream::loopback(inbound_cfg.id(), tcp::Tier::T2).await;
     let mut inbound = PeerHandle::start_endpoint(clock.clock(), inbound_cfg, inbound_stream).await;
@@ -171,7 +182,7 @@ async fn peer_communication() -> anyhow::Result<()> {
                     continue;
                 }
             }
-            test_peer_communication(*outbound, *inbound)
+            test_peer_communication(*outbound, *inbound, false, false, false)
                 .await
                 .with_context(|| format!("(outbound={outbound:?},inbound={inbound:?})"))?;
         }
@@ -179,6 +190,36 @@ async fn peer_communication() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+// Verifies that peers communicate whether one, both or none of them enable encryption.
+async fn encrypted_peer_communication() -> anyhow::Result<()> {
+    init_test_logger();
+    for encoding in [None, Some(Encoding::Proto), Some(Encoding::Borsh)] {
+        for (outbound, inbound) in [(true, true), (true, false), (false, true)] {
+            test_peer_communication(encoding, encoding, outbound, inbound, false)
+                .await
+                .with_context(|| {
+                    format!("(encoding={encoding:?},outbound={outbound},inbound={inbound})")
+                })?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+// Verifies that peers communicate when compressing all the messages worth it.
+async fn compressed_peer_communication() -> anyhow::Result<()> {
+    init_test_logger();
+    for encoding in [None, Some(Encoding::Proto), Some(Encoding::Borsh)] {
+        for encrypted in [false, true] {
+            test_peer_communication(encoding, encoding, encrypted, encrypted, true)
+                .await
+                .with_context(|| format!("(encoding={encoding:?},encrypted={encrypted})"))?;
+        }
+    }
+    Ok(())
+}
+
 async fn test_handshake(outbound_encoding: Option<Encoding>, inbound_encoding: Option<Encoding>) {
     let mut rng = make_rng(89028037453);
     let mut clock = time::FakeClock::default();
@@ -210,6 +251,8 @@ async fn test_handshake(outbound_encoding: Option<Encoding>, inbound_encoding: O
         sender_chain_info: outbound_cfg.chain.get_peer_chain_info(),
         partial_edge_info: outbound_cfg.partial_edge_info(&inbound.cfg.id(), 1),
         owned_account: None,
+        session_key: None,
+        accepts_compression: false,
     };
     // We will also introduce chain_id mismatch, but ProtocolVersionMismatch is expected to take priority.
     handshake.sender_chain_info.genesis_id.chain_id = "unknown_chain".to_string();
@@ -266,3 +309,178 @@ async fn handshake() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[tokio::test]
+// Verifies that the peer encrypts the connection if asked to in the Handshake.
+async fn encrypted_handshake() {
+    init_test_logger();
+    let mut rng = make_rng(89028037453);
+    let mut clock = time::FakeClock::default();
+
+    let chain = Arc::new(data::Chain::make(&mut clock, &mut rng, 12));
+    let mut inbound_cfg = PeerConfig {
+        network: chain.make_config(&mut rng),
+        chain: chain.clone(),
+        force_encoding: Some(Encoding::Proto),
+    };
+    inbound_cfg.network.encrypted_transport = true;
+    let outbound_cfg = PeerConfig {
+        network: chain.make_config(&mut rng),
+        chain: chain.clone(),
+        force_encoding: Some(Encoding::Proto),
+    };
+    let (outbound_stream, inbound_stream) =
+        tcp::Stream::loopback(inbound_cfg.id(), tcp::Tier::T2).await;
+    let inbound = PeerHandle::start_endpoint(clock.clock(), inbound_cfg, inbound_stream).await;
+    let outbound_port = outbound_stream.local_addr.port();
+    let mut outbound = Stream::new(Some(Encoding::Proto), outbound_stream);
+
+    let session_secret = encryption::EphemeralSecret::new();
+    outbound
+        .write(&PeerMessage::Tier2Handshake(Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            oldest_supported_version: PROTOCOL_VERSION,
+            sender_peer_id: outbound_cfg.id(),
+            target_peer_id: inbound.cfg.id(),
+            sender_listen_port: Some(outbound_port),
+            sender_chain_info: chain.get_peer_chain_info(),
+            partial_edge_info: outbound_cfg.partial_edge_info(&inbound.cfg.id(), 1),
+            owned_account: None,
+            session_key: Some(session_secret.public_key()),
+            accepts_compression: false,
+        }))
+        .await;
+    let handshake = match outbound.read().await.unwrap() {
+        PeerMessage::Tier2Handshake(handshake) => handshake,
+        msg => panic!("unexpected message {msg}"),
+    };
+    let keys = encryption::derive_session_keys(
+        &outbound_cfg.network.node_key,
+        &session_secret,
+        inbound.cfg.id().public_key(),
+        &handshake.session_key.expect("session key in the Handshake response"),
+        true,
+    )
+    .unwrap();
+    outbound.start_encryption(keys);
+
+    // Frames sent by the peer after the Handshake are encrypted.
+    outbound.read().await.unwrap();
+    // And it decrypts the received frames.
+    let mut events = inbound.events.from_now();
+    let want = PeerMessage::PeersRequest(PeersRequest { max_peers: None, max_direct_peers: None });
+    outbound.write(&want).await;
+    events
+        .recv_until(|ev| match ev {
+            Event::Network(PME::MessageProcessed(_, got)) if got == want => Some(()),
+            _ => None,
+        })
+        .await;
+}
+
+#[tokio::test]
+// Verifies that the peer ignores compressed messages from peers which didn't negotiate compression.
+async fn unnegotiated_compression() {
+    init_test_logger();
+    let mut rng = make_rng(89028037453);
+    let mut clock = time::FakeClock::default();
+
+    let chain = Arc::new(data::Chain::make(&mut clock, &mut rng, 12));
+    let mut inbound_cfg = PeerConfig {
+        network: chain.make_config(&mut rng),
+        chain: chain.clone(),
+        force_encoding: Some(Encoding::Proto),
+    };
+    inbound_cfg.network.message_compression_threshold = Some(0);
+    let outbound_cfg = PeerConfig {
+        network: chain.make_config(&mut rng),
+        chain: chain.clone(),
+        force_encoding: Some(Encoding::Proto),
+    };
+    let (outbound_stream, inbound_stream) =
+        tcp::Stream::loopback(inbound_cfg.id(), tcp::Tier::T2).await;
+    let inbound = PeerHandle::start_endpoint(clock.clock(), inbound_cfg, inbound_stream).await;
+    let outbound_port = outbound_stream.local_addr.port();
+    let mut outbound = Stream::new(Some(Encoding::Proto), outbound_stream);
+
+    outbound
+        .write(&PeerMessage::Tier2Handshake(Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            oldest_supported_version: PROTOCOL_VERSION,
+            sender_peer_id: outbound_cfg.id(),
+            target_peer_id: inbound.cfg.id(),
+            sender_listen_port: Some(outbound_port),
+            sender_chain_info: chain.get_peer_chain_info(),
+            partial_edge_info: outbound_cfg.partial_edge_info(&inbound.cfg.id(), 1),
+            owned_account: None,
+            session_key: None,
+            accepts_compression: false,
+        }))
+        .await;
+    assert_matches!(outbound.read().await.unwrap(), PeerMessage::Tier2Handshake(_));
+
+    // The compressed message is dropped, the next one is processed.
+    let mut events = inbound.events.from_now();
+    outbound.write_compressed(&PeerMessage::BlockHeaders(chain.get_block_headers())).await;
+    let want = PeerMessage::PeersRequest(PeersRequest { max_peers: None, max_direct_peers: None });
+    outbound.write(&want).await;
+    let got = events
+        .recv_until(|ev| match ev {
+            Event::Network(PME::MessageProcessed(_, got)) => Some(got),
+            _ => None,
+        })
+        .await;
+    assert_eq!(got, want);
+}
+
+#[tokio::test]
+// Verifies that the peer requiring encryption closes the connections which can't be encrypted.
+async fn required_encryption() {
+    init_test_logger();
+    let mut rng = make_rng(89028037453);
+    let mut clock = time::FakeClock::default();
+
+    let chain = Arc::new(data::Chain::make(&mut clock, &mut rng, 12));
+    for encoding in [Encoding::Proto, Encoding::Borsh] {
+        let mut inbound_cfg = PeerConfig {
+            network: chain.make_config(&mut rng),
+            chain: chain.clone(),
+            force_encoding: None,
+        };
+        inbound_cfg.network.encrypted_transport = true;
+        inbound_cfg.network.require_encrypted_transport = true;
+        let outbound_cfg = PeerConfig {
+            network: chain.make_config(&mut rng),
+            chain: chain.clone(),
+            force_encoding: Some(encoding),
+        };
+        let (outbound_stream, inbound_stream) =
+            tcp::Stream::loopback(inbound_cfg.id(), tcp::Tier::T2).await;
+        let inbound = PeerHandle::start_endpoint(clock.clock(), inbound_cfg, inbound_stream).await;
+        let outbound_port = outbound_stream.local_addr.port();
+        let mut outbound = Stream::new(Some(encoding), outbound_stream);
+
+        let mut events = inbound.events.from_now();
+        outbound
+            .write(&PeerMessage::Tier2Handshake(Handshake {
+                protocol_version: PROTOCOL_VERSION,
+                oldest_supported_version: PROTOCOL_VERSION,
+                sender_peer_id: outbound_cfg.id(),
+                target_peer_id: inbound.cfg.id(),
+                sender_listen_port: Some(outbound_port),
+                sender_chain_info: chain.get_peer_chain_info(),
+                partial_edge_info: outbound_cfg.partial_edge_info(&inbound.cfg.id(), 1),
+                owned_account: None,
+                session_key: None,
+                accepts_compression: false,
+            }))
+            .await;
+        let reason = events
+            .recv_until(|ev| match ev {
+                Event::Network(PME::ConnectionClosed(ev)) => Some(ev.reason),
+                _ => None,
+            })
+            .await;
+        assert_eq!(reason, ClosingReason::UnencryptedConnection, "encoding={encoding:?}");
+    }
+}
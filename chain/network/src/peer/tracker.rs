@@ -45,6 +45,14 @@ pub(crate) struct Tracker {
     pub(crate) sent_bytes: TransferStats,
     /// Bytes we've received.
     pub(crate) received_bytes: TransferStats,
+    /// Bytes of the compressed messages we've sent, before compression.
+    pub(crate) sent_uncompressed_bytes: TransferStats,
+    /// Bytes of the compressed messages we've sent, after compression.
+    pub(crate) sent_compressed_bytes: TransferStats,
+    /// Bytes of the compressed messages we've received, after decompression.
+    pub(crate) received_uncompressed_bytes: TransferStats,
+    /// Bytes of the compressed messages we've received, before decompression.
+    pub(crate) received_compressed_bytes: TransferStats,
     /// Sent requests.
     requested: CircularUniqueQueue,
     /// Received elements.
@@ -56,6 +64,10 @@ impl Default for Tracker {
         Tracker {
             sent_bytes: TransferStats::default(),
             received_bytes: TransferStats::default(),
+            sent_uncompressed_bytes: TransferStats::default(),
+            sent_compressed_bytes: TransferStats::default(),
+            received_uncompressed_bytes: TransferStats::default(),
+            received_compressed_bytes: TransferStats::default(),
             requested: CircularUniqueQueue::new(MAX_TRACK_SIZE),
             received: CircularUniqueQueue::new(MAX_TRACK_SIZE),
         }
@@ -71,6 +83,26 @@ impl Tracker {
         self.sent_bytes.record(clock, size);
     }
 
+    pub(crate) fn increment_received_compressed(
+        &mut self,
+        clock: &time::Clock,
+        uncompressed_size: u64,
+        compressed_size: u64,
+    ) {
+        self.received_uncompressed_bytes.record(clock, uncompressed_size);
+        self.received_compressed_bytes.record(clock, compressed_size);
+    }
+
+    pub(crate) fn increment_sent_compressed(
+        &mut self,
+        clock: &time::Clock,
+        uncompressed_size: u64,
+        compressed_size: u64,
+    ) {
+        self.sent_uncompressed_bytes.record(clock, uncompressed_size);
+        self.sent_compressed_bytes.record(clock, compressed_size);
+    }
+
     // TODO: uncomment this once we add a new message type to sync block height
     /*
     pub(crate) fn has_received(&self, hash: &CryptoHash) -> bool {
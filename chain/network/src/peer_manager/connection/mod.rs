@@ -62,6 +62,12 @@ pub(crate) struct Stats {
     pub received_bytes_per_sec: AtomicU64,
     /// Avg sent bytes/s, based on the last few minutes of traffic.
     pub sent_bytes_per_sec: AtomicU64,
+    /// Avg received bytes/s of the compressed messages, after and before decompression.
+    pub received_uncompressed_bytes_per_sec: AtomicU64,
+    pub received_compressed_bytes_per_sec: AtomicU64,
+    /// Avg sent bytes/s of the compressed messages, before and after compression.
+    pub sent_uncompressed_bytes_per_sec: AtomicU64,
+    pub sent_compressed_bytes_per_sec: AtomicU64,
 
     /// Number of messages in the buffer to send.
     pub messages_to_send: AtomicU64,
@@ -15,6 +15,7 @@ use crate::peer_manager::connection_store;
 use crate::peer_manager::peer_manager_actor::Event;
 use crate::peer_manager::peer_store;
 use crate::private_actix::RegisterPeerError;
+use crate::reputation::{PendingRequests, ReputationEvent, RequestKey};
 use crate::routing::route_back_cache::RouteBackCache;
 use crate::routing::NetworkTopologyChange;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
@@ -31,7 +32,6 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::network::PeerId;
 use near_primitives::types::AccountId;
 use parking_lot::Mutex;
-use std::net::SocketAddr;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use tracing::Instrument as _;
@@ -60,27 +60,6 @@ pub const PRUNE_EDGES_AFTER: time::Duration = time::Duration::minutes(30);
 /// How long to wait between reconnection attempts to the same peer
 pub(crate) const RECONNECT_ATTEMPT_INTERVAL: time::Duration = time::Duration::seconds(10);
 
-impl WhitelistNode {
-    pub fn from_peer_info(pi: &PeerInfo) -> anyhow::Result<Self> {
-        Ok(Self {
-            id: pi.id.clone(),
-            addr: if let Some(addr) = pi.addr {
-                addr
-            } else {
-                anyhow::bail!("addess is missing");
-            },
-            account_id: pi.account_id.clone(),
-        })
-    }
-}
-
-#[derive(Clone, PartialEq, Eq)]
-pub(crate) struct WhitelistNode {
-    id: PeerId,
-    addr: SocketAddr,
-    account_id: Option<AccountId>,
-}
-
 pub(crate) struct NetworkState {
     /// Dedicated runtime for `NetworkState` which runs in a separate thread.
     /// Async methods of NetworkState are not cancellable,
@@ -135,14 +114,13 @@ pub(crate) struct NetworkState {
     /// TODO(gprusak): consider removing it altogether.
     pub tier1_route_back: Mutex<RouteBackCache>,
 
+    /// Requests sent to the peers, awaiting a response. Used to measure the peers' reputation.
+    pending_requests: Mutex<PendingRequests>,
+
     /// Shared counter across all PeerActors, which counts number of `RoutedMessageBody::ForwardTx`
     /// messages sincce last block.
     pub txns_since_last_block: AtomicUsize,
 
-    /// Whitelisted nodes, which are allowed to connect even if the connection limit has been
-    /// reached.
-    whitelist_nodes: Vec<WhitelistNode>,
-
     /// Mutex which prevents overlapping calls to tier1_advertise_proxies.
     tier1_advertise_proxies_mutex: tokio::sync::Mutex<()>,
     /// Demultiplexer aggregating calls to add_edges(), for V1 routing protocol
@@ -165,7 +143,6 @@ impl NetworkState {
         genesis_id: GenesisId,
         client: Arc<dyn client::Client>,
         shards_manager_adapter: Sender<ShardsManagerRequestFromNetwork>,
-        whitelist_nodes: Vec<WhitelistNode>,
     ) -> Self {
         Self {
             runtime: Runtime::new(),
@@ -195,11 +172,11 @@ impl NetworkState {
             account_announcements: Arc::new(AnnounceAccountCache::new(store)),
             tier2_route_back: Mutex::new(RouteBackCache::default()),
             tier1_route_back: Mutex::new(RouteBackCache::default()),
+            pending_requests: Mutex::new(PendingRequests::new()),
             recent_routed_messages: Mutex::new(lru::LruCache::new(
                 RECENT_ROUTED_MESSAGES_CACHE_SIZE,
             )),
             txns_since_last_block: AtomicUsize::new(0),
-            whitelist_nodes,
             add_edges_demux: demux::Demux::new(config.routing_table_update_rate_limit),
             update_routes_demux: demux::Demux::new(config.routing_table_update_rate_limit),
             set_chain_info_mutex: Mutex::new(()),
@@ -242,15 +219,49 @@ impl NetworkState {
         }
     }
 
+    /// Records a request sent to the peer, so that we can measure how fast it responds.
+    pub(crate) fn request_sent(&self, clock: &time::Clock, peer_id: PeerId, key: RequestKey) {
+        self.pending_requests.lock().push(clock.now(), peer_id, key);
+    }
+
+    /// Records a response received from the peer. Responses that we didn't ask for, or which
+    /// arrived after the request has timed out, don't affect the reputation of the peer.
+    pub(crate) fn response_received(&self, clock: &time::Clock, peer_id: PeerId, key: RequestKey) {
+        let latency = self.pending_requests.lock().pop(clock.now(), peer_id.clone(), key);
+        if let Some(latency) = latency {
+            self.peer_store.record_reputation_event(&peer_id, ReputationEvent::Response(latency));
+        }
+    }
+
+    /// Lowers the reputation of the peers which didn't respond to our requests in time.
+    pub fn expire_pending_requests(&self, clock: &time::Clock) {
+        let expired = self.pending_requests.lock().pop_expired(clock.now());
+        for peer_id in expired {
+            self.peer_store.record_reputation_event(&peer_id, ReputationEvent::SlowResponse);
+        }
+    }
+
+    /// Disconnects the connected peers which the authorization rules deny. Returns the ids of
+    /// the disconnected peers.
+    pub fn disconnect_denied_peers(&self, clock: &time::Clock) -> Vec<PeerId> {
+        let mut disconnected = vec![];
+        for pool in [&self.tier1, &self.tier2] {
+            for (peer_id, conn) in &pool.load().ready {
+                if self.peer_store.is_denied(clock, &conn.peer_info) {
+                    tracing::info!(target: "network", ?peer_id, "Disconnecting denied peer");
+                    conn.stop(None);
+                    disconnected.push(peer_id.clone());
+                }
+            }
+        }
+        disconnected
+    }
+
     /// is_peer_whitelisted checks whether a peer is a whitelisted node.
     /// whitelisted nodes are allowed to connect, even if the inbound connections limit has
     /// been reached. This predicate should be evaluated AFTER the Handshake.
     pub fn is_peer_whitelisted(&self, peer_info: &PeerInfo) -> bool {
-        self.whitelist_nodes
-            .iter()
-            .filter(|wn| wn.id == peer_info.id)
-            .filter(|wn| Some(wn.addr) == peer_info.addr)
-            .any(|wn| wn.account_id.is_none() || wn.account_id == peer_info.account_id)
+        self.peer_store.is_whitelisted(peer_info)
     }
 
     /// predicate checking whether we should allow an inbound connection from peer_info.
@@ -286,9 +297,9 @@ impl NetworkState {
         let clock = clock.clone();
         self.spawn(async move {
             let peer_info = &conn.peer_info;
-            // Check if this is a blacklisted peer.
-            if peer_info.addr.as_ref().map_or(true, |addr| this.peer_store.is_blacklisted(addr)) {
-                tracing::debug!(target: "network", peer_info = ?peer_info, "Dropping connection from blacklisted peer or unknown address");
+            // Check if this is a denied peer.
+            if peer_info.addr.is_none() || this.peer_store.is_denied(&clock, peer_info) {
+                tracing::debug!(target: "network", peer_info = ?peer_info, "Dropping connection from denied peer or unknown address");
                 return Err(RegisterPeerError::Blacklisted);
             }
 
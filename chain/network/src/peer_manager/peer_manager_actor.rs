@@ -7,16 +7,17 @@ use crate::network_protocol::{
 };
 use crate::peer::peer_actor::PeerActor;
 use crate::peer_manager::connection;
-use crate::peer_manager::network_state::{NetworkState, WhitelistNode};
+use crate::peer_manager::network_state::NetworkState;
 use crate::peer_manager::peer_store;
+use crate::reputation::RequestKey;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
 use crate::stats::metrics;
 use crate::store;
 use crate::tcp;
 use crate::types::{
-    ConnectedPeerInfo, HighestHeightPeerInfo, KnownProducer, NetworkInfo, NetworkRequests,
-    NetworkResponses, PeerInfo, PeerManagerMessageRequest, PeerManagerMessageResponse, PeerType,
-    SetChainInfo,
+    AuthorizationUpdate, ConnectedPeerInfo, HighestHeightPeerInfo, KnownProducer, NetworkInfo,
+    NetworkRequests, NetworkResponses, PeerInfo, PeerManagerMessageRequest,
+    PeerManagerMessageResponse, PeerType, SetChainInfo,
 };
 use actix::fut::future::wrap_future;
 use actix::{Actor as _, AsyncContext as _};
@@ -215,14 +216,7 @@ impl PeerManagerActor {
                boot_nodes = config.peer_store.boot_nodes.len(),
                banned = peer_store.count_banned(),
                "Found known peers");
-        tracing::debug!(target: "network", blacklist = ?config.peer_store.blacklist, "Blacklist");
-        let whitelist_nodes = {
-            let mut v = vec![];
-            for wn in &config.whitelist_nodes {
-                v.push(WhitelistNode::from_peer_info(wn)?);
-            }
-            v
-        };
+        tracing::debug!(target: "network", authorization = ?config.peer_store.authorization, "Authorization");
         let my_peer_id = config.node_id();
         let arbiter = actix::Arbiter::new().handle();
         let clock = clock;
@@ -234,7 +228,6 @@ impl PeerManagerActor {
             genesis_id,
             client,
             shards_manager_adapter,
-            whitelist_nodes,
         ));
         arbiter.spawn({
             let arbiter = arbiter.clone();
@@ -408,6 +401,10 @@ impl PeerManagerActor {
             .ready
             .values()
             .filter_map(|p| p.full_peer_info().into())
+            .map(|mut info: HighestHeightPeerInfo| {
+                info.reputation = self.state.peer_store.reputation_score(&info.peer_info.id);
+                info
+            })
             .collect();
 
         // This finds max height among peers, and returns one peer close to such height.
@@ -566,6 +563,7 @@ impl PeerManagerActor {
             metrics::PEER_MANAGER_TRIGGER_TIME.with_label_values(&["monitor_peers"]).start_timer();
 
         self.state.peer_store.update(&self.clock);
+        self.state.expire_pending_requests(&self.clock);
 
         if self.is_outbound_bootstrap_needed() {
             let tier2 = self.state.tier2.load();
@@ -573,6 +571,7 @@ impl PeerManagerActor {
             let prefer_previously_connected_peer =
                 thread_rng().gen_bool(PREFER_PREVIOUSLY_CONNECTED_PEER);
             if let Some(peer_info) = self.state.peer_store.unconnected_peer(
+                &self.clock,
                 |peer_state| {
                     // Ignore connecting to ourself
                     self.my_peer_id == peer_state.peer_info.id
@@ -655,6 +654,22 @@ impl PeerManagerActor {
             full_peer_info: cp.full_peer_info(),
             received_bytes_per_sec: cp.stats.received_bytes_per_sec.load(Ordering::Relaxed),
             sent_bytes_per_sec: cp.stats.sent_bytes_per_sec.load(Ordering::Relaxed),
+            received_uncompressed_bytes_per_sec: cp
+                .stats
+                .received_uncompressed_bytes_per_sec
+                .load(Ordering::Relaxed),
+            received_compressed_bytes_per_sec: cp
+                .stats
+                .received_compressed_bytes_per_sec
+                .load(Ordering::Relaxed),
+            sent_uncompressed_bytes_per_sec: cp
+                .stats
+                .sent_uncompressed_bytes_per_sec
+                .load(Ordering::Relaxed),
+            sent_compressed_bytes_per_sec: cp
+                .stats
+                .sent_compressed_bytes_per_sec
+                .load(Ordering::Relaxed),
             last_time_peer_requested: cp.last_time_peer_requested.load().unwrap_or(now),
             last_time_received_message: cp.last_time_received_message.load(),
             connection_established_time: cp.established_time,
@@ -749,19 +764,23 @@ impl PeerManagerActor {
                 NetworkResponses::NoResponse
             }
             NetworkRequests::BlockRequest { hash, peer_id } => {
-                if self.state.tier2.send_message(peer_id, Arc::new(PeerMessage::BlockRequest(hash)))
+                if self
+                    .state
+                    .tier2
+                    .send_message(peer_id.clone(), Arc::new(PeerMessage::BlockRequest(hash)))
                 {
+                    self.state.request_sent(&self.clock, peer_id, RequestKey::Block(hash));
                     NetworkResponses::NoResponse
                 } else {
                     NetworkResponses::RouteNotFound
                 }
             }
             NetworkRequests::BlockHeadersRequest { hashes, peer_id } => {
-                if self
-                    .state
-                    .tier2
-                    .send_message(peer_id, Arc::new(PeerMessage::BlockHeadersRequest(hashes)))
-                {
+                if self.state.tier2.send_message(
+                    peer_id.clone(),
+                    Arc::new(PeerMessage::BlockHeadersRequest(hashes)),
+                ) {
+                    self.state.request_sent(&self.clock, peer_id, RequestKey::BlockHeaders);
                     NetworkResponses::NoResponse
                 } else {
                     NetworkResponses::RouteNotFound
@@ -801,6 +820,10 @@ impl PeerManagerActor {
                 self.state.disconnect_and_ban(&self.clock, &peer_id, ban_reason);
                 NetworkResponses::NoResponse
             }
+            NetworkRequests::ReportPeer { peer_id, event } => {
+                self.state.peer_store.record_reputation_event(&peer_id, event);
+                NetworkResponses::NoResponse
+            }
             NetworkRequests::AnnounceAccount(announce_account) => {
                 let state = self.state.clone();
                 ctx.spawn(wrap_future(async move {
@@ -855,6 +878,11 @@ impl PeerManagerActor {
                                     },
                                 ),
                             ) {
+                                self.state.request_sent(
+                                    &self.clock,
+                                    matching_peer.clone(),
+                                    RequestKey::Chunk(request.chunk_hash.clone()),
+                                );
                                 success = true;
                                 break;
                             }
@@ -964,6 +992,23 @@ impl PeerManagerActor {
             PeerManagerMessageRequest::FetchRoutingTable => {
                 PeerManagerMessageResponse::FetchRoutingTable(self.state.graph.routing_table.info())
             }
+            PeerManagerMessageRequest::UpdateAuthorization(update) => {
+                match update {
+                    AuthorizationUpdate::SetConfig(config) => {
+                        self.state.peer_store.set_authorization_config(config)
+                    }
+                    AuthorizationUpdate::Ban(rule, duration) => self
+                        .state
+                        .peer_store
+                        .ban_rule(rule, self.clock.now_utc().saturating_add(duration)),
+                    AuthorizationUpdate::Unban(rule) => {
+                        self.state.peer_store.unban_rule(&rule);
+                    }
+                }
+                PeerManagerMessageResponse::UpdateAuthorization(
+                    self.state.disconnect_denied_peers(&self.clock),
+                )
+            }
         }
     }
 }
@@ -1041,6 +1086,11 @@ impl actix::Handler<GetDebugStatus> for PeerManagerActor {
                                 (attempt_time.unix_timestamp(), foo)
                             },
                         ),
+                        reputation: known_peer_state.reputation.score(),
+                        avg_response_latency_ms: known_peer_state
+                            .reputation
+                            .avg_latency()
+                            .map(|latency| latency.whole_milliseconds() as i64),
                     })
                     .collect::<Vec<_>>();
 
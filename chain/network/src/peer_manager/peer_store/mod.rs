@@ -1,5 +1,6 @@
-use crate::blacklist;
+use crate::authorization::{Authorization, AuthorizationConfig, Rule};
 use crate::network_protocol::PeerInfo;
+use crate::reputation::{self, ReputationEvent};
 use crate::types::{KnownPeerState, KnownPeerStatus, ReasonForBan};
 use anyhow::bail;
 use im::hashmap::Entry;
@@ -8,8 +9,9 @@ use lru::LruCache;
 use near_async::time;
 use near_primitives::network::PeerId;
 use parking_lot::Mutex;
-use rand::seq::IteratorRandom;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::cell::RefCell;
 use std::net::SocketAddr;
 use std::ops::Not;
 
@@ -60,8 +62,8 @@ pub struct Config {
     /// file, but you can modify the boot_nodes field to contain any nodes that
     /// you trust.
     pub boot_nodes: Vec<PeerInfo>,
-    /// Nodes will not accept or try to establish connection to such peers.
-    pub blacklist: blacklist::Blacklist,
+    /// Nodes will not accept or try to establish connection to the peers it denies.
+    pub authorization: AuthorizationConfig,
     /// If true - connect only to the bootnodes.
     pub connect_only_to_boot_nodes: bool,
     /// The maximum number of peers to store. If capacity is exceeded, the peers
@@ -84,6 +86,10 @@ struct Inner {
     // It can happens that some peers don't have known address, so
     // they will not be present in this list, otherwise they will be present.
     addr_peers: HashMap<SocketAddr, VerifiedPeer>,
+    /// Current authorization rules, initialized from `config.authorization`.
+    authorization: Authorization,
+    /// Randomness of the choice of peers.
+    rng: RefCell<StdRng>,
 }
 
 impl Inner {
@@ -167,17 +173,18 @@ impl Inner {
         }
     }
 
-    /// Find a random subset of peers based on filter.
+    /// Find a random subset of peers based on filter, preferring peers with a better reputation.
     fn find_peers<F>(&self, filter: F, count: usize) -> Vec<PeerInfo>
     where
         F: FnMut(&&KnownPeerState) -> bool,
     {
-        (self.peer_states.iter().map(|(_, v)| v))
-            .filter(filter)
-            .choose_multiple(&mut thread_rng(), count)
-            .into_iter()
-            .map(|kps| kps.peer_info.clone())
-            .collect()
+        let peers: Vec<_> = self.peer_states.iter().map(|(_, v)| v).filter(filter).collect();
+        reputation::choose_multiple_weighted(&mut *self.rng.borrow_mut(), &peers, count, |kps| {
+            kps.reputation.score()
+        })
+        .into_iter()
+        .map(|kps| kps.peer_info.clone())
+        .collect()
     }
 
     /// Create new pair between peer_info.id and peer_addr removing
@@ -273,12 +280,14 @@ impl Inner {
 
     /// Cleans up the state of the PeerStore, due to passing time.
     /// * it unbans a peer if config.ban_window has passed
+    /// * it removes the expired temporary bans of the authorization rules
     /// * it updates KnownPeerStatus.last_seen of the connected peers
     /// * it removes peers which were not seen for config.peer_expiration_duration
     /// This function should be called periodically.
     pub fn update(&mut self, clock: &time::Clock) {
         let now = clock.now_utc();
         self.unban(now);
+        self.authorization.remove_expired(now);
         self.update_last_seen(now);
         self.remove_expired(now);
     }
@@ -334,13 +343,50 @@ impl PeerStore {
             }
         }
 
-        let inner =
-            Inner { config, boot_nodes, peer_states: peerid_2_state, addr_peers: addr_2_peer };
+        let authorization = Authorization::new(config.authorization.clone());
+        let inner = Inner {
+            config,
+            boot_nodes,
+            peer_states: peerid_2_state,
+            addr_peers: addr_2_peer,
+            authorization,
+            rng: RefCell::new(StdRng::from_entropy()),
+        };
         Ok(PeerStore(Mutex::new(inner)))
     }
 
-    pub fn is_blacklisted(&self, addr: &SocketAddr) -> bool {
-        self.0.lock().config.blacklist.contains(*addr)
+    /// Makes the choice of peers deterministic.
+    #[cfg(test)]
+    pub(crate) fn seed_rng(&self, seed: u64) {
+        *self.0.lock().rng.get_mut() = StdRng::seed_from_u64(seed);
+    }
+
+    /// Whether the authorization rules deny connections to the peer.
+    pub fn is_denied(&self, clock: &time::Clock, peer_info: &PeerInfo) -> bool {
+        self.0.lock().authorization.is_denied(clock.now_utc(), &peer_info.id, peer_info.addr)
+    }
+
+    /// Whether the peer may connect even if the limit of inbound connections has been reached.
+    pub fn is_whitelisted(&self, peer_info: &PeerInfo) -> bool {
+        self.0.lock().authorization.is_whitelisted(peer_info)
+    }
+
+    /// Replaces the authorization config. Temporary bans are kept.
+    pub fn set_authorization_config(&self, config: AuthorizationConfig) {
+        tracing::info!(target: "network", ?config, "Updating authorization config");
+        self.0.lock().authorization.set_config(config);
+    }
+
+    /// Denies connections to the peers matching the rule until the given time.
+    pub fn ban_rule(&self, rule: Rule, until: time::Utc) {
+        tracing::info!(target: "network", %rule, %until, "Banning peers temporarily");
+        self.0.lock().authorization.ban(rule, until);
+    }
+
+    /// Lifts the temporary ban of the rule. Returns whether the rule was banned.
+    pub fn unban_rule(&self, rule: &Rule) -> bool {
+        tracing::info!(target: "network", %rule, "Lifting temporary ban");
+        self.0.lock().authorization.unban(rule)
     }
 
     pub fn len(&self) -> usize {
@@ -420,26 +466,45 @@ impl PeerStore {
             let now = clock.now_utc();
             peer_state.last_seen = now;
             peer_state.status = KnownPeerStatus::Banned(ban_reason, now);
+            peer_state.reputation.record(ReputationEvent::InvalidResponse);
         } else {
             bail!("Peer {} is missing in the peer store", peer_id);
         }
         Ok(())
     }
 
+    /// Updates the reputation of the peer. Unknown peers are ignored.
+    pub fn record_reputation_event(&self, peer_id: &PeerId, event: ReputationEvent) {
+        if let Some(peer_state) = self.0.lock().peer_states.peek_mut(peer_id) {
+            peer_state.reputation.record(event);
+        }
+    }
+
+    /// Reputation score of the peer, or the neutral score if the peer is unknown.
+    pub fn reputation_score(&self, peer_id: &PeerId) -> i64 {
+        self.0.lock().peer_states.peek(peer_id).map_or(0, |s| s.reputation.score())
+    }
+
     /// Return unconnected or peers with unknown status that we can try to connect to.
-    /// Peers with unknown addresses are filtered out.
+    /// Peers with unknown addresses and the denied peers are filtered out.
     pub fn unconnected_peer(
         &self,
+        clock: &time::Clock,
         ignore_fn: impl Fn(&KnownPeerState) -> bool,
         prefer_previously_connected_peer: bool,
     ) -> Option<PeerInfo> {
         let inner = self.0.lock();
+        let now = clock.now_utc();
+        let is_denied = |p: &KnownPeerState| {
+            inner.authorization.is_denied(now, &p.peer_info.id, p.peer_info.addr)
+        };
         if prefer_previously_connected_peer {
             let preferred_peer = inner.find_peers(
                 |p| {
                     (p.status == KnownPeerStatus::NotConnected)
                         && !ignore_fn(p)
                         && p.peer_info.addr.is_some()
+                        && !is_denied(p)
                         // if we're connecting only to the boot nodes - filter out the nodes that are not bootnodes.
                         && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
                 },
@@ -458,6 +523,7 @@ impl PeerStore {
                 (p.status == KnownPeerStatus::NotConnected || p.status == KnownPeerStatus::Unknown)
                     && !ignore_fn(p)
                     && p.peer_info.addr.is_some()
+                    && !is_denied(p)
                     // If we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
                     && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
             },
@@ -484,20 +550,19 @@ impl PeerStore {
     pub fn add_indirect_peers(&self, clock: &time::Clock, peers: impl Iterator<Item = PeerInfo>) {
         let mut inner = self.0.lock();
         let mut total: usize = 0;
-        let mut blacklisted: usize = 0;
+        let mut denied: usize = 0;
+        let now = clock.now_utc();
         for peer_info in peers {
             total += 1;
-            let is_blacklisted =
-                peer_info.addr.map_or(false, |addr| inner.config.blacklist.contains(addr));
-            if is_blacklisted {
-                blacklisted += 1;
+            if inner.authorization.is_denied(now, &peer_info.id, peer_info.addr) {
+                denied += 1;
             } else {
                 inner.add_peer(clock, peer_info, TrustLevel::Indirect);
             }
         }
-        if blacklisted != 0 {
-            tracing::info!(target: "network", "Ignored {} blacklisted peers out of {} indirect peer(s)",
-                  blacklisted, total);
+        if denied != 0 {
+            tracing::info!(target: "network", "Ignored {} denied peers out of {} indirect peer(s)",
+                  denied, total);
         }
     }
 
@@ -1,5 +1,4 @@
 use super::*;
-use crate::blacklist::Blacklist;
 use near_async::time;
 use near_crypto::{KeyType, SecretKey};
 use std::collections::HashSet;
@@ -27,12 +26,12 @@ fn gen_peer_info(port: u16) -> PeerInfo {
 
 fn make_config(
     boot_nodes: &[PeerInfo],
-    blacklist: blacklist::Blacklist,
+    authorization: AuthorizationConfig,
     connect_only_to_boot_nodes: bool,
 ) -> Config {
     Config {
         boot_nodes: boot_nodes.iter().cloned().collect(),
-        blacklist,
+        authorization,
         peer_states_cache_size: 1000,
         connect_only_to_boot_nodes,
         ban_window: time::Duration::seconds(1),
@@ -47,9 +46,11 @@ fn ban_store() {
     let peer_info_to_ban = gen_peer_info(1);
     let boot_nodes = vec![peer_info_a, peer_info_to_ban.clone()];
 
-    let peer_store =
-        PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
-            .unwrap();
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&boot_nodes, AuthorizationConfig::default(), false),
+    )
+    .unwrap();
     assert_eq!(peer_store.healthy_peers(3).len(), 2);
     peer_store.peer_ban(&clock.clock(), &peer_info_to_ban.id, ReasonForBan::Abusive).unwrap();
     assert_eq!(peer_store.healthy_peers(3).len(), 1);
@@ -62,12 +63,63 @@ fn test_unconnected_peer() {
     let peer_info_to_ban = gen_peer_info(1);
     let boot_nodes = vec![peer_info_a, peer_info_to_ban];
 
-    let peer_store =
-        PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
-            .unwrap();
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&boot_nodes, AuthorizationConfig::default(), false),
+    )
+    .unwrap();
 
-    assert!(peer_store.unconnected_peer(|_| false, false).is_some());
-    assert!(peer_store.unconnected_peer(|_| true, false).is_none());
+    assert!(peer_store.unconnected_peer(&clock.clock(), |_| false, false).is_some());
+    assert!(peer_store.unconnected_peer(&clock.clock(), |_| true, false).is_none());
+}
+
+#[test]
+fn unconnected_peer_prefers_good_reputation() {
+    let clock = time::FakeClock::default();
+    let good = gen_peer_info(0);
+    let bad = gen_peer_info(1);
+    let boot_nodes = vec![good.clone(), bad.clone()];
+
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&boot_nodes, AuthorizationConfig::default(), false),
+    )
+    .unwrap();
+    peer_store.seed_rng(89028037453);
+    for _ in 0..10 {
+        peer_store.record_reputation_event(
+            &good.id,
+            ReputationEvent::Response(time::Duration::milliseconds(10)),
+        );
+        peer_store.record_reputation_event(&bad.id, ReputationEvent::InvalidResponse);
+    }
+    assert_eq!(20, peer_store.reputation_score(&good.id));
+    assert_eq!(-100, peer_store.reputation_score(&bad.id));
+
+    let mut chosen_good = 0;
+    for _ in 0..100 {
+        let peer = peer_store.unconnected_peer(&clock.clock(), |_| false, false).unwrap();
+        if peer.id == good.id {
+            chosen_good += 1;
+        }
+    }
+    // The weights are 2 and 1/32, so the bad peer is chosen ~1.5% of the time.
+    assert!(chosen_good > 90, "{chosen_good}");
+}
+
+#[test]
+fn ban_lowers_reputation() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[peer_info.clone()], AuthorizationConfig::default(), false),
+    )
+    .unwrap();
+    peer_store.peer_ban(&clock.clock(), &peer_info.id, ReasonForBan::BadBlock).unwrap();
+    assert!(peer_store.reputation_score(&peer_info.id) < 0);
+    // Unknown peers have a neutral reputation.
+    assert_eq!(0, peer_store.reputation_score(&gen_peer_info(1).id));
 }
 
 #[test]
@@ -85,9 +137,11 @@ fn test_unknown_vs_not_connected() {
         nodes.map(|peer| peer_store.get_peer_state(&peer.id).map(|known_state| known_state.status))
     };
 
-    let peer_store =
-        PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
-            .unwrap();
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&boot_nodes, AuthorizationConfig::default(), false),
+    )
+    .unwrap();
 
     // Check the status of the in-memory store.
     // Boot node should be marked as not-connected, as we've verified it.
@@ -120,7 +174,7 @@ fn test_unknown_vs_not_connected() {
     // if we prefer 'previously connected' peers - we should keep picking 'b'.
     assert_eq!(
         (0..10)
-            .map(|_| peer_store.unconnected_peer(|_| false, true).unwrap().id)
+            .map(|_| peer_store.unconnected_peer(&clock.clock(), |_| false, true).unwrap().id)
             .collect::<HashSet<PeerId>>(),
         [peer_info_b.id.clone()].into_iter().collect::<HashSet<_>>()
     );
@@ -128,7 +182,7 @@ fn test_unknown_vs_not_connected() {
     // if we don't care, we should pick either 'b' or 'boot'.
     assert_eq!(
         (0..100)
-            .map(|_| peer_store.unconnected_peer(|_| false, false).unwrap().id)
+            .map(|_| peer_store.unconnected_peer(&clock.clock(), |_| false, false).unwrap().id)
             .collect::<HashSet<PeerId>>(),
         [peer_info_b.id.clone(), peer_info_boot_node.id.clone()]
             .into_iter()
@@ -159,12 +213,17 @@ fn test_unconnected_peer_only_boot_nodes() {
     // 1 non-boot (peer_in_store) node peer that is in the store.
     // we should connect to peer_in_store
     {
-        let peer_store =
-            PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
-                .unwrap();
+        let peer_store = PeerStore::new(
+            &clock.clock(),
+            make_config(&boot_nodes, AuthorizationConfig::default(), false),
+        )
+        .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_in_store.clone());
         peer_store.peer_connected(&clock.clock(), &peer_info_a);
-        assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_in_store.clone()));
+        assert_eq!(
+            peer_store.unconnected_peer(&clock.clock(), |_| false, false),
+            Some(peer_in_store.clone())
+        );
     }
 
     // 1 boot node (peer_info_a) that we're already connected to.
@@ -176,7 +235,7 @@ fn test_unconnected_peer_only_boot_nodes() {
                 .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_in_store);
         peer_store.peer_connected(&clock.clock(), &peer_info_a);
-        assert_eq!(peer_store.unconnected_peer(|_| false, false), None);
+        assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false, false), None);
     }
 
     // 1 boot node (peer_info_a) is in the store.
@@ -188,7 +247,10 @@ fn test_unconnected_peer_only_boot_nodes() {
         )
         .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_info_a.clone());
-        assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_info_a.clone()));
+        assert_eq!(
+            peer_store.unconnected_peer(&clock.clock(), |_| false, false),
+            Some(peer_info_a.clone())
+        );
     }
 }
 
@@ -356,7 +418,7 @@ fn check_add_peers_overriding() {
 }
 
 #[test]
-fn check_ignore_blacklisted_peers() {
+fn check_ignore_denied_peers() {
     let clock = time::FakeClock::default();
 
     #[track_caller]
@@ -369,10 +431,11 @@ fn check_ignore_blacklisted_peers() {
 
     let ids = (0..3).map(|ix| get_peer_id(format!("node{}", ix))).collect::<Vec<_>>();
 
-    let blacklist: blacklist::Blacklist =
-        ["127.0.0.1:1"].iter().map(|e| e.parse().unwrap()).collect();
+    let authorization =
+        AuthorizationConfig { deny: vec!["127.0.0.1:1".parse().unwrap()], ..Default::default() };
 
-    let peer_store = PeerStore::new(&clock.clock(), make_config(&[], blacklist, false)).unwrap();
+    let peer_store =
+        PeerStore::new(&clock.clock(), make_config(&[], authorization, false)).unwrap();
 
     peer_store.add_indirect_peers(
         &clock.clock(),
@@ -388,6 +451,46 @@ fn check_ignore_blacklisted_peers() {
     assert_peers(&peer_store, &[&ids[0], &ids[2]]);
 }
 
+#[test]
+fn test_unconnected_peer_denied() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[peer_info.clone()], AuthorizationConfig::default(), false),
+    )
+    .unwrap();
+    assert_eq!(
+        peer_store.unconnected_peer(&clock.clock(), |_| false, false),
+        Some(peer_info.clone())
+    );
+
+    // Peers which become denied are not connected to, until the ban expires.
+    let rule = Rule::PeerId(peer_info.id.clone());
+    peer_store.ban_rule(rule.clone(), clock.now_utc() + time::Duration::seconds(10));
+    assert!(peer_store.is_denied(&clock.clock(), &peer_info));
+    assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false, false), None);
+    clock.advance(time::Duration::seconds(10));
+    peer_store.update(&clock.clock());
+    assert!(!peer_store.unban_rule(&rule));
+    assert_eq!(
+        peer_store.unconnected_peer(&clock.clock(), |_| false, false),
+        Some(peer_info.clone())
+    );
+
+    peer_store.set_authorization_config(AuthorizationConfig {
+        deny: vec!["127.0.0.0/8".parse().unwrap()],
+        ..Default::default()
+    });
+    assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false, false), None);
+    peer_store.set_authorization_config(AuthorizationConfig {
+        allow: vec![Rule::PeerId(peer_info.id.clone())],
+        deny: vec!["127.0.0.0/8".parse().unwrap()],
+        ..Default::default()
+    });
+    assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false, false), Some(peer_info));
+}
+
 #[track_caller]
 fn assert_peers_in_cache(
     peer_store: &PeerStore,
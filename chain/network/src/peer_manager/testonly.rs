@@ -14,8 +14,8 @@ use crate::test_utils;
 use crate::testonly::actix::ActixSystem;
 use crate::testonly::fake_client;
 use crate::types::{
-    AccountKeys, ChainInfo, KnownPeerStatus, NetworkRequests, PeerManagerMessageRequest,
-    ReasonForBan,
+    AccountKeys, AuthorizationUpdate, ChainInfo, KnownPeerStatus, NetworkRequests,
+    PeerManagerMessageRequest, PeerManagerMessageResponse, ReasonForBan,
 };
 use crate::PeerManagerActor;
 use near_async::messaging::IntoSender;
@@ -360,6 +360,20 @@ impl ActorHandler {
             .await
     }
 
+    /// Updates the authorization rules, returns the ids of the disconnected peers.
+    pub async fn update_authorization(&self, update: AuthorizationUpdate) -> Vec<PeerId> {
+        match self
+            .actix
+            .addr
+            .send(PeerManagerMessageRequest::UpdateAuthorization(update).with_span_context())
+            .await
+            .unwrap()
+        {
+            PeerManagerMessageResponse::UpdateAuthorization(disconnected) => disconnected,
+            resp => panic!("unexpected response {resp:?}"),
+        }
+    }
+
     pub async fn peer_store_update(&self, clock: &time::Clock) {
         let clock = clock.clone();
         self.with_state(move |s| async move { s.peer_store.update(&clock) }).await;
@@ -1,4 +1,4 @@
-use crate::blacklist;
+use crate::authorization;
 use crate::broadcast;
 use crate::config::NetworkConfig;
 use crate::network_protocol::testonly as data;
@@ -16,7 +16,7 @@ use crate::store;
 use crate::tcp;
 use crate::testonly::{abort_on_panic, make_rng, Rng};
 use crate::types::PeerMessage;
-use crate::types::{PeerInfo, ReasonForBan};
+use crate::types::{AuthorizationUpdate, PeerInfo, ReasonForBan};
 use near_async::time;
 use near_primitives::network::PeerId;
 use near_store::db::TestDB;
@@ -688,8 +688,8 @@ async fn blacklist_01() {
 
     tracing::info!(target:"test", "start two nodes with 0 blacklisting 1");
     let mut cfgs = make_configs(&chain, rng, 2, 2, true);
-    cfgs[0].peer_store.blacklist =
-        [blacklist::Entry::from_addr(**cfgs[1].node_addr.as_ref().unwrap())].into_iter().collect();
+    cfgs[0].peer_store.authorization.deny =
+        vec![authorization::Rule::from_addr(**cfgs[1].node_addr.as_ref().unwrap())];
 
     let pm0 = start_pm(clock.clock(), TestDB::new(), cfgs[0].clone(), chain.clone()).await;
     let pm1 = start_pm(clock.clock(), TestDB::new(), cfgs[1].clone(), chain.clone()).await;
@@ -721,8 +721,8 @@ async fn blacklist_10() {
 
     tracing::info!(target:"test", "start two nodes with 1 blacklisting 0");
     let mut cfgs = make_configs(&chain, rng, 2, 2, true);
-    cfgs[1].peer_store.blacklist =
-        [blacklist::Entry::from_addr(**cfgs[0].node_addr.as_ref().unwrap())].into_iter().collect();
+    cfgs[1].peer_store.authorization.deny =
+        vec![authorization::Rule::from_addr(**cfgs[0].node_addr.as_ref().unwrap())];
 
     let pm0 = start_pm(clock.clock(), TestDB::new(), cfgs[0].clone(), chain.clone()).await;
     let pm1 = start_pm(clock.clock(), TestDB::new(), cfgs[1].clone(), chain.clone()).await;
@@ -743,6 +743,40 @@ async fn blacklist_10() {
     pm1.wait_for_routing_table(&[]).await;
 }
 
+// test node 0 denying node 1 after they have connected
+#[tokio::test]
+async fn deny_connected_peer() {
+    abort_on_panic();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    tracing::info!(target:"test", "start two nodes");
+    let cfgs = make_configs(&chain, rng, 2, 2, true);
+    let pm0 = start_pm(clock.clock(), TestDB::new(), cfgs[0].clone(), chain.clone()).await;
+    let pm1 = start_pm(clock.clock(), TestDB::new(), cfgs[1].clone(), chain.clone()).await;
+
+    let id0 = pm0.cfg.node_id();
+    let id1 = pm1.cfg.node_id();
+
+    tracing::info!(target:"test", "wait for {id0} routing table");
+    pm0.wait_for_routing_table(&[(id1.clone(), vec![id1.clone()])]).await;
+
+    tracing::info!(target:"test", "ban {id1} on {id0}");
+    let mut events = pm0.events.from_now();
+    let rule = authorization::Rule::PeerId(id1.clone());
+    let disconnected =
+        pm0.update_authorization(AuthorizationUpdate::Ban(rule, time::Duration::hours(1))).await;
+    assert_eq!(disconnected, vec![id1.clone()]);
+    wait_for_connection_closed(&mut events, ClosingReason::PeerManagerRequest).await;
+
+    tracing::info!(target:"test", "wait for {id0} routing table");
+    pm0.wait_for_routing_table(&[]).await;
+    tracing::info!(target:"test", "wait for {id1} routing table");
+    pm1.wait_for_routing_table(&[]).await;
+}
+
 // test node 0 blacklisting all nodes
 #[tokio::test]
 async fn blacklist_all() {
@@ -754,8 +788,8 @@ async fn blacklist_all() {
 
     tracing::info!(target:"test", "start two nodes with 0 blacklisting everything");
     let mut cfgs = make_configs(&chain, rng, 2, 2, true);
-    cfgs[0].peer_store.blacklist =
-        [blacklist::Entry::from_ip(Ipv6Addr::LOCALHOST.into())].into_iter().collect();
+    cfgs[0].peer_store.authorization.deny =
+        vec![authorization::Rule::from_ip(Ipv6Addr::LOCALHOST.into())];
 
     let pm0 = start_pm(clock.clock(), TestDB::new(), cfgs[0].clone(), chain.clone()).await;
     let pm1 = start_pm(clock.clock(), TestDB::new(), cfgs[1].clone(), chain.clone()).await;
@@ -214,6 +214,8 @@ fn new_handshake(
         },
         partial_edge_info: PartialEdgeInfo::new(my_peer_id, target_peer_id, nonce, secret_key),
         owned_account: None,
+        session_key: None,
+        accepts_compression: true,
     })
 }
 
@@ -0,0 +1,268 @@
+//! Reputation of the known peers.
+//!
+//! Every peer starts with a neutral score, which goes up when the peer serves our requests quickly
+//! and goes down when it responds slowly, not at all, or with invalid data. The score is used to
+//! prefer the better peers when choosing whom to connect to and whom to sync from. It never bans
+//! a peer on its own: banning is still done explicitly with a `ReasonForBan`.
+use near_async::time;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use near_primitives::sharding::ChunkHash;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Lowest possible score.
+pub const MIN_SCORE: i64 = -100;
+/// Highest possible score.
+pub const MAX_SCORE: i64 = 100;
+
+/// Responses received within this time are considered fast.
+const FAST_RESPONSE: time::Duration = time::Duration::milliseconds(500);
+/// Responses received later than this are considered slow.
+pub const SLOW_RESPONSE: time::Duration = time::Duration::seconds(2);
+
+/// Requests which were not responded to within this time are considered slow.
+const REQUEST_TIMEOUT: time::Duration = time::Duration::seconds(10);
+
+/// Weight of the latest sample in the average latency.
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// Limit of the requests awaiting a response, to bound the memory usage.
+const PENDING_REQUESTS_CACHE_SIZE: usize = 10000;
+
+/// Observation about a peer affecting its reputation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReputationEvent {
+    /// The peer responded to our request after the given time.
+    Response(time::Duration),
+    /// The peer didn't respond to our request in time.
+    SlowResponse,
+    /// The peer responded with data which turned out to be invalid.
+    InvalidResponse,
+    /// The peer served us a valid state part.
+    StatePartServed,
+}
+
+/// Reputation of a single peer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Reputation {
+    score: i64,
+    /// Exponential moving average of the response latency.
+    avg_latency: Option<time::Duration>,
+    pub responses: u64,
+    pub slow_responses: u64,
+    pub invalid_responses: u64,
+    pub state_parts_served: u64,
+}
+
+impl Reputation {
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    pub fn avg_latency(&self) -> Option<time::Duration> {
+        self.avg_latency
+    }
+
+    pub fn record(&mut self, event: ReputationEvent) {
+        let delta = match event {
+            ReputationEvent::Response(latency) => {
+                self.responses += 1;
+                self.avg_latency = Some(match self.avg_latency {
+                    None => latency,
+                    Some(avg) => avg * (1. - LATENCY_SMOOTHING) + latency * LATENCY_SMOOTHING,
+                });
+                if latency <= FAST_RESPONSE {
+                    2
+                } else if latency <= SLOW_RESPONSE {
+                    1
+                } else {
+                    -2
+                }
+            }
+            ReputationEvent::SlowResponse => {
+                self.slow_responses += 1;
+                -2
+            }
+            ReputationEvent::InvalidResponse => {
+                self.invalid_responses += 1;
+                -10
+            }
+            ReputationEvent::StatePartServed => {
+                self.state_parts_served += 1;
+                1
+            }
+        };
+        self.score = (self.score + delta).clamp(MIN_SCORE, MAX_SCORE);
+    }
+}
+
+/// Relative probability of choosing a peer with the given score.
+///
+/// Every 20 points double the chance, so that good peers are strongly preferred, but peers with
+/// a bad reputation still get picked from time to time and have a chance to recover.
+pub fn weight(score: i64) -> f64 {
+    2f64.powf(score.clamp(MIN_SCORE, MAX_SCORE) as f64 / 20.)
+}
+
+/// Chooses a random item, preferring the items with the higher score.
+pub fn choose_weighted<'a, T>(
+    rng: &mut impl Rng,
+    items: &'a [T],
+    score: impl Fn(&T) -> i64,
+) -> Option<&'a T> {
+    items.choose_weighted(rng, |item| weight(score(item))).ok()
+}
+
+/// Chooses up to `count` distinct random items, preferring the items with the higher score.
+pub fn choose_multiple_weighted<'a, T>(
+    rng: &mut impl Rng,
+    items: &'a [T],
+    count: usize,
+    score: impl Fn(&T) -> i64,
+) -> Vec<&'a T> {
+    match items.choose_multiple_weighted(rng, count, |item| weight(score(item))) {
+        Ok(chosen) => chosen.collect(),
+        Err(_) => items.choose_multiple(rng, count).collect(),
+    }
+}
+
+/// Request sent to a peer, to which we expect a response.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum RequestKey {
+    Block(CryptoHash),
+    /// Responses to the headers requests don't identify the request, so we track at most one
+    /// headers request per peer.
+    BlockHeaders,
+    Chunk(ChunkHash),
+}
+
+/// Requests sent to the peers which await a response, used to measure the response latency.
+pub(crate) struct PendingRequests(lru::LruCache<(PeerId, RequestKey), time::Instant>);
+
+impl PendingRequests {
+    pub fn new() -> Self {
+        Self(lru::LruCache::new(PENDING_REQUESTS_CACHE_SIZE))
+    }
+
+    pub fn push(&mut self, now: time::Instant, peer_id: PeerId, key: RequestKey) {
+        self.0.put((peer_id, key), now);
+    }
+
+    /// Removes the request, returning how long it took to get the response.
+    /// Returns None if the request is unknown, i.e. the response was not requested
+    /// or it arrived after the request timed out.
+    pub fn pop(
+        &mut self,
+        now: time::Instant,
+        peer_id: PeerId,
+        key: RequestKey,
+    ) -> Option<time::Duration> {
+        self.0.pop(&(peer_id, key)).map(|sent| now - sent)
+    }
+
+    /// Removes the timed out requests, returning the peers which didn't respond to them.
+    pub fn pop_expired(&mut self, now: time::Instant) -> Vec<PeerId> {
+        let expired: Vec<_> = (self.0.iter())
+            .filter(|(_, sent)| now - **sent > REQUEST_TIMEOUT)
+            .map(|(k, _)| k.clone())
+            .collect();
+        for k in &expired {
+            self.0.pop(k);
+        }
+        expired.into_iter().map(|(peer_id, _)| peer_id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network_protocol::testonly as data;
+    use crate::testonly::make_rng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn record() {
+        let mut r = Reputation::default();
+        assert_eq!(0, r.score());
+        r.record(ReputationEvent::Response(time::Duration::milliseconds(100)));
+        assert_eq!(2, r.score());
+        r.record(ReputationEvent::Response(time::Duration::seconds(1)));
+        assert_eq!(3, r.score());
+        r.record(ReputationEvent::Response(time::Duration::seconds(5)));
+        assert_eq!(1, r.score());
+        r.record(ReputationEvent::StatePartServed);
+        assert_eq!(2, r.score());
+        r.record(ReputationEvent::InvalidResponse);
+        assert_eq!(-8, r.score());
+        r.record(ReputationEvent::SlowResponse);
+        assert_eq!(-10, r.score());
+        assert_eq!(3, r.responses);
+        assert_eq!(1, r.slow_responses);
+        assert_eq!(1, r.invalid_responses);
+        assert_eq!(1, r.state_parts_served);
+    }
+
+    #[test]
+    fn score_is_bounded() {
+        let mut r = Reputation::default();
+        for _ in 0..100 {
+            r.record(ReputationEvent::InvalidResponse);
+        }
+        assert_eq!(MIN_SCORE, r.score());
+        for _ in 0..200 {
+            r.record(ReputationEvent::StatePartServed);
+        }
+        assert_eq!(MAX_SCORE, r.score());
+    }
+
+    #[test]
+    fn avg_latency() {
+        let mut r = Reputation::default();
+        assert_eq!(None, r.avg_latency());
+        r.record(ReputationEvent::Response(time::Duration::milliseconds(1000)));
+        assert_eq!(Some(time::Duration::milliseconds(1000)), r.avg_latency());
+        r.record(ReputationEvent::Response(time::Duration::milliseconds(500)));
+        assert_eq!(Some(time::Duration::milliseconds(900)), r.avg_latency());
+    }
+
+    #[test]
+    fn prefer_high_score() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let items = [MIN_SCORE, 0, MAX_SCORE];
+        let mut counts = [0; 3];
+        for _ in 0..1000 {
+            let chosen = choose_weighted(&mut rng, &items, |s| *s).unwrap();
+            counts[items.iter().position(|s| s == chosen).unwrap()] += 1;
+        }
+        assert!(counts[2] > counts[1] && counts[1] > counts[0], "{counts:?}");
+        // Peers with a bad reputation are still chosen sometimes.
+        let chosen = choose_multiple_weighted(&mut rng, &items, 3, |s| *s);
+        assert_eq!(3, chosen.len());
+        assert_eq!(None, choose_weighted(&mut rng, &[] as &[i64], |s| *s));
+    }
+
+    #[test]
+    fn pending_requests() {
+        let mut rng = make_rng(921853233);
+        let clock = time::FakeClock::default();
+        let peer0 = data::make_peer_id(&mut rng);
+        let peer1 = data::make_peer_id(&mut rng);
+        let hash = CryptoHash::hash_bytes(b"block");
+        let mut pending = PendingRequests::new();
+        pending.push(clock.now(), peer0.clone(), RequestKey::Block(hash));
+        pending.push(clock.now(), peer1.clone(), RequestKey::BlockHeaders);
+        clock.advance(time::Duration::seconds(1));
+        // Only the peer which was asked can respond.
+        assert_eq!(None, pending.pop(clock.now(), peer1.clone(), RequestKey::Block(hash)));
+        assert_eq!(
+            Some(time::Duration::seconds(1)),
+            pending.pop(clock.now(), peer0.clone(), RequestKey::Block(hash))
+        );
+        assert_eq!(None, pending.pop(clock.now(), peer0.clone(), RequestKey::Block(hash)));
+        assert!(pending.pop_expired(clock.now()).is_empty());
+        clock.advance(REQUEST_TIMEOUT);
+        assert_eq!(vec![peer1.clone()], pending.pop_expired(clock.now()));
+        assert_eq!(None, pending.pop(clock.now(), peer1, RequestKey::BlockHeaders));
+    }
+}
@@ -1,7 +1,7 @@
 use std::time::Instant;
 
 use actix::Message;
-use near_primitives::{hash::CryptoHash, sharding::PartialEncodedChunk};
+use near_primitives::{hash::CryptoHash, network::PeerId, sharding::PartialEncodedChunk};
 
 use crate::types::{
     PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg,
@@ -14,6 +14,8 @@ pub enum ShardsManagerRequestFromNetwork {
     ProcessPartialEncodedChunkForward(PartialEncodedChunkForwardMsg),
     ProcessPartialEncodedChunkResponse {
         partial_encoded_chunk_response: PartialEncodedChunkResponseMsg,
+        /// Peer which sent the response, whose reputation suffers if the response is invalid.
+        responder: PeerId,
         received_time: Instant,
     },
     ProcessPartialEncodedChunkRequest {
@@ -184,6 +184,14 @@ pub(crate) static PEER_MESSAGE_SENT_BY_TYPE_BYTES: Lazy<IntCounterVec> = Lazy::n
     )
     .unwrap()
 });
+pub(crate) static PEER_MESSAGE_COMPRESSION_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_message_compression_bytes",
+        "Total size of the compressed messages exchanged with peers, before and after compression",
+        &["direction", "stage"],
+    )
+    .unwrap()
+});
 pub(crate) static PEER_MESSAGE_SENT_BY_TYPE_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
         "near_peer_message_sent_by_type_total",
@@ -4,17 +4,24 @@ use bytes::BytesMut;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use crate::network_protocol::{Encoding, PeerMessage};
+use crate::peer::encryption;
 use crate::tcp;
 
 pub struct Stream {
     stream: tcp::Stream,
     force_encoding: Option<Encoding>,
     protocol_buffers_supported: bool,
+    session_keys: Option<encryption::SessionKeys>,
 }
 
 impl Stream {
     pub fn new(force_encoding: Option<Encoding>, stream: tcp::Stream) -> Self {
-        Self { stream, force_encoding, protocol_buffers_supported: false }
+        Self { stream, force_encoding, protocol_buffers_supported: false, session_keys: None }
+    }
+
+    /// Encrypts the frames written and decrypts the frames read from now on.
+    pub fn start_encryption(&mut self, keys: encryption::SessionKeys) {
+        self.session_keys = Some(keys);
     }
 
     fn encoding(&self) -> Option<Encoding> {
@@ -33,6 +40,13 @@ impl Stream {
             let mut buf = BytesMut::new();
             buf.resize(n, 0);
             self.stream.stream.read_exact(&mut buf[..]).await?;
+            if let Some(keys) = &mut self.session_keys {
+                let frame = keys
+                    .recv
+                    .open(buf.to_vec())
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+                buf = BytesMut::from(&frame[..]);
+            }
             for enc in [Encoding::Proto, Encoding::Borsh] {
                 if let Ok(msg) = PeerMessage::deserialize(enc, &buf[..]) {
                     // If deserialize() succeeded but we expected different encoding, ignore the
@@ -60,9 +74,20 @@ impl Stream {
         }
     }
 
+    /// Writes the message compressed, regardless of what the peer advertised.
+    pub async fn write_compressed(&mut self, msg: &PeerMessage) {
+        let (bytes, uncompressed_len) = msg.serialize_compressed(0);
+        assert!(uncompressed_len.is_some(), "{msg} is not worth compressing");
+        self.write_encoded(&bytes).await;
+    }
+
     async fn write_encoded(&mut self, msg: &[u8]) {
+        let msg = match &mut self.session_keys {
+            Some(keys) => keys.send.seal(msg.to_vec()).unwrap(),
+            None => msg.to_vec(),
+        };
         self.stream.stream.write_u32_le(msg.len() as u32).await.unwrap();
-        self.stream.stream.write_all(msg).await.unwrap();
+        self.stream.stream.write_all(&msg).await.unwrap();
         self.stream.stream.flush().await.unwrap();
     }
 }
@@ -1,8 +1,10 @@
+use crate::authorization::{AuthorizationConfig, Rule};
 /// Type that belong to the network protocol.
 pub use crate::network_protocol::{
     AccountOrPeerIdOrHash, Disconnect, Encoding, Handshake, HandshakeFailureReason, PeerMessage,
     RoutingTableUpdate, SignedAccountData,
 };
+use crate::reputation::{Reputation, ReputationEvent};
 use crate::routing::routing_table_view::RoutingTableInfo;
 use near_async::messaging::{
     AsyncSender, CanSend, CanSendAsync, IntoAsyncSender, IntoSender, Sender,
@@ -103,6 +105,9 @@ pub struct KnownPeerState {
     // Last time we tried to connect to this peer.
     // This data is not persisted in storage.
     pub last_outbound_attempt: Option<(time::Utc, Result<(), String>)>,
+    /// How well the peer served our requests so far.
+    /// This data is not persisted in storage.
+    pub reputation: Reputation,
 }
 
 impl KnownPeerState {
@@ -113,6 +118,7 @@ impl KnownPeerState {
             first_seen: now,
             last_seen: now,
             last_outbound_attempt: None,
+            reputation: Reputation::default(),
         }
     }
 }
@@ -166,6 +172,19 @@ pub enum PeerManagerMessageRequest {
     /// The following types of requests are used to trigger actions in the Peer Manager for testing.
     /// TEST-ONLY: Fetch current routing table.
     FetchRoutingTable,
+    /// Update the authorization rules and disconnect the peers which became denied.
+    UpdateAuthorization(AuthorizationUpdate),
+}
+
+/// Update of the authorization rules, see `crate::authorization`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthorizationUpdate {
+    /// Replace the authorization config. Temporary bans are kept.
+    SetConfig(AuthorizationConfig),
+    /// Deny the peers matching the rule for the given duration.
+    Ban(Rule, time::Duration),
+    /// Lift the temporary ban of the rule.
+    Unban(Rule),
 }
 
 impl PeerManagerMessageRequest {
@@ -193,6 +212,8 @@ pub enum PeerManagerMessageResponse {
     /// TEST-ONLY
     OutboundTcpConnect,
     FetchRoutingTable(RoutingTableInfo),
+    /// Ids of the peers disconnected because of the update.
+    UpdateAuthorization(Vec<PeerId>),
 }
 
 impl PeerManagerMessageResponse {
@@ -234,6 +255,8 @@ pub enum NetworkRequests {
     },
     /// Ban given peer.
     BanPeer { peer_id: PeerId, ban_reason: ReasonForBan },
+    /// Report an observation affecting the reputation of the given peer.
+    ReportPeer { peer_id: PeerId, event: ReputationEvent },
     /// Announce account
     AnnounceAccount(AnnounceAccount),
 
@@ -283,6 +306,8 @@ pub struct HighestHeightPeerInfo {
     pub tracked_shards: Vec<ShardId>,
     /// Denote if a node is running in archival mode or not.
     pub archival: bool,
+    /// Reputation score of the peer, see `crate::reputation`.
+    pub reputation: i64,
 }
 
 impl From<FullPeerInfo> for Option<HighestHeightPeerInfo> {
@@ -295,6 +320,7 @@ impl From<FullPeerInfo> for Option<HighestHeightPeerInfo> {
                 highest_block_hash: p.chain_info.last_block.unwrap().hash,
                 tracked_shards: p.chain_info.tracked_shards,
                 archival: p.chain_info.archival,
+                reputation: 0,
             })
         } else {
             None
@@ -331,6 +357,14 @@ pub struct ConnectedPeerInfo {
     pub received_bytes_per_sec: u64,
     /// Number of bytes we've sent to the peer.
     pub sent_bytes_per_sec: u64,
+    /// Number of bytes of the compressed messages we've received from the peer, after and
+    /// before decompression.
+    pub received_uncompressed_bytes_per_sec: u64,
+    pub received_compressed_bytes_per_sec: u64,
+    /// Number of bytes of the compressed messages we've sent to the peer, before and after
+    /// compression.
+    pub sent_uncompressed_bytes_per_sec: u64,
+    pub sent_compressed_bytes_per_sec: u64,
     /// Last time requested peers.
     pub last_time_peer_requested: time::Instant,
     /// Last time we received a message from this peer.
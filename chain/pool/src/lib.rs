@@ -1,10 +1,12 @@
 use std::collections::btree_map::Entry;
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
 
 use crate::types::{PoolIterator, PoolKey, TransactionGroup};
 use borsh::BorshSerialize;
+use near_chain_configs::TransactionPoolMode;
 use near_crypto::PublicKey;
 use near_o11y::metrics::prometheus::core::{AtomicI64, GenericGauge};
+use near_o11y::metrics::IntCounter;
 use near_primitives::epoch_manager::RngSeed;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::transaction::SignedTransaction;
@@ -22,13 +24,20 @@ pub enum InsertTransactionResult {
     Duplicate,
     /// Not enough space to fit the transaction.
     NoSpaceLeft,
+    /// Transaction was inserted in place of a pooled transaction with the same signer, public key
+    /// and nonce. Transactions listed in `evicted_tx_hashes` were evicted to make space for it.
+    Replaced { replaced_tx_hash: CryptoHash, evicted_tx_hashes: Vec<CryptoHash> },
+    /// Transaction was inserted after evicting the listed transactions to make space for it.
+    Evicted { evicted_tx_hashes: Vec<CryptoHash> },
 }
 
 /// Transaction pool: keeps track of transactions that were not yet accepted into the block chain.
 pub struct TransactionPool {
     /// Transactions are grouped by a pair of (account ID, signer public key).
-    /// NOTE: It's more efficient on average to keep transactions unsorted and with potentially
-    /// conflicting nonce than to create a BTreeMap for every transaction.
+    /// NOTE: In `TransactionPoolMode::Unordered` it's more efficient on average to keep
+    /// transactions unsorted and with potentially conflicting nonce than to create a BTreeMap for
+    /// every transaction. In `TransactionPoolMode::NonceOrdered` every group is kept sorted by
+    /// nonce in non-increasing order, and no two transactions of a group share a nonce.
     transactions: BTreeMap<PoolKey, Vec<SignedTransaction>>,
     /// Set of all hashes to quickly check if the given transaction is in the pool.
     unique_transactions: HashSet<CryptoHash>,
@@ -36,28 +45,40 @@ pub struct TransactionPool {
     key_seed: RngSeed,
     /// The key after which the pool iterator starts. Doesn't have to be present in the pool.
     last_used_key: PoolKey,
-    /// If set, new transactions that bring the size of the pool over this limit will be rejected.
+    /// If set, new transactions that bring the size of the pool over this limit will be rejected,
+    /// or, in `TransactionPoolMode::NonceOrdered`, will evict other transactions.
     total_transaction_size_limit: Option<u64>,
     /// Total size of transactions in the pool measured in bytes.
     total_transaction_size: u64,
+    /// Defines how transactions are ordered, replaced and evicted.
+    mode: TransactionPoolMode,
     /// Metrics tracked for transaction pool.
     transaction_pool_count_metric: GenericGauge<AtomicI64>,
     transaction_pool_size_metric: GenericGauge<AtomicI64>,
+    transaction_pool_replaced_metric: IntCounter,
+    transaction_pool_evicted_metric: IntCounter,
 }
 
 impl TransactionPool {
     pub fn new(
         key_seed: RngSeed,
         total_transaction_size_limit: Option<u64>,
+        mode: TransactionPoolMode,
         metrics_label: &str,
     ) -> Self {
         let transaction_pool_count_metric =
             metrics::TRANSACTION_POOL_COUNT.with_label_values(&[metrics_label]);
         let transaction_pool_size_metric =
             metrics::TRANSACTION_POOL_SIZE.with_label_values(&[metrics_label]);
+        let transaction_pool_replaced_metric =
+            metrics::TRANSACTION_POOL_REPLACED.with_label_values(&[metrics_label]);
+        let transaction_pool_evicted_metric =
+            metrics::TRANSACTION_POOL_EVICTED.with_label_values(&[metrics_label]);
         // A `get()` call initializes a metric even if its value is zero.
         transaction_pool_count_metric.get();
         transaction_pool_size_metric.get();
+        transaction_pool_replaced_metric.get();
+        transaction_pool_evicted_metric.get();
 
         Self {
             key_seed,
@@ -66,8 +87,11 @@ impl TransactionPool {
             last_used_key: CryptoHash::default(),
             total_transaction_size_limit,
             total_transaction_size: 0,
+            mode,
             transaction_pool_count_metric,
             transaction_pool_size_metric,
+            transaction_pool_replaced_metric,
+            transaction_pool_evicted_metric,
         }
     }
 
@@ -84,7 +108,17 @@ impl TransactionPool {
         &mut self,
         signed_transaction: SignedTransaction,
     ) -> InsertTransactionResult {
-        if !self.unique_transactions.insert(signed_transaction.get_hash()) {
+        match self.mode {
+            TransactionPoolMode::Unordered => self.insert_unordered(signed_transaction),
+            TransactionPoolMode::NonceOrdered => self.insert_nonce_ordered(signed_transaction),
+        }
+    }
+
+    fn insert_unordered(
+        &mut self,
+        signed_transaction: SignedTransaction,
+    ) -> InsertTransactionResult {
+        if self.unique_transactions.contains(&signed_transaction.get_hash()) {
             // The hash of this transaction was already seen, skip it.
             return InsertTransactionResult::Duplicate;
         }
@@ -102,6 +136,7 @@ impl TransactionPool {
         }
 
         // At this point transaction is accepted to the pool.
+        self.unique_transactions.insert(signed_transaction.get_hash());
         self.total_transaction_size = new_total_transaction_size;
         let signer_id = &signed_transaction.transaction.signer_id;
         let signer_public_key = &signed_transaction.transaction.public_key;
@@ -115,6 +150,117 @@ impl TransactionPool {
         InsertTransactionResult::Success
     }
 
+    /// Inserts the transaction keeping its group sorted by nonce.
+    ///
+    /// A pooled transaction with the same nonce is replaced by the new one. If the pool is over
+    /// the size limit, transactions with the highest nonces are evicted from the longest groups,
+    /// since the round robin pool iterator would reach them last. Only groups that are strictly
+    /// longer than the group of the new transaction are evicted from, so a single signer can't
+    /// push other signers out of the pool by flooding it. If not enough space can be freed, the
+    /// pool is left untouched and the transaction is rejected.
+    fn insert_nonce_ordered(
+        &mut self,
+        signed_transaction: SignedTransaction,
+    ) -> InsertTransactionResult {
+        let tx_hash = signed_transaction.get_hash();
+        if self.unique_transactions.contains(&tx_hash) {
+            return InsertTransactionResult::Duplicate;
+        }
+        let key = self.key(
+            &signed_transaction.transaction.signer_id,
+            &signed_transaction.transaction.public_key,
+        );
+        let nonce = signed_transaction.transaction.nonce;
+        // Groups are sorted by nonce in non-increasing order.
+        let (position, replaced_size, group_len) = match self.transactions.get(&key) {
+            Some(group) => {
+                let position = group.partition_point(|tx| tx.transaction.nonce > nonce);
+                let replaced_size = group
+                    .get(position)
+                    .filter(|tx| tx.transaction.nonce == nonce)
+                    .map(|tx| tx.get_size());
+                (position, replaced_size, group.len())
+            }
+            None => (0, None, 0),
+        };
+        let group_len_after_insert = group_len + usize::from(replaced_size.is_none());
+        // See the comment in `insert_unordered` for reasoning why panicing here is fine.
+        let new_total_transaction_size = self
+            .total_transaction_size
+            .checked_sub(replaced_size.unwrap_or(0))
+            .and_then(|size| size.checked_add(signed_transaction.get_size()))
+            .expect("Total transaction size is out of bounds");
+
+        let mut evictions = HashMap::new();
+        if let Some(limit) = self.total_transaction_size_limit {
+            let mut excess = new_total_transaction_size.saturating_sub(limit);
+            let mut candidates: BinaryHeap<(usize, PoolKey)> = self
+                .transactions
+                .iter()
+                .filter(|(group_key, group)| {
+                    **group_key != key && group.len() > group_len_after_insert
+                })
+                .map(|(group_key, group)| (group.len(), *group_key))
+                .collect();
+            // Only plan the evictions at first, so that the pool stays untouched if the
+            // transaction doesn't fit anyway.
+            while excess > 0 {
+                let Some((len, victim_key)) = candidates.pop() else {
+                    return InsertTransactionResult::NoSpaceLeft;
+                };
+                let evicted: &mut usize = evictions.entry(victim_key).or_default();
+                let victim = &self.transactions[&victim_key][*evicted];
+                *evicted += 1;
+                excess = excess.saturating_sub(victim.get_size());
+                if len - 1 > group_len_after_insert {
+                    candidates.push((len - 1, victim_key));
+                }
+            }
+        }
+
+        // At this point transaction is accepted to the pool.
+        let mut evicted_tx_hashes = vec![];
+        for (victim_key, count) in evictions {
+            let Entry::Occupied(mut entry) = self.transactions.entry(victim_key) else {
+                unreachable!("evictions are planned for existing groups only");
+            };
+            for tx in entry.get_mut().drain(..count) {
+                self.unique_transactions.remove(&tx.get_hash());
+                self.total_transaction_size -= tx.get_size();
+                evicted_tx_hashes.push(tx.get_hash());
+            }
+            if entry.get().is_empty() {
+                entry.remove_entry();
+            }
+        }
+        self.transaction_pool_evicted_metric.inc_by(evicted_tx_hashes.len() as u64);
+
+        self.unique_transactions.insert(tx_hash);
+        self.total_transaction_size += signed_transaction.get_size();
+        let group = self.transactions.entry(key).or_insert_with(Vec::new);
+        let result = if replaced_size.is_some() {
+            let replaced = std::mem::replace(&mut group[position], signed_transaction);
+            self.unique_transactions.remove(&replaced.get_hash());
+            self.total_transaction_size -= replaced.get_size();
+            self.transaction_pool_replaced_metric.inc();
+            InsertTransactionResult::Replaced {
+                replaced_tx_hash: replaced.get_hash(),
+                evicted_tx_hashes,
+            }
+        } else {
+            group.insert(position, signed_transaction);
+            if evicted_tx_hashes.is_empty() {
+                InsertTransactionResult::Success
+            } else {
+                InsertTransactionResult::Evicted { evicted_tx_hashes }
+            }
+        };
+
+        self.transaction_pool_count_metric.set(self.unique_transactions.len() as i64);
+        self.transaction_pool_size_metric.set(self.total_transaction_size as i64);
+        result
+    }
+
     /// Returns a pool iterator wrapper that implements an iterator-like trait to iterate over
     /// transaction groups in the proper order defined by the protocol.
     /// When the iterator is dropped, all remaining groups are inserted back into the pool.
@@ -175,6 +321,25 @@ impl TransactionPool {
     pub fn transaction_size(&self) -> u64 {
         self.total_transaction_size
     }
+
+    /// Returns whether a transaction with the given hash is in the pool.
+    pub fn contains(&self, tx_hash: &CryptoHash) -> bool {
+        self.unique_transactions.contains(tx_hash)
+    }
+
+    /// Returns the pooled transaction with the given hash.
+    pub fn get(&self, tx_hash: &CryptoHash) -> Option<&SignedTransaction> {
+        if !self.contains(tx_hash) {
+            return None;
+        }
+        self.transactions.values().flatten().find(|tx| &tx.get_hash() == tx_hash)
+    }
+
+    /// Returns groups of pooled transactions sharing the same signer and public key.
+    /// Transactions within a group are only sorted in `TransactionPoolMode::NonceOrdered`.
+    pub fn transaction_groups(&self) -> impl Iterator<Item = &[SignedTransaction]> {
+        self.transactions.values().map(|group| group.as_slice())
+    }
 }
 
 /// PoolIterator is a structure to pull transactions from the pool.
@@ -335,7 +500,7 @@ mod tests {
         mut transactions: Vec<SignedTransaction>,
         expected_weight: u32,
     ) -> (Vec<u64>, TransactionPool) {
-        let mut pool = TransactionPool::new(TEST_SEED, None, "");
+        let mut pool = TransactionPool::new(TEST_SEED, None, TransactionPoolMode::Unordered, "");
         let mut rng = thread_rng();
         transactions.shuffle(&mut rng);
         for tx in transactions {
@@ -446,7 +611,7 @@ mod tests {
             })
             .collect::<Vec<_>>();
 
-        let mut pool = TransactionPool::new(TEST_SEED, None, "");
+        let mut pool = TransactionPool::new(TEST_SEED, None, TransactionPoolMode::Unordered, "");
         let mut rng = thread_rng();
         transactions.shuffle(&mut rng);
         for tx in transactions.clone() {
@@ -561,7 +726,7 @@ mod tests {
 
     #[test]
     fn test_transaction_pool_size() {
-        let mut pool = TransactionPool::new(TEST_SEED, None, "");
+        let mut pool = TransactionPool::new(TEST_SEED, None, TransactionPoolMode::Unordered, "");
         let transactions = generate_transactions("alice.near", "alice.near", 1, 100);
         let mut total_transaction_size = 0;
         // Adding transactions increases the size.
@@ -585,7 +750,12 @@ mod tests {
         // Each transaction is at least 1 byte in size, so the last transaction will not fit.
         let pool_size_limit =
             transactions.iter().map(|tx| tx.get_size()).sum::<u64>().checked_sub(1).unwrap();
-        let mut pool = TransactionPool::new(TEST_SEED, Some(pool_size_limit), "");
+        let mut pool = TransactionPool::new(
+            TEST_SEED,
+            Some(pool_size_limit),
+            TransactionPoolMode::Unordered,
+            "",
+        );
         for (i, tx) in transactions.iter().cloned().enumerate() {
             if i + 1 < transactions.len() {
                 assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
@@ -594,4 +764,87 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_nonce_ordered_replacement() {
+        let mut pool = TransactionPool::new(TEST_SEED, None, TransactionPoolMode::NonceOrdered, "");
+        let transactions = generate_transactions("alice.near", "alice.near", 1, 10);
+        for tx in transactions.iter().rev().cloned() {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        // Same nonce as a pooled transaction, but a different deposit and thus a different hash.
+        let signer = InMemorySigner::from_seed(
+            "alice.near".parse().unwrap(),
+            KeyType::ED25519,
+            "alice.near",
+        );
+        let replacement = SignedTransaction::send_money(
+            5,
+            "alice.near".parse().unwrap(),
+            "bob.near".parse().unwrap(),
+            &signer,
+            100,
+            CryptoHash::default(),
+        );
+        assert_ne!(replacement.get_hash(), transactions[4].get_hash());
+        assert_eq!(
+            pool.insert_transaction(replacement.clone()),
+            InsertTransactionResult::Replaced {
+                replaced_tx_hash: transactions[4].get_hash(),
+                evicted_tx_hashes: vec![],
+            }
+        );
+        assert_eq!(pool.len(), 10);
+        let expected_size = transactions.iter().map(|tx| tx.get_size()).sum::<u64>()
+            - transactions[4].get_size()
+            + replacement.get_size();
+        assert_eq!(pool.transaction_size(), expected_size);
+
+        let txs = prepare_transactions(&mut pool, 10);
+        assert_eq!(
+            txs.iter().map(|tx| tx.transaction.nonce).collect::<Vec<_>>(),
+            (1..=10).collect::<Vec<u64>>()
+        );
+        assert_eq!(txs[4], replacement);
+    }
+
+    #[test]
+    fn test_nonce_ordered_eviction() {
+        let long_group = generate_transactions("alice.near", "alice.near", 1, 10);
+        let short_group = generate_transactions("bob.near", "bob.near", 1, 2);
+        let pool_size_limit = long_group.iter().map(|tx| tx.get_size()).sum::<u64>();
+        let mut pool = TransactionPool::new(
+            TEST_SEED,
+            Some(pool_size_limit),
+            TransactionPoolMode::NonceOrdered,
+            "",
+        );
+        for tx in long_group.clone() {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        // The long group loses its transactions with the highest nonces.
+        for (tx, evicted) in short_group.into_iter().zip([&long_group[9], &long_group[8]]) {
+            assert_eq!(
+                pool.insert_transaction(tx),
+                InsertTransactionResult::Evicted { evicted_tx_hashes: vec![evicted.get_hash()] }
+            );
+        }
+        assert_eq!(pool.len(), 10);
+        assert!(pool.transaction_size() <= pool_size_limit);
+
+        // A transaction of the longest group can't evict transactions of shorter groups.
+        let len_before = pool.len();
+        let size_before = pool.transaction_size();
+        assert_eq!(
+            pool.insert_transaction(long_group[9].clone()),
+            InsertTransactionResult::NoSpaceLeft
+        );
+        assert_eq!(pool.len(), len_before);
+        assert_eq!(pool.transaction_size(), size_before);
+
+        let mut nonces: Vec<u64> =
+            prepare_transactions(&mut pool, 10).iter().map(|tx| tx.transaction.nonce).collect();
+        nonces.sort();
+        assert_eq!(nonces, vec![1, 1, 2, 2, 3, 4, 5, 6, 7, 8]);
+    }
 }
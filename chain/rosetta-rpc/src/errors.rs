@@ -73,3 +73,9 @@ impl From<near_client_primitives::types::GetStateChangesError> for ErrorKind {
         }
     }
 }
+
+impl From<near_client_primitives::types::GetTxPoolError> for ErrorKind {
+    fn from(err: near_client_primitives::types::GetTxPoolError) -> Self {
+        match err {}
+    }
+}
@@ -392,20 +392,34 @@ async fn account_balance(
 }
 
 #[api_v2_operation]
-/// Get All Mempool Transactions (not implemented)
+/// Get All Mempool Transactions
 ///
 /// Get all Transaction Identifiers in the mempool
 ///
-/// NOTE: The mempool is short-lived, so it is currently not implemented.
+/// NOTE: The mempool is short-lived and local to the node, so transactions
+/// submitted through other nodes do not show up here until they are included
+/// in a block.
 async fn mempool(
-    _client_addr: web::Data<Addr<ClientActor>>,
-    _body: Json<models::NetworkRequest>,
+    client_addr: web::Data<Addr<ClientActor>>,
+    body: Json<models::NetworkRequest>,
 ) -> Result<Json<models::MempoolResponse>, models::Error> {
-    Ok(Json(models::MempoolResponse { transaction_identifiers: vec![] }))
+    let Json(models::NetworkRequest { network_identifier }) = body;
+
+    check_network_identifier(&client_addr, network_identifier).await?;
+
+    let tx_pool = client_addr
+        .send(
+            near_client::GetTxPool { account_id: None, public_key: None, transaction_hash: None }
+                .with_span_context(),
+        )
+        .await??;
+    let transaction_identifiers = crate::utils::get_pooled_transaction_identifiers(&tx_pool);
+
+    Ok(Json(models::MempoolResponse { transaction_identifiers }))
 }
 
 #[api_v2_operation]
-/// Get a Mempool Transaction (not implemented)
+/// Get a Mempool Transaction
 ///
 /// Get a transaction in the mempool by its Transaction Identifier. This is a
 /// separate request than fetching a block transaction (/block/transaction)
@@ -416,13 +430,37 @@ async fn mempool(
 /// endpoint, it is ok that returned transactions are only estimates of what may
 /// actually be included in a block.
 ///
-/// NOTE: The mempool is short-lived, so this method does not make a lot of
-/// sense to be implemented.
+/// NOTE: The returned operations describe the actions of the transaction only,
+/// fees and the effects of the receipts are not known until it is executed.
 async fn mempool_transaction(
-    _client_addr: web::Data<Addr<ClientActor>>,
-    _body: Json<models::MempoolTransactionRequest>,
+    client_addr: web::Data<Addr<ClientActor>>,
+    body: Json<models::MempoolTransactionRequest>,
 ) -> Result<Json<models::MempoolTransactionResponse>, models::Error> {
-    Err(errors::ErrorKind::InternalError("Not implemented yet".to_string()).into())
+    let Json(models::MempoolTransactionRequest { network_identifier, transaction_identifier }) =
+        body;
+
+    check_network_identifier(&client_addr, network_identifier).await?;
+
+    let signed_transaction =
+        crate::utils::get_pooled_transaction(&client_addr, &transaction_identifier).await?;
+
+    let near_primitives::transaction::Transaction { signer_id, receiver_id, actions, .. } =
+        signed_transaction.transaction;
+    let operations = crate::adapters::NearActions {
+        sender_account_id: signer_id,
+        receiver_account_id: receiver_id,
+        actions,
+    }
+    .into();
+
+    Ok(Json(models::MempoolTransactionResponse {
+        transaction: models::Transaction {
+            transaction_identifier,
+            operations,
+            related_transactions: Vec::new(),
+            metadata: models::TransactionMetadata { type_: models::TransactionType::Transaction },
+        },
+    }))
 }
 
 #[api_v2_operation]
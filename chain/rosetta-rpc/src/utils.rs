@@ -6,7 +6,7 @@ use crate::{
 use actix::Addr;
 use futures::StreamExt;
 use near_chain_configs::ProtocolConfigView;
-use near_client::ViewClientActor;
+use near_client::{ClientActor, ViewClientActor};
 use near_o11y::WithSpanContextExt;
 use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
 
@@ -582,3 +582,124 @@ pub(crate) async fn get_nonces(
     }
     Ok(models::AccountBalanceResponseMetadata { nonces })
 }
+
+/// Returns the identifiers of all the transactions in the transaction pool.
+pub(crate) fn get_pooled_transaction_identifiers(
+    tx_pool: &near_primitives::views::TxPoolView,
+) -> Vec<models::TransactionIdentifier> {
+    tx_pool
+        .shards
+        .iter()
+        .flat_map(|shard| shard.groups.iter())
+        .flat_map(|group| group.transactions.iter())
+        .map(|transaction| models::TransactionIdentifier::transaction(&transaction.hash))
+        .collect()
+}
+
+/// Looks up a transaction in the transaction pool by its `tx:<hash>` identifier.
+pub(crate) async fn get_pooled_transaction(
+    client_addr: &Addr<ClientActor>,
+    transaction_identifier: &models::TransactionIdentifier,
+) -> Result<near_primitives::transaction::SignedTransaction, errors::ErrorKind> {
+    let transaction_hash = transaction_identifier
+        .hash
+        .strip_prefix("tx:")
+        .and_then(|hash| hash.parse().ok())
+        .ok_or_else(|| {
+            errors::ErrorKind::InvalidInput(format!(
+                "Invalid transaction identifier {}, expecting tx:<hash>",
+                transaction_identifier.hash
+            ))
+        })?;
+
+    client_addr
+        .send(near_client::GetPooledTransaction { transaction_hash }.with_span_context())
+        .await??
+        .ok_or_else(|| errors::ErrorKind::NotFound("Transaction not found in mempool".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix::System;
+    use near_actix_test_utils::run_actix;
+    use near_client::test_utils::setup_no_network;
+    use near_crypto::{KeyType, SecretKey};
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::views::{
+        TxPoolGroupView, TxPoolShardView, TxPoolTransactionView, TxPoolView,
+    };
+
+    #[test]
+    fn test_get_pooled_transaction_identifiers() {
+        let transaction = |hash: CryptoHash, nonce| TxPoolTransactionView {
+            hash,
+            nonce,
+            receiver_id: "bob.near".parse().unwrap(),
+            size: 100,
+            num_ahead: 0,
+        };
+        let group = |signer_id: &str, transactions| TxPoolGroupView {
+            signer_id: signer_id.parse().unwrap(),
+            public_key: SecretKey::from_seed(KeyType::ED25519, signer_id).public_key(),
+            transactions,
+        };
+        let shard = |shard_id, groups| TxPoolShardView {
+            shard_id,
+            num_transactions: 0,
+            total_size: 0,
+            groups,
+            recently_dropped: vec![],
+        };
+        let hashes = [CryptoHash([1; 32]), CryptoHash([2; 32]), CryptoHash([3; 32])];
+        let tx_pool = TxPoolView {
+            shards: vec![
+                shard(
+                    0,
+                    vec![group(
+                        "alice.near",
+                        vec![transaction(hashes[0], 1), transaction(hashes[1], 2)],
+                    )],
+                ),
+                shard(1, vec![]),
+                shard(2, vec![group("carol.near", vec![transaction(hashes[2], 1)])]),
+            ],
+        };
+
+        let identifiers = get_pooled_transaction_identifiers(&tx_pool);
+        let expected: Vec<_> =
+            hashes.iter().map(models::TransactionIdentifier::transaction).collect();
+        assert_eq!(identifiers, expected);
+        assert!(get_pooled_transaction_identifiers(&TxPoolView { shards: vec![] }).is_empty());
+    }
+
+    #[test]
+    fn test_get_pooled_transaction() {
+        run_actix(async {
+            let actor_handles = setup_no_network(
+                vec!["test".parse().unwrap()],
+                "test".parse().unwrap(),
+                true,
+                false,
+            );
+            let client_addr = actor_handles.client_actor;
+
+            let unknown = models::TransactionIdentifier::transaction(&CryptoHash([1; 32]));
+            let error = get_pooled_transaction(&client_addr, &unknown).await.unwrap_err();
+            assert!(matches!(error, errors::ErrorKind::NotFound(_)), "{:?}", error);
+
+            for hash in ["receipt:11111111111111111111111111111111", "tx:not-a-hash", "tx:"] {
+                let malformed = models::TransactionIdentifier { hash: hash.to_string() };
+                let error = get_pooled_transaction(&client_addr, &malformed).await.unwrap_err();
+                assert!(
+                    matches!(error, errors::ErrorKind::InvalidInput(_)),
+                    "{}: {:?}",
+                    hash,
+                    error
+                );
+            }
+
+            System::current().stop();
+        });
+    }
+}
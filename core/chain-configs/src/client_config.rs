@@ -1,10 +1,12 @@
 //! Chain Client Configuration
 use crate::MutableConfigValue;
+use near_primitives::shard_layout::ShardUId;
 use near_primitives::types::{
     AccountId, BlockHeight, BlockHeightDelta, Gas, NumBlocks, NumSeats, ShardId,
 };
 use near_primitives::version::Version;
 use std::cmp::{max, min};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -44,6 +46,15 @@ pub struct GCConfig {
     /// Number of epochs for which we keep store data.
     #[serde(default = "default_gc_num_epochs_to_keep")]
     pub gc_num_epochs_to_keep: u64,
+
+    /// Number of epochs for which archival nodes keep the data of the given
+    /// columns, by column name. The data of older blocks is deleted. Only the
+    /// columns which aren't needed to serve blocks, chunks, transactions and
+    /// receipts are supported: `StateChanges`, `StateChangesForSplitStates`
+    /// and `TrieChanges`. The values can't be lower than
+    /// `gc_num_epochs_to_keep`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub column_num_epochs_to_keep: BTreeMap<String, u64>,
 }
 
 impl Default for GCConfig {
@@ -52,6 +63,7 @@ impl Default for GCConfig {
             gc_blocks_limit: 2,
             gc_fork_clean_step: 100,
             gc_num_epochs_to_keep: DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
+            column_num_epochs_to_keep: BTreeMap::new(),
         }
     }
 }
@@ -103,6 +115,17 @@ pub enum ExternalStorageLocation {
         bucket: String,
         /// Data may only be available in certain locations.
         region: String,
+        /// URL of an S3-compatible service, for example a MinIO cluster, to use instead of AWS.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        endpoint: Option<String>,
+        /// Address the bucket as `{endpoint}/{bucket}` instead of `{bucket}.{endpoint}`. Most
+        /// S3-compatible services, including MinIO, need this.
+        #[serde(default, skip_serializing_if = "is_false")]
+        path_style: bool,
+        /// Whether to send requests without credentials. Defaults to `true` when syncing state
+        /// and to `false` when dumping state.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        anonymous: Option<bool>,
     },
     Filesystem {
         root_dir: PathBuf,
@@ -110,6 +133,16 @@ pub enum ExternalStorageLocation {
     GCS {
         bucket: String,
     },
+    /// Read-only location served by a plain HTTP(S) file server, for example a CDN in front of
+    /// a bucket. Can only be used to sync state, not to dump it.
+    Http {
+        /// State parts are fetched from `{base_url}/{location of the part}`.
+        base_url: String,
+    },
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 /// Configures how to dump state to external storage.
@@ -162,6 +195,20 @@ impl SyncConfig {
     }
 }
 
+/// Configures how the transaction pool orders transactions and what it does when it is full.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionPoolMode {
+    /// Transactions of a group are kept unsorted and sorted only when the pool is iterated.
+    /// New transactions are rejected once the pool is full.
+    #[default]
+    Unordered,
+    /// Transactions of a group are kept sorted by nonce. A transaction with the same nonce as
+    /// a pooled one replaces it, and once the pool is full the tails of the longest groups are
+    /// evicted to make space for new transactions.
+    NonceOrdered,
+}
+
 /// ClientConfig where some fields can be updated at runtime.
 #[derive(Clone, serde::Serialize)]
 pub struct ClientConfig {
@@ -229,9 +276,13 @@ pub struct ClientConfig {
     pub tracked_accounts: Vec<AccountId>,
     /// Shards that this client tracks.
     pub tracked_shards: Vec<ShardId>,
+    /// Shards that this client tracks, unlike `tracked_shards` which tracks all shards.
+    /// After resharding, the shards split from a tracked shard are tracked as well.
+    /// This field is only used if `tracked_shards` is empty.
+    pub tracked_shard_uids: Vec<ShardUId>,
     /// Rotate between these sets of tracked shards.
     /// Used to simulate the behavior of chunk only producers without staking tokens.
-    /// This field is only used if `tracked_shards` is empty.
+    /// This field is only used if `tracked_shards` and `tracked_shard_uids` are empty.
     pub tracked_shard_schedule: Vec<Vec<ShardId>>,
     /// Not clear old data, set `true` for archive nodes.
     pub archive: bool,
@@ -240,6 +291,9 @@ pub struct ClientConfig {
     /// - archive is true, cold_store is configured and migration to split_storage is finished - node
     /// working in split storage mode needs trie changes in order to do garbage collection on hot.
     pub save_trie_changes: bool,
+    /// Maintain the state history index and use it to answer queries about past blocks in the
+    /// tracked shards.
+    pub save_state_history: bool,
     /// Number of threads for ViewClientActor pool.
     pub view_client_threads: usize,
     /// Run Epoch Sync on the start.
@@ -270,6 +324,8 @@ pub struct ClientConfig {
     /// Limit of the size of per-shard transaction pool measured in bytes. If not set, the size
     /// will be unbounded.
     pub transaction_pool_size_limit: Option<u64>,
+    /// How the transaction pool orders, replaces and evicts transactions.
+    pub transaction_pool_mode: TransactionPoolMode,
     // Allows more detailed logging, for example a list of orphaned blocks.
     pub enable_multiline_logging: bool,
 }
@@ -329,9 +385,11 @@ impl ClientConfig {
             gc: GCConfig { gc_blocks_limit: 100, ..GCConfig::default() },
             tracked_accounts: vec![],
             tracked_shards: vec![],
+            tracked_shard_uids: vec![],
             tracked_shard_schedule: vec![],
             archive,
             save_trie_changes,
+            save_state_history: false,
             log_summary_style: LogSummaryStyle::Colored,
             view_client_threads: 1,
             epoch_sync_enabled,
@@ -346,6 +404,7 @@ impl ClientConfig {
             state_sync: StateSyncConfig::default(),
             state_snapshot_every_n_blocks: None,
             transaction_pool_size_limit: None,
+            transaction_pool_mode: TransactionPoolMode::default(),
             enable_multiline_logging: false,
         }
     }
@@ -1,6 +1,7 @@
 #![doc = include_str!("../README.md")]
 
 use near_chain_configs::UpdateableClientConfig;
+use near_network::config::UpdateableNetworkConfig;
 use near_o11y::log_config::LogConfig;
 use near_primitives::static_clock::StaticClock;
 use serde::{Deserialize, Serialize};
@@ -17,6 +18,8 @@ pub struct UpdateableConfigs {
     pub log_config: Option<LogConfig>,
     /// Contents of the `config.json` corresponding to the mutable fields of `ClientConfig`.
     pub client_config: Option<UpdateableClientConfig>,
+    /// Contents of the `config.json` corresponding to the mutable fields of `NetworkConfig`.
+    pub network_config: Option<UpdateableNetworkConfig>,
 }
 
 /// Pushes the updates to listeners.
@@ -2,6 +2,9 @@
 #![deny(clippy::arithmetic_side_effects)]
 
 pub use context::*;
+pub use log_format::LogFormat;
+use log_format::{LogFormatHandle, NearFields, NearFormat};
+use log_sampling::{LogFilter, Sampling};
 use near_crypto::PublicKey;
 use near_primitives_core::types::AccountId;
 use once_cell::sync::OnceCell;
@@ -10,6 +13,7 @@ use opentelemetry::sdk::Resource;
 use opentelemetry::KeyValue;
 use opentelemetry_semantic_conventions::resource::SERVICE_NAME;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use tracing::level_filters::LevelFilter;
 use tracing::subscriber::DefaultGuard;
@@ -26,6 +30,8 @@ pub use {tracing, tracing_appender, tracing_subscriber};
 pub mod context;
 mod io_tracer;
 pub mod log_config;
+mod log_format;
+mod log_sampling;
 pub mod macros;
 pub mod metrics;
 pub mod testonly;
@@ -46,27 +52,22 @@ macro_rules! io_trace {
     ($($fields:tt)*) => {};
 }
 
-static LOG_LAYER_RELOAD_HANDLE: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+static LOG_LAYER_RELOAD_HANDLE: OnceCell<reload::Handle<LogFilter, Registry>> = OnceCell::new();
+static LOG_FORMAT_HANDLE: OnceCell<LogFormatHandle> = OnceCell::new();
 static OTLP_LAYER_RELOAD_HANDLE: OnceCell<reload::Handle<LevelFilter, LogLayer<Registry>>> =
     OnceCell::new();
 
 type LogLayer<Inner> = Layered<
     Filtered<
-        fmt::Layer<Inner, fmt::format::DefaultFields, fmt::format::Format, NonBlocking>,
-        reload::Layer<EnvFilter, Inner>,
+        fmt::Layer<Inner, NearFields, NearFormat, NonBlocking>,
+        reload::Layer<LogFilter, Inner>,
         Inner,
     >,
     Inner,
 >;
 
-type SimpleLogLayer<Inner, W> = Layered<
-    Filtered<
-        fmt::Layer<Inner, fmt::format::DefaultFields, fmt::format::Format, W>,
-        EnvFilter,
-        Inner,
-    >,
-    Inner,
->;
+type SimpleLogLayer<Inner, W> =
+    Layered<Filtered<fmt::Layer<Inner, NearFields, NearFormat, W>, EnvFilter, Inner>, Inner>;
 
 type TracingLayer<Inner> = Layered<
     Filtered<OpenTelemetryLayer<Inner, Tracer>, reload::Layer<LevelFilter, Inner>, Inner>,
@@ -75,6 +76,8 @@ type TracingLayer<Inner> = Layered<
 
 // Records the level of opentelemetry tracing verbosity configured via command-line flags at the startup.
 static DEFAULT_OTLP_LEVEL: OnceCell<OpenTelemetryLevel> = OnceCell::new();
+// Records the log format configured via command-line flags at the startup.
+static DEFAULT_LOG_FORMAT: OnceCell<LogFormat> = OnceCell::new();
 
 /// The default value for the `RUST_LOG` environment variable if one isn't specified otherwise.
 pub const DEFAULT_RUST_LOG: &str = "tokio_reactor=info,\
@@ -131,6 +134,11 @@ pub struct Options {
     #[clap(long, value_enum, default_value = "auto")]
     color: ColorOutput,
 
+    /// Format of the log lines. `json` writes one JSON object per line, including the fields of
+    /// the enclosing spans.
+    #[clap(long, value_enum, default_value = "text")]
+    log_format: LogFormat,
+
     /// Enable logging of spans. For instance, this prints timestamps of entering and exiting a span,
     /// together with the span duration and used/idle CPU time.
     #[clap(long)]
@@ -189,15 +197,19 @@ fn add_simple_log_layer<S, W>(
     writer: W,
     ansi: bool,
     with_span_events: bool,
+    log_format: LogFormat,
     subscriber: S,
 ) -> SimpleLogLayer<S, W>
 where
     S: tracing::Subscriber + for<'span> LookupSpan<'span> + Send + Sync,
     W: for<'writer> fmt::MakeWriter<'writer> + 'static,
 {
+    let format_handle = LogFormatHandle::new(log_format);
     let layer = fmt::layer()
         .with_ansi(ansi)
         .with_span_events(get_fmt_span(with_span_events))
+        .fmt_fields(NearFields::new(format_handle.clone()))
+        .event_format(NearFormat::new(format_handle))
         .with_writer(writer)
         .with_filter(filter);
 
@@ -217,16 +229,19 @@ fn add_non_blocking_log_layer<S>(
     writer: NonBlocking,
     ansi: bool,
     with_span_events: bool,
+    format_handle: LogFormatHandle,
     subscriber: S,
-) -> (LogLayer<S>, reload::Handle<EnvFilter, S>)
+) -> (LogLayer<S>, reload::Handle<LogFilter, S>)
 where
     S: tracing::Subscriber + for<'span> LookupSpan<'span> + Send + Sync,
 {
-    let (filter, handle) = reload::Layer::<EnvFilter, S>::new(filter);
+    let (filter, handle) = reload::Layer::<LogFilter, S>::new(LogFilter::new(filter));
 
     let layer = fmt::layer()
         .with_ansi(ansi)
         .with_span_events(get_fmt_span(with_span_events))
+        .fmt_fields(NearFields::new(format_handle.clone()))
+        .event_format(NearFormat::new(format_handle))
         .with_writer(writer)
         .with_filter(filter);
 
@@ -347,6 +362,7 @@ pub fn default_subscriber(
         make_writer,
         color_output,
         options.log_span_events,
+        options.log_format,
         subscriber,
     );
 
@@ -397,17 +413,23 @@ pub async fn default_subscriber_with_opentelemetry(
     let subscriber = tracing_subscriber::registry();
 
     set_default_otlp_level(options);
+    DEFAULT_LOG_FORMAT.set(options.log_format).unwrap();
 
+    let format_handle = LogFormatHandle::new(options.log_format);
     let (subscriber, handle) = add_non_blocking_log_layer(
         env_filter,
         writer,
         color_output,
         options.log_span_events,
+        format_handle.clone(),
         subscriber,
     );
     LOG_LAYER_RELOAD_HANDLE
         .set(handle)
         .unwrap_or_else(|_| panic!("Failed to set Log Layer Filter"));
+    LOG_FORMAT_HANDLE
+        .set(format_handle)
+        .unwrap_or_else(|_| panic!("Failed to set Log Layer Format"));
 
     let (subscriber, handle) = add_opentelemetry_layer(
         options.opentelemetry,
@@ -462,11 +484,13 @@ pub fn reload_log_config(config: Option<&log_config::LogConfig>) {
             config.rust_log.as_deref(),
             config.verbose_module.as_deref(),
             config.opentelemetry_level,
+            config.log_format,
+            config.log_sampling.as_ref(),
         )
     } else {
         // When the LOG_CONFIG_FILENAME is not available, reset to the tracing and logging config
         // when the node was started.
-        reload(None, None, None)
+        reload(None, None, None, None, None)
     };
     match result {
         Ok(_) => {
@@ -490,10 +514,14 @@ pub fn reload_log_config(config: Option<&log_config::LogConfig>) {
 /// `rust_log` is equivalent to setting `RUST_LOG` environment variable.
 /// `verbose` indicates whether `--verbose` command-line flag is present.
 /// `verbose_module` is equivalent to the value of the `--verbose` command-line flag.
+/// `log_format` replaces the format set by the `--log-format` command-line flag.
+/// `log_sampling` limits the number of events logged per second for the given targets.
 pub fn reload(
     rust_log: Option<&str>,
     verbose_module: Option<&str>,
     opentelemetry_level: Option<OpenTelemetryLevel>,
+    log_format: Option<LogFormat>,
+    log_sampling: Option<&BTreeMap<String, u64>>,
 ) -> Result<(), Vec<ReloadError>> {
     let log_reload_result = LOG_LAYER_RELOAD_HANDLE.get().map_or(
         Err(ReloadError::NoLogReloadHandle),
@@ -504,12 +532,18 @@ pub fn reload(
                 builder = builder.verbose(Some(module));
             }
             let env_filter = builder.finish().map_err(ReloadError::Parse)?;
+            let sampling = log_sampling.map(Sampling::new).unwrap_or_default();
 
             reload_handle
                 .modify(|log_filter| {
-                    *log_filter = env_filter;
+                    *log_filter = LogFilter { env_filter, sampling };
                 })
                 .map_err(ReloadError::ReloadLogLayer)?;
+            if let Some(format_handle) = LOG_FORMAT_HANDLE.get() {
+                format_handle.set(
+                    log_format.unwrap_or(*DEFAULT_LOG_FORMAT.get().unwrap_or(&LogFormat::Text)),
+                );
+            }
             Ok(())
         },
     );
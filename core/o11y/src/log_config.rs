@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::{fs::File, io::Write};
 
@@ -12,6 +13,11 @@ pub struct LogConfig {
     pub verbose_module: Option<String>,
     /// Verbosity level of collected traces.
     pub opentelemetry_level: Option<crate::OpenTelemetryLevel>,
+    /// Format of the log lines, overrides `--log-format`.
+    pub log_format: Option<crate::LogFormat>,
+    /// Maximum number of events logged per second for each target and its sub-targets,
+    /// e.g. `{"network": 100, "chunks": 50}`. Excess events are dropped.
+    pub log_sampling: Option<BTreeMap<String, u64>>,
 }
 
 impl LogConfig {
@@ -0,0 +1,280 @@
+//! Formatting of the log lines, either as human-readable text or as JSON.
+//!
+//! The format can be switched at runtime through a [`LogFormatHandle`], which is shared between the
+//! event formatter and the span fields formatter of a log layer.
+use serde_json::{Map, Value};
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::{span, Event, Subscriber};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::{DefaultFields, Format, Writer};
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Format of the log lines.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+    /// Newline-delimited JSON objects, with the fields of the event and of its spans.
+    Json,
+}
+
+/// Shared, reloadable choice of the [`LogFormat`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LogFormatHandle(Arc<AtomicU8>);
+
+impl LogFormatHandle {
+    pub(crate) fn new(format: LogFormat) -> Self {
+        let handle = Self::default();
+        handle.set(format);
+        handle
+    }
+
+    pub(crate) fn set(&self, format: LogFormat) {
+        self.0.store(format as u8, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> LogFormat {
+        match self.0.load(Ordering::Relaxed) {
+            x if x == LogFormat::Json as u8 => LogFormat::Json,
+            _ => LogFormat::Text,
+        }
+    }
+}
+
+/// Formats the fields of the spans. In JSON format the fields are stored as a JSON object, so that
+/// [`NearFormat`] can include them in the log lines.
+#[derive(Debug)]
+pub(crate) struct NearFields {
+    handle: LogFormatHandle,
+    text: DefaultFields,
+}
+
+impl NearFields {
+    pub(crate) fn new(handle: LogFormatHandle) -> Self {
+        Self { handle, text: DefaultFields::new() }
+    }
+}
+
+impl<'writer> FormatFields<'writer> for NearFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        match self.handle.get() {
+            LogFormat::Text => self.text.format_fields(writer, fields),
+            LogFormat::Json => {
+                let mut visitor = JsonVisitor::default();
+                fields.record(&mut visitor);
+                write!(writer, "{}", Value::Object(visitor.0))
+            }
+        }
+    }
+
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &span::Record<'_>,
+    ) -> fmt::Result {
+        if self.handle.get() == LogFormat::Json {
+            // Spans created before the format was switched keep their text fields.
+            if let Ok(map) = serde_json::from_str(&current.fields) {
+                let mut visitor = JsonVisitor(map);
+                fields.record(&mut visitor);
+                current.fields = Value::Object(visitor.0).to_string();
+                return Ok(());
+            }
+        }
+        if !current.fields.is_empty() {
+            current.fields.push(' ');
+        }
+        self.text.format_fields(current.as_writer(), fields)
+    }
+}
+
+/// Formats the events as text or as JSON objects like
+/// `{"timestamp":"…","level":"INFO","target":"…","fields":{"message":"…"},"spans":[{"name":"…"}]}`
+/// where `spans` go from the root to the innermost span.
+#[derive(Debug)]
+pub(crate) struct NearFormat {
+    handle: LogFormatHandle,
+    text: Format,
+}
+
+impl NearFormat {
+    pub(crate) fn new(handle: LogFormatHandle) -> Self {
+        Self { handle, text: Format::default() }
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for NearFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        if self.handle.get() == LogFormat::Text {
+            return self.text.format_event(ctx, writer, event);
+        }
+
+        let metadata = event.metadata();
+        let mut fields = JsonVisitor::default();
+        event.record(&mut fields);
+        let mut spans = vec![];
+        for span in ctx.event_scope().into_iter().flat_map(|scope| scope.from_root()) {
+            let mut span_object = Map::new();
+            span_object.insert("name".to_string(), span.name().into());
+            if let Some(span_fields) = span.extensions().get::<FormattedFields<N>>() {
+                match serde_json::from_str::<Map<String, Value>>(&span_fields.fields) {
+                    Ok(span_fields) => span_object.extend(span_fields),
+                    Err(_) if span_fields.fields.is_empty() => {}
+                    Err(_) => {
+                        span_object.insert("fields".to_string(), span_fields.fields.clone().into());
+                    }
+                }
+            }
+            spans.push(Value::Object(span_object));
+        }
+
+        let mut object = Map::new();
+        object.insert("level".to_string(), metadata.level().as_str().into());
+        object.insert("target".to_string(), metadata.target().into());
+        object.insert("fields".to_string(), Value::Object(fields.0));
+        if !spans.is_empty() {
+            object.insert("spans".to_string(), Value::Array(spans));
+        }
+        let object = Value::Object(object).to_string();
+
+        // The timestamp goes first for readability, and never needs escaping.
+        write!(writer, "{{\"timestamp\":\"")?;
+        SystemTime.format_time(&mut writer)?;
+        writeln!(writer, "\",{}", &object[1..])
+    }
+}
+
+/// Records the fields as a JSON object.
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.0.insert(field.name().to_string(), value.to_string().into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn take_lines(&self) -> Vec<String> {
+            let data = std::mem::take(&mut *self.0.lock().unwrap());
+            String::from_utf8(data).unwrap().lines().map(str::to_string).collect()
+        }
+    }
+
+    #[test]
+    fn test_json_format() {
+        let buffer = Buffer::default();
+        let handle = LogFormatHandle::new(LogFormat::Json);
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .fmt_fields(NearFields::new(handle.clone()))
+                .event_format(NearFormat::new(handle.clone()))
+                .with_writer(move || writer.clone()),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span =
+                tracing::info_span!("apply_chunk", shard_id = 3, height = tracing::field::Empty);
+            span.record("height", 100);
+            let _guard = span.enter();
+            tracing::info!(target: "chunks", num_receipts = 2, "applied");
+
+            let lines = buffer.take_lines();
+            assert_eq!(lines.len(), 1);
+            let line: Value = serde_json::from_str(&lines[0]).unwrap();
+            assert!(line["timestamp"].is_string());
+            assert_eq!(line["level"], "INFO");
+            assert_eq!(line["target"], "chunks");
+            assert_eq!(
+                line["fields"],
+                serde_json::json!({"message": "applied", "num_receipts": 2})
+            );
+            assert_eq!(
+                line["spans"],
+                serde_json::json!([{"name": "apply_chunk", "shard_id": 3, "height": 100}])
+            );
+
+            handle.set(LogFormat::Text);
+            tracing::info!(target: "chunks", "applied");
+            let lines = buffer.take_lines();
+            assert_eq!(lines.len(), 1);
+            assert!(serde_json::from_str::<Value>(&lines[0]).is_err());
+            assert!(lines[0].contains("chunks: applied"), "{}", lines[0]);
+        });
+    }
+}
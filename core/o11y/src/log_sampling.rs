@@ -0,0 +1,220 @@
+//! Rate limiting of the events logged for noisy targets.
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::subscriber::Interest;
+use tracing::{span, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Filter};
+use tracing_subscriber::EnvFilter;
+
+/// Filter of a log layer: an `EnvFilter` followed by the sampling of the events it enabled.
+pub(crate) struct LogFilter {
+    pub(crate) env_filter: EnvFilter,
+    pub(crate) sampling: Sampling,
+}
+
+impl LogFilter {
+    pub(crate) fn new(env_filter: EnvFilter) -> Self {
+        Self { env_filter, sampling: Sampling::default() }
+    }
+}
+
+impl<S: Subscriber> Filter<S> for LogFilter {
+    fn enabled(&self, meta: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        Filter::<S>::enabled(&self.env_filter, meta, cx) && self.sampling.sample(meta)
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        let interest = Filter::<S>::callsite_enabled(&self.env_filter, meta);
+        // Sampled events need to be checked every time they are emitted.
+        if !interest.is_never() && meta.is_event() && self.sampling.rule(meta.target()).is_some() {
+            Interest::sometimes()
+        } else {
+            interest
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        Filter::<S>::max_level_hint(&self.env_filter)
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        Filter::<S>::on_new_span(&self.env_filter, attrs, id, ctx)
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        Filter::<S>::on_record(&self.env_filter, id, values, ctx)
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        Filter::<S>::on_enter(&self.env_filter, id, ctx)
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        Filter::<S>::on_exit(&self.env_filter, id, ctx)
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        Filter::<S>::on_close(&self.env_filter, id, ctx)
+    }
+}
+
+/// Limits the number of events logged per second for the configured targets.
+#[derive(Debug, Default)]
+pub(crate) struct Sampling {
+    /// Sorted from the most specific target to the least specific one.
+    rules: Vec<SamplingRule>,
+}
+
+#[derive(Debug)]
+struct SamplingRule {
+    target: String,
+    max_events_per_second: u64,
+    /// Start of the current one second window and the number of events logged in it.
+    window: Mutex<(Instant, u64)>,
+}
+
+impl Sampling {
+    /// Creates the sampling from a map of targets to the maximum number of events per second.
+    /// A target also applies to its sub-targets, e.g. `network` applies to `network::routing`,
+    /// unless they have a rule of their own.
+    pub(crate) fn new(max_events_per_second: &BTreeMap<String, u64>) -> Self {
+        let now = Instant::now();
+        let mut rules: Vec<SamplingRule> = max_events_per_second
+            .iter()
+            .map(|(target, &max_events_per_second)| SamplingRule {
+                target: target.clone(),
+                max_events_per_second,
+                window: Mutex::new((now, 0)),
+            })
+            .collect();
+        rules.sort_by(|a, b| b.target.len().cmp(&a.target.len()));
+        Self { rules }
+    }
+
+    fn rule(&self, target: &str) -> Option<&SamplingRule> {
+        self.rules.iter().find(|rule| {
+            target == rule.target
+                || target.strip_prefix(&rule.target).map_or(false, |rest| rest.starts_with("::"))
+        })
+    }
+
+    /// Returns whether an event should be logged, spans are never sampled.
+    fn sample(&self, meta: &Metadata<'_>) -> bool {
+        if !meta.is_event() {
+            return true;
+        }
+        match self.rule(meta.target()) {
+            Some(rule) => rule.sample(Instant::now()),
+            None => true,
+        }
+    }
+}
+
+impl SamplingRule {
+    fn sample(&self, now: Instant) -> bool {
+        let mut window = self.window.lock().unwrap();
+        let (start, count) = &mut *window;
+        if now.saturating_duration_since(*start) >= Duration::from_secs(1) {
+            *start = now;
+            *count = 0;
+        }
+        if *count < self.max_events_per_second {
+            *count = count.saturating_add(1);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tracing::Event;
+    use tracing_subscriber::layer::{Layer, SubscriberExt};
+    use tracing_subscriber::reload;
+
+    /// Records the targets of the events that pass the filter.
+    #[derive(Clone, Default)]
+    struct Targets(Arc<Mutex<Vec<&'static str>>>);
+
+    impl<S: Subscriber> Layer<S> for Targets {
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            self.0.lock().unwrap().push(event.metadata().target());
+        }
+    }
+
+    impl Targets {
+        fn count(&self, target: &str) -> usize {
+            self.0.lock().unwrap().iter().filter(|t| **t == target).count()
+        }
+    }
+
+    #[test]
+    fn test_sampling_rules() {
+        let sampling = Sampling::new(&BTreeMap::from([
+            ("network".to_string(), 10),
+            ("network::routing".to_string(), 1),
+        ]));
+        assert_eq!(sampling.rule("network").unwrap().max_events_per_second, 10);
+        assert_eq!(sampling.rule("network::routing::edge").unwrap().max_events_per_second, 1);
+        assert!(sampling.rule("chunks").is_none());
+        assert!(sampling.rule("networking").is_none());
+        assert_eq!(sampling.rule("network::routing_table").unwrap().max_events_per_second, 10);
+    }
+
+    #[test]
+    fn test_sampling_window() {
+        let sampling = Sampling::new(&BTreeMap::from([("chunks".to_string(), 2)]));
+        let rule = sampling.rule("chunks").unwrap();
+        let start = rule.window.lock().unwrap().0;
+        assert!(rule.sample(start));
+        assert!(rule.sample(start.checked_add(Duration::from_millis(500)).unwrap()));
+        assert!(!rule.sample(start.checked_add(Duration::from_millis(999)).unwrap()));
+        assert!(rule.sample(start.checked_add(Duration::from_secs(1)).unwrap()));
+    }
+
+    #[test]
+    fn test_reload_sampling() {
+        let (filter, handle) = reload::Layer::new(LogFilter::new(EnvFilter::new("info")));
+        crate::LOG_LAYER_RELOAD_HANDLE
+            .set(handle)
+            .unwrap_or_else(|_| panic!("Failed to set Log Layer Filter"));
+        let targets = Targets::default();
+        let subscriber = tracing_subscriber::registry().with(targets.clone().with_filter(filter));
+
+        let reload = |log_sampling: Option<BTreeMap<String, u64>>| {
+            // Only the log layer is set up, not the opentelemetry one.
+            let errors =
+                crate::reload(Some("info"), None, None, None, log_sampling.as_ref()).unwrap_err();
+            assert!(
+                matches!(errors[..], [crate::ReloadError::NoOpentelemetryReloadHandle]),
+                "{:?}",
+                errors
+            );
+        };
+        // Returns the number of logged events of `network` and its sub-targets, and of `networking`.
+        let log_events = || {
+            targets.0.lock().unwrap().clear();
+            for _ in 0..5 {
+                tracing::info!(target: "network", "event");
+                tracing::info!(target: "network::routing", "event");
+                tracing::info!(target: "networking", "event");
+            }
+            (
+                targets.count("network") + targets.count("network::routing"),
+                targets.count("networking"),
+            )
+        };
+
+        tracing::subscriber::with_default(subscriber, || {
+            assert_eq!(log_events(), (10, 5));
+            reload(Some(BTreeMap::from([("network".to_string(), 3)])));
+            assert_eq!(log_events(), (3, 5));
+            reload(None);
+            assert_eq!(log_events(), (10, 5));
+        });
+    }
+}
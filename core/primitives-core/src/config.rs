@@ -33,6 +33,10 @@ impl AccountIdValidityRulesVersion {
 pub struct ViewConfig {
     /// If specified, defines max burnt gas per view method.
     pub max_gas_burnt: Gas,
+    /// If true, the contract can read the signer and the predecessor of the
+    /// call, which were set by the caller of the view method.
+    #[serde(default)]
+    pub expose_caller: bool,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -120,6 +120,10 @@ pub enum ProtocolFeature {
     RejectBlocksWithOutdatedProtocolVersions,
     #[cfg(feature = "protocol_feature_simple_nightshade_v2")]
     SimpleNightshadeV2,
+    /// Peers may compress the messages they send to each other. It only affects the network
+    /// protocol: peers compress their messages if both of them support it.
+    #[cfg(feature = "protocol_feature_peer_message_compression")]
+    PeerMessageCompression,
 }
 
 impl ProtocolFeature {
@@ -173,6 +177,8 @@ impl ProtocolFeature {
             ProtocolFeature::RejectBlocksWithOutdatedProtocolVersions => 132,
             #[cfg(feature = "protocol_feature_simple_nightshade_v2")]
             ProtocolFeature::SimpleNightshadeV2 => 135,
+            #[cfg(feature = "protocol_feature_peer_message_compression")]
+            ProtocolFeature::PeerMessageCompression => 137,
         }
     }
 }
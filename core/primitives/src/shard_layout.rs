@@ -212,6 +212,21 @@ impl ShardLayout {
         Ok(parent_shard_id)
     }
 
+    /// Return the shard uid of the parent shard of a given shard in the previous shard layout,
+    /// or None if `self` has no parent shard layout or `shard_id` is invalid
+    pub fn get_parent_shard_uid(&self, shard_id: ShardId) -> Option<ShardUId> {
+        match self {
+            Self::V0(_) => None,
+            Self::V1(v1) => {
+                let parent_shard_id = *v1.to_parent_shard_map.as_ref()?.get(shard_id as usize)?;
+                Some(ShardUId {
+                    version: v1.version.checked_sub(1)?,
+                    shard_id: parent_shard_id as u32,
+                })
+            }
+        }
+    }
+
     #[inline]
     pub fn version(&self) -> ShardVersion {
         match self {
@@ -517,7 +532,13 @@ mod tests {
         for x in 0..3 {
             assert_eq!(shard_layout.get_parent_shard_id(x).unwrap(), 0);
             assert_eq!(shard_layout.get_parent_shard_id(x + 3).unwrap(), 1);
+            assert_eq!(
+                shard_layout.get_parent_shard_uid(x + 3),
+                Some(ShardUId { version: 0, shard_id: 1 })
+            );
         }
+        assert_eq!(shard_layout.get_parent_shard_uid(6), None);
+        assert_eq!(ShardLayout::v0(1, 0).get_parent_shard_uid(0), None);
 
         assert_eq!(account_id_to_shard_id(&"aurora".parse().unwrap(), &shard_layout), 1);
         assert_eq!(account_id_to_shard_id(&"foo.aurora".parse().unwrap(), &shard_layout), 3);
@@ -31,10 +31,10 @@ use crate::transaction::{
     SignedTransaction, StakeAction, TransferAction,
 };
 use crate::types::{
-    AccountId, AccountWithPublicKey, Balance, BlockHeight, EpochHeight, EpochId, FunctionArgs, Gas,
-    Nonce, NumBlocks, ShardId, StateChangeCause, StateChangeKind, StateChangeValue,
-    StateChangeWithCause, StateChangesRequest, StateRoot, StorageUsage, StoreKey, StoreValue,
-    ValidatorKickoutReason,
+    AccountId, AccountWithPublicKey, Balance, BlockHeight, Compute, EpochHeight, EpochId,
+    FunctionArgs, Gas, Nonce, NumBlocks, ShardId, StateChangeCause, StateChangeKind,
+    StateChangeValue, StateChangeWithCause, StateChangesRequest, StateRoot, StorageUsage, StoreKey,
+    StoreValue, ValidatorKickoutReason,
 };
 use crate::version::{ProtocolVersion, Version};
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -239,6 +239,13 @@ pub struct ViewStateResult {
 pub struct CallResult {
     pub result: Vec<u8>,
     pub logs: Vec<String>,
+    #[serde(default)]
+    pub burnt_gas: Gas,
+    #[serde(default)]
+    pub compute_usage: Compute,
+    /// Burnt gas by the parameters of the runtime config.
+    #[serde(default)]
+    pub gas_profile: Vec<CostGasUsed>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -273,6 +280,10 @@ pub struct KnownPeerStateView {
     pub first_seen: i64,
     pub last_seen: i64,
     pub last_attempt: Option<(i64, String)>,
+    /// Reputation score of the peer, higher is better.
+    pub reputation: i64,
+    /// Average time it took the peer to respond to our requests.
+    pub avg_response_latency_ms: Option<i64>,
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -323,6 +334,18 @@ pub enum QueryRequest {
         method_name: String,
         #[serde(rename = "args_base64")]
         args: FunctionArgs,
+        /// Signer of the call. Setting the signer or the predecessor lets the
+        /// contract read them, otherwise the contract calls itself.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signer_id: Option<AccountId>,
+        /// Defaults to the signer.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        predecessor_id: Option<AccountId>,
+        #[serde(default, with = "dec_format", skip_serializing_if = "Option::is_none")]
+        attached_deposit: Option<Balance>,
+        /// Limits the gas of the call below the node's `max_gas_burnt_view`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gas: Option<Gas>,
     },
 }
 
@@ -370,6 +393,11 @@ pub struct PeerInfoView {
     pub peer_id: PublicKey,
     pub received_bytes_per_sec: u64,
     pub sent_bytes_per_sec: u64,
+    /// Traffic of the compressed messages, after decompression and before compression.
+    pub received_uncompressed_bytes_per_sec: u64,
+    pub received_compressed_bytes_per_sec: u64,
+    pub sent_uncompressed_bytes_per_sec: u64,
+    pub sent_compressed_bytes_per_sec: u64,
     pub last_time_peer_requested_millis: u64,
     pub last_time_received_message_millis: u64,
     pub connection_established_time_millis: u64,
@@ -490,6 +518,23 @@ pub struct NetworkRoutesView {
 pub struct ShardSyncDownloadView {
     pub downloads: Vec<DownloadStatusView>,
     pub status: String,
+    /// Peers which the state parts are downloaded from.
+    pub peers: Vec<StateSyncPeerView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct StateSyncPeerView {
+    /// Peer id or account id of the peer.
+    pub target: String,
+    pub parts_in_flight: u64,
+    /// Limit of parts requested from the peer at the same time.
+    pub max_parts_in_flight: u64,
+    pub parts_received: u64,
+    pub bytes_received: u64,
+    /// Number of timed out requests and invalid parts.
+    pub failures: u64,
+    pub avg_latency_ms: Option<u64>,
+    pub throughput_bytes_per_sec: u64,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
@@ -2267,6 +2312,76 @@ impl From<StateChangeWithCause> for StateChangeWithCauseView {
 
 pub type StateChangesView = Vec<StateChangeWithCauseView>;
 
+/// Result of executing a transaction and the receipts it produces on top of the
+/// state after some block, without changing the state.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TransactionSimulationView {
+    /// Outcomes of the transaction and the executed receipts. They have no
+    /// proofs and refer to the block the simulation started from.
+    #[serde(flatten)]
+    pub final_outcome: FinalExecutionOutcomeView,
+    /// Gas burnt by the transaction and all the executed receipts.
+    pub gas_burnt: Gas,
+    /// Tokens burnt by the transaction and all the executed receipts.
+    #[serde(with = "dec_format")]
+    pub tokens_burnt: Balance,
+    /// Changes the transaction and the receipts would make to the state.
+    pub state_changes: StateChangesView,
+    /// Number of simulated blocks in which receipts were executed.
+    pub num_blocks: u64,
+    /// Receipts which weren't executed within the simulated blocks.
+    pub pending_receipts: Vec<CryptoHash>,
+}
+
+impl TransactionSimulationView {
+    pub fn new(
+        transaction: SignedTransactionView,
+        transaction_outcome: ExecutionOutcomeWithIdView,
+        receipts_outcome: Vec<ExecutionOutcomeWithIdView>,
+        state_changes: StateChangesView,
+        num_blocks: u64,
+        pending_receipts: Vec<CryptoHash>,
+    ) -> Self {
+        let mut status = FinalExecutionStatus::Started;
+        let mut outcome = &transaction_outcome.outcome;
+        loop {
+            match &outcome.status {
+                ExecutionStatusView::Unknown => break,
+                ExecutionStatusView::Failure(e) => {
+                    status = FinalExecutionStatus::Failure(e.clone());
+                    break;
+                }
+                ExecutionStatusView::SuccessValue(v) => {
+                    status = FinalExecutionStatus::SuccessValue(v.clone());
+                    break;
+                }
+                ExecutionStatusView::SuccessReceiptId(id) => {
+                    match receipts_outcome.iter().find(|receipt| &receipt.id == id) {
+                        Some(receipt) => outcome = &receipt.outcome,
+                        None => break,
+                    }
+                }
+            }
+        }
+        let outcomes = std::iter::once(&transaction_outcome).chain(&receipts_outcome);
+        let gas_burnt = outcomes.clone().map(|outcome| outcome.outcome.gas_burnt).sum();
+        let tokens_burnt = outcomes.map(|outcome| outcome.outcome.tokens_burnt).sum();
+        Self {
+            final_outcome: FinalExecutionOutcomeView {
+                status,
+                transaction,
+                transaction_outcome,
+                receipts_outcome,
+            },
+            gas_burnt,
+            tokens_burnt,
+            state_changes,
+            num_blocks,
+            pending_receipts,
+        }
+    }
+}
+
 /// Maintenance windows view are a vector of maintenance window.
 pub type MaintenanceWindowsView = Vec<Range<BlockHeight>>;
 
@@ -2402,6 +2517,65 @@ pub struct SplitStorageInfoView {
     pub hot_db_kind: Option<String>,
 }
 
+/// A transaction waiting in the transaction pool of the node.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxPoolTransactionView {
+    pub hash: CryptoHash,
+    pub nonce: Nonce,
+    pub receiver_id: AccountId,
+    /// Size of the transaction in bytes as accounted by the pool.
+    pub size: u64,
+    /// Number of pooled transactions of the same signer and public key with a smaller nonce.
+    pub num_ahead: usize,
+}
+
+/// Pooled transactions of the same signer and public key, sorted by nonce.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxPoolGroupView {
+    pub signer_id: AccountId,
+    pub public_key: PublicKey,
+    pub transactions: Vec<TxPoolTransactionView>,
+}
+
+/// Reason why a transaction left the transaction pool or never entered it.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum TxPoolDropReason {
+    /// The transaction was included into a chunk of a processed block.
+    IncludedInChunk,
+    /// A transaction with the same signer, public key and nonce took its place.
+    Replaced { by: CryptoHash },
+    /// The transaction was evicted to make space for another transaction.
+    Evicted { by: CryptoHash },
+    /// The pool was full, so the transaction was not accepted.
+    NoSpaceLeft,
+    /// The transaction turned out to be invalid or expired while preparing a chunk.
+    Invalid,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxPoolDroppedTransactionView {
+    pub hash: CryptoHash,
+    pub reason: TxPoolDropReason,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxPoolShardView {
+    pub shard_id: ShardId,
+    /// Number of transactions in the pool, regardless of the filters.
+    pub num_transactions: usize,
+    /// Total size of transactions in the pool in bytes, regardless of the filters.
+    pub total_size: u64,
+    pub groups: Vec<TxPoolGroupView>,
+    /// The most recently dropped transactions, oldest first.
+    pub recently_dropped: Vec<TxPoolDroppedTransactionView>,
+}
+
+/// Contains the transactions in the transaction pool of the node.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxPoolView {
+    pub shards: Vec<TxPoolShardView>,
+}
+
 impl From<RuntimeConfig> for RuntimeConfigView {
     fn from(config: RuntimeConfig) -> Self {
         Self {
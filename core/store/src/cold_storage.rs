@@ -84,6 +84,67 @@ pub fn update_cold_db(
     Ok(true)
 }
 
+/// Entry which `update_cold_db` copies to cold storage, but which cold storage doesn't have.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColdDbGap {
+    /// The key is missing in cold storage.
+    Missing { col: DBCol, key: StoreKey },
+    /// The key has a different value in cold storage than in hot storage.
+    DifferentValue { col: DBCol, key: StoreKey },
+}
+
+impl ColdDbGap {
+    pub fn col(&self) -> DBCol {
+        match self {
+            ColdDbGap::Missing { col, .. } | ColdDbGap::DifferentValue { col, .. } => *col,
+        }
+    }
+}
+
+/// Checks that provided cold store contains every entry that `update_cold_db` copies for the
+/// block at `height`, with the same value as in hot store.
+/// Returns the entries which are missing or different in cold store, or None if the block at
+/// `height` can't be checked (there is no block at that height, or it is not in `hot_store`
+/// anymore).
+pub fn verify_cold_db(
+    cold_store: &Store,
+    hot_store: &Store,
+    shard_layout: &ShardLayout,
+    height: &BlockHeight,
+) -> io::Result<Option<Vec<ColdDbGap>>> {
+    let _span = tracing::debug_span!(target: "store", "verify cold db", height = height);
+
+    let mut store_with_cache = StoreWithCache { store: hot_store, cache: StoreCache::new() };
+
+    let Some(block_hash) = store_with_cache.get(DBCol::BlockHeight, &height.to_le_bytes())? else {
+        return Ok(None);
+    };
+    if store_with_cache.get(DBCol::Block, &block_hash)?.is_none() {
+        return Ok(None);
+    }
+
+    let key_type_to_keys = get_keys_from_store(&mut store_with_cache, shard_layout, height)?;
+    let mut gaps = vec![];
+    for col in DBCol::iter() {
+        if !col.is_cold() {
+            continue;
+        }
+        for key in combine_keys(&key_type_to_keys, &col.key_type()) {
+            let Some(hot_value) = store_with_cache.get(col, &key)? else {
+                continue;
+            };
+            match cold_store.get(col, &key)? {
+                None => gaps.push(ColdDbGap::Missing { col, key }),
+                Some(cold_value) if cold_value.as_slice() != hot_value.as_slice() => {
+                    gaps.push(ColdDbGap::DifferentValue { col, key })
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    Ok(Some(gaps))
+}
+
 // Correctly set the key and value on DBTransaction, taking reference counting
 // into account. For non-rc columns it just sets the value. For rc columns it
 // appends rc = 1 to the value and sets it.
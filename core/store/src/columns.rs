@@ -277,6 +277,14 @@ pub enum DBCol {
     /// - *Rows*: arbitrary string, see `crate::db::FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS_KEY` for example
     /// - *Column type*: arbitrary bytes
     Misc,
+    /// Values of the account, contract code, access key and contract data trie keys at all
+    /// heights since the state history index was started. Only written if `save_state_history`
+    /// is enabled. Garbage collected along with the blocks the values were changed in, except on
+    /// archival nodes.
+    /// See `crate::state_history` for the key encoding.
+    /// - *Rows*: escaped trie key || (u64::MAX - block height)
+    /// - *Column type*: Option<Vec<u8>>
+    StateHistory,
     /// Column to store data for Epoch Sync.
     /// Does not contain data for genesis epoch.
     /// - *Rows*: `epoch_id`
@@ -476,7 +484,9 @@ impl DBCol {
             | DBCol::FlatState
             | DBCol::FlatStateChanges
             | DBCol::FlatStateDeltaMetadata
-            | DBCol::FlatStorageStatus  => false,
+            | DBCol::FlatStorageStatus
+            // StateHistory is only maintained in the hot storage.
+            | DBCol::StateHistory => false,
             #[cfg(feature = "new_epoch_sync")]
             DBCol::EpochSyncInfo => false
         }
@@ -487,6 +497,19 @@ impl DBCol {
         matches!(*self, DBCol::DbVersion | DBCol::BlockMisc) || self.is_cold()
     }
 
+    /// Whether the data of this column can be kept only for the recent blocks,
+    /// even by archival nodes. See `crate::retention`.
+    ///
+    /// The keys of all such columns start with the block hash, and the data is
+    /// not needed to process new blocks or to serve the blocks, transactions
+    /// and receipts.
+    pub const fn supports_retention(&self) -> bool {
+        matches!(
+            *self,
+            DBCol::StateChanges | DBCol::StateChangesForSplitStates | DBCol::TrieChanges
+        )
+    }
+
     /// Vector of DBKeyType s concatenation of which results in key for the column.
     pub fn key_type(&self) -> &'static [DBKeyType] {
         match self {
@@ -547,6 +570,7 @@ impl DBCol {
             DBCol::FlatStateChanges => &[DBKeyType::ShardUId, DBKeyType::BlockHash],
             DBCol::FlatStateDeltaMetadata => &[DBKeyType::ShardUId, DBKeyType::BlockHash],
             DBCol::FlatStorageStatus => &[DBKeyType::ShardUId],
+            DBCol::StateHistory => &[DBKeyType::TrieKey, DBKeyType::BlockHeight],
             #[cfg(feature = "new_epoch_sync")]
             DBCol::EpochSyncInfo => &[DBKeyType::EpochId],
         }
@@ -34,6 +34,9 @@ pub const GENESIS_STATE_ROOTS_KEY: &[u8; 19] = b"GENESIS_STATE_ROOTS";
 pub const COLD_HEAD_KEY: &[u8; 9] = b"COLD_HEAD";
 pub const STATE_SYNC_DUMP_KEY: &[u8; 15] = b"STATE_SYNC_DUMP";
 pub const STATE_SNAPSHOT_KEY: &[u8; 18] = b"STATE_SNAPSHOT_KEY";
+pub const STATE_HISTORY_KEY: &[u8; 13] = b"STATE_HISTORY";
+/// Prefix of the keys of the retention tails of the columns, see `crate::retention`.
+pub const RETENTION_TAIL_KEY: &[u8; 14] = b"RETENTION_TAIL";
 
 // `DBCol::Misc` keys
 pub const FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS_KEY: &[u8] =
@@ -12,7 +12,8 @@ use crate::DBCol;
 ///
 /// Lastly, since no data is ever deleted from cold storage, trying to decrease
 /// reference of a value count or delete data is ignored and if debug assertions
-/// are enabled will cause a panic.
+/// are enabled will cause a panic.  The only exception is deleting single keys
+/// of the columns which support retention, see `crate::retention`.
 pub struct ColdDB {
     cold: std::sync::Arc<dyn Database>,
 }
@@ -85,7 +86,8 @@ impl Database for ColdDB {
     /// Atomically applies operations in given transaction.
     ///
     /// If debug assertions are enabled, panics if there are any delete
-    /// operations or operations decreasing reference count of a value.  If
+    /// operations (apart from deleting keys of the columns which support
+    /// retention) or operations decreasing reference count of a value.  If
     /// debug assertions are not enabled, such operations are filtered out.
     fn write(&self, mut transaction: DBTransaction) -> std::io::Result<()> {
         let mut idx = 0;
@@ -146,6 +148,7 @@ fn adjust_op(op: &mut DBOp) -> bool {
                 }
             };
         }
+        DBOp::Delete { col, .. } if col.supports_retention() => true,
         DBOp::Delete { col, key } => {
             log_assert_fail!("Unexpected delete from {col} in cold store: {key:?}");
             false
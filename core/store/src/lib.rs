@@ -15,7 +15,8 @@ pub use columns::DBCol;
 pub use db::{
     CHUNK_TAIL_KEY, COLD_HEAD_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY, GENESIS_JSON_HASH_KEY,
     GENESIS_STATE_ROOTS_KEY, HEADER_HEAD_KEY, HEAD_KEY, LARGEST_TARGET_HEIGHT_KEY,
-    LATEST_KNOWN_KEY, STATE_SNAPSHOT_KEY, STATE_SYNC_DUMP_KEY, TAIL_KEY,
+    LATEST_KNOWN_KEY, RETENTION_TAIL_KEY, STATE_HISTORY_KEY, STATE_SNAPSHOT_KEY,
+    STATE_SYNC_DUMP_KEY, TAIL_KEY,
 };
 use near_crypto::PublicKey;
 use near_fmt::{AbbrBytes, StorageKey};
@@ -49,7 +50,9 @@ pub mod metadata;
 pub mod metrics;
 pub mod migrations;
 mod opener;
+pub mod retention;
 mod rocksdb_metrics;
+pub mod state_history;
 mod sync_utils;
 pub mod test_utils;
 pub mod trie;
@@ -231,6 +231,14 @@ pub static COLD_MIGRATION_READS: Lazy<IntCounterVec> = Lazy::new(|| {
 pub static COLD_HEAD_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
     try_create_int_gauge("near_cold_head_height", "Height of the head of cold storage").unwrap()
 });
+pub static RETENTION_TAIL_HEIGHT: Lazy<IntGaugeVec> = Lazy::new(|| {
+    try_create_int_gauge_vec(
+        "near_retention_tail_height",
+        "Lowest height for which the data of the column with a limited retention is kept",
+        &["col"],
+    )
+    .unwrap()
+});
 pub static COLD_COPY_DURATION: Lazy<Histogram> = Lazy::new(|| {
     try_create_histogram(
         "near_cold_copy_duration",
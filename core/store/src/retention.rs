@@ -0,0 +1,213 @@
+//! Retention of the data of some columns for the recent blocks only.
+//!
+//! Archival nodes keep all the data by default. The data of the columns which
+//! support retention (see `DBCol::supports_retention`) can instead be kept
+//! only for a configured number of recent epochs. The data of the older blocks
+//! is deleted gradually: by the garbage collection of archival nodes with a
+//! single database, and by the cold store loop of nodes with split storage.
+//!
+//! For every such column the lowest height whose data is still kept is stored
+//! as the retention tail of the column in `DBCol::BlockMisc` of the hot
+//! database, so that the queries for the deleted data can be told apart from
+//! the queries for the data which never existed.
+use crate::db::RETENTION_TAIL_KEY;
+use crate::{metrics, DBCol, Store, StoreUpdate};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, BlockHeightDelta, EpochId};
+use near_primitives::utils::index_to_bytes;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use strum::IntoEnumIterator;
+
+/// Returns the column with the given name if it supports retention.
+pub fn parse_column(name: &str) -> Option<DBCol> {
+    DBCol::iter().find(|col| col.supports_retention() && <&str>::from(col) == name)
+}
+
+/// Returns the names of the columns which support retention.
+pub fn column_names() -> Vec<&'static str> {
+    DBCol::iter().filter(DBCol::supports_retention).map(<&str>::from).collect()
+}
+
+/// Number of epochs for which the data of the columns is kept, parsed from
+/// `GCConfig::column_num_epochs_to_keep`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RetentionConfig {
+    columns: Vec<(DBCol, u64)>,
+}
+
+impl RetentionConfig {
+    /// Returns an error if some of the columns don't support retention.
+    pub fn new(column_num_epochs_to_keep: &BTreeMap<String, u64>) -> Result<Self, String> {
+        let columns = column_num_epochs_to_keep
+            .iter()
+            .map(|(column, num_epochs)| match parse_column(column) {
+                Some(col) => Ok((col, *num_epochs)),
+                None => Err(format!("{column} doesn't support retention")),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// The columns with a limited retention and the number of epochs to keep.
+    pub fn columns(&self) -> &[(DBCol, u64)] {
+        &self.columns
+    }
+}
+
+fn tail_key(col: DBCol) -> Vec<u8> {
+    [RETENTION_TAIL_KEY.as_slice(), b":", <&str>::from(col).as_bytes()].concat()
+}
+
+/// Returns the lowest height whose data of the column is kept, or None if no
+/// data of the column was deleted yet.
+pub fn get_tail(store: &Store, col: DBCol) -> io::Result<Option<BlockHeight>> {
+    store.get_ser(DBCol::BlockMisc, &tail_key(col))
+}
+
+/// Call `update_tail_metric` once the update is committed.
+pub fn set_tail(store_update: &mut StoreUpdate, col: DBCol, height: BlockHeight) -> io::Result<()> {
+    store_update.set_ser(DBCol::BlockMisc, &tail_key(col), &height)
+}
+
+pub fn update_tail_metric(col: DBCol, height: BlockHeight) {
+    metrics::RETENTION_TAIL_HEIGHT.with_label_values(&[<&str>::from(col)]).set(height as i64);
+}
+
+/// Returns the height below which the data is deleted to keep `num_epochs`
+/// epochs of `epoch_length` blocks before `head_height`.
+pub fn get_stop_height(
+    head_height: BlockHeight,
+    epoch_length: BlockHeightDelta,
+    num_epochs: u64,
+) -> BlockHeight {
+    head_height.saturating_sub(epoch_length.saturating_mul(num_epochs))
+}
+
+/// Deletes the data of the column for the blocks at heights from `start_height`
+/// up to `stop_height` (exclusive), processing at most `limit` heights which
+/// have blocks. The blocks are looked up in `DBCol::BlockPerHeight` of `store`,
+/// so that the data of the forks is deleted as well.
+///
+/// Returns the height from which the data is still kept, that is the new
+/// retention tail of the column.
+pub fn prune(
+    store: &Store,
+    store_update: &mut StoreUpdate,
+    col: DBCol,
+    start_height: BlockHeight,
+    stop_height: BlockHeight,
+    limit: u64,
+) -> io::Result<BlockHeight> {
+    assert!(col.supports_retention(), "{col} doesn't support retention");
+    let mut height = start_height;
+    let mut remaining = limit;
+    while height < stop_height && remaining > 0 {
+        let block_hashes = store.get_ser::<HashMap<EpochId, HashSet<CryptoHash>>>(
+            DBCol::BlockPerHeight,
+            &index_to_bytes(height),
+        )?;
+        height += 1;
+        let Some(block_hashes) = block_hashes else {
+            continue;
+        };
+        remaining -= 1;
+        for block_hash in block_hashes.values().flatten() {
+            for item in store.iter_prefix(col, block_hash.as_ref()) {
+                let (key, _) = item?;
+                store_update.delete(col, &key);
+            }
+        }
+    }
+    Ok(height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_store;
+
+    fn block_hash(height: BlockHeight) -> CryptoHash {
+        CryptoHash::hash_bytes(&height.to_le_bytes())
+    }
+
+    /// Creates blocks at the given heights, each with a state change.
+    fn create_blocks(store: &Store, heights: &[BlockHeight]) {
+        let mut store_update = store.store_update();
+        for &height in heights {
+            let block_hash = block_hash(height);
+            let block_hashes = HashMap::from([(EpochId::default(), HashSet::from([block_hash]))]);
+            store_update
+                .set_ser(DBCol::BlockPerHeight, &index_to_bytes(height), &block_hashes)
+                .unwrap();
+            let key = [block_hash.as_ref(), b"key"].concat();
+            store_update.set(DBCol::StateChanges, &key, b"value");
+        }
+        store_update.commit().unwrap();
+    }
+
+    fn has_state_changes(store: &Store, height: BlockHeight) -> bool {
+        store.iter_prefix(DBCol::StateChanges, block_hash(height).as_ref()).next().is_some()
+    }
+
+    #[test]
+    fn test_parse_column() {
+        assert_eq!(parse_column("StateChanges"), Some(DBCol::StateChanges));
+        assert_eq!(parse_column("TrieChanges"), Some(DBCol::TrieChanges));
+        assert_eq!(parse_column("Block"), None);
+        assert_eq!(parse_column("NoSuchColumn"), None);
+        assert!(column_names().contains(&"StateChangesForSplitStates"));
+    }
+
+    #[test]
+    fn test_retention_config() {
+        let config = RetentionConfig::new(&BTreeMap::from([
+            ("StateChanges".to_string(), 10),
+            ("TrieChanges".to_string(), 20),
+        ]))
+        .unwrap();
+        assert_eq!(config.columns(), &[(DBCol::StateChanges, 10), (DBCol::TrieChanges, 20)]);
+        assert!(RetentionConfig::new(&BTreeMap::new()).unwrap().is_empty());
+        assert_eq!(
+            RetentionConfig::new(&BTreeMap::from([("Block".to_string(), 10)])),
+            Err("Block doesn't support retention".to_string())
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let store = create_test_store();
+        create_blocks(&store, &[1, 2, 4, 5, 6, 7]);
+        assert_eq!(get_tail(&store, DBCol::StateChanges).unwrap(), None);
+
+        // Only two heights with blocks are processed, height 3 is skipped.
+        let mut store_update = store.store_update();
+        let tail = prune(&store, &mut store_update, DBCol::StateChanges, 1, 6, 2).unwrap();
+        assert_eq!(tail, 3);
+        set_tail(&mut store_update, DBCol::StateChanges, tail).unwrap();
+        store_update.commit().unwrap();
+        assert_eq!(get_tail(&store, DBCol::StateChanges).unwrap(), Some(3));
+        assert!(!has_state_changes(&store, 1));
+        assert!(!has_state_changes(&store, 2));
+        assert!(has_state_changes(&store, 4));
+
+        // Pruning stops at the stop height.
+        let mut store_update = store.store_update();
+        let tail = prune(&store, &mut store_update, DBCol::StateChanges, 3, 6, 10).unwrap();
+        store_update.commit().unwrap();
+        assert_eq!(tail, 6);
+        assert!(!has_state_changes(&store, 5));
+        assert!(has_state_changes(&store, 6));
+        assert!(has_state_changes(&store, 7));
+    }
+
+    #[test]
+    fn test_get_stop_height() {
+        assert_eq!(get_stop_height(100, 10, 3), 70);
+        assert_eq!(get_stop_height(20, 10, 3), 0);
+    }
+}
@@ -0,0 +1,369 @@
+//! Index of the history of account state, which answers queries about past blocks without a
+//! trie traversal.
+//!
+//! The index stores all values the account, contract code, access key and contract data trie
+//! keys had since the block it was seeded at. Entries are built from the `DBCol::StateChanges`
+//! of the final blocks of the canonical chain, so the index covers a contiguous range of heights
+//! described by `StateHistoryStatus`.
+//!
+//! Row key of an entry is the trie key, escaped so that no key is a prefix of another one,
+//! followed by the inverted block height. Thus all entries of a trie key are adjacent, the most
+//! recent first, and a prefix of a trie key is still a prefix of the row keys.
+use crate::db::STATE_HISTORY_KEY;
+use crate::{DBCol, KeyForStateChanges, Store, StoreUpdate};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::hash::CryptoHash;
+use near_primitives::trie_key::col;
+use near_primitives::types::{BlockHeight, RawStateChangesWithTrieKey};
+use near_primitives::utils::index_to_bytes;
+use std::io;
+
+/// Range of heights covered by the state history index.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateHistoryStatus {
+    /// Height of the block the index was seeded at with the full state.
+    pub start_height: BlockHeight,
+    /// Height of the last block whose state changes are in the index.
+    pub head_height: BlockHeight,
+}
+
+impl StateHistoryStatus {
+    pub fn contains(&self, height: BlockHeight) -> bool {
+        self.start_height <= height && height <= self.head_height
+    }
+}
+
+pub fn get_status(store: &Store) -> io::Result<Option<StateHistoryStatus>> {
+    store.get_ser(DBCol::BlockMisc, STATE_HISTORY_KEY)
+}
+
+pub fn set_status(store_update: &mut StoreUpdate, status: &StateHistoryStatus) -> io::Result<()> {
+    store_update.set_ser(DBCol::BlockMisc, STATE_HISTORY_KEY, status)
+}
+
+/// Whether values of the trie key are kept in the index. Only the keys which identify the
+/// account, and thus are unique across shards, are.
+pub fn is_indexed_key(trie_key: &[u8]) -> bool {
+    matches!(
+        trie_key.first(),
+        Some(&(col::ACCOUNT | col::CONTRACT_CODE | col::ACCESS_KEY | col::CONTRACT_DATA))
+    )
+}
+
+/// Records the value of the trie key at the height, `None` meaning that the key was deleted.
+pub fn set_value(
+    store_update: &mut StoreUpdate,
+    trie_key: &[u8],
+    height: BlockHeight,
+    value: Option<&[u8]>,
+) -> io::Result<()> {
+    store_update.set_ser(DBCol::StateHistory, &encode_row_key(trie_key, height), &value)
+}
+
+/// Returns the value the trie key had at the height.
+///
+/// The caller must check that the height is covered by the index.
+pub fn get_value(
+    store: &Store,
+    trie_key: &[u8],
+    height: BlockHeight,
+) -> io::Result<Option<Vec<u8>>> {
+    let lower_bound = encode_row_key(trie_key, height);
+    let upper_bound = row_keys_end(trie_key);
+    match store.iter_range(DBCol::StateHistory, Some(&lower_bound), Some(&upper_bound)).next() {
+        Some(item) => {
+            let (_, value) = item?;
+            Ok(Option::<Vec<u8>>::try_from_slice(&value)?)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns the trie keys starting with the prefix and their values at the height, ordered by
+/// the key.
+///
+/// The caller must check that the height is covered by the index.
+pub fn get_values_by_prefix(
+    store: &Store,
+    trie_key_prefix: &[u8],
+    height: BlockHeight,
+) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let prefix = escape(trie_key_prefix);
+    let mut values = vec![];
+    let mut last_trie_key: Option<Vec<u8>> = None;
+    for item in store.iter_prefix(DBCol::StateHistory, &prefix) {
+        let (row_key, value) = item?;
+        let (trie_key, row_height) = decode_row_key(&row_key)?;
+        if row_height > height || last_trie_key.as_ref() == Some(&trie_key) {
+            continue;
+        }
+        // Entries of a key go from the most recent one, so the first entry not above the height
+        // holds the value at the height.
+        if let Some(value) = Option::<Vec<u8>>::try_from_slice(&value)? {
+            values.push((trie_key.clone(), value));
+        }
+        last_trie_key = Some(trie_key);
+    }
+    Ok(values)
+}
+
+/// Adds the changes of the state of the block to the index.
+pub fn record_block(
+    store: &Store,
+    store_update: &mut StoreUpdate,
+    block_hash: &CryptoHash,
+    height: BlockHeight,
+) -> io::Result<()> {
+    let key = KeyForStateChanges::for_block(block_hash);
+    for changes in key.find_iter(store) {
+        let RawStateChangesWithTrieKey { trie_key, changes } = changes?;
+        let trie_key = trie_key.to_vec();
+        if !is_indexed_key(&trie_key) {
+            continue;
+        }
+        if let Some(change) = changes.last() {
+            set_value(store_update, &trie_key, height, change.data.as_deref())?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the blocks of the canonical chain above the head of the index, up to and including
+/// `height`, to the index. `height` must not be above the final head. Returns the updated
+/// status, which is also written to `store_update`.
+///
+/// State changes are read from `store`, which doesn't have to be the store `store_update`
+/// belongs to.
+pub fn record_blocks_up_to(
+    store: &Store,
+    store_update: &mut StoreUpdate,
+    status: StateHistoryStatus,
+    height: BlockHeight,
+) -> io::Result<StateHistoryStatus> {
+    if height <= status.head_height {
+        return Ok(status);
+    }
+    for block_height in status.head_height + 1..=height {
+        // Heights without a block are skipped.
+        let Some(block_hash) =
+            store.get_ser::<CryptoHash>(DBCol::BlockHeight, &index_to_bytes(block_height))?
+        else {
+            continue;
+        };
+        record_block(store, store_update, &block_hash, block_height)?;
+    }
+    let status = StateHistoryStatus { head_height: height, ..status };
+    set_status(store_update, &status)?;
+    Ok(status)
+}
+
+/// Removes the entries which are not needed once the blocks below the canonical block at the
+/// height are garbage collected: the older values of the trie keys changed in the block, and the
+/// deletions made in the block. The index starts at the height afterwards.
+///
+/// Must be called before the state changes of the block are garbage collected.
+pub fn prune_block(
+    store: &Store,
+    store_update: &mut StoreUpdate,
+    block_hash: &CryptoHash,
+    height: BlockHeight,
+) -> io::Result<()> {
+    let Some(status) = get_status(store)? else {
+        return Ok(());
+    };
+    // Keys changed in the blocks which are not in the index still need their older values.
+    if height <= status.start_height || !status.contains(height) {
+        return Ok(());
+    }
+    let key = KeyForStateChanges::for_block(block_hash);
+    for changes in key.find_iter(store) {
+        let RawStateChangesWithTrieKey { trie_key, changes } = changes?;
+        let trie_key = trie_key.to_vec();
+        if !is_indexed_key(&trie_key) {
+            continue;
+        }
+        let Some(change) = changes.last() else {
+            continue;
+        };
+        // A missing entry means that the key doesn't exist, so deletions aren't kept either.
+        let from = match change.data {
+            Some(_) => encode_row_key(&trie_key, height - 1),
+            None => encode_row_key(&trie_key, height),
+        };
+        store_update.delete_range(DBCol::StateHistory, &from, &row_keys_end(&trie_key));
+    }
+    set_status(store_update, &StateHistoryStatus { start_height: height, ..status })
+}
+
+/// Escapes the zero bytes of the trie key, so that the `[0, 0]` terminator of the row keys can't
+/// occur in it. Ordering of the escaped keys is the same as of the original ones.
+fn escape(trie_key: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(trie_key.len() + 2);
+    for &byte in trie_key {
+        escaped.push(byte);
+        if byte == 0 {
+            escaped.push(0xff);
+        }
+    }
+    escaped
+}
+
+fn encode_row_key(trie_key: &[u8], height: BlockHeight) -> Vec<u8> {
+    let mut row_key = escape(trie_key);
+    row_key.extend_from_slice(&[0, 0]);
+    row_key.extend_from_slice(&(BlockHeight::MAX - height).to_be_bytes());
+    row_key
+}
+
+/// Returns the key following all row keys of the trie key.
+fn row_keys_end(trie_key: &[u8]) -> Vec<u8> {
+    let mut end = escape(trie_key);
+    end.extend_from_slice(&[0, 1]);
+    end
+}
+
+fn decode_row_key(row_key: &[u8]) -> io::Result<(Vec<u8>, BlockHeight)> {
+    let invalid_key = || {
+        io::Error::new(io::ErrorKind::InvalidData, format!("invalid StateHistory key {row_key:?}"))
+    };
+    let mut trie_key = Vec::with_capacity(row_key.len());
+    let mut rest = row_key;
+    loop {
+        match rest {
+            [0, 0, height @ ..] => {
+                let height = <[u8; 8]>::try_from(height).map_err(|_| invalid_key())?;
+                return Ok((trie_key, BlockHeight::MAX - BlockHeight::from_be_bytes(height)));
+            }
+            [0, 0xff, tail @ ..] => {
+                trie_key.push(0);
+                rest = tail;
+            }
+            [byte, tail @ ..] if *byte != 0 => {
+                trie_key.push(*byte);
+                rest = tail;
+            }
+            _ => return Err(invalid_key()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_store;
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::{RawStateChange, StateChangeCause};
+
+    #[test]
+    fn test_row_key_encoding() {
+        for trie_key in [&b""[..], &[0], &[0, 0, 1], &[9, 0xff, 0, 0xff], b"alice"] {
+            for height in [0, 1, 1000, u64::MAX] {
+                let row_key = encode_row_key(trie_key, height);
+                assert_eq!(decode_row_key(&row_key).unwrap(), (trie_key.to_vec(), height));
+            }
+        }
+        // Keys of the same trie key go from the most recent one, and a trie key which is a prefix
+        // of another one is ordered before it.
+        assert!(encode_row_key(b"a", 10) < encode_row_key(b"a", 5));
+        assert!(encode_row_key(b"a", 5) < encode_row_key(b"a\0", 10));
+        assert!(encode_row_key(b"a\0", 5) < encode_row_key(b"a\x01", 10));
+    }
+
+    #[test]
+    fn test_state_history() {
+        let store = create_test_store();
+        let mut store_update = store.store_update();
+        set_value(&mut store_update, b"\x09alice,a", 10, Some(b"1")).unwrap();
+        set_value(&mut store_update, b"\x09alice,a", 20, Some(b"2")).unwrap();
+        set_value(&mut store_update, b"\x09alice,a", 30, None).unwrap();
+        set_value(&mut store_update, b"\x09alice,a\0", 15, Some(b"3")).unwrap();
+        set_value(&mut store_update, b"\x09alice,b", 10, Some(b"4")).unwrap();
+        set_value(&mut store_update, b"\x09alice2,a", 10, Some(b"5")).unwrap();
+        store_update.commit().unwrap();
+
+        assert_eq!(get_value(&store, b"\x09alice,a", 5).unwrap(), None);
+        assert_eq!(get_value(&store, b"\x09alice,a", 10).unwrap(), Some(b"1".to_vec()));
+        assert_eq!(get_value(&store, b"\x09alice,a", 29).unwrap(), Some(b"2".to_vec()));
+        assert_eq!(get_value(&store, b"\x09alice,a", 30).unwrap(), None);
+        assert_eq!(get_value(&store, b"\x09alice,a\0", 40).unwrap(), Some(b"3".to_vec()));
+
+        let values = |height| get_values_by_prefix(&store, b"\x09alice,", height).unwrap();
+        assert_eq!(values(5), vec![]);
+        assert_eq!(
+            values(15),
+            vec![
+                (b"\x09alice,a".to_vec(), b"1".to_vec()),
+                (b"\x09alice,a\0".to_vec(), b"3".to_vec()),
+                (b"\x09alice,b".to_vec(), b"4".to_vec()),
+            ]
+        );
+        assert_eq!(
+            values(30),
+            vec![
+                (b"\x09alice,a\0".to_vec(), b"3".to_vec()),
+                (b"\x09alice,b".to_vec(), b"4".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prune_block() {
+        let store = create_test_store();
+        let block_hash = |height| CryptoHash::hash_bytes(&[height]);
+        let account_key =
+            |account_id: &str| TrieKey::Account { account_id: account_id.parse().unwrap() };
+        let mut store_update = store.store_update();
+        set_status(&mut store_update, &StateHistoryStatus { start_height: 10, head_height: 30 })
+            .unwrap();
+        for height in [10, 20, 30] {
+            set_value(&mut store_update, &account_key("alice").to_vec(), height, Some(b"a"))
+                .unwrap();
+        }
+        set_value(&mut store_update, &account_key("bob").to_vec(), 10, Some(b"b")).unwrap();
+        set_value(&mut store_update, &account_key("bob").to_vec(), 20, None).unwrap();
+        set_value(&mut store_update, &account_key("carol").to_vec(), 10, Some(b"c")).unwrap();
+        // Block 20 updated alice and deleted bob.
+        for (trie_key, data) in
+            [(account_key("alice"), Some(b"a".to_vec())), (account_key("bob"), None)]
+        {
+            let changes = RawStateChangesWithTrieKey {
+                trie_key: trie_key.clone(),
+                changes: vec![RawStateChange { cause: StateChangeCause::InitialState, data }],
+            };
+            let key = KeyForStateChanges::from_trie_key(&block_hash(20), &trie_key);
+            store_update.set_ser(DBCol::StateChanges, key.as_ref(), &changes).unwrap();
+        }
+        store_update.commit().unwrap();
+
+        // Blocks which are not after the start of the index are not pruned.
+        let mut store_update = store.store_update();
+        prune_block(&store, &mut store_update, &block_hash(10), 10).unwrap();
+        store_update.commit().unwrap();
+        assert_eq!(get_status(&store).unwrap().unwrap().start_height, 10);
+
+        let mut store_update = store.store_update();
+        prune_block(&store, &mut store_update, &block_hash(20), 20).unwrap();
+        store_update.commit().unwrap();
+        assert_eq!(
+            get_status(&store).unwrap(),
+            Some(StateHistoryStatus { start_height: 20, head_height: 30 })
+        );
+        let rows: Vec<_> = store
+            .iter(DBCol::StateHistory)
+            .map(|item| decode_row_key(&item.unwrap().0).unwrap())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (account_key("alice").to_vec(), 30),
+                (account_key("alice").to_vec(), 20),
+                (account_key("carol").to_vec(), 10),
+            ]
+        );
+        assert_eq!(get_value(&store, &account_key("bob").to_vec(), 25).unwrap(), None);
+        assert_eq!(
+            get_value(&store, &account_key("carol").to_vec(), 25).unwrap(),
+            Some(b"c".to_vec())
+        );
+    }
+}
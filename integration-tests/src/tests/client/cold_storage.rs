@@ -14,7 +14,7 @@ use near_primitives::transaction::{
 use near_primitives_core::types::AccountId;
 use near_store::cold_storage::{
     copy_all_data_to_cold, test_cold_genesis_update, test_get_store_initial_writes,
-    test_get_store_reads, update_cold_db, update_cold_head,
+    test_get_store_reads, update_cold_db, update_cold_head, verify_cold_db, ColdDbGap,
 };
 use near_store::metadata::DbKind;
 use near_store::metadata::DB_VERSION;
@@ -378,6 +378,66 @@ fn test_cold_db_copy_with_height_skips() {
     }
 }
 
+/// Producing 2 epochs of blocks and copying every height to cold storage except one.
+/// Checks that `verify_cold_db` reports gaps only for the height which wasn't copied,
+/// and that copying that height again fixes them.
+#[test]
+fn test_verify_cold_db() {
+    init_test_logger();
+
+    let epoch_length = 5;
+    let max_height = epoch_length * 2;
+    let not_copied_height = 6;
+
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+
+    genesis.config.epoch_length = epoch_length;
+    let mut chain_genesis = ChainGenesis::test();
+    chain_genesis.epoch_length = epoch_length;
+    let mut env = TestEnv::builder(chain_genesis)
+        .real_epoch_managers(&genesis.config)
+        .nightshade_runtimes(&genesis)
+        .build();
+
+    let (storage, ..) = create_test_node_storage_with_cold(DB_VERSION, DbKind::Hot);
+    let hot_store = env.clients[0].runtime_adapter.store();
+    let cold_store = storage.get_cold_store().unwrap();
+
+    test_cold_genesis_update(&*storage.cold_db().unwrap(), &hot_store).unwrap();
+
+    for h in 1..max_height {
+        env.produce_block(0, h);
+    }
+    // Copy the blocks once their successors are known, like the cold store loop does for the
+    // final blocks, so that `NextBlockHashes` is copied too.
+    let shard_layout = env.clients[0]
+        .epoch_manager
+        .get_shard_layout(env.clients[0].chain.genesis().epoch_id())
+        .unwrap();
+    for h in 1..max_height {
+        if h != not_copied_height {
+            update_cold_db(&*storage.cold_db().unwrap(), &hot_store, &shard_layout, &h).unwrap();
+        }
+    }
+
+    for h in 1..max_height {
+        let gaps = verify_cold_db(&cold_store, &hot_store, &shard_layout, &h).unwrap().unwrap();
+        if h == not_copied_height {
+            assert!(gaps.iter().any(|gap| gap.col() == DBCol::Block));
+            assert!(gaps.iter().all(|gap| matches!(gap, ColdDbGap::Missing { .. })));
+        } else {
+            assert_eq!(gaps, vec![], "unexpected gaps at height {}", h);
+        }
+    }
+    // There is no block at this height yet.
+    assert_eq!(verify_cold_db(&cold_store, &hot_store, &shard_layout, &max_height).unwrap(), None);
+
+    update_cold_db(&*storage.cold_db().unwrap(), &hot_store, &shard_layout, &not_copied_height)
+        .unwrap();
+    let gaps = verify_cold_db(&cold_store, &hot_store, &shard_layout, &not_copied_height).unwrap();
+    assert_eq!(gaps, Some(vec![]));
+}
+
 /// Producing 4 epochs of blocks with some transactions.
 /// Call copying full contents of cold columns to cold storage in batches of specified max_size.
 /// Checks COLD_STORE_MIGRATION_BATCH_WRITE_COUNT metric for some batch_sizes:
@@ -1035,6 +1035,10 @@ fn client_sync_headers() {
                     },
                     received_bytes_per_sec: 0,
                     sent_bytes_per_sec: 0,
+                    received_uncompressed_bytes_per_sec: 0,
+                    received_compressed_bytes_per_sec: 0,
+                    sent_uncompressed_bytes_per_sec: 0,
+                    sent_compressed_bytes_per_sec: 0,
                     last_time_peer_requested: near_async::time::Instant::now(),
                     last_time_received_message: near_async::time::Instant::now(),
                     connection_established_time: near_async::time::Instant::now(),
@@ -1050,6 +1054,7 @@ fn client_sync_headers() {
                     highest_block_hash: hash(&[5]),
                     tracked_shards: vec![],
                     archival: false,
+                    reputation: 0,
                 }],
                 sent_bytes_per_sec: 0,
                 received_bytes_per_sec: 0,
@@ -3615,7 +3620,7 @@ mod contract_precompilation_tests {
     use near_vm_runner::internal::VMKind;
     use near_vm_runner::logic::CompiledContractCache;
     use near_vm_runner::{get_contract_cache_key, ContractCode};
-    use node_runtime::state_viewer::TrieViewer;
+    use node_runtime::state_viewer::{TrieViewer, ViewCallContext};
 
     const EPOCH_LENGTH: u64 = 25;
 
@@ -3749,6 +3754,7 @@ mod contract_precompilation_tests {
                 &"test0".parse().unwrap(),
                 "log_something",
                 &[],
+                &ViewCallContext::default(),
                 &mut logs,
                 &MockEpochInfoProvider::default(),
             )
@@ -11,12 +11,13 @@ use near_chain::{ChainGenesis, ChainStoreAccess, Provenance};
 use near_chain_configs::Genesis;
 use near_client::test_utils::{run_catchup, TestEnv};
 use near_crypto::{InMemorySigner, KeyType, Signer};
+use near_epoch_manager::shard_tracker::TrackedConfig;
 use near_o11y::testonly::init_test_logger;
 use near_primitives::account::id::AccountId;
 use near_primitives::block::{Block, Tip};
 use near_primitives::hash::CryptoHash;
 use near_primitives::serialize::to_base64;
-use near_primitives::shard_layout::{account_id_to_shard_id, account_id_to_shard_uid};
+use near_primitives::shard_layout::{account_id_to_shard_id, account_id_to_shard_uid, ShardUId};
 use near_primitives::transaction::{
     Action, DeployContractAction, FunctionCallAction, SignedTransaction,
 };
@@ -176,6 +177,7 @@ impl TestShardUpgradeEnv {
         num_init_accounts: usize,
         gas_limit: Option<u64>,
         genesis_protocol_version: ProtocolVersion,
+        tracked_config: TrackedConfig,
     ) -> Self {
         let mut rng = thread_rng();
         let validators: Vec<AccountId> =
@@ -195,7 +197,7 @@ impl TestShardUpgradeEnv {
             .validator_seats(num_validators)
             .real_epoch_managers(&genesis.config)
             .nightshade_runtimes(&genesis)
-            .track_all_shards()
+            .tracked_config(tracked_config)
             .build();
         assert_eq!(env.validators.len(), num_validators);
         Self {
@@ -369,6 +371,30 @@ impl TestShardUpgradeEnv {
         assert_eq!(response_valid_count + response_routed_count, env.validators.len());
     }
 
+    /// Check that every client tracks all the shards of the latest block and has their state.
+    fn check_tracks_all_shards(&mut self) {
+        tracing::debug!(target: "test", "checking tracked shards");
+
+        let env = &mut self.env;
+        let head = env.clients[0].chain.head().unwrap();
+        let shard_layout = env.clients[0]
+            .epoch_manager
+            .get_shard_layout_from_prev_block(&head.prev_block_hash)
+            .unwrap();
+        for client in env.clients.iter() {
+            for shard_id in 0..shard_layout.num_shards() {
+                assert!(client.shard_tracker.care_about_shard(
+                    None,
+                    &head.prev_block_hash,
+                    shard_id,
+                    true
+                ));
+                let shard_uid = ShardUId::from_shard_id_and_layout(shard_id, &shard_layout);
+                client.chain.get_chunk_extra(&head.last_block_hash, &shard_uid).unwrap();
+            }
+        }
+    }
+
     /// check that all accounts in `accounts` exist in the current state
     fn check_accounts(&mut self, accounts: Vec<&AccountId>) {
         tracing::debug!(target: "test", "checking accounts");
@@ -806,7 +832,10 @@ fn generate_create_accounts_txs(
     .collect()
 }
 
-fn test_shard_layout_upgrade_simple_impl(resharding_type: ReshardingType) {
+fn test_shard_layout_upgrade_simple_impl(
+    resharding_type: ReshardingType,
+    tracked_config: TrackedConfig,
+) {
     init_test_logger();
     tracing::info!(target: "test", "test_shard_layout_upgrade_simple_impl starting");
 
@@ -817,8 +846,15 @@ fn test_shard_layout_upgrade_simple_impl(resharding_type: ReshardingType) {
 
     // setup
     let epoch_length = 5;
-    let mut test_env =
-        TestShardUpgradeEnv::new(epoch_length, 2, 2, 100, None, genesis_protocol_version);
+    let mut test_env = TestShardUpgradeEnv::new(
+        epoch_length,
+        2,
+        2,
+        100,
+        None,
+        genesis_protocol_version,
+        tracked_config,
+    );
     test_env.set_init_tx(vec![]);
 
     let mut nonce = 100;
@@ -864,18 +900,40 @@ fn test_shard_layout_upgrade_simple_impl(resharding_type: ReshardingType) {
     test_env.check_accounts(accounts_to_check.iter().collect());
     test_env.check_split_states_artifacts();
     test_env.check_outgoing_receipts_reassigned(&resharding_type);
+    test_env.check_tracks_all_shards();
     tracing::info!(target: "test", "test_shard_layout_upgrade_simple_impl finished");
 }
 
 #[test]
 fn test_shard_layout_upgrade_simple_v1() {
-    test_shard_layout_upgrade_simple_impl(ReshardingType::V1);
+    test_shard_layout_upgrade_simple_impl(ReshardingType::V1, TrackedConfig::AllShards);
 }
 
 #[cfg(feature = "protocol_feature_simple_nightshade_v2")]
 #[test]
 fn test_shard_layout_upgrade_simple_v2() {
-    test_shard_layout_upgrade_simple_impl(ReshardingType::V2);
+    test_shard_layout_upgrade_simple_impl(ReshardingType::V2, TrackedConfig::AllShards);
+}
+
+/// Tracking the genesis shard with `tracked_shard_uids` catches up the states of all the shards
+/// split from it.
+#[test]
+fn test_shard_layout_upgrade_tracked_shard_uids_v1() {
+    test_shard_layout_upgrade_simple_impl(
+        ReshardingType::V1,
+        TrackedConfig::Shards(vec!["s0.v0".parse().unwrap()]),
+    );
+}
+
+/// The genesis layout is the version 1 layout split from `s0.v0`, so the shards of the version 2
+/// layout descend from `s0.v0` through two reshardings.
+#[cfg(feature = "protocol_feature_simple_nightshade_v2")]
+#[test]
+fn test_shard_layout_upgrade_tracked_shard_uids_v2() {
+    test_shard_layout_upgrade_simple_impl(
+        ReshardingType::V2,
+        TrackedConfig::Shards(vec!["s0.v0".parse().unwrap()]),
+    );
 }
 
 const GAS_1: u64 = 300_000_000_000_000;
@@ -956,6 +1014,7 @@ fn setup_test_env_with_cross_contract_txs(
         100,
         Some(100_000_000_000_000),
         genesis_protocol_version,
+        TrackedConfig::AllShards,
     );
     let mut rng = thread_rng();
 
@@ -11,7 +11,7 @@ use near_chunks::shards_manager_actor::start_shards_manager;
 use near_client::{start_client, start_view_client};
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_network::actix::ActixSystem;
-use near_network::blacklist;
+use near_network::authorization;
 use near_network::config;
 use near_network::tcp;
 use near_network::test_utils::{expected_routing_tables, peer_id_from_seed, GetInfo};
@@ -383,16 +383,22 @@ impl Runner {
 
         let boot_nodes =
             config.boot_nodes.iter().map(|ix| self.test_config[*ix].peer_info()).collect();
-        let blacklist: blacklist::Blacklist = config
+        let deny = config
             .blacklist
             .iter()
             .map(|x| match x {
-                Some(x) => blacklist::Entry::from_addr(self.test_config[*x].addr()),
-                None => blacklist::Entry::from_ip(Ipv6Addr::LOCALHOST.into()),
+                Some(x) => authorization::Rule::from_addr(self.test_config[*x].addr()),
+                None => authorization::Rule::from_ip(Ipv6Addr::LOCALHOST.into()),
+            })
+            .collect();
+        let whitelist = config
+            .whitelist
+            .iter()
+            .map(|ix| {
+                authorization::WhitelistNode::from_peer_info(&self.test_config[*ix].peer_info())
+                    .unwrap()
             })
             .collect();
-        let whitelist =
-            config.whitelist.iter().map(|ix| self.test_config[*ix].peer_info()).collect();
 
         let mut network_config =
             config::NetworkConfig::from_seed(&config.account_id, config.node_addr);
@@ -400,8 +406,8 @@ impl Runner {
         network_config.max_num_peers = config.max_num_peers;
         network_config.ttl_account_id_router = time::Duration::seconds(5);
         network_config.routed_message_ttl = config.routed_message_ttl;
-        network_config.peer_store.blacklist = blacklist;
-        network_config.whitelist_nodes = whitelist;
+        network_config.peer_store.authorization.deny = deny;
+        network_config.peer_store.authorization.whitelist = whitelist;
         network_config.outbound_disabled = config.outbound_disabled;
         network_config.peer_store.boot_nodes = boot_nodes;
         network_config.archive = config.archive;
@@ -122,11 +122,12 @@ fn test_view_call() {
         &"test.contract".parse().unwrap(),
         "run_test",
         &[],
+        &ViewCallContext::default(),
         &mut logs,
         &MockEpochInfoProvider::default(),
     );
 
-    assert_eq!(result.unwrap(), (10i32).to_le_bytes());
+    assert_eq!(result.unwrap().result, (10i32).to_le_bytes());
 }
 
 #[test]
@@ -150,6 +151,7 @@ fn test_view_call_try_changing_storage() {
         &"test.contract".parse().unwrap(),
         "run_test_with_storage_change",
         &[],
+        &ViewCallContext::default(),
         &mut logs,
         &MockEpochInfoProvider::default(),
     );
@@ -182,10 +184,51 @@ fn test_view_call_with_args() {
         &"test.contract".parse().unwrap(),
         "sum_with_input",
         &args,
+        &ViewCallContext::default(),
         &mut logs,
         &MockEpochInfoProvider::default(),
     );
-    assert_eq!(view_call_result.unwrap(), 3u64.to_le_bytes().to_vec());
+    assert_eq!(view_call_result.unwrap().result, 3u64.to_le_bytes().to_vec());
+}
+
+#[test]
+fn test_view_call_with_caller_context() {
+    let view_state = || ViewApplyState {
+        block_height: 1,
+        prev_block_hash: CryptoHash::default(),
+        block_hash: CryptoHash::default(),
+        epoch_id: EpochId::default(),
+        epoch_height: 0,
+        block_timestamp: 1,
+        current_protocol_version: PROTOCOL_VERSION,
+        cache: None,
+    };
+    let call = |context: &ViewCallContext| {
+        let (viewer, root) = get_test_trie_viewer();
+        viewer.call_function(
+            root,
+            view_state(),
+            &"test.contract".parse().unwrap(),
+            "ext_predecessor_account_id",
+            &[],
+            context,
+            &mut vec![],
+            &MockEpochInfoProvider::default(),
+        )
+    };
+
+    let err = call(&ViewCallContext::default()).unwrap_err();
+    assert!(err.to_string().contains("ProhibitedInView"), "{}", err);
+
+    let context =
+        ViewCallContext { predecessor_id: Some(alice_account()), ..ViewCallContext::default() };
+    let result = call(&context).unwrap();
+    assert_eq!(result.result, alice_account().as_bytes());
+    assert!(result.burnt_gas > 0);
+
+    let context = ViewCallContext { gas: Some(1), ..context };
+    let err = call(&context).unwrap_err();
+    assert!(err.to_string().contains("Exceeded"), "{}", err);
 }
 
 fn assert_view_state(
@@ -404,6 +447,7 @@ fn test_log_when_panic() {
             &"test.contract".parse().unwrap(),
             "panic_after_logging",
             &[],
+            &ViewCallContext::default(),
             &mut logs,
             &MockEpochInfoProvider::default(),
         )
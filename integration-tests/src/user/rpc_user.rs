@@ -106,6 +106,10 @@ impl User for RpcUser {
             account_id: account_id.clone(),
             method_name: method_name.to_string(),
             args: args.to_vec().into(),
+            signer_id: None,
+            predecessor_id: None,
+            attached_deposit: None,
+            gas: None,
         };
         match self.query(query)?.kind {
             near_jsonrpc_primitives::types::query::QueryResponseKind::CallResult(call_result) => {
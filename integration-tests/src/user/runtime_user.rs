@@ -20,7 +20,7 @@ use near_primitives::views::{
 };
 use near_store::{ShardTries, TrieUpdate};
 use nearcore::config::MIN_GAS_PRICE;
-use node_runtime::state_viewer::TrieViewer;
+use node_runtime::state_viewer::{TrieViewer, ViewCallContext};
 use node_runtime::{ApplyState, Runtime};
 
 use crate::user::{User, POISONED_LOCK_ERR};
@@ -261,7 +261,7 @@ impl User for RuntimeUser {
         let apply_state = self.apply_state();
         let client = self.client.read().expect(POISONED_LOCK_ERR);
         let state_update = client.get_state_update();
-        let mut result = CallResult::default();
+        let mut logs = vec![];
         let view_state = ViewApplyState {
             block_height: apply_state.block_height,
             prev_block_hash: apply_state.prev_block_hash,
@@ -272,7 +272,7 @@ impl User for RuntimeUser {
             current_protocol_version: PROTOCOL_VERSION,
             cache: apply_state.cache,
         };
-        result.result = self
+        let result = self
             .trie_viewer
             .call_function(
                 state_update,
@@ -280,11 +280,18 @@ impl User for RuntimeUser {
                 account_id,
                 method_name,
                 args,
-                &mut result.logs,
+                &ViewCallContext::default(),
+                &mut logs,
                 &self.epoch_info_provider,
             )
             .map_err(|err| err.to_string())?;
-        Ok(result)
+        Ok(CallResult {
+            result: result.result,
+            logs,
+            burnt_gas: result.burnt_gas,
+            compute_usage: result.compute_usage,
+            gas_profile: result.gas_profile,
+        })
     }
 
     fn add_transaction(&self, transaction: SignedTransaction) -> Result<(), ServerError> {
@@ -2,11 +2,15 @@ use std::sync::{atomic::AtomicBool, Arc};
 
 use near_chain::types::Tip;
 use near_epoch_manager::{EpochManagerAdapter, EpochManagerHandle};
-use near_primitives::{hash::CryptoHash, types::BlockHeight};
+use near_primitives::{
+    hash::CryptoHash,
+    types::{BlockHeight, BlockHeightDelta, NumBlocks},
+};
 use near_store::cold_storage::{copy_all_data_to_cold, CopyAllDataToColdStatus};
 use near_store::{
     cold_storage::{update_cold_db, update_cold_head},
     db::ColdDB,
+    retention::{self, RetentionConfig},
     DBCol, NodeStorage, Store, FINAL_HEAD_KEY, HEAD_KEY, TAIL_KEY,
 };
 
@@ -127,6 +131,54 @@ fn cold_store_copy(
     }
 }
 
+/// Deletes from cold storage the data of the columns which are kept only for the
+/// recent epochs, as configured by `column_num_epochs_to_keep`. At most
+/// `gc_blocks_limit` heights are processed for every column.
+/// The retention tails are stored in hot storage, where the queries read them.
+/// Returns whether there is more data to delete.
+fn cold_store_prune(
+    hot_store: &Store,
+    cold_store: &Store,
+    retention_config: &RetentionConfig,
+    gc_blocks_limit: NumBlocks,
+    genesis_height: BlockHeight,
+    epoch_length: BlockHeightDelta,
+) -> anyhow::Result<bool> {
+    let Some(cold_head) = cold_store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)? else {
+        return Ok(false);
+    };
+    let mut has_more = false;
+    for &(col, num_epochs) in retention_config.columns() {
+        // Columns which aren't copied to cold storage are garbage collected from
+        // hot storage sooner anyway.
+        if !col.is_cold() {
+            continue;
+        }
+        let stop_height = retention::get_stop_height(cold_head.height, epoch_length, num_epochs);
+        let tail = retention::get_tail(hot_store, col)?.unwrap_or(genesis_height);
+        if tail >= stop_height {
+            continue;
+        }
+        let mut cold_store_update = cold_store.store_update();
+        let new_tail = retention::prune(
+            cold_store,
+            &mut cold_store_update,
+            col,
+            tail,
+            stop_height,
+            gc_blocks_limit,
+        )?;
+        cold_store_update.commit()?;
+        let mut hot_store_update = hot_store.store_update();
+        retention::set_tail(&mut hot_store_update, col, new_tail)?;
+        hot_store_update.commit()?;
+        retention::update_tail_metric(col, new_tail);
+        tracing::debug!(target: "cold_store", %col, new_tail, stop_height, "deleted old data from cold storage");
+        has_more |= new_tail < stop_height;
+    }
+    Ok(has_more)
+}
+
 fn cold_store_copy_result_to_string(result: &anyhow::Result<ColdStoreCopyResult>) -> &str {
     match result {
         Err(_) => "error",
@@ -252,6 +304,9 @@ fn cold_store_loop(
     cold_db: Arc<ColdDB>,
     genesis_height: BlockHeight,
     epoch_manager: &EpochManagerHandle,
+    retention_config: &RetentionConfig,
+    gc_blocks_limit: NumBlocks,
+    epoch_length: BlockHeightDelta,
 ) {
     tracing::info!(target : "cold_store", "Starting the cold store loop");
 
@@ -267,6 +322,29 @@ fn cold_store_loop(
             .with_label_values(&[cold_store_copy_result_to_string(&result)])
             .inc();
 
+        // Once the cold head is up to date with the final head, delete the old data
+        // of the columns with a limited retention. Keep deleting without sleeping
+        // until all of it is deleted.
+        if matches!(
+            result,
+            Ok(ColdStoreCopyResult::NoBlockCopied | ColdStoreCopyResult::LatestBlockCopied)
+        ) {
+            match cold_store_prune(
+                &hot_store,
+                &cold_store,
+                retention_config,
+                gc_blocks_limit,
+                genesis_height,
+                epoch_length,
+            ) {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(err) => {
+                    tracing::error!(target : "cold_store", error = format!("{err:#?}"), "cold_store_prune failed");
+                }
+            }
+        }
+
         let sleep_duration = split_storage_config.cold_store_loop_sleep_duration;
         match result {
             Err(err) => {
@@ -326,6 +404,10 @@ pub fn spawn_cold_store_loop(
     };
 
     let genesis_height = config.genesis.config.genesis_height;
+    let epoch_length = config.genesis.config.epoch_length;
+    let retention_config = RetentionConfig::new(&config.client_config.gc.column_num_epochs_to_keep)
+        .map_err(anyhow::Error::msg)?;
+    let gc_blocks_limit = config.client_config.gc.gc_blocks_limit;
     let keep_going = Arc::new(AtomicBool::new(true));
     let keep_going_clone = keep_going.clone();
 
@@ -349,8 +431,81 @@ pub fn spawn_cold_store_loop(
                 cold_db,
                 genesis_height,
                 epoch_manager.as_ref(),
+                &retention_config,
+                gc_blocks_limit,
+                epoch_length,
             )
         })?;
 
     Ok(Some(ColdStoreLoopHandle { join_handle, keep_going }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::types::EpochId;
+    use near_primitives::utils::index_to_bytes;
+    use near_store::metadata::{DbKind, DB_VERSION};
+    use near_store::test_utils::create_test_node_storage_with_cold;
+    use std::collections::{BTreeMap, HashMap, HashSet};
+
+    fn block_hash(height: BlockHeight) -> CryptoHash {
+        CryptoHash::hash_bytes(&height.to_le_bytes())
+    }
+
+    fn has_state_changes(store: &Store, height: BlockHeight) -> bool {
+        store.iter_prefix(DBCol::StateChanges, block_hash(height).as_ref()).next().is_some()
+    }
+
+    /// The state changes of the old blocks are deleted from cold storage, at most
+    /// `gc_blocks_limit` heights at a time, and the retention tail is stored in hot storage.
+    #[test]
+    fn test_cold_store_prune() {
+        let (storage, ..) = create_test_node_storage_with_cold(DB_VERSION, DbKind::Hot);
+        let hot_store = storage.get_hot_store();
+        let cold_store = storage.get_cold_store().unwrap();
+        // Keep one epoch of two blocks.
+        let retention_config =
+            RetentionConfig::new(&BTreeMap::from([("StateChanges".to_string(), 1)])).unwrap();
+        let prune = || cold_store_prune(&hot_store, &cold_store, &retention_config, 2, 0, 2);
+
+        // Nothing is deleted before the cold head is known.
+        assert!(!prune().unwrap());
+
+        let mut store_update = cold_store.store_update();
+        for height in 1..=6 {
+            let block_hashes =
+                HashMap::from([(EpochId::default(), HashSet::from([block_hash(height)]))]);
+            store_update
+                .set_ser(DBCol::BlockPerHeight, &index_to_bytes(height), &block_hashes)
+                .unwrap();
+            let key = [block_hash(height).as_ref(), b"key"].concat();
+            store_update.set(DBCol::StateChanges, &key, b"value");
+        }
+        let head = Tip {
+            height: 6,
+            last_block_hash: block_hash(6),
+            prev_block_hash: block_hash(5),
+            epoch_id: EpochId::default(),
+            next_epoch_id: EpochId::default(),
+        };
+        store_update.set_ser(DBCol::BlockMisc, HEAD_KEY, &head).unwrap();
+        store_update.commit().unwrap();
+
+        assert!(prune().unwrap());
+        assert_eq!(retention::get_tail(&hot_store, DBCol::StateChanges).unwrap(), Some(3));
+        assert!(!has_state_changes(&cold_store, 2));
+        assert!(has_state_changes(&cold_store, 3));
+
+        assert!(!prune().unwrap());
+        assert_eq!(retention::get_tail(&hot_store, DBCol::StateChanges).unwrap(), Some(4));
+        assert!(!has_state_changes(&cold_store, 3));
+        for height in 4..=6 {
+            assert!(has_state_changes(&cold_store, height));
+        }
+
+        // Everything older than the last epoch is already deleted.
+        assert!(!prune().unwrap());
+        assert_eq!(retention::get_tail(&hot_store, DBCol::StateChanges).unwrap(), Some(4));
+    }
+}
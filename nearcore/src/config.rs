@@ -3,7 +3,7 @@ use crate::dyn_config::LOG_CONFIG_FILENAME;
 use anyhow::{anyhow, bail, Context};
 use near_chain_configs::{
     get_initial_supply, ClientConfig, GCConfig, Genesis, GenesisConfig, GenesisValidationMode,
-    LogSummaryStyle, MutableConfigValue, StateSyncConfig,
+    LogSummaryStyle, MutableConfigValue, StateSyncConfig, TransactionPoolMode,
 };
 use near_config_utils::{ValidationError, ValidationErrors};
 use near_crypto::{InMemorySigner, KeyFile, KeyType, PublicKey, Signer};
@@ -16,7 +16,7 @@ use near_primitives::account::{AccessKey, Account};
 use near_primitives::hash::CryptoHash;
 #[cfg(test)]
 use near_primitives::shard_layout::account_id_to_shard_id;
-use near_primitives::shard_layout::ShardLayout;
+use near_primitives::shard_layout::{ShardLayout, ShardUId};
 use near_primitives::state_record::StateRecord;
 use near_primitives::static_clock::StaticClock;
 use near_primitives::test_utils::create_test_signer;
@@ -293,6 +293,10 @@ pub struct Config {
     pub consensus: Consensus,
     pub tracked_accounts: Vec<AccountId>,
     pub tracked_shards: Vec<ShardId>,
+    /// Tracks only the given shards, e.g. `["s0.v1", "s3.v1"]`.
+    /// Ignored if `tracked_shards` is not empty since that tracks all shards.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tracked_shard_uids: Vec<ShardUId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tracked_shard_schedule: Option<Vec<Vec<ShardId>>>,
     #[serde(skip_serializing_if = "is_false")]
@@ -305,6 +309,11 @@ pub struct Config {
     /// needs trie changes in order to do garbage collection on hot and populate cold State column.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub save_trie_changes: Option<bool>,
+    /// Whether to maintain the state history index, which answers `view_account`,
+    /// `view_access_key` and `view_state` queries about past blocks without a trie traversal.
+    /// The index has to be created by `neard database backfill-state-history` first.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub save_state_history: bool,
     pub log_summary_style: LogSummaryStyle,
     #[serde(default = "default_log_summary_period")]
     pub log_summary_period: Duration,
@@ -350,11 +359,19 @@ pub struct Config {
     /// chunks and underutilizing the capacity of the network.
     #[serde(default = "default_transaction_pool_size_limit")]
     pub transaction_pool_size_limit: Option<u64>,
+    /// How the transaction pool orders transactions of the same signer and access key, and
+    /// whether it evicts pooled transactions when `transaction_pool_size_limit` is reached.
+    #[serde(default, skip_serializing_if = "is_default_transaction_pool_mode")]
+    pub transaction_pool_mode: TransactionPoolMode,
 }
 
 fn is_false(value: &bool) -> bool {
     !*value
 }
+
+fn is_default_transaction_pool_mode(value: &TransactionPoolMode) -> bool {
+    *value == TransactionPoolMode::default()
+}
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -371,9 +388,11 @@ impl Default for Config {
             consensus: Consensus::default(),
             tracked_accounts: vec![],
             tracked_shards: vec![],
+            tracked_shard_uids: vec![],
             tracked_shard_schedule: None,
             archive: false,
             save_trie_changes: None,
+            save_state_history: false,
             log_summary_style: LogSummaryStyle::Colored,
             log_summary_period: default_log_summary_period(),
             gc: GCConfig::default(),
@@ -389,6 +408,7 @@ impl Default for Config {
             state_sync: None,
             state_sync_enabled: None,
             transaction_pool_size_limit: default_transaction_pool_size_limit(),
+            transaction_pool_mode: TransactionPoolMode::default(),
             enable_multiline_logging: None,
         }
     }
@@ -668,9 +688,11 @@ impl NearConfig {
                 doosmslug_step_period: config.consensus.doomslug_step_period,
                 tracked_accounts: config.tracked_accounts,
                 tracked_shards: config.tracked_shards,
+                tracked_shard_uids: config.tracked_shard_uids,
                 tracked_shard_schedule: config.tracked_shard_schedule.unwrap_or(vec![]),
                 archive: config.archive,
                 save_trie_changes: config.save_trie_changes.unwrap_or(!config.archive),
+                save_state_history: config.save_state_history,
                 log_summary_style: config.log_summary_style,
                 gc: config.gc,
                 view_client_threads: config.view_client_threads,
@@ -686,6 +708,7 @@ impl NearConfig {
                 state_sync: config.state_sync.unwrap_or_default(),
                 state_snapshot_every_n_blocks: None,
                 transaction_pool_size_limit: config.transaction_pool_size_limit,
+                transaction_pool_mode: config.transaction_pool_mode,
                 enable_multiline_logging: config.enable_multiline_logging.unwrap_or(true),
             },
             network_config: NetworkConfig::new(
@@ -1615,9 +1638,19 @@ fn test_config_from_file_skip_validation() {
         // values is probably not worth it but there may be some other defaults
         // we want to ensure that they happen.
         let want_gc = if has_gc {
-            GCConfig { gc_blocks_limit: 42, gc_fork_clean_step: 420, gc_num_epochs_to_keep: 24 }
+            GCConfig {
+                gc_blocks_limit: 42,
+                gc_fork_clean_step: 420,
+                gc_num_epochs_to_keep: 24,
+                ..GCConfig::default()
+            }
         } else {
-            GCConfig { gc_blocks_limit: 2, gc_fork_clean_step: 100, gc_num_epochs_to_keep: 5 }
+            GCConfig {
+                gc_blocks_limit: 2,
+                gc_fork_clean_step: 100,
+                gc_num_epochs_to_keep: 5,
+                ..GCConfig::default()
+            }
         };
         assert_eq!(want_gc, config.gc);
 
@@ -1,5 +1,6 @@
 use near_chain_configs::{ExternalStorageLocation, SyncConfig};
 use near_config_utils::{ValidationError, ValidationErrors};
+use near_primitives::shard_layout::ShardLayout;
 use std::collections::HashSet;
 use std::path::Path;
 
@@ -79,6 +80,71 @@ impl<'a> ConfigValidator<'a> {
             self.validation_errors.push_config_semantics_error(error_message);
         }
 
+        for (column, num_epochs) in &self.config.gc.column_num_epochs_to_keep {
+            if near_store::retention::parse_column(column).is_none() {
+                let error_message = format!("'config.gc.column_num_epochs_to_keep' contains {}, but only the following columns are supported: {}.", column, near_store::retention::column_names().join(", "));
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+            if *num_epochs < self.config.gc.gc_num_epochs_to_keep() {
+                let error_message = format!("'config.gc.column_num_epochs_to_keep.{}' is {}, but it can't be lower than gc_num_epochs_to_keep, which is {}.", column, num_epochs, self.config.gc.gc_num_epochs_to_keep());
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+        }
+        if !self.config.gc.column_num_epochs_to_keep.is_empty() && !self.config.archive {
+            let error_message = format!(
+                "'config.gc.column_num_epochs_to_keep' is only supported by archival nodes."
+            );
+            self.validation_errors.push_config_semantics_error(error_message);
+        }
+
+        if !self.config.tracked_shard_uids.is_empty() && !self.config.tracked_shards.is_empty() {
+            let error_message = format!("'config.tracked_shard_uids' is ignored because 'config.tracked_shards' is not empty and tracks all shards.");
+            self.validation_errors.push_config_semantics_error(error_message);
+        }
+        let unique_shard_uids: HashSet<_> = self.config.tracked_shard_uids.iter().collect();
+        if unique_shard_uids.len() != self.config.tracked_shard_uids.len() {
+            let error_message = format!("'config.tracked_shard_uids' contains duplicate values.");
+            self.validation_errors.push_config_semantics_error(error_message);
+        }
+        // The number of shards of the version 0 layout is set by the genesis, the later versions
+        // are the layouts of the protocol upgrades.
+        let shard_layouts = [
+            ShardLayout::get_simple_nightshade_layout(),
+            ShardLayout::get_simple_nightshade_layout_v2(),
+        ];
+        for shard_uid in &self.config.tracked_shard_uids {
+            if shard_uid.version == 0 {
+                continue;
+            }
+            match shard_layouts.iter().find(|layout| layout.version() == shard_uid.version) {
+                Some(layout) if shard_uid.shard_id() >= layout.num_shards() => {
+                    let error_message = format!("'config.tracked_shard_uids' contains {}, but the shard layout version {} only has {} shards.", shard_uid, shard_uid.version, layout.num_shards());
+                    self.validation_errors.push_config_semantics_error(error_message);
+                }
+                Some(_) => {}
+                None => {
+                    let error_message = format!("'config.tracked_shard_uids' contains {}, but there is no shard layout version {}.", shard_uid, shard_uid.version);
+                    self.validation_errors.push_config_semantics_error(error_message);
+                }
+            }
+        }
+
+        #[cfg(feature = "json_rpc")]
+        if let Some(rpc) = &self.config.rpc {
+            if rpc.limits_config.max_batch_size == 0 {
+                let error_message =
+                    "'config.rpc.limits_config.max_batch_size' needs to be greater than 0"
+                        .to_string();
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+            for (method, limit) in &rpc.limits_config.rate_limits {
+                if limit.burst == 0 || limit.qps.is_nan() || limit.qps <= 0.0 {
+                    let error_message = format!("'config.rpc.limits_config.rate_limits.{}' needs burst and qps greater than 0, but burst is {} and qps is {}.", method, limit.burst, limit.qps);
+                    self.validation_errors.push_config_semantics_error(error_message);
+                }
+            }
+        }
+
         if let Some(state_sync) = &self.config.state_sync {
             if let Some(dump_config) = &state_sync.dump {
                 if let Some(restart_dump_for_shards) = &dump_config.restart_dump_for_shards {
@@ -90,11 +156,15 @@ impl<'a> ConfigValidator<'a> {
                 }
 
                 match &dump_config.location {
-                    ExternalStorageLocation::S3 { bucket, region } => {
+                    ExternalStorageLocation::S3 { bucket, region, endpoint, .. } => {
                         if bucket.is_empty() || region.is_empty() {
                             let error_message = format!("'config.state_sync.dump.location.S3.bucket' and 'config.state_sync.dump.location.S3.region' need to be specified when 'config.state_sync.dump.location.S3' is present.");
                             self.validation_errors.push_config_semantics_error(error_message);
                         }
+                        if matches!(endpoint, Some(endpoint) if !is_http_url(endpoint)) {
+                            let error_message = format!("'config.state_sync.dump.location.S3.endpoint' needs to be an http:// or https:// URL.");
+                            self.validation_errors.push_config_semantics_error(error_message);
+                        }
                     }
                     ExternalStorageLocation::Filesystem { root_dir } => {
                         if root_dir.as_path() == Path::new("") {
@@ -108,6 +178,10 @@ impl<'a> ConfigValidator<'a> {
                             self.validation_errors.push_config_semantics_error(error_message);
                         }
                     }
+                    ExternalStorageLocation::Http { .. } => {
+                        let error_message = format!("'config.state_sync.dump.location.Http' is read-only and can't be used to dump state.");
+                        self.validation_errors.push_config_semantics_error(error_message);
+                    }
                 }
 
                 if let Some(credentials_file) = &dump_config.credentials_file {
@@ -121,11 +195,15 @@ impl<'a> ConfigValidator<'a> {
                 SyncConfig::Peers => {}
                 SyncConfig::ExternalStorage(config) => {
                     match &config.location {
-                        ExternalStorageLocation::S3 { bucket, region } => {
+                        ExternalStorageLocation::S3 { bucket, region, endpoint, .. } => {
                             if bucket.is_empty() || region.is_empty() {
                                 let error_message = format!("'config.state_sync.sync.ExternalStorage.location.S3.bucket' and 'config.state_sync.sync.ExternalStorage.location.S3.region' need to be specified when 'config.state_sync.sync.ExternalStorage.location.S3' is present.");
                                 self.validation_errors.push_config_semantics_error(error_message);
                             }
+                            if matches!(endpoint, Some(endpoint) if !is_http_url(endpoint)) {
+                                let error_message = format!("'config.state_sync.sync.ExternalStorage.location.S3.endpoint' needs to be an http:// or https:// URL.");
+                                self.validation_errors.push_config_semantics_error(error_message);
+                            }
                         }
                         ExternalStorageLocation::Filesystem { root_dir } => {
                             if root_dir.as_path() == Path::new("") {
@@ -139,6 +217,12 @@ impl<'a> ConfigValidator<'a> {
                                 self.validation_errors.push_config_semantics_error(error_message);
                             }
                         }
+                        ExternalStorageLocation::Http { base_url } => {
+                            if !is_http_url(base_url) {
+                                let error_message = format!("'config.state_sync.sync.ExternalStorage.location.Http.base_url' needs to be an http:// or https:// URL when 'config.state_sync.sync.ExternalStorage.location.Http' is present.");
+                                self.validation_errors.push_config_semantics_error(error_message);
+                            }
+                        }
                     }
                     if config.num_concurrent_requests == 0 {
                         let error_message = format!("'config.state_sync.sync.ExternalStorage.num_concurrent_requests' needs to be greater than 0");
@@ -159,6 +243,10 @@ impl<'a> ConfigValidator<'a> {
     }
 }
 
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -186,6 +274,85 @@ mod test {
         validate_config(&config).unwrap();
     }
 
+    #[test]
+    #[should_panic(
+        expected = "'config.gc.column_num_epochs_to_keep' contains Block, but only the following columns are supported"
+    )]
+    fn test_column_retention_unsupported_column() {
+        let mut config = Config::default();
+        config.archive = true;
+        config.gc.column_num_epochs_to_keep.insert("Block".to_string(), 100);
+        // set tracked_shards to be non-empty
+        config.tracked_shards.push(20);
+        validate_config(&config).unwrap();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "'config.tracked_shard_uids' contains s4.v1, but the shard layout version 1 only has 4 shards."
+    )]
+    fn test_tracked_shard_uids_unknown_shard() {
+        let mut config = Config::default();
+        config.tracked_shard_uids =
+            vec!["s0.v0".parse().unwrap(), "s3.v1".parse().unwrap(), "s4.v1".parse().unwrap()];
+        validate_config(&config).unwrap();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "'config.tracked_shard_uids' contains s0.v9, but there is no shard layout version 9."
+    )]
+    fn test_tracked_shard_uids_unknown_version() {
+        let mut config = Config::default();
+        config.tracked_shard_uids = vec!["s0.v9".parse().unwrap()];
+        validate_config(&config).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "json_rpc")]
+    #[should_panic(
+        expected = "'config.rpc.limits_config.rate_limits.query' needs burst and qps greater than 0"
+    )]
+    fn test_rpc_rate_limit_positive() {
+        let mut config = Config::default();
+        config
+            .rpc
+            .as_mut()
+            .unwrap()
+            .limits_config
+            .rate_limits
+            .insert("query".to_string(), near_jsonrpc::RpcRateLimit { burst: 10, qps: 0.0 });
+        // set tracked_shards to be non-empty
+        config.tracked_shards.push(20);
+        validate_config(&config).unwrap();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "'config.state_sync.dump.location.S3.endpoint' needs to be an http:// or https:// URL"
+    )]
+    fn test_s3_endpoint_url() {
+        let mut config = Config::default();
+        config.state_sync = Some(near_chain_configs::StateSyncConfig {
+            dump: Some(near_chain_configs::DumpConfig {
+                location: ExternalStorageLocation::S3 {
+                    bucket: "state-parts".to_string(),
+                    region: "us-east-1".to_string(),
+                    endpoint: Some("localhost:9000".to_string()),
+                    path_style: true,
+                    anonymous: None,
+                },
+                restart_dump_for_shards: None,
+                iteration_delay: None,
+                credentials_file: None,
+            }),
+            sync: SyncConfig::Peers,
+        });
+        // set tracked_shards to be non-empty
+        config.tracked_shards.push(20);
+        validate_config(&config).unwrap();
+    }
+
     #[test]
     #[should_panic(
         expected = "\\nconfig.json semantic issue: Configuration with archive = false and save_trie_changes = false is not supported because non-archival nodes must save trie changes in order to do do garbage collection.\\nconfig.json semantic issue: gc config values should all be greater than 0"
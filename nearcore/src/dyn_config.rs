@@ -1,6 +1,7 @@
 use crate::config::Config;
 use near_chain_configs::UpdateableClientConfig;
 use near_dyn_configs::{UpdateableConfigLoaderError, UpdateableConfigs};
+use near_network::config::UpdateableNetworkConfig;
 use near_o11y::log_config::LogConfig;
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
@@ -19,22 +20,29 @@ pub fn read_updateable_configs(
             None
         }
     };
-    let updateable_client_config =
+    let (updateable_client_config, updateable_network_config) =
         match Config::from_file(&home_dir.join(crate::config::CONFIG_FILENAME))
-            .map(get_updateable_client_config)
-        {
-            Ok(config) => Some(config),
+            .map_err(anyhow::Error::from)
+            .and_then(|config| {
+                let network_config = get_updateable_network_config(&config)?;
+                Ok((get_updateable_client_config(config), network_config))
+            }) {
+            Ok((client_config, network_config)) => (Some(client_config), Some(network_config)),
             Err(err) => {
                 errs.push(UpdateableConfigLoaderError::ConfigFileError {
                     file: PathBuf::from(crate::config::CONFIG_FILENAME),
-                    err: err.into(),
+                    err,
                 });
-                None
+                (None, None)
             }
         };
     if errs.is_empty() {
         crate::metrics::CONFIG_CORRECT.set(1);
-        Ok(UpdateableConfigs { log_config, client_config: updateable_client_config })
+        Ok(UpdateableConfigs {
+            log_config,
+            client_config: updateable_client_config,
+            network_config: updateable_network_config,
+        })
     } else {
         tracing::warn!(target: "neard", "Dynamically updateable configs are not valid. Please fix this ASAP otherwise the node will be unable to restart: {:?}", &errs);
         crate::metrics::CONFIG_CORRECT.set(0);
@@ -48,6 +56,11 @@ pub fn get_updateable_client_config(config: Config) -> UpdateableClientConfig {
     UpdateableClientConfig { expected_shutdown: config.expected_shutdown }
 }
 
+pub fn get_updateable_network_config(config: &Config) -> anyhow::Result<UpdateableNetworkConfig> {
+    // Keep this in-sync with `core/dyn-configs/README.md`.
+    UpdateableNetworkConfig::new(&config.network)
+}
+
 fn read_log_config(home_dir: &Path) -> Result<Option<LogConfig>, UpdateableConfigLoaderError> {
     read_json_config::<LogConfig>(&home_dir.join(LOG_CONFIG_FILENAME))
 }
@@ -26,17 +26,18 @@ use near_primitives::shard_layout::{
     account_id_to_shard_id, account_id_to_shard_uid, ShardLayout, ShardUId,
 };
 use near_primitives::state_part::PartId;
-use near_primitives::transaction::SignedTransaction;
+use near_primitives::transaction::{ExecutionOutcomeWithIdAndProof, SignedTransaction};
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::validator_stake::ValidatorStakeIter;
 use near_primitives::types::{
     AccountId, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, MerkleHash,
-    ShardId, StateChangeCause, StateChangesForSplitStates, StateRoot, StateRootNode,
+    ShardId, StateChangeCause, StateChanges, StateChangesExt, StateChangesForSplitStates,
+    StateRoot, StateRootNode,
 };
 use near_primitives::version::ProtocolVersion;
 use near_primitives::views::{
-    AccessKeyInfoView, CallResult, QueryRequest, QueryResponse, QueryResponseKind, ViewApplyState,
-    ViewStateResult,
+    AccessKeyInfoView, CallResult, ExecutionOutcomeWithIdView, QueryRequest, QueryResponse,
+    QueryResponseKind, TransactionSimulationView, ViewApplyState, ViewStateResult,
 };
 use near_store::flat::FlatStorageManager;
 use near_store::metadata::DbKind;
@@ -48,7 +49,7 @@ use near_vm_runner::logic::CompiledContractCache;
 use near_vm_runner::precompile_contract;
 use near_vm_runner::ContractCode;
 use node_runtime::adapter::ViewRuntimeAdapter;
-use node_runtime::state_viewer::TrieViewer;
+use node_runtime::state_viewer::{TrieViewer, ViewCallContext, ViewCallResult};
 use node_runtime::{
     validate_transaction, verify_and_charge_transaction, ApplyState, Runtime,
     ValidatorAccountsUpdate,
@@ -920,7 +921,15 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
-            QueryRequest::CallFunction { account_id, method_name, args } => {
+            QueryRequest::CallFunction {
+                account_id,
+                method_name,
+                args,
+                signer_id,
+                predecessor_id,
+                attached_deposit,
+                gas,
+            } => {
                 let mut logs = vec![];
                 let (epoch_height, current_protocol_version) = {
                     let epoch_manager = self.epoch_manager.read();
@@ -947,6 +956,12 @@ impl RuntimeAdapter for NightshadeRuntime {
                         account_id,
                         method_name,
                         args.as_ref(),
+                        &ViewCallContext {
+                            signer_id: signer_id.clone(),
+                            predecessor_id: predecessor_id.clone(),
+                            attached_deposit: attached_deposit.unwrap_or_default(),
+                            gas: *gas,
+                        },
                         &mut logs,
                         self.epoch_manager.as_ref(),
                         current_protocol_version,
@@ -954,8 +969,11 @@ impl RuntimeAdapter for NightshadeRuntime {
                     .map_err(|err| near_chain::near_chain_primitives::error::QueryError::from_call_function_error(err, block_height, *block_hash))?;
                 Ok(QueryResponse {
                     kind: QueryResponseKind::CallResult(CallResult {
-                        result: call_function_result,
+                        result: call_function_result.result,
                         logs,
+                        burnt_gas: call_function_result.burnt_gas,
+                        compute_usage: call_function_result.compute_usage,
+                        gas_profile: call_function_result.gas_profile,
                     }),
                     block_height,
                     block_hash: *block_hash,
@@ -1025,6 +1043,81 @@ impl RuntimeAdapter for NightshadeRuntime {
         }
     }
 
+    fn simulate_transaction(
+        &self,
+        state_roots: &[StateRoot],
+        block_height: BlockHeight,
+        block_timestamp: u64,
+        prev_block_hash: &CryptoHash,
+        block_hash: &CryptoHash,
+        epoch_id: &EpochId,
+        gas_price: Balance,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+        max_blocks: u64,
+    ) -> Result<Result<TransactionSimulationView, InvalidTxError>, Error> {
+        let (epoch_height, current_protocol_version) = {
+            let epoch_manager = self.epoch_manager.read();
+            let epoch_info = epoch_manager.get_epoch_info(epoch_id)?;
+            (epoch_info.epoch_height(), epoch_info.protocol_version())
+        };
+        let shard_layout = self.epoch_manager.get_shard_layout(epoch_id)?;
+        let state_updates = shard_layout
+            .get_shard_uids()
+            .into_iter()
+            .zip(state_roots)
+            .map(|(shard_uid, state_root)| self.tries.new_trie_update_view(shard_uid, *state_root))
+            .collect();
+        let view_state = ViewApplyState {
+            block_height,
+            prev_block_hash: *prev_block_hash,
+            block_hash: *block_hash,
+            epoch_id: epoch_id.clone(),
+            epoch_height,
+            block_timestamp,
+            current_protocol_version,
+            cache: Some(Box::new(StoreCompiledContractCache::new(&self.tries.get_store()))),
+        };
+        let result = match self.trie_viewer.simulate_transaction(
+            state_updates,
+            &shard_layout,
+            view_state,
+            gas_price,
+            transaction,
+            verify_signature,
+            max_blocks,
+            self.epoch_manager.as_ref(),
+        ) {
+            Ok(result) => result,
+            Err(RuntimeError::InvalidTxError(err)) => {
+                debug!(target: "runtime", "Tx {:?} simulation failed: {:?}", transaction, err);
+                return Ok(Err(err));
+            }
+            Err(RuntimeError::StorageError(err)) => return Err(Error::StorageError(err)),
+            Err(err) => return Err(Error::Other(err.to_string())),
+        };
+
+        // Simulated outcomes aren't included in any block, so there is nothing to prove.
+        let to_view = |outcome_with_id| -> ExecutionOutcomeWithIdView {
+            ExecutionOutcomeWithIdAndProof {
+                proof: vec![],
+                block_hash: *block_hash,
+                outcome_with_id,
+            }
+            .into()
+        };
+        let state_changes = StateChanges::from_changes(result.state_changes.into_iter().map(Ok))
+            .map_err(|err| Error::Other(err.to_string()))?;
+        Ok(Ok(TransactionSimulationView::new(
+            transaction.clone().into(),
+            to_view(result.transaction_outcome),
+            result.receipt_outcomes.into_iter().map(to_view).collect(),
+            state_changes.into_iter().map(Into::into).collect(),
+            result.num_blocks,
+            result.pending_receipts.iter().map(|receipt| receipt.receipt_id).collect(),
+        )))
+    }
+
     // Wrapper to get the metrics.
     fn obtain_state_part(
         &self,
@@ -1231,10 +1324,11 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         contract_id: &AccountId,
         method_name: &str,
         args: &[u8],
+        context: &ViewCallContext,
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
         current_protocol_version: ProtocolVersion,
-    ) -> Result<Vec<u8>, node_runtime::state_viewer::errors::CallFunctionError> {
+    ) -> Result<ViewCallResult, node_runtime::state_viewer::errors::CallFunctionError> {
         let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
         let view_state = ViewApplyState {
             block_height: height,
@@ -1252,6 +1346,7 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
             contract_id,
             method_name,
             args,
+            context,
             logs,
             epoch_info_provider,
         )
@@ -3,10 +3,13 @@ use borsh::BorshSerialize;
 use near_chain::types::RuntimeAdapter;
 use near_chain::{Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode, Error};
 use near_chain_configs::{ClientConfig, ExternalStorageLocation};
-use near_client::sync::external::{create_bucket_readwrite, external_storage_location};
 use near_client::sync::external::{
-    external_storage_location_directory, get_part_id_from_filename, is_part_filename,
-    ExternalConnection,
+    create_bucket_readonly, create_bucket_readwrite, external_storage_location,
+};
+use near_client::sync::external::{
+    external_storage_location_directory, external_storage_manifest_location,
+    get_part_id_from_filename, is_part_filename, ExternalConnection, StatePartsManifest,
+    StatePartsManifestEntry, MANIFEST_FILENAME,
 };
 use near_client::sync::state::{StateSync, STATE_DUMP_ITERATION_TIME_LIMIT_SECS};
 use near_epoch_manager::shard_tracker::ShardTracker;
@@ -42,11 +45,25 @@ pub fn spawn_state_sync_dump(
     tracing::info!(target: "state_sync_dump", "Spawning the state sync dump loop");
 
     let external = match dump_config.location {
-        ExternalStorageLocation::S3 { bucket, region } => ExternalConnection::S3{
-            bucket: Arc::new(create_bucket_readwrite(&bucket, &region, Duration::from_secs(30), dump_config.credentials_file).expect(
-                "Failed to authenticate connection to S3. Please either provide AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY in the environment, or create a credentials file and link it in config.json as 's3_credentials_file'."))
-        },
-        ExternalStorageLocation::Filesystem { root_dir } => ExternalConnection::Filesystem { root_dir },
+        ExternalStorageLocation::S3 { bucket, region, endpoint, path_style, anonymous } => {
+            let bucket = if anonymous.unwrap_or(false) {
+                create_bucket_readonly(
+                    &bucket,
+                    &region,
+                    endpoint.as_deref(),
+                    path_style,
+                    Duration::from_secs(30),
+                )
+                .expect("Failed to create an S3 bucket")
+            } else {
+                create_bucket_readwrite(&bucket, &region, endpoint.as_deref(), path_style, Duration::from_secs(30), dump_config.credentials_file).expect(
+                    "Failed to authenticate connection to S3. Please either provide AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY in the environment, or create a credentials file and link it in config.json as 's3_credentials_file'.")
+            };
+            ExternalConnection::S3 { bucket: Arc::new(bucket) }
+        }
+        ExternalStorageLocation::Filesystem { root_dir } => {
+            ExternalConnection::Filesystem { root_dir }
+        }
         ExternalStorageLocation::GCS { bucket } => {
             if let Some(credentials_file) = dump_config.credentials_file {
                 if let Ok(var) = std::env::var("SERVICE_ACCOUNT") {
@@ -59,9 +76,12 @@ pub fn spawn_state_sync_dump(
             ExternalConnection::GCS {
                 gcs_client: Arc::new(cloud_storage::Client::default()),
                 reqwest_client: Arc::new(reqwest::Client::default()),
-                bucket
+                bucket,
             }
-        },
+        }
+        ExternalStorageLocation::Http { base_url } => {
+            anyhow::bail!("Can't dump state to {base_url}: HTTP external storage is read-only");
+        }
     };
 
     // Determine how many threads to start.
@@ -143,6 +163,7 @@ fn extract_part_id_from_part_file_name(file_name: &String) -> u64 {
     return get_part_id_from_filename(file_name).unwrap();
 }
 
+/// Returns ids of the parts not dumped yet, and whether the manifest was already dumped.
 async fn get_missing_part_ids_for_epoch(
     shard_id: ShardId,
     chain_id: &String,
@@ -150,25 +171,73 @@ async fn get_missing_part_ids_for_epoch(
     epoch_height: u64,
     total_parts: u64,
     external: &ExternalConnection,
-) -> Result<Vec<u64>, anyhow::Error> {
+) -> Result<(Vec<u64>, bool), anyhow::Error> {
     let directory_path =
         external_storage_location_directory(chain_id, epoch_id, epoch_height, shard_id);
     let file_names = external.list_state_parts(shard_id, &directory_path).await?;
-    if !file_names.is_empty() {
-        let existing_nums: HashSet<_> = file_names
-            .iter()
-            .map(|file_name| extract_part_id_from_part_file_name(file_name))
-            .collect();
+    let existing_nums: HashSet<_> =
+        file_names.iter().map(|file_name| extract_part_id_from_part_file_name(file_name)).collect();
+    if !existing_nums.is_empty() {
         let missing_nums: Vec<u64> =
             (0..total_parts).filter(|i| !existing_nums.contains(i)).collect();
         let num_missing = missing_nums.len();
-        tracing::debug!(target: "state_sync_dump", ?num_missing, ?directory_path, "Some parts have already been dumped.");
-        Ok(missing_nums)
+        // The manifest only matters once all parts are dumped.
+        let has_manifest = missing_nums.is_empty()
+            && external
+                .list_files(&directory_path)
+                .await?
+                .iter()
+                .any(|file_name| file_name == MANIFEST_FILENAME);
+        tracing::debug!(target: "state_sync_dump", ?num_missing, has_manifest, ?directory_path, "Some parts have already been dumped.");
+        Ok((missing_nums, has_manifest))
     } else {
         tracing::debug!(target: "state_sync_dump", ?total_parts, ?directory_path, "No part has been dumped.");
         let missing_nums = (0..total_parts).collect::<Vec<_>>();
-        Ok(missing_nums)
+        Ok((missing_nums, false))
+    }
+}
+
+/// Builds the manifest of all parts of the shard and uploads it next to the parts.
+/// Parts are taken from the DB if they were dumped by this node, otherwise they are obtained again.
+async fn dump_manifest(
+    runtime: &dyn RuntimeAdapter,
+    shard_id: ShardId,
+    sync_hash: CryptoHash,
+    sync_prev_prev_hash: &CryptoHash,
+    state_root: &StateRoot,
+    num_parts: u64,
+    chain: &Chain,
+    chain_id: &str,
+    epoch_id: &EpochId,
+    epoch_height: EpochHeight,
+    external: &ExternalConnection,
+) -> Result<(), anyhow::Error> {
+    let mut parts = Vec::with_capacity(num_parts as usize);
+    for part_id in 0..num_parts {
+        let key = StatePartKey(sync_hash, shard_id, part_id).try_to_vec()?;
+        let entry = match chain.store().store().get(DBCol::StateParts, &key)? {
+            Some(state_part) => StatePartsManifestEntry::new(part_id, &state_part),
+            None => {
+                let state_part = obtain_and_store_state_part(
+                    runtime,
+                    shard_id,
+                    sync_hash,
+                    sync_prev_prev_hash,
+                    state_root,
+                    part_id,
+                    num_parts,
+                    chain,
+                )?;
+                StatePartsManifestEntry::new(part_id, &state_part)
+            }
+        };
+        parts.push(entry);
     }
+    let manifest = StatePartsManifest { state_root: *state_root, num_parts, parts };
+    let location = external_storage_manifest_location(chain_id, epoch_id, epoch_height, shard_id);
+    external.put_state_part(&manifest.to_bytes(), shard_id, &location).await?;
+    tracing::debug!(target: "state_sync_dump", shard_id, epoch_height, num_parts, ?location, "Dumped the manifest");
+    Ok(())
 }
 
 fn select_random_part_id_with_index(parts_to_be_dumped: &Vec<u64>) -> (u64, usize) {
@@ -277,7 +346,7 @@ async fn state_sync_dump(
                                 tracing::error!(target: "state_sync_dump", ?err, ?shard_id, "Failed to determine missing parts");
                                 None
                             }
-                            Ok(missing_parts) if missing_parts.is_empty() => {
+                            Ok((missing_parts, has_manifest)) if missing_parts.is_empty() => {
                                 update_dumped_size_and_cnt_metrics(
                                     &shard_id,
                                     epoch_height,
@@ -285,9 +354,39 @@ async fn state_sync_dump(
                                     num_parts,
                                     num_parts,
                                 );
-                                Some(StateSyncDumpProgress::AllDumped { epoch_id, epoch_height })
+                                if has_manifest {
+                                    Some(StateSyncDumpProgress::AllDumped {
+                                        epoch_id,
+                                        epoch_height,
+                                    })
+                                } else {
+                                    match dump_manifest(
+                                        runtime.as_ref(),
+                                        shard_id,
+                                        sync_hash,
+                                        &sync_prev_prev_hash,
+                                        &state_root,
+                                        num_parts,
+                                        &chain,
+                                        &chain_id,
+                                        &epoch_id,
+                                        epoch_height,
+                                        &external,
+                                    )
+                                    .await
+                                    {
+                                        Ok(()) => Some(StateSyncDumpProgress::AllDumped {
+                                            epoch_id,
+                                            epoch_height,
+                                        }),
+                                        Err(err) => {
+                                            tracing::warn!(target: "state_sync_dump", shard_id, epoch_height, ?err, "Failed to dump the manifest. Will retry.");
+                                            None
+                                        }
+                                    }
+                                }
                             }
-                            Ok(missing_parts) => {
+                            Ok((missing_parts, _)) => {
                                 let mut parts_to_dump = missing_parts.clone();
                                 let timer = Instant::now();
                                 let mut dumped_any_state_part = false;
@@ -357,9 +456,12 @@ async fn state_sync_dump(
                                     dumped_any_state_part = true;
                                 }
                                 if parts_to_dump.is_empty() {
-                                    Some(StateSyncDumpProgress::AllDumped {
+                                    // The manifest is dumped in the next iteration, after all
+                                    // parts are confirmed to be present in external storage.
+                                    Some(StateSyncDumpProgress::InProgress {
                                         epoch_id,
                                         epoch_height,
+                                        sync_hash,
                                     })
                                 } else if dumped_any_state_part {
                                     Some(StateSyncDumpProgress::InProgress {
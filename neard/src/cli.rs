@@ -8,6 +8,7 @@ use near_database_tool::commands::DatabaseCommand;
 use near_dyn_configs::{UpdateableConfigLoader, UpdateableConfigLoaderError, UpdateableConfigs};
 use near_flat_storage::commands::FlatStorageCommand;
 use near_fork_network::cli::ForkNetworkCommand;
+use near_genesis_builder::cli::GenesisCommand;
 use near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse;
 use near_mirror::MirrorCommand;
 use near_network::tcp;
@@ -135,6 +136,9 @@ impl NeardCmd {
             NeardSubCommand::ForkNetwork(cmd) => {
                 cmd.run(&home_dir, genesis_validation)?;
             }
+            NeardSubCommand::Genesis(cmd) => {
+                cmd.run()?;
+            }
         };
         Ok(())
     }
@@ -260,6 +264,9 @@ pub(super) enum NeardSubCommand {
 
     /// Resets the network into a forked network at the given block height and state.
     ForkNetwork(ForkNetworkCommand),
+
+    /// Builds a genesis from a declarative spec.
+    Genesis(GenesisCommand),
 }
 
 #[derive(clap::Parser)]
@@ -569,8 +576,14 @@ impl RunCmd {
             .await;
             actix::System::current().stop();
             // Disable the subscriber to properly shutdown the tracer.
-            near_o11y::reload(Some("error"), None, Some(near_o11y::OpenTelemetryLevel::OFF))
-                .unwrap();
+            near_o11y::reload(
+                Some("error"),
+                None,
+                Some(near_o11y::OpenTelemetryLevel::OFF),
+                None,
+                None,
+            )
+            .unwrap();
         });
         sys.run().unwrap();
         info!(target: "neard", "Waiting for RocksDB to gracefully shutdown");
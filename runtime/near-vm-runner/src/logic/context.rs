@@ -57,4 +57,9 @@ impl VMContext {
     pub fn is_view(&self) -> bool {
         self.view_config.is_some()
     }
+
+    /// Whether the view call can read its signer and predecessor.
+    pub fn view_exposes_caller(&self) -> bool {
+        self.view_config.as_ref().map_or(false, |config| config.expose_caller)
+    }
 }
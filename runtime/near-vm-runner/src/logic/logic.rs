@@ -137,7 +137,7 @@ impl<'a> VMLogic<'a> {
         let current_account_balance = context.account_balance + context.attached_deposit;
         let current_storage_usage = context.storage_usage;
         let max_gas_burnt = match context.view_config {
-            Some(ViewConfig { max_gas_burnt: max_gas_burnt_view }) => max_gas_burnt_view,
+            Some(ViewConfig { max_gas_burnt: max_gas_burnt_view, .. }) => max_gas_burnt_view,
             None => config.limit_config.max_gas_burnt,
         };
 
@@ -493,7 +493,7 @@ impl<'a> VMLogic<'a> {
     /// # Errors
     ///
     /// * If the registers exceed the memory limit returns `MemoryAccessViolation`.
-    /// * If called as view function returns `ProhibitedInView`.
+    /// * If called as view function which doesn't expose the caller returns `ProhibitedInView`.
     ///
     /// # Cost
     ///
@@ -501,7 +501,7 @@ impl<'a> VMLogic<'a> {
     pub fn signer_account_id(&mut self, register_id: u64) -> Result<()> {
         self.gas_counter.pay_base(base)?;
 
-        if self.context.is_view() {
+        if self.context.is_view() && !self.context.view_exposes_caller() {
             return Err(HostError::ProhibitedInView {
                 method_name: "signer_account_id".to_string(),
             }
@@ -551,7 +551,7 @@ impl<'a> VMLogic<'a> {
     /// # Errors
     ///
     /// * If the registers exceed the memory limit returns `MemoryAccessViolation`.
-    /// * If called as view function returns `ProhibitedInView`.
+    /// * If called as view function which doesn't expose the caller returns `ProhibitedInView`.
     ///
     /// # Cost
     ///
@@ -559,7 +559,7 @@ impl<'a> VMLogic<'a> {
     pub fn predecessor_account_id(&mut self, register_id: u64) -> Result<()> {
         self.gas_counter.pay_base(base)?;
 
-        if self.context.is_view() {
+        if self.context.is_view() && !self.context.view_exposes_caller() {
             return Err(HostError::ProhibitedInView {
                 method_name: "predecessor_account_id".to_string(),
             }
@@ -97,7 +97,10 @@ fn test_attached_deposit_view() {
     fn test_view(amount: u128) {
         let mut logic_builder = VMLogicBuilder::default();
         let context = &mut logic_builder.context;
-        context.view_config = Some(ViewConfig { max_gas_burnt: LimitConfig::test().max_gas_burnt });
+        context.view_config = Some(ViewConfig {
+            max_gas_burnt: LimitConfig::test().max_gas_burnt,
+            expose_caller: false,
+        });
         context.account_balance = 0;
         context.attached_deposit = amount;
         let mut logic = logic_builder.build();
@@ -114,3 +117,22 @@ fn test_attached_deposit_view() {
     test_view(1);
     test_view(u128::MAX);
 }
+
+#[test]
+fn test_caller_view() {
+    let mut logic_builder = VMLogicBuilder::view();
+    let mut logic = logic_builder.build();
+    logic.signer_account_id(0).expect_err("signer should be hidden in view");
+    logic.predecessor_account_id(0).expect_err("predecessor should be hidden in view");
+
+    let mut logic_builder = VMLogicBuilder::view();
+    logic_builder.context.view_config.as_mut().unwrap().expose_caller = true;
+    let signer_account_id = logic_builder.context.signer_account_id.clone();
+    let predecessor_account_id = logic_builder.context.predecessor_account_id.clone();
+    let mut logic = logic_builder.build();
+    logic.signer_account_id(0).expect("signer should be exposed");
+    logic.assert_read_register(signer_account_id.as_ref().as_bytes(), 0);
+    logic.predecessor_account_id(1).expect("predecessor should be exposed");
+    logic.assert_read_register(predecessor_account_id.as_ref().as_bytes(), 1);
+    logic.signer_account_pk(0).expect_err("signer key is never known in view");
+}
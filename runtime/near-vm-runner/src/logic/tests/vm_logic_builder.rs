@@ -31,7 +31,7 @@ impl VMLogicBuilder {
         let mut builder = Self::default();
         let max_gas_burnt = builder.config.limit_config.max_gas_burnt;
         builder.context.view_config =
-            Some(near_primitives_core::config::ViewConfig { max_gas_burnt });
+            Some(near_primitives_core::config::ViewConfig { max_gas_burnt, expose_caller: false });
         builder
     }
 
@@ -1,4 +1,5 @@
 use crate::near_primitives::shard_layout::ShardUId;
+use crate::state_viewer::{ViewCallContext, ViewCallResult};
 use near_crypto::PublicKey;
 use near_primitives::account::{AccessKey, Account};
 use near_primitives::hash::CryptoHash;
@@ -38,10 +39,11 @@ pub trait ViewRuntimeAdapter {
         contract_id: &AccountId,
         method_name: &str,
         args: &[u8],
+        context: &ViewCallContext,
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
         current_protocol_version: ProtocolVersion,
-    ) -> Result<Vec<u8>, crate::state_viewer::errors::CallFunctionError>;
+    ) -> Result<ViewCallResult, crate::state_viewer::errors::CallFunctionError>;
 
     fn view_access_key(
         &self,
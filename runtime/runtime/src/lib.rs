@@ -24,6 +24,7 @@ pub use near_primitives::runtime::apply_state::ApplyState;
 use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
 use near_primitives::sandbox::state_patch::SandboxStatePatch;
+use near_primitives::shard_layout::{account_id_to_shard_id, ShardLayout};
 use near_primitives::state_record::StateRecord;
 use near_primitives::transaction::ExecutionMetadata;
 use near_primitives::transaction::{
@@ -119,6 +120,20 @@ pub struct ApplyResult {
     pub metrics: Option<metrics::ApplyMetrics>,
 }
 
+/// Result of [`Runtime::simulate_transaction`].
+#[derive(Debug)]
+pub struct SimulationResult {
+    pub transaction_outcome: ExecutionOutcomeWithId,
+    /// Outcomes of the receipts in the order of their execution.
+    pub receipt_outcomes: Vec<ExecutionOutcomeWithId>,
+    /// Changes of the state of all the shards, which were never committed.
+    pub state_changes: Vec<RawStateChangesWithTrieKey>,
+    /// Number of simulated blocks in which receipts were executed.
+    pub num_blocks: u64,
+    /// Receipts which weren't executed before the block limit was reached.
+    pub pending_receipts: Vec<Receipt>,
+}
+
 #[derive(Debug)]
 pub struct ActionResult {
     pub gas_burnt: Gas,
@@ -217,6 +232,7 @@ impl Runtime {
         state_update: &mut TrieUpdate,
         apply_state: &ApplyState,
         signed_transaction: &SignedTransaction,
+        verify_signature: bool,
         stats: &mut ApplyStats,
     ) -> Result<(Receipt, ExecutionOutcomeWithId), RuntimeError> {
         let _span = tracing::debug_span!(target: "runtime", "process_transaction", tx_hash = %signed_transaction.get_hash()).entered();
@@ -227,7 +243,7 @@ impl Runtime {
             state_update,
             apply_state.gas_price,
             signed_transaction,
-            true,
+            verify_signature,
             Some(apply_state.block_height),
             apply_state.current_protocol_version,
         ) {
@@ -1258,6 +1274,7 @@ impl Runtime {
                 &mut state_update,
                 apply_state,
                 signed_transaction,
+                true,
                 &mut stats,
             )?;
             if receipt.receiver_id == signed_transaction.transaction.signer_id {
@@ -1486,6 +1503,67 @@ impl Runtime {
         })
     }
 
+    /// Executes the transaction and all the receipts it produces on top of the given states
+    /// of the shards, without writing anything to the storage. The receipts produced in one
+    /// simulated block are executed in the next one, until no receipts are left or
+    /// `max_blocks` blocks were simulated. `state_updates` are indexed by shard id.
+    pub fn simulate_transaction(
+        &self,
+        mut state_updates: Vec<TrieUpdate>,
+        shard_layout: &ShardLayout,
+        apply_state: &ApplyState,
+        signed_transaction: &SignedTransaction,
+        verify_signature: bool,
+        max_blocks: u64,
+        epoch_info_provider: &dyn EpochInfoProvider,
+    ) -> Result<SimulationResult, RuntimeError> {
+        let shard_index =
+            |account_id: &AccountId| account_id_to_shard_id(account_id, shard_layout) as usize;
+        let mut stats = ApplyStats::default();
+        let (receipt, transaction_outcome) = self.process_transaction(
+            &mut state_updates[shard_index(&signed_transaction.transaction.signer_id)],
+            apply_state,
+            signed_transaction,
+            verify_signature,
+            &mut stats,
+        )?;
+
+        let mut receipts = vec![receipt];
+        let mut receipt_outcomes = vec![];
+        let mut validator_proposals = vec![];
+        let mut num_blocks = 0;
+        while !receipts.is_empty() && num_blocks < max_blocks {
+            num_blocks += 1;
+            let mut outgoing_receipts = vec![];
+            for receipt in &receipts {
+                let outcome = self.process_receipt(
+                    &mut state_updates[shard_index(&receipt.receiver_id)],
+                    apply_state,
+                    receipt,
+                    &mut outgoing_receipts,
+                    &mut validator_proposals,
+                    &mut stats,
+                    epoch_info_provider,
+                )?;
+                receipt_outcomes.extend(outcome);
+            }
+            receipts = outgoing_receipts;
+        }
+
+        let mut state_changes = vec![];
+        for state_update in state_updates {
+            let (_, _, shard_state_changes) = state_update.finalize()?;
+            state_changes.extend(shard_state_changes);
+        }
+        Ok(SimulationResult {
+            transaction_outcome,
+            receipt_outcomes,
+            state_changes,
+            num_blocks,
+            pending_receipts: receipts,
+        })
+    }
+
     fn apply_state_patch(&self, state_update: &mut TrieUpdate, state_patch: SandboxStatePatch) {
         if state_patch.is_empty() {
             return;
@@ -2614,6 +2692,55 @@ mod tests {
             assert_matches!(second.outcome.status, ExecutionStatus::Failure(_));
         });
     }
+
+    #[test]
+    fn test_simulate_transaction() {
+        let initial_balance = to_yocto(1_000_000);
+        let (runtime, tries, root, apply_state, signer, epoch_info_provider) =
+            setup_runtime(initial_balance, 0, 10u64.pow(15));
+        let shard_layout = ShardLayout::v0_single_shard();
+        // Transfer to an account which doesn't exist fails and refunds the deposit.
+        let tx = SignedTransaction::send_money(
+            1,
+            alice_account(),
+            bob_account(),
+            &*signer,
+            to_yocto(100),
+            CryptoHash::default(),
+        );
+        let simulate = |max_blocks| {
+            runtime
+                .simulate_transaction(
+                    vec![tries.new_trie_update(ShardUId::single_shard(), root)],
+                    &shard_layout,
+                    &apply_state,
+                    &tx,
+                    true,
+                    max_blocks,
+                    &epoch_info_provider,
+                )
+                .unwrap()
+        };
+
+        let result = simulate(1);
+        assert_eq!(result.num_blocks, 1);
+        assert_matches!(&result.receipt_outcomes[..], [outcome] => {
+            assert_matches!(outcome.outcome.status, ExecutionStatus::Failure(_));
+        });
+        let num_refunds = result.pending_receipts.len();
+        assert!(num_refunds > 0);
+
+        let result = simulate(10);
+        assert_eq!(result.num_blocks, 2);
+        assert_eq!(result.receipt_outcomes.len(), 1 + num_refunds);
+        assert!(result.pending_receipts.is_empty());
+        assert!(!result.state_changes.is_empty());
+
+        // Nothing was written to the storage.
+        let state_update = tries.new_trie_update(ShardUId::single_shard(), root);
+        let account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+        assert_eq!(account.amount(), initial_balance);
+    }
 }
 
 /// Interface provided for gas cost estimations.
@@ -1,18 +1,23 @@
 use crate::near_primitives::version::PROTOCOL_VERSION;
 use crate::receipt_manager::ReceiptManager;
 use crate::{actions::execute_function_call, ext::RuntimeExt};
+use crate::{Runtime, SimulationResult};
 use near_crypto::{KeyType, PublicKey};
 use near_primitives::account::{AccessKey, Account};
 use near_primitives::borsh::BorshDeserialize;
+use near_primitives::errors::RuntimeError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::ActionReceipt;
 use near_primitives::runtime::apply_state::ApplyState;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
-use near_primitives::transaction::FunctionCallAction;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::transaction::{ExecutionMetadata, FunctionCallAction, SignedTransaction};
 use near_primitives::trie_key::trie_key_parsers;
-use near_primitives::types::{AccountId, EpochInfoProvider, Gas};
-use near_primitives::views::{StateItem, ViewApplyState, ViewStateResult};
+use near_primitives::types::{AccountId, Balance, Compute, EpochInfoProvider, Gas};
+use near_primitives::views::{
+    CostGasUsed, ExecutionMetadataView, StateItem, ViewApplyState, ViewStateResult,
+};
 use near_primitives_core::config::ViewConfig;
 use near_store::{get_access_key, get_account, get_code, TrieUpdate};
 use near_vm_runner::logic::ReturnData;
@@ -22,6 +27,28 @@ use tracing::debug;
 
 pub mod errors;
 
+/// Caller of a view function. By default, the contract calls itself with no
+/// deposit and the maximum gas of view calls.
+#[derive(Debug, Clone, Default)]
+pub struct ViewCallContext {
+    /// Defaults to the predecessor.
+    pub signer_id: Option<AccountId>,
+    /// Defaults to the signer.
+    pub predecessor_id: Option<AccountId>,
+    pub attached_deposit: Balance,
+    /// Can only lower the maximum gas of view calls.
+    pub gas: Option<Gas>,
+}
+
+/// Outcome of a successful view function call.
+#[derive(Debug)]
+pub struct ViewCallResult {
+    pub result: Vec<u8>,
+    pub burnt_gas: Gas,
+    pub compute_usage: Compute,
+    pub gas_profile: Vec<CostGasUsed>,
+}
+
 pub struct TrieViewer {
     /// Upper bound of the byte size of contract state that is still viewable. None is no limit
     state_size_limit: Option<u64>,
@@ -159,9 +186,10 @@ impl TrieViewer {
         contract_id: &AccountId,
         method_name: &str,
         args: &[u8],
+        context: &ViewCallContext,
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
-    ) -> Result<Vec<u8>, errors::CallFunctionError> {
+    ) -> Result<ViewCallResult, errors::CallFunctionError> {
         let now = Instant::now();
         let root = *state_update.get_root();
         let mut account = get_account(&state_update, contract_id)?.ok_or_else(|| {
@@ -169,9 +197,15 @@ impl TrieViewer {
                 requested_account_id: contract_id.clone(),
             }
         })?;
-        // TODO(#1015): Add ability to pass public key and originator_id
-        let originator_id = contract_id;
+        // The public key of the signer is unknown, so the contract can't read it.
+        let signer_id = context.signer_id.as_ref().or(context.predecessor_id.as_ref());
+        let expose_caller = signer_id.is_some();
+        let signer_id = signer_id.unwrap_or(contract_id);
+        let predecessor_id = context.predecessor_id.as_ref().unwrap_or(signer_id);
         let public_key = PublicKey::empty(KeyType::ED25519);
+        let max_gas_burnt = context
+            .gas
+            .map_or(self.max_gas_burnt_view, |gas| std::cmp::min(gas, self.max_gas_burnt_view));
         let empty_hash = CryptoHash::default();
         let mut receipt_manager = ReceiptManager::default();
         let mut runtime_ext = RuntimeExt::new(
@@ -206,7 +240,7 @@ impl TrieViewer {
             migration_flags: MigrationFlags::default(),
         };
         let action_receipt = ActionReceipt {
-            signer_id: originator_id.clone(),
+            signer_id: signer_id.clone(),
             signer_public_key: public_key,
             gas_price: 0,
             output_data_receivers: vec![],
@@ -216,21 +250,21 @@ impl TrieViewer {
         let function_call = FunctionCallAction {
             method_name: method_name.to_string(),
             args: args.to_vec(),
-            gas: self.max_gas_burnt_view,
-            deposit: 0,
+            gas: max_gas_burnt,
+            deposit: context.attached_deposit,
         };
         let outcome = execute_function_call(
             &apply_state,
             &mut runtime_ext,
             &mut account,
-            originator_id,
+            predecessor_id,
             &action_receipt,
             &[],
             &function_call,
             &empty_hash,
             config,
             true,
-            Some(ViewConfig { max_gas_burnt: self.max_gas_burnt_view }),
+            Some(ViewConfig { max_gas_burnt, expose_caller }),
         )
         .map_err(|e| errors::CallFunctionError::InternalError { error_message: e.to_string() })?;
         let elapsed = now.elapsed();
@@ -250,7 +284,58 @@ impl TrieViewer {
                 ReturnData::Value(buf) => buf,
                 ReturnData::ReceiptIndex(_) | ReturnData::None => vec![],
             };
-            Ok(result)
+            Ok(ViewCallResult {
+                result,
+                burnt_gas: outcome.burnt_gas,
+                compute_usage: outcome.compute_usage,
+                gas_profile: ExecutionMetadataView::from(ExecutionMetadata::V3(outcome.profile))
+                    .gas_profile
+                    .unwrap_or_default(),
+            })
         }
     }
+
+    /// Executes the transaction and the receipts it produces on top of the
+    /// given states of all the shards, without committing the changes.
+    /// See [`Runtime::simulate_transaction`].
+    pub fn simulate_transaction(
+        &self,
+        state_updates: Vec<TrieUpdate>,
+        shard_layout: &ShardLayout,
+        view_state: ViewApplyState,
+        gas_price: Balance,
+        signed_transaction: &SignedTransaction,
+        verify_signature: bool,
+        max_blocks: u64,
+        epoch_info_provider: &dyn EpochInfoProvider,
+    ) -> Result<SimulationResult, RuntimeError> {
+        let config_store = RuntimeConfigStore::new(None);
+        let config = config_store.get_config(view_state.current_protocol_version);
+        let apply_state = ApplyState {
+            block_height: view_state.block_height,
+            prev_block_hash: view_state.prev_block_hash,
+            block_hash: view_state.block_hash,
+            epoch_id: view_state.epoch_id,
+            epoch_height: view_state.epoch_height,
+            gas_price,
+            block_timestamp: view_state.block_timestamp,
+            gas_limit: None,
+            random_seed: view_state.block_hash,
+            current_protocol_version: view_state.current_protocol_version,
+            config: config.clone(),
+            cache: view_state.cache,
+            is_new_chunk: true,
+            migration_data: Arc::new(MigrationData::default()),
+            migration_flags: MigrationFlags::default(),
+        };
+        Runtime::new().simulate_transaction(
+            state_updates,
+            shard_layout,
+            &apply_state,
+            signed_transaction,
+            verify_signature,
+            max_blocks,
+            epoch_info_provider,
+        )
+    }
 }
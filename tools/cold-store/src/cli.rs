@@ -7,14 +7,18 @@ use near_epoch_manager::{EpochManager, EpochManagerAdapter, EpochManagerHandle};
 use near_primitives::block::Tip;
 use near_primitives::epoch_manager::block_info::BlockInfo;
 use near_primitives::hash::CryptoHash;
-use near_store::cold_storage::{copy_all_data_to_cold, update_cold_db, update_cold_head};
+use near_primitives::types::BlockHeight;
+use near_store::cold_storage::{
+    copy_all_data_to_cold, update_cold_db, update_cold_head, verify_cold_db, ColdDbGap,
+};
 use near_store::metadata::DbKind;
 use near_store::{DBCol, NodeStorage, Store, StoreOpener};
 use near_store::{COLD_HEAD_KEY, FINAL_HEAD_KEY, HEAD_KEY, TAIL_KEY};
 use nearcore::NearConfig;
 use rand::seq::SliceRandom;
+use std::collections::HashMap;
 use std::io::Result;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use strum::IntoEnumIterator;
 
 #[derive(clap::Parser)]
@@ -53,6 +57,10 @@ enum SubCommand {
     /// You can provide maximum depth and/or maximum number of vertices to traverse for each root.
     /// Trie is traversed using DFS with randomly shuffled kids for every node.
     CheckStateRoot(CheckStateRootCmd),
+    /// Check that cold storage has every entry of the cold columns for a range of heights,
+    /// comparing it with hot storage, and optionally re-copy the heights with gaps.
+    /// The progress is saved, so that an interrupted run continues where it stopped.
+    Verify(VerifyCmd),
 }
 
 impl ColdStoreCommand {
@@ -87,6 +95,9 @@ impl ColdStoreCommand {
             }
             SubCommand::PrepareHot(cmd) => cmd.run(&storage, &home_dir, &near_config),
             SubCommand::CheckStateRoot(cmd) => cmd.run(&storage),
+            SubCommand::Verify(cmd) => {
+                cmd.run(&storage, &near_config, epoch_manager.as_ref(), home_dir, self.readwrite)
+            }
         }
     }
 
@@ -651,3 +662,165 @@ impl CheckStateRootCmd {
         store.get(DBCol::State, &cold_state_key)
     }
 }
+
+#[derive(clap::Args)]
+struct VerifyCmd {
+    /// First height to check. By default continues after the last height checked by the previous
+    /// run, or starts from the genesis height.
+    #[clap(long)]
+    from: Option<BlockHeight>,
+    /// Last height to check. Defaults to the cold head.
+    #[clap(long)]
+    to: Option<BlockHeight>,
+    /// Copy the heights with gaps to cold storage again. Requires `--readwrite`, so the node must
+    /// not be running.
+    #[clap(long)]
+    repair: bool,
+    /// File where the progress is saved, relative to the home dir.
+    #[clap(long, default_value = "cold-store-verify.json")]
+    progress_file: PathBuf,
+}
+
+/// Progress of `VerifyCmd`, saved after every batch of heights.
+#[derive(serde::Serialize, serde::Deserialize, Default, Debug)]
+struct VerifyProgress {
+    last_checked_height: Option<BlockHeight>,
+    /// Heights which have gaps in cold storage and weren't repaired.
+    heights_with_gaps: Vec<BlockHeight>,
+}
+
+impl VerifyCmd {
+    /// Number of heights checked between saving the progress.
+    const PROGRESS_SAVE_INTERVAL: u64 = 1000;
+
+    pub fn run(
+        self,
+        storage: &NodeStorage,
+        near_config: &NearConfig,
+        epoch_manager: &EpochManagerHandle,
+        home_dir: &Path,
+        readwrite: bool,
+    ) -> anyhow::Result<()> {
+        if self.repair && !readwrite {
+            anyhow::bail!("--repair requires opening the storage with --readwrite");
+        }
+        let hot_store = storage.get_hot_store();
+        let cold_store =
+            storage.get_cold_store().ok_or(anyhow::anyhow!("Cold storage is not configured"))?;
+
+        let progress_path = home_dir.join(&self.progress_file);
+        let mut progress: VerifyProgress = match std::fs::read(&progress_path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("Failed to parse {}", progress_path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => VerifyProgress::default(),
+            Err(err) => return Err(err.into()),
+        };
+        let from = match (self.from, progress.last_checked_height) {
+            (Some(from), _) => from,
+            (None, Some(last_checked_height)) => last_checked_height + 1,
+            (None, None) => near_config.genesis.config.genesis_height,
+        };
+        let to = match self.to {
+            Some(to) => to,
+            None => {
+                cold_store
+                    .get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?
+                    .ok_or(anyhow::anyhow!("Cold HEAD is not set"))?
+                    .height
+            }
+        };
+        println!("Checking heights from {} to {}", from, to);
+        // The heights in the range are checked again, so forget the gaps found there before.
+        progress.heights_with_gaps.retain(|height| !(from..=to).contains(height));
+
+        let mut num_checked = 0;
+        let mut num_unavailable = 0;
+        let mut gaps_by_col = HashMap::<DBCol, u64>::new();
+        for height in from..=to {
+            let gaps = match Self::verify_height(&cold_store, &hot_store, epoch_manager, height)? {
+                Some(gaps) => gaps,
+                None => {
+                    num_unavailable += 1;
+                    continue;
+                }
+            };
+            num_checked += 1;
+            if !gaps.is_empty() {
+                for gap in &gaps {
+                    tracing::debug!(target: "cold-store-verify", height, ?gap, "Gap in cold storage");
+                    *gaps_by_col.entry(gap.col()).or_default() += 1;
+                }
+                println!("Height {} has {} gaps in cold storage", height, gaps.len());
+                if self.repair && Self::repair_height(storage, epoch_manager, height)? {
+                    println!("Height {} is repaired", height);
+                } else {
+                    progress.heights_with_gaps.push(height);
+                }
+            }
+            progress.last_checked_height = Some(height);
+            if (height - from + 1) % Self::PROGRESS_SAVE_INTERVAL == 0 {
+                Self::save_progress(&progress_path, &progress)?;
+            }
+        }
+        Self::save_progress(&progress_path, &progress)?;
+
+        println!(
+            "Checked {} heights, {} heights have no block or are not in hot storage anymore",
+            num_checked, num_unavailable
+        );
+        for col in DBCol::iter() {
+            if let Some(num_gaps) = gaps_by_col.get(&col) {
+                println!("{}: {} gaps", col, num_gaps);
+            }
+        }
+        if progress.heights_with_gaps.is_empty() {
+            println!("No gaps left");
+        } else {
+            println!("Heights with gaps: {:?}", progress.heights_with_gaps);
+        }
+        Ok(())
+    }
+
+    /// Returns the gaps in cold storage for the block at `height`, or None if it can't be checked.
+    fn verify_height(
+        cold_store: &Store,
+        hot_store: &Store,
+        epoch_manager: &EpochManagerHandle,
+        height: BlockHeight,
+    ) -> anyhow::Result<Option<Vec<ColdDbGap>>> {
+        let Some(block_hash) =
+            hot_store.get_ser::<CryptoHash>(DBCol::BlockHeight, &height.to_le_bytes())?
+        else {
+            return Ok(None);
+        };
+        // The epoch of the block is unknown if the block was garbage collected from hot storage.
+        let Ok(epoch_id) = epoch_manager.get_epoch_id(&block_hash) else {
+            return Ok(None);
+        };
+        let shard_layout = epoch_manager.get_shard_layout(&epoch_id)?;
+        Ok(verify_cold_db(cold_store, hot_store, &shard_layout, &height)?)
+    }
+
+    /// Copies the block at `height` to cold storage again and checks that the gaps are gone.
+    fn repair_height(
+        storage: &NodeStorage,
+        epoch_manager: &EpochManagerHandle,
+        height: BlockHeight,
+    ) -> anyhow::Result<bool> {
+        let hot_store = storage.get_hot_store();
+        let cold_store = storage.get_cold_store().unwrap();
+        let block_hash = hot_store
+            .get_ser::<CryptoHash>(DBCol::BlockHeight, &height.to_le_bytes())?
+            .ok_or(anyhow::anyhow!("No block hash in hot storage for height {}", height))?;
+        let shard_layout =
+            epoch_manager.get_shard_layout(&epoch_manager.get_epoch_id(&block_hash)?)?;
+        update_cold_db(&*storage.cold_db().unwrap(), &hot_store, &shard_layout, &height)?;
+        let gaps = Self::verify_height(&cold_store, &hot_store, epoch_manager, height)?;
+        Ok(gaps.map_or(false, |gaps| gaps.is_empty()))
+    }
+
+    fn save_progress(path: &Path, progress: &VerifyProgress) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(progress)?)?;
+        Ok(())
+    }
+}
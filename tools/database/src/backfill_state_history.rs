@@ -0,0 +1,155 @@
+use anyhow::{anyhow, bail};
+use clap::Parser;
+use near_chain_configs::GenesisValidationMode;
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::block::Tip;
+use near_primitives::block_header::BlockHeader;
+use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::get_block_shard_uid;
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::BlockHeight;
+use near_primitives::utils::index_to_bytes;
+use near_store::state_history::{self, StateHistoryStatus};
+use near_store::{DBCol, NodeStorage, Store, Trie, TrieDBStorage, FINAL_HEAD_KEY, TAIL_KEY};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Number of blocks added to the index in a single transaction.
+const BLOCKS_BATCH_SIZE: u64 = 1000;
+/// Number of entries of the seeded state written in a single transaction.
+const SEED_BATCH_SIZE: usize = 100_000;
+
+#[derive(Parser)]
+pub(crate) struct BackfillStateHistoryCommand {
+    /// Height of the block whose full state the index is seeded with, if the index doesn't
+    /// exist yet. Defaults to the tail of the chain.
+    #[arg(long)]
+    start_height: Option<BlockHeight>,
+}
+
+impl BackfillStateHistoryCommand {
+    pub(crate) fn run(&self, home: &Path) -> anyhow::Result<()> {
+        let near_config = nearcore::config::load_config(home, GenesisValidationMode::UnsafeFast)?;
+        let storage = NodeStorage::opener(
+            home,
+            near_config.config.archive,
+            &near_config.config.store,
+            near_config.config.cold_store.as_ref(),
+        )
+        .open()?;
+        let hot_store = storage.get_hot_store();
+        // State changes of old blocks may be available in the cold storage only.
+        let store = storage.get_split_store().unwrap_or_else(|| hot_store.clone());
+
+        let final_head = store
+            .get_ser::<Tip>(DBCol::BlockMisc, FINAL_HEAD_KEY)?
+            .ok_or_else(|| anyhow!("final head is missing"))?;
+        let mut status = match state_history::get_status(&hot_store)? {
+            Some(status) => {
+                if self.start_height.is_some() {
+                    bail!("state history index already starts at height {}", status.start_height);
+                }
+                tracing::info!(
+                    target: "state_history",
+                    head_height = status.head_height,
+                    "Resuming the index"
+                );
+                status
+            }
+            None => {
+                let start_height = match self.start_height {
+                    Some(start_height) => start_height,
+                    None => store
+                        .get_ser::<BlockHeight>(DBCol::BlockMisc, TAIL_KEY)?
+                        .ok_or_else(|| anyhow!("tail is missing"))?,
+                };
+                seed(&near_config.genesis.config, &store, &hot_store, start_height)?
+            }
+        };
+
+        while status.head_height < final_head.height {
+            let height = final_head.height.min(status.head_height + BLOCKS_BATCH_SIZE);
+            let mut store_update = hot_store.store_update();
+            status = state_history::record_blocks_up_to(&store, &mut store_update, status, height)?;
+            store_update.commit()?;
+            tracing::info!(
+                target: "state_history",
+                head_height = status.head_height,
+                final_height = final_head.height,
+                "Indexed blocks"
+            );
+        }
+        tracing::info!(
+            target: "state_history",
+            start_height = status.start_height,
+            head_height = status.head_height,
+            "State history index is up to date"
+        );
+        Ok(())
+    }
+}
+
+/// Writes the state of all shards at the block at the height to the index.
+fn seed(
+    genesis_config: &near_chain_configs::GenesisConfig,
+    store: &Store,
+    hot_store: &Store,
+    height: BlockHeight,
+) -> anyhow::Result<StateHistoryStatus> {
+    let block_hash = store
+        .get_ser::<CryptoHash>(DBCol::BlockHeight, &index_to_bytes(height))?
+        .ok_or_else(|| anyhow!("no block at height {height}"))?;
+    let header = store
+        .get_ser::<BlockHeader>(DBCol::BlockHeader, block_hash.as_ref())?
+        .ok_or_else(|| anyhow!("header of block {block_hash} is missing"))?;
+    let epoch_manager = EpochManager::new_arc_handle(store.clone(), genesis_config);
+    let shard_layout = epoch_manager.get_shard_layout(header.epoch_id())?;
+
+    for shard_uid in shard_layout.get_shard_uids() {
+        // Nodes which don't track all shards have the state of some shards only.
+        let Some(chunk_extra) = store.get_ser::<ChunkExtra>(
+            DBCol::ChunkExtra,
+            &get_block_shard_uid(&block_hash, &shard_uid),
+        )?
+        else {
+            tracing::warn!(
+                target: "state_history",
+                %shard_uid,
+                height,
+                "State of the shard is missing, skipping it"
+            );
+            continue;
+        };
+        tracing::info!(
+            target: "state_history",
+            %shard_uid,
+            height,
+            "Seeding the index with the state of the shard"
+        );
+        let storage = TrieDBStorage::new(store.clone(), shard_uid);
+        let trie = Trie::new(Rc::new(storage), *chunk_extra.state_root(), None);
+
+        let mut store_update = hot_store.store_update();
+        let mut entries = 0;
+        for item in trie.iter()? {
+            let (key, value) = item?;
+            if !state_history::is_indexed_key(&key) {
+                continue;
+            }
+            state_history::set_value(&mut store_update, &key, height, Some(&value))?;
+            entries += 1;
+            if entries % SEED_BATCH_SIZE == 0 {
+                std::mem::replace(&mut store_update, hot_store.store_update()).commit()?;
+            }
+        }
+        store_update.commit()?;
+        tracing::info!(target: "state_history", %shard_uid, entries, "Seeded the index");
+    }
+
+    // The index is only used once the status is written.
+    let status = StateHistoryStatus { start_height: height, head_height: height };
+    let mut store_update = hot_store.store_update();
+    state_history::set_status(&mut store_update, &status)?;
+    store_update.commit()?;
+    Ok(status)
+}
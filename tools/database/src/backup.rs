@@ -0,0 +1,477 @@
+use anyhow::Context;
+use futures::{StreamExt, TryStreamExt};
+use near_client::sync::external::{
+    create_bucket_readonly, create_bucket_readwrite, ExternalConnection,
+};
+use near_primitives::block::Tip;
+use near_primitives::hash::CryptoHash;
+use near_primitives::static_clock::StaticClock;
+use near_primitives::types::BlockHeight;
+use near_store::{
+    checkpoint_hot_storage_and_cleanup_columns, DBCol, Mode, NodeStorage, StoreConfig, HEAD_KEY,
+};
+use sha2::Digest;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Directory in the external storage with the files of all backups, named by the hashes of their
+/// contents. Files shared by several backups, such as SST files, are stored once.
+const FILES_DIR: &str = "files";
+/// Directory in the external storage with the manifests, one per backup.
+const MANIFESTS_DIR: &str = "manifests";
+/// Maximum number of files uploaded or downloaded at the same time.
+const MAX_CONCURRENT_TRANSFERS: usize = 8;
+/// A running node may delete the files of the database while the checkpoint is being made, in
+/// which case making the checkpoint is retried.
+const CHECKPOINT_ATTEMPTS: usize = 3;
+/// Files of RocksDB which are not needed to open the database.
+const SKIPPED_FILES: &[&str] = &["LOCK", "LOG"];
+
+/// Location of the backups.
+#[derive(clap::Args)]
+pub(crate) struct BackupStorageArgs {
+    /// Directory on the local filesystem.
+    #[clap(long)]
+    root_dir: Option<PathBuf>,
+    /// S3 bucket. Requires `--s3-region`.
+    #[clap(long)]
+    s3_bucket: Option<String>,
+    #[clap(long)]
+    s3_region: Option<String>,
+    /// URL of an S3-compatible service to use instead of AWS.
+    #[clap(long)]
+    s3_endpoint: Option<String>,
+    /// Address the S3 bucket in the path of the URL instead of the host name.
+    #[clap(long)]
+    s3_path_style: bool,
+    /// GCS bucket.
+    #[clap(long)]
+    gcs_bucket: Option<String>,
+    /// Read-only static file server mirroring the backups. Can only be used to restore.
+    #[clap(long)]
+    http_base_url: Option<String>,
+    /// File with the credentials to write to the S3 or GCS bucket.
+    #[clap(long)]
+    credentials_file: Option<PathBuf>,
+    /// Directory within the storage which contains the backups.
+    #[clap(long, default_value = "backup")]
+    prefix: String,
+}
+
+impl BackupStorageArgs {
+    fn connect(&self, read_write: bool) -> anyhow::Result<ExternalConnection> {
+        let timeout = Duration::from_secs(60);
+        if let Some(root_dir) = &self.root_dir {
+            Ok(ExternalConnection::Filesystem { root_dir: root_dir.clone() })
+        } else if let (Some(bucket), Some(region)) = (&self.s3_bucket, &self.s3_region) {
+            let endpoint = self.s3_endpoint.as_deref();
+            let bucket = if read_write {
+                create_bucket_readwrite(
+                    bucket,
+                    region,
+                    endpoint,
+                    self.s3_path_style,
+                    timeout,
+                    self.credentials_file.clone(),
+                )?
+            } else {
+                create_bucket_readonly(bucket, region, endpoint, self.s3_path_style, timeout)?
+            };
+            Ok(ExternalConnection::S3 { bucket: Arc::new(bucket) })
+        } else if let Some(bucket) = &self.gcs_bucket {
+            if let Some(credentials_file) = &self.credentials_file {
+                std::env::set_var("SERVICE_ACCOUNT", credentials_file);
+            }
+            Ok(ExternalConnection::GCS {
+                gcs_client: Arc::new(cloud_storage::Client::default()),
+                reqwest_client: Arc::new(reqwest::Client::default()),
+                bucket: bucket.clone(),
+            })
+        } else if let Some(base_url) = &self.http_base_url {
+            if read_write {
+                anyhow::bail!("--http-base-url is read-only and can't be used to make backups");
+            }
+            Ok(ExternalConnection::Http {
+                reqwest_client: Arc::new(reqwest::Client::default()),
+                base_url: base_url.clone(),
+            })
+        } else {
+            anyhow::bail!(
+                "Please provide --root-dir, or both of --s3-bucket and --s3-region, or --gcs-bucket, or --http-base-url"
+            )
+        }
+    }
+
+    fn file_location(&self, hash: &CryptoHash) -> String {
+        format!("{}/{}/{}", self.prefix, FILES_DIR, hash)
+    }
+
+    fn manifest_location(&self, name: &str) -> String {
+        format!("{}/{}/{}", self.prefix, MANIFESTS_DIR, name)
+    }
+}
+
+/// Describes a backup: the files of the database and where to find them.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+struct BackupManifest {
+    created_at: chrono::DateTime<chrono::Utc>,
+    /// Height of the chain head in the backed up database.
+    head_height: Option<BlockHeight>,
+    files: Vec<BackupFile>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+struct BackupFile {
+    /// Name of the file in the database directory.
+    name: String,
+    size: u64,
+    /// Hash of the contents, which is also the name of the file in the external storage.
+    hash: CryptoHash,
+}
+
+impl BackupManifest {
+    /// Names sort in the order the backups were made.
+    fn file_name(&self) -> String {
+        format!("{}.json", self.created_at.format("%Y%m%dT%H%M%S%.3fZ"))
+    }
+}
+
+async fn read_manifest(
+    external: &ExternalConnection,
+    storage: &BackupStorageArgs,
+    name: &str,
+) -> anyhow::Result<BackupManifest> {
+    let data = external.get_file(&storage.manifest_location(name)).await?;
+    serde_json::from_slice(&data).with_context(|| format!("Failed to parse manifest {name}"))
+}
+
+/// Returns the name and the contents of the manifest of the latest backup, if any.
+async fn latest_manifest(
+    external: &ExternalConnection,
+    storage: &BackupStorageArgs,
+) -> anyhow::Result<Option<(String, BackupManifest)>> {
+    let names = external.list_files(&format!("{}/{}", storage.prefix, MANIFESTS_DIR)).await?;
+    match names.into_iter().filter(|name| name.ends_with(".json")).max() {
+        Some(name) => {
+            let manifest = read_manifest(external, storage, &name).await?;
+            Ok(Some((name, manifest)))
+        }
+        None => Ok(None),
+    }
+}
+
+#[derive(clap::Args)]
+pub(crate) struct BackupCommand {
+    #[clap(flatten)]
+    storage: BackupStorageArgs,
+}
+
+impl BackupCommand {
+    /// Makes a checkpoint of the hot database, which works while the node is running, and
+    /// uploads the files of the checkpoint which are not in the latest backup yet.
+    pub(crate) fn run(
+        &self,
+        home_dir: &Path,
+        archive: bool,
+        store_config: &StoreConfig,
+    ) -> anyhow::Result<()> {
+        let external = self.storage.connect(true)?;
+        let opener = NodeStorage::opener(home_dir, archive, store_config, None);
+        // The checkpoint hard links the files of the database, so it must be on the same
+        // filesystem.
+        let checkpoint_dir = tempfile::Builder::new()
+            .prefix("backup")
+            .tempdir_in(opener.path().parent().unwrap_or(home_dir))?;
+
+        let mut attempt = 1;
+        let checkpoint = loop {
+            // Opening the database in read-only mode doesn't lock it.
+            let result = opener.open_in_mode(Mode::ReadOnly).and_then(|storage| {
+                checkpoint_hot_storage_and_cleanup_columns(
+                    &storage.get_hot_store(),
+                    checkpoint_dir.path(),
+                    None,
+                )
+            });
+            match result {
+                Ok(checkpoint) => break checkpoint,
+                Err(err) if attempt < CHECKPOINT_ATTEMPTS => {
+                    tracing::warn!(target: "backup", ?err, attempt, "Failed to make a checkpoint, retrying");
+                    let _ = std::fs::remove_dir_all(checkpoint_dir.path().join("data"));
+                    attempt += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+        let head_height = checkpoint
+            .get_hot_store()
+            .get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?
+            .map(|tip| tip.height);
+        // Closes the checkpoint, so that its files don't change anymore.
+        drop(checkpoint);
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        let manifest = runtime.block_on(upload_backup(
+            &external,
+            &self.storage,
+            &checkpoint_dir.path().join("data"),
+            head_height,
+        ))?;
+        println!("Made backup {} at height {:?}", manifest.file_name(), head_height);
+        Ok(())
+    }
+}
+
+async fn upload_backup(
+    external: &ExternalConnection,
+    storage: &BackupStorageArgs,
+    db_path: &Path,
+    head_height: Option<BlockHeight>,
+) -> anyhow::Result<BackupManifest> {
+    // Files are reused by the hashes of their contents only, as names and sizes don't identify
+    // the contents, e.g. after the database is restored or recreated.
+    let uploaded: HashSet<CryptoHash> = match latest_manifest(external, storage).await? {
+        Some((_, manifest)) => manifest.files.into_iter().map(|file| file.hash).collect(),
+        None => HashSet::new(),
+    };
+    let mut paths = vec![];
+    for entry in std::fs::read_dir(db_path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_file() && !SKIPPED_FILES.iter().any(|s| name.starts_with(s)) {
+            paths.push((name, entry.path(), entry.metadata()?.len()));
+        }
+    }
+
+    let uploaded = &uploaded;
+    let mut files: Vec<BackupFile> = futures::stream::iter(paths)
+        .map(|(name, path, size)| async move {
+            let hash = {
+                let path = path.clone();
+                tokio::task::spawn_blocking(move || hash_file(&path)).await??
+            };
+            let file = BackupFile { name, size, hash };
+            if !uploaded.contains(&file.hash) {
+                external.put_file_from_path(&path, &storage.file_location(&file.hash)).await?;
+                tracing::info!(target: "backup", name = %file.name, size = file.size, "Uploaded");
+            }
+            anyhow::Ok(file)
+        })
+        .buffer_unordered(MAX_CONCURRENT_TRANSFERS)
+        .try_collect()
+        .await?;
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let manifest = BackupManifest { created_at: StaticClock::utc(), head_height, files };
+    // The manifest is uploaded last, so that a backup is visible only once all of its files are.
+    external
+        .put_file(
+            &serde_json::to_vec_pretty(&manifest)?,
+            &storage.manifest_location(&manifest.file_name()),
+        )
+        .await?;
+    Ok(manifest)
+}
+
+/// Hashes the contents of the file the same way as `CryptoHash::hash_bytes`, without reading the
+/// whole file into memory.
+fn hash_file(path: &Path) -> anyhow::Result<CryptoHash> {
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(CryptoHash(hasher.finalize().into()))
+}
+
+#[derive(clap::Args)]
+pub(crate) struct RestoreCommand {
+    #[clap(flatten)]
+    storage: BackupStorageArgs,
+    /// Name of the manifest of the backup to restore, for example `20231017T120000.000Z.json`.
+    /// The latest backup is restored by default.
+    #[clap(long)]
+    manifest: Option<String>,
+}
+
+impl RestoreCommand {
+    /// Downloads the files of the backup into the hot database directory, which must not exist
+    /// or be empty.
+    pub(crate) fn run(
+        &self,
+        home_dir: &Path,
+        archive: bool,
+        store_config: &StoreConfig,
+    ) -> anyhow::Result<()> {
+        let external = self.storage.connect(false)?;
+        let opener = NodeStorage::opener(home_dir, archive, store_config, None);
+        let db_path = opener.path().to_path_buf();
+        if db_path.exists() && std::fs::read_dir(&db_path)?.next().is_some() {
+            anyhow::bail!(
+                "{} is not empty, remove it before restoring a backup",
+                db_path.display()
+            );
+        }
+        // Downloads into a temporary directory, so that an interrupted restore doesn't leave a
+        // broken database behind.
+        let restore_path = db_path.with_extension("restore");
+        if restore_path.exists() {
+            std::fs::remove_dir_all(&restore_path)?;
+        }
+        std::fs::create_dir_all(&restore_path)?;
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        let (name, manifest) = runtime.block_on(async {
+            let (name, manifest) = match &self.manifest {
+                Some(name) => (name.clone(), read_manifest(&external, &self.storage, name).await?),
+                None => latest_manifest(&external, &self.storage)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("No backups found"))?,
+            };
+            download_backup(&external, &self.storage, &manifest, &restore_path).await?;
+            anyhow::Ok((name, manifest))
+        })?;
+
+        if db_path.exists() {
+            std::fs::remove_dir(&db_path)?;
+        }
+        std::fs::rename(&restore_path, &db_path)?;
+        // Checks that the restored database can be opened.
+        opener.open_in_mode(Mode::ReadOnly)?;
+        println!("Restored backup {} at height {:?}", name, manifest.head_height);
+        Ok(())
+    }
+}
+
+async fn download_backup(
+    external: &ExternalConnection,
+    storage: &BackupStorageArgs,
+    manifest: &BackupManifest,
+    db_path: &Path,
+) -> anyhow::Result<()> {
+    futures::stream::iter(&manifest.files)
+        .map(|file| async move {
+            let data = external.get_file(&storage.file_location(&file.hash)).await?;
+            if data.len() as u64 != file.size || CryptoHash::hash_bytes(&data) != file.hash {
+                anyhow::bail!("File {} of the backup is corrupted", file.name);
+            }
+            tokio::fs::write(db_path.join(&file.name), data).await?;
+            tracing::info!(target: "backup", name = %file.name, size = file.size, "Downloaded");
+            anyhow::Ok(())
+        })
+        .buffer_unordered(MAX_CONCURRENT_TRANSFERS)
+        .try_collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backup::{
+        hash_file, upload_backup, BackupCommand, BackupStorageArgs, RestoreCommand, FILES_DIR,
+        MANIFESTS_DIR,
+    };
+    use near_client::sync::external::ExternalConnection;
+    use near_primitives::hash::CryptoHash;
+    use near_store::{DBCol, Mode, NodeStorage, StoreConfig};
+    use std::path::Path;
+
+    fn storage_args(root_dir: &Path) -> BackupStorageArgs {
+        BackupStorageArgs {
+            root_dir: Some(root_dir.to_path_buf()),
+            s3_bucket: None,
+            s3_region: None,
+            s3_endpoint: None,
+            s3_path_style: false,
+            gcs_bucket: None,
+            http_base_url: None,
+            credentials_file: None,
+            prefix: "backup".to_string(),
+        }
+    }
+
+    fn num_files(dir: &Path) -> usize {
+        std::fs::read_dir(dir).unwrap().count()
+    }
+
+    /// Makes two backups of a DB, the second one after adding more data, and restores the latest.
+    #[test]
+    fn test() {
+        let home_dir = tempfile::tempdir().unwrap();
+        let external_dir = tempfile::tempdir().unwrap();
+        let store_config = StoreConfig::test_config();
+        let opener = NodeStorage::opener(home_dir.path(), false, &store_config, None);
+
+        {
+            let node_storage = opener.open().unwrap();
+            let mut store_update = node_storage.get_hot_store().store_update();
+            store_update.insert(DBCol::Block, &[0], &[42]);
+            store_update.insert(DBCol::Block, &[1], &[42]);
+            store_update.commit().unwrap();
+        }
+        let backup = BackupCommand { storage: storage_args(external_dir.path()) };
+        backup.run(home_dir.path(), false, &store_config).unwrap();
+        let files_dir = external_dir.path().join("backup").join(FILES_DIR);
+        let manifests_dir = external_dir.path().join("backup").join(MANIFESTS_DIR);
+        assert_eq!(1, num_files(&manifests_dir));
+        let num_files_first = num_files(&files_dir);
+
+        // Backups don't lock the database, so it can be written meanwhile.
+        let node_storage = opener.open().unwrap();
+        let mut store_update = node_storage.get_hot_store().store_update();
+        store_update.insert(DBCol::Block, &[2], &[42]);
+        store_update.commit().unwrap();
+        backup.run(home_dir.path(), false, &store_config).unwrap();
+        drop(node_storage);
+        assert_eq!(2, num_files(&manifests_dir));
+        assert!(num_files(&files_dir) > num_files_first);
+
+        let restore_home_dir = tempfile::tempdir().unwrap();
+        let restore = RestoreCommand { storage: storage_args(external_dir.path()), manifest: None };
+        restore.run(restore_home_dir.path(), false, &store_config).unwrap();
+        // Restoring over an existing database isn't allowed.
+        assert!(restore.run(restore_home_dir.path(), false, &store_config).is_err());
+
+        let restored = NodeStorage::opener(restore_home_dir.path(), false, &store_config, None)
+            .open_in_mode(Mode::ReadOnly)
+            .unwrap();
+        for key in [[0u8], [1], [2]] {
+            assert!(restored.get_hot_store().exists(DBCol::Block, &key).unwrap());
+        }
+    }
+
+    /// Files of the previous backup are reused if their contents are the same, whatever their names
+    /// and sizes.
+    #[test]
+    fn test_reuse_files() {
+        let db_dir = tempfile::tempdir().unwrap();
+        let external_dir = tempfile::tempdir().unwrap();
+        let storage = storage_args(external_dir.path());
+        let external = ExternalConnection::Filesystem { root_dir: external_dir.path().into() };
+        let files_dir = external_dir.path().join("backup").join(FILES_DIR);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let backup = |files: &[(&str, &[u8])]| {
+            for entry in std::fs::read_dir(db_dir.path()).unwrap() {
+                std::fs::remove_file(entry.unwrap().path()).unwrap();
+            }
+            for (name, contents) in files {
+                std::fs::write(db_dir.path().join(name), contents).unwrap();
+            }
+            // Manifests are named by the time, make sure they are different.
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            runtime.block_on(upload_backup(&external, &storage, db_dir.path(), None)).unwrap()
+        };
+
+        let first = backup(&[("000001.sst", &b"first"[..]), ("CURRENT", &b"first"[..])]);
+        assert!(first.files.iter().all(|file| file.hash == CryptoHash::hash_bytes(b"first")));
+        assert_eq!(1, num_files(&files_dir));
+
+        // An SST file with the same name and size but other contents, as in a recreated database.
+        let second = backup(&[("000001.sst", &b"other"[..]), ("000002.sst", &b"first"[..])]);
+        assert_eq!(CryptoHash::hash_bytes(b"other"), second.files[0].hash);
+        assert_eq!(CryptoHash::hash_bytes(b"first"), second.files[1].hash);
+        assert_eq!(2, num_files(&files_dir));
+        assert_eq!(
+            hash_file(&db_dir.path().join("000001.sst")).unwrap(),
+            CryptoHash::hash_bytes(b"other")
+        );
+    }
+}
@@ -1,11 +1,13 @@
 use crate::adjust_database::ChangeDbKindCommand;
 use crate::analyse_data_size_distribution::AnalyseDataSizeDistributionCommand;
+use crate::backfill_state_history::BackfillStateHistoryCommand;
+use crate::backup::{BackupCommand, RestoreCommand};
 use crate::compact::RunCompactionCommand;
 use crate::make_snapshot::MakeSnapshotCommand;
 use crate::run_migrations::RunMigrationsCommand;
 use crate::state_perf::StatePerfCommand;
 use clap::Parser;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 pub struct DatabaseCommand {
@@ -19,6 +21,14 @@ enum SubCommand {
     /// Analyse data size distribution in RocksDB
     AnalyseDataSizeDistribution(AnalyseDataSizeDistributionCommand),
 
+    /// Create the state history index, or add the blocks finalized since the node last ran to
+    /// it. The node must not be running.
+    BackfillStateHistory(BackfillStateHistoryCommand),
+
+    /// Make a backup of the hot database in external storage. Works while the node is running.
+    /// Only the files which are not in the latest backup yet are uploaded.
+    Backup(BackupCommand),
+
     /// Change DbKind of hot or cold db.
     ChangeDbKind(ChangeDbKindCommand),
 
@@ -28,6 +38,9 @@ enum SubCommand {
     /// Make snapshot of the database
     MakeSnapshot(MakeSnapshotCommand),
 
+    /// Restore the hot database from a backup made with `backup`.
+    Restore(RestoreCommand),
+
     /// Run migrations,
     RunMigrations(RunMigrationsCommand),
 
@@ -40,14 +53,19 @@ impl DatabaseCommand {
     pub fn run(&self, home: &PathBuf) -> anyhow::Result<()> {
         match &self.subcmd {
             SubCommand::AnalyseDataSizeDistribution(cmd) => cmd.run(home),
+            SubCommand::BackfillStateHistory(cmd) => cmd.run(home),
+            SubCommand::Backup(cmd) => {
+                let near_config = load_config(home);
+                cmd.run(home, near_config.config.archive, &near_config.config.store)
+            }
             SubCommand::ChangeDbKind(cmd) => cmd.run(home),
             SubCommand::CompactDatabase(cmd) => cmd.run(home),
             SubCommand::MakeSnapshot(cmd) => {
-                let near_config = nearcore::config::load_config(
-                    &home,
-                    near_chain_configs::GenesisValidationMode::UnsafeFast,
-                )
-                .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+                let near_config = load_config(home);
+                cmd.run(home, near_config.config.archive, &near_config.config.store)
+            }
+            SubCommand::Restore(cmd) => {
+                let near_config = load_config(home);
                 cmd.run(home, near_config.config.archive, &near_config.config.store)
             }
             SubCommand::RunMigrations(cmd) => cmd.run(home),
@@ -55,3 +73,8 @@ impl DatabaseCommand {
         }
     }
 }
+
+fn load_config(home: &Path) -> nearcore::NearConfig {
+    nearcore::config::load_config(home, near_chain_configs::GenesisValidationMode::UnsafeFast)
+        .unwrap_or_else(|e| panic!("Error loading config: {:#}", e))
+}
@@ -1,5 +1,7 @@
 mod adjust_database;
 mod analyse_data_size_distribution;
+mod backfill_state_history;
+mod backup;
 pub mod commands;
 mod compact;
 mod make_snapshot;
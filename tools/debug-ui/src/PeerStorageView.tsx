@@ -28,6 +28,8 @@ export const PeerStorageView = ({ addr }: PeerStorageViewProps) => {
                 <th>Last seen</th>
                 <th>Last connection attempt</th>
                 <th>Status</th>
+                <th>Reputation</th>
+                <th>Avg response latency</th>
             </thead>
             <tbody>
                 {peerStore!.status_response.PeerStore.peer_states.map((peer) => {
@@ -56,6 +58,11 @@ export const PeerStorageView = ({ addr }: PeerStorageViewProps) => {
                                     <td>{peer.status}</td>
                                 </>
                             )}
+                            <td>{peer.reputation}</td>
+                            <td>
+                                {peer.avg_response_latency_ms !== null &&
+                                    `${peer.avg_response_latency_ms} ms`}
+                            </td>
                         </tr>
                     );
                 })}
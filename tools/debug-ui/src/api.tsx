@@ -136,6 +136,18 @@ export type SyncStatusView =
 export interface ShardSyncDownloadView {
     downloads: { error: boolean; done: boolean }[];
     status: string;
+    peers: StateSyncPeerView[];
+}
+
+export interface StateSyncPeerView {
+    target: string;
+    parts_in_flight: number;
+    max_parts_in_flight: number;
+    parts_received: number;
+    bytes_received: number;
+    failures: number;
+    avg_latency_ms: number | null;
+    throughput_bytes_per_sec: number;
 }
 
 export interface DebugBlockStatusData {
@@ -243,6 +255,8 @@ export interface KnownPeerStateView {
     first_seen: number;
     last_seen: number;
     last_attempt: [number, string] | null;
+    reputation: number;
+    avg_response_latency_ms: number | null;
 }
 
 export interface SyncStatusResponse {
@@ -1,13 +1,12 @@
+use crate::patch::{apply_account_patch, install_validator, ForkPatch};
 use crate::storage_mutator::StorageMutator;
 use near_chain::types::Tip;
 use near_chain_configs::{Genesis, GenesisConfig, GenesisValidationMode};
 use near_epoch_manager::{EpochManager, EpochManagerAdapter};
-use near_primitives::account::AccessKey;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
+use near_primitives::utils::index_to_bytes;
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::{
-    account::Account, borsh::BorshSerialize, hash::CryptoHash, types::AccountInfo,
-};
+use near_primitives::{borsh::BorshSerialize, hash::CryptoHash, types::AccountInfo};
 use near_store::{flat::FlatStorageStatus, DBCol, Mode, NodeStorage, HEAD_KEY};
 use nearcore::{load_config, NightshadeRuntime, NEAR_BASE};
 use std::path::{Path, PathBuf};
@@ -19,6 +18,10 @@ pub struct ForkNetworkCommand {
     pub reset: bool,
     #[arg(short, long, default_value = "1000")]
     pub epoch_length: u64,
+    /// JSON file with the fork height, validators and account changes of the
+    /// forked network. See `patch.rs` for the format.
+    #[arg(long)]
+    pub patch: Option<PathBuf>,
 }
 
 impl ForkNetworkCommand {
@@ -59,6 +62,11 @@ impl ForkNetworkCommand {
         }
         let near_config = load_config(home_dir, genesis_validation)
             .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+        let patch = match &self.patch {
+            Some(path) => ForkPatch::from_file(path)?,
+            None => ForkPatch::default(),
+        };
+        let patch_dir = self.patch.as_deref().and_then(Path::parent).unwrap_or(home_dir);
 
         if !near_config.config.store.flat_storage_creation_enabled {
             panic!("Flat storage must be enabled");
@@ -103,7 +111,7 @@ impl ForkNetworkCommand {
         let shard_layout = epoch_manager.get_shard_layout(&head.epoch_id)?;
         let all_shard_uids = shard_layout.get_shard_uids();
 
-        let fork_head = {
+        let flat_head = {
             let mut flat_head: Option<CryptoHash> = None;
             for shard_uid in all_shard_uids {
                 let flat_storage_status = store
@@ -124,9 +132,21 @@ impl ForkNetworkCommand {
                     );
                 }
             }
-            println!("Forking from the flat storage final head: {}", flat_head.unwrap());
             flat_head.unwrap()
         };
+        let (fork_head, update_flat_state) = match patch.fork_height {
+            Some(fork_height) => {
+                let fork_head = store
+                    .get_ser::<CryptoHash>(DBCol::BlockHeight, &index_to_bytes(fork_height))?
+                    .ok_or_else(|| anyhow::anyhow!("No block at height {fork_height}"))?;
+                println!("Forking from block {} at height {}", fork_head, fork_height);
+                (fork_head, fork_head == flat_head)
+            }
+            None => {
+                println!("Forking from the flat storage final head: {}", flat_head);
+                (flat_head, true)
+            }
+        };
 
         let fork_head_block = store
             .get_ser::<near_primitives::block::Block>(
@@ -141,12 +161,15 @@ impl ForkNetworkCommand {
             .map(|chunk| chunk.prev_state_root())
             .collect::<Vec<_>>();
 
-        // TODO(robin-near): Make this part customizable via some kind of config file.
-        let self_validator = near_config.validator_signer.as_ref().unwrap();
-        let self_account = AccountInfo {
-            account_id: self_validator.validator_id().clone(),
-            amount: 50_000 * NEAR_BASE,
-            public_key: self_validator.public_key(),
+        let validators: Vec<AccountInfo> = if patch.validators.is_empty() {
+            let self_validator = near_config.validator_signer.as_ref().unwrap();
+            vec![AccountInfo {
+                account_id: self_validator.validator_id().clone(),
+                amount: 50_000 * NEAR_BASE,
+                public_key: self_validator.public_key(),
+            }]
+        } else {
+            patch.validators.into_iter().map(Into::into).collect()
         };
 
         let mut storage_mutator = StorageMutator::new(
@@ -159,19 +182,27 @@ impl ForkNetworkCommand {
 
         let runtime_config_store = RuntimeConfigStore::new(None);
         let runtime_config = runtime_config_store.get_config(PROTOCOL_VERSION);
-        let storage_bytes = runtime_config.fees.storage_usage_config.num_bytes_account;
+        let storage_usage_config = &runtime_config.fees.storage_usage_config;
         let liquid_balance = 100_000_000 * NEAR_BASE;
-        storage_mutator.set_account(
-            self_validator.validator_id().clone(),
-            Account::new(liquid_balance, self_account.amount, CryptoHash::default(), storage_bytes),
-        )?;
-        storage_mutator.set_access_key(
-            self_account.account_id.clone(),
-            self_account.public_key.clone(),
-            AccessKey::full_access(),
-        )?;
+        for validator in &validators {
+            install_validator(
+                &mut storage_mutator,
+                storage_usage_config,
+                validator,
+                liquid_balance,
+            )?;
+        }
+        for account_patch in patch.accounts {
+            println!("Patching account {}", account_patch.account_id);
+            apply_account_patch(
+                &mut storage_mutator,
+                storage_usage_config,
+                account_patch,
+                patch_dir,
+            )?;
+        }
 
-        let new_state_roots = storage_mutator.commit()?;
+        let new_state_roots = storage_mutator.commit(update_flat_state)?;
 
         println!("Creating a new genesis");
         let epoch_config = epoch_manager.get_epoch_config(&fork_head_block.header().epoch_id())?;
@@ -179,10 +210,10 @@ impl ForkNetworkCommand {
         let original_config = near_config.genesis.config.clone();
 
         let new_config = GenesisConfig {
-            chain_id: original_config.chain_id.clone() + "-fork",
+            chain_id: patch.chain_id.unwrap_or_else(|| original_config.chain_id.clone() + "-fork"),
             genesis_height: fork_head_block.header().height(),
             genesis_time: fork_head_block.header().timestamp(),
-            epoch_length: self.epoch_length,
+            epoch_length: patch.epoch_length.unwrap_or(self.epoch_length),
             num_block_producer_seats: epoch_config.num_block_producer_seats,
             num_block_producer_seats_per_shard: epoch_config.num_block_producer_seats_per_shard,
             avg_hidden_validator_seats_per_shard: epoch_config.avg_hidden_validator_seats_per_shard,
@@ -204,7 +235,7 @@ impl ForkNetworkCommand {
             minimum_stake_ratio: epoch_config.validator_selection_config.minimum_stake_ratio,
             dynamic_resharding: false,
             protocol_version: epoch_info.protocol_version(),
-            validators: vec![self_account],
+            validators,
             gas_price_adjustment_rate: original_config.gas_price_adjustment_rate,
             gas_limit: original_config.gas_limit,
             max_gas_price: original_config.max_gas_price,
@@ -1,2 +1,3 @@
 pub mod cli;
+mod patch;
 mod storage_mutator;
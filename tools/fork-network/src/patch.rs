@@ -0,0 +1,364 @@
+use crate::storage_mutator::StorageMutator;
+use anyhow::Context;
+use near_crypto::PublicKey;
+use near_primitives::account::{AccessKey, Account};
+use near_primitives::borsh::BorshSerialize;
+use near_primitives::hash::CryptoHash;
+use near_primitives::runtime::fees::StorageUsageConfig;
+use near_primitives::serialize::dec_format;
+use near_primitives::types::{AccountId, AccountInfo, Balance, BlockHeight, BlockHeightDelta};
+use near_primitives::views::AccessKeyView;
+use std::path::{Path, PathBuf};
+
+/// Changes to make to the forked network, read from a JSON file.
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ForkPatch {
+    /// Height of the block to fork at. Defaults to the flat storage head.
+    /// Forking at another block rebuilds the flat state from scratch.
+    pub fork_height: Option<BlockHeight>,
+    /// Defaults to the original chain id with a `-fork` suffix.
+    pub chain_id: Option<String>,
+    /// Overrides `--epoch-length`.
+    pub epoch_length: Option<BlockHeightDelta>,
+    /// Validators of the forked network. Defaults to the validator of this node.
+    #[serde(default)]
+    pub validators: Vec<ValidatorPatch>,
+    /// Changes to the accounts, applied after the validators are installed.
+    #[serde(default)]
+    pub accounts: Vec<AccountPatch>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorPatch {
+    pub account_id: AccountId,
+    pub public_key: PublicKey,
+    #[serde(with = "dec_format")]
+    pub amount: Balance,
+}
+
+impl From<ValidatorPatch> for AccountInfo {
+    fn from(validator: ValidatorPatch) -> Self {
+        AccountInfo {
+            account_id: validator.account_id,
+            public_key: validator.public_key,
+            amount: validator.amount,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AccountPatch {
+    pub account_id: AccountId,
+    /// Liquid balance. The account is created if it doesn't exist.
+    #[serde(default, with = "dec_format")]
+    pub amount: Option<Balance>,
+    #[serde(default, with = "dec_format")]
+    pub locked: Option<Balance>,
+    /// Path to the contract to deploy, relative to the patch file.
+    pub code: Option<PathBuf>,
+    /// Removes all the existing access keys of the account.
+    #[serde(default)]
+    pub remove_access_keys: bool,
+    /// Access keys to add or replace.
+    #[serde(default)]
+    pub access_keys: Vec<AccessKeyPatch>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AccessKeyPatch {
+    pub public_key: PublicKey,
+    pub access_key: AccessKeyView,
+}
+
+impl ForkPatch {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&data).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+}
+
+fn access_key_storage_usage(
+    config: &StorageUsageConfig,
+    public_key: &PublicKey,
+    access_key: &AccessKey,
+) -> anyhow::Result<u64> {
+    Ok(config.num_extra_bytes_record
+        + public_key.len() as u64
+        + access_key.try_to_vec()?.len() as u64)
+}
+
+/// Stakes `amount` for the validator, creating its account with
+/// `liquid_balance` and a full access key if it doesn't exist.
+pub fn install_validator(
+    storage_mutator: &mut StorageMutator,
+    config: &StorageUsageConfig,
+    validator: &AccountInfo,
+    liquid_balance: Balance,
+) -> anyhow::Result<()> {
+    let account = match storage_mutator.get_account(&validator.account_id)? {
+        Some(mut account) => {
+            account.set_locked(validator.amount);
+            account
+        }
+        None => {
+            let access_key = AccessKey::full_access();
+            let storage_usage = config.num_bytes_account
+                + access_key_storage_usage(config, &validator.public_key, &access_key)?;
+            storage_mutator.set_access_key(
+                validator.account_id.clone(),
+                validator.public_key.clone(),
+                access_key,
+            )?;
+            Account::new(liquid_balance, validator.amount, CryptoHash::default(), storage_usage)
+        }
+    };
+    storage_mutator.set_account(validator.account_id.clone(), account)
+}
+
+/// Applies the changes to the account, keeping its storage usage consistent
+/// with the changed code and access keys.
+pub fn apply_account_patch(
+    storage_mutator: &mut StorageMutator,
+    config: &StorageUsageConfig,
+    patch: AccountPatch,
+    base_dir: &Path,
+) -> anyhow::Result<()> {
+    let account_id = patch.account_id;
+    let mut account = match storage_mutator.get_account(&account_id)? {
+        Some(account) => account,
+        None => Account::new(0, 0, CryptoHash::default(), config.num_bytes_account),
+    };
+    let mut storage_usage = account.storage_usage();
+    if let Some(amount) = patch.amount {
+        account.set_amount(amount);
+    }
+    if let Some(locked) = patch.locked {
+        account.set_locked(locked);
+    }
+
+    if let Some(code) = patch.code {
+        let path = base_dir.join(code);
+        let code =
+            std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let old_len = storage_mutator.get_code_len(&account_id)? as u64;
+        storage_usage = storage_usage.saturating_sub(old_len) + code.len() as u64;
+        account.set_code_hash(CryptoHash::hash_bytes(&code));
+        storage_mutator.set_code(account_id.clone(), code)?;
+    }
+
+    let mut access_keys = storage_mutator.get_access_keys(&account_id)?;
+    if patch.remove_access_keys {
+        for (public_key, access_key) in access_keys.drain(..) {
+            storage_usage = storage_usage.saturating_sub(access_key_storage_usage(
+                config,
+                &public_key,
+                &access_key,
+            )?);
+            storage_mutator.remove_access_key(account_id.clone(), public_key)?;
+        }
+    }
+    for AccessKeyPatch { public_key, access_key } in patch.access_keys {
+        let access_key = AccessKey::from(access_key);
+        if let Some((_, old_access_key)) = access_keys.iter().find(|(key, _)| key == &public_key) {
+            storage_usage = storage_usage.saturating_sub(access_key_storage_usage(
+                config,
+                &public_key,
+                old_access_key,
+            )?);
+        }
+        storage_usage += access_key_storage_usage(config, &public_key, &access_key)?;
+        storage_mutator.set_access_key(account_id.clone(), public_key, access_key)?;
+    }
+
+    account.set_storage_usage(storage_usage);
+    storage_mutator.set_account(account_id, account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_chain::test_utils::MockEpochManager;
+    use near_crypto::{KeyType, SecretKey};
+    use near_primitives::runtime::fees::RuntimeFeesConfig;
+    use near_primitives::types::EpochId;
+    use near_primitives::views::AccessKeyPermissionView;
+    use near_store::test_utils::{create_test_store, create_tries};
+
+    fn storage_mutator() -> StorageMutator {
+        let epoch_manager = MockEpochManager::new(create_test_store(), 10);
+        StorageMutator::new_empty(epoch_manager, &EpochId::default(), create_tries()).unwrap()
+    }
+
+    fn public_key(seed: &str) -> PublicKey {
+        SecretKey::from_seed(KeyType::ED25519, seed).public_key()
+    }
+
+    fn function_call_key() -> AccessKeyView {
+        AccessKeyView {
+            nonce: 0,
+            permission: AccessKeyPermissionView::FunctionCall {
+                allowance: None,
+                receiver_id: "alice.near".to_string(),
+                method_names: vec!["set_status".to_string()],
+            },
+        }
+    }
+
+    fn account_patch(account_id: &AccountId) -> AccountPatch {
+        AccountPatch {
+            account_id: account_id.clone(),
+            amount: None,
+            locked: None,
+            code: None,
+            remove_access_keys: false,
+            access_keys: vec![],
+        }
+    }
+
+    #[test]
+    fn test_parse_patch() {
+        let patch: ForkPatch = serde_json::from_str(
+            r#"{
+                "fork_height": 1000,
+                "epoch_length": 100,
+                "validators": [{
+                    "account_id": "test0",
+                    "public_key": "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp",
+                    "amount": "50000000000000000000000000000"
+                }],
+                "accounts": [{
+                    "account_id": "alice.near",
+                    "amount": "1000000000000000000000000",
+                    "code": "contract.wasm",
+                    "remove_access_keys": true,
+                    "access_keys": [{
+                        "public_key": "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp",
+                        "access_key": {"nonce": 0, "permission": "FullAccess"}
+                    }]
+                }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(patch.fork_height, Some(1000));
+        assert_eq!(patch.epoch_length, Some(100));
+        assert_eq!(patch.validators[0].amount, 50_000_000_000_000_000_000_000_000_000);
+        let account = &patch.accounts[0];
+        assert_eq!(account.amount, Some(1_000_000_000_000_000_000_000_000));
+        assert_eq!(account.locked, None);
+        assert!(account.remove_access_keys);
+        assert_eq!(account.access_keys.len(), 1);
+
+        let err = serde_json::from_str::<ForkPatch>(r#"{"epoch_lenght": 100}"#).unwrap_err();
+        assert!(err.to_string().contains("epoch_lenght"), "{err}");
+    }
+
+    #[test]
+    fn test_install_validator() {
+        let config = RuntimeFeesConfig::test().storage_usage_config;
+        let mut storage_mutator = storage_mutator();
+        let mut validator = AccountInfo {
+            account_id: "test0".parse().unwrap(),
+            public_key: public_key("test0"),
+            amount: 100,
+        };
+        install_validator(&mut storage_mutator, &config, &validator, 20).unwrap();
+        let account = storage_mutator.get_account(&validator.account_id).unwrap().unwrap();
+        let full_access_usage =
+            access_key_storage_usage(&config, &validator.public_key, &AccessKey::full_access())
+                .unwrap();
+        assert_eq!(account.amount(), 20);
+        assert_eq!(account.locked(), 100);
+        assert_eq!(account.storage_usage(), config.num_bytes_account + full_access_usage);
+        assert_eq!(
+            storage_mutator.get_access_keys(&validator.account_id).unwrap(),
+            vec![(validator.public_key.clone(), AccessKey::full_access())]
+        );
+
+        // Only the stake of an existing account changes.
+        validator.amount = 200;
+        validator.public_key = public_key("other");
+        install_validator(&mut storage_mutator, &config, &validator, 30).unwrap();
+        let account = storage_mutator.get_account(&validator.account_id).unwrap().unwrap();
+        assert_eq!(account.amount(), 20);
+        assert_eq!(account.locked(), 200);
+        assert_eq!(account.storage_usage(), config.num_bytes_account + full_access_usage);
+        assert_eq!(storage_mutator.get_access_keys(&validator.account_id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_apply_account_patch() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("large.wasm"), vec![1; 100]).unwrap();
+        std::fs::write(dir.path().join("small.wasm"), vec![2; 40]).unwrap();
+        let config = RuntimeFeesConfig::test().storage_usage_config;
+        let mut storage_mutator = storage_mutator();
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let (key1, key2) = (public_key("key1"), public_key("key2"));
+        let usage = |public_key: &PublicKey, access_key: AccessKeyView| {
+            access_key_storage_usage(&config, public_key, &access_key.into()).unwrap()
+        };
+        let full_access = || AccessKeyView::from(AccessKey::full_access());
+
+        // A new account with a contract and a key.
+        let patch = AccountPatch {
+            amount: Some(1000),
+            code: Some("large.wasm".into()),
+            access_keys: vec![AccessKeyPatch {
+                public_key: key1.clone(),
+                access_key: full_access(),
+            }],
+            ..account_patch(&account_id)
+        };
+        apply_account_patch(&mut storage_mutator, &config, patch, dir.path()).unwrap();
+        let account = storage_mutator.get_account(&account_id).unwrap().unwrap();
+        assert_eq!(account.amount(), 1000);
+        assert_eq!(account.code_hash(), CryptoHash::hash_bytes(&[1; 100]));
+        assert_eq!(storage_mutator.get_code_len(&account_id).unwrap(), 100);
+        let mut expected_usage = config.num_bytes_account + 100 + usage(&key1, full_access());
+        assert_eq!(account.storage_usage(), expected_usage);
+
+        // The code is replaced by a smaller one, and the key by a function call key.
+        let patch = AccountPatch {
+            code: Some("small.wasm".into()),
+            access_keys: vec![AccessKeyPatch {
+                public_key: key1.clone(),
+                access_key: function_call_key(),
+            }],
+            ..account_patch(&account_id)
+        };
+        apply_account_patch(&mut storage_mutator, &config, patch, dir.path()).unwrap();
+        let account = storage_mutator.get_account(&account_id).unwrap().unwrap();
+        assert_eq!(account.amount(), 1000);
+        assert_eq!(account.code_hash(), CryptoHash::hash_bytes(&[2; 40]));
+        assert_eq!(storage_mutator.get_code_len(&account_id).unwrap(), 40);
+        expected_usage = config.num_bytes_account + 40 + usage(&key1, function_call_key());
+        assert_eq!(account.storage_usage(), expected_usage);
+        assert_eq!(
+            storage_mutator.get_access_keys(&account_id).unwrap(),
+            vec![(key1.clone(), AccessKey::from(function_call_key()))]
+        );
+
+        // The existing keys are removed before the new ones are added.
+        let patch = AccountPatch {
+            remove_access_keys: true,
+            access_keys: vec![AccessKeyPatch {
+                public_key: key2.clone(),
+                access_key: full_access(),
+            }],
+            ..account_patch(&account_id)
+        };
+        apply_account_patch(&mut storage_mutator, &config, patch, dir.path()).unwrap();
+        let account = storage_mutator.get_account(&account_id).unwrap().unwrap();
+        expected_usage = config.num_bytes_account + 40 + usage(&key2, full_access());
+        assert_eq!(account.storage_usage(), expected_usage);
+        assert_eq!(
+            storage_mutator.get_access_keys(&account_id).unwrap(),
+            vec![(key2, AccessKey::full_access())]
+        );
+    }
+}
@@ -7,10 +7,15 @@ use near_primitives::{
     account::{AccessKey, Account},
     borsh::BorshSerialize,
     hash::CryptoHash,
-    trie_key::TrieKey,
+    shard_layout::ShardUId,
+    state::FlatStateValue,
+    trie_key::{trie_key_parsers, TrieKey},
     types::{AccountId, EpochId, StateRoot},
 };
-use near_store::{flat::FlatStateChanges, ShardTries, TrieUpdate};
+use near_store::{
+    flat::{store_helper, FlatStateChanges},
+    ShardTries, TrieUpdate,
+};
 use nearcore::NightshadeRuntime;
 
 pub struct StorageMutator {
@@ -49,6 +54,59 @@ impl StorageMutator {
         })
     }
 
+    /// Changes the empty state of every shard of the epoch.
+    #[cfg(test)]
+    pub(crate) fn new_empty(
+        epoch_manager: Arc<dyn EpochManagerAdapter>,
+        epoch_id: &EpochId,
+        shard_tries: ShardTries,
+    ) -> anyhow::Result<Self> {
+        let shard_layout = epoch_manager.get_shard_layout(epoch_id)?;
+        let tries = shard_layout
+            .get_shard_uids()
+            .into_iter()
+            .map(|shard_uid| shard_tries.new_trie_update(shard_uid, near_store::Trie::EMPTY_ROOT))
+            .collect();
+        Ok(Self { epoch_manager, epoch_id: epoch_id.clone(), tries, shard_tries })
+    }
+
+    fn trie(&self, account_id: &AccountId) -> anyhow::Result<&TrieUpdate> {
+        let shard_id = self.epoch_manager.account_id_to_shard_id(account_id, &self.epoch_id)?;
+        Ok(&self.tries[shard_id as usize])
+    }
+
+    pub fn get_account(&self, account_id: &AccountId) -> anyhow::Result<Option<Account>> {
+        Ok(near_store::get_account(self.trie(account_id)?, account_id)?)
+    }
+
+    /// Returns the size of the contract deployed to the account, or 0 if there is none.
+    pub fn get_code_len(&self, account_id: &AccountId) -> anyhow::Result<usize> {
+        let code = self
+            .trie(account_id)?
+            .get(&TrieKey::ContractCode { account_id: account_id.clone() })?;
+        Ok(code.map_or(0, |code| code.len()))
+    }
+
+    pub fn get_access_keys(
+        &self,
+        account_id: &AccountId,
+    ) -> anyhow::Result<Vec<(PublicKey, AccessKey)>> {
+        let trie = self.trie(account_id)?;
+        let prefix = trie_key_parsers::get_raw_prefix_for_access_keys(account_id);
+        let mut access_keys = vec![];
+        for raw_key in trie.iter(&prefix)? {
+            let raw_key = raw_key?;
+            let public_key =
+                trie_key_parsers::parse_public_key_from_access_key_key(&raw_key, account_id)?;
+            let access_key = near_store::get_access_key(trie, account_id, &public_key)?
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Missing access key {public_key} of {account_id}")
+                })?;
+            access_keys.push((public_key, access_key));
+        }
+        Ok(access_keys)
+    }
+
     pub fn set_account(&mut self, account_id: AccountId, value: Account) -> anyhow::Result<()> {
         let shard_id = self.epoch_manager.account_id_to_shard_id(&account_id, &self.epoch_id)?;
         self.tries[shard_id as usize].set(TrieKey::Account { account_id }, value.try_to_vec()?);
@@ -67,7 +125,28 @@ impl StorageMutator {
         Ok(())
     }
 
-    pub fn commit(self) -> anyhow::Result<Vec<StateRoot>> {
+    pub fn remove_access_key(
+        &mut self,
+        account_id: AccountId,
+        public_key: PublicKey,
+    ) -> anyhow::Result<()> {
+        let shard_id = self.epoch_manager.account_id_to_shard_id(&account_id, &self.epoch_id)?;
+        self.tries[shard_id as usize].remove(TrieKey::AccessKey { account_id, public_key });
+        Ok(())
+    }
+
+    pub fn set_code(&mut self, account_id: AccountId, code: Vec<u8>) -> anyhow::Result<()> {
+        let shard_id = self.epoch_manager.account_id_to_shard_id(&account_id, &self.epoch_id)?;
+        self.tries[shard_id as usize].set(TrieKey::ContractCode { account_id }, code);
+        Ok(())
+    }
+
+    /// Writes the changes to the state and returns the new state roots.
+    ///
+    /// If the flat state corresponds to the state the changes were made to,
+    /// the changes are applied to it. Otherwise the flat state is rebuilt
+    /// from the new state, which requires iterating over the whole state.
+    pub fn commit(self, update_flat_state: bool) -> anyhow::Result<Vec<StateRoot>> {
         let shard_layout = self.epoch_manager.get_shard_layout(&self.epoch_id)?;
         let all_shard_uids = shard_layout.get_shard_uids();
         let mut state_roots = Vec::new();
@@ -78,10 +157,55 @@ impl StorageMutator {
             let (_, trie_updates, raw_changes) = trie_update.finalize()?;
             let state_root = self.shard_tries.apply_all(&trie_updates, shard_uid, &mut update);
             state_roots.push(state_root);
-            let flat_state_changes = FlatStateChanges::from_state_changes(&raw_changes);
-            flat_state_changes.apply_to_flat_state(&mut update, shard_uid);
+            if update_flat_state {
+                let flat_state_changes = FlatStateChanges::from_state_changes(&raw_changes);
+                flat_state_changes.apply_to_flat_state(&mut update, shard_uid);
+            }
         }
         update.commit()?;
+
+        if !update_flat_state {
+            for (shard_uid, state_root) in
+                shard_layout.get_shard_uids().into_iter().zip(&state_roots)
+            {
+                rebuild_flat_state(&self.shard_tries, shard_uid, *state_root)?;
+            }
+        }
         Ok(state_roots)
     }
 }
+
+/// Replaces the flat state of the shard with the values of the given state.
+fn rebuild_flat_state(
+    shard_tries: &ShardTries,
+    shard_uid: ShardUId,
+    state_root: StateRoot,
+) -> anyhow::Result<()> {
+    const BATCH_SIZE: usize = 100_000;
+
+    println!("Rebuilding flat state of shard {shard_uid} from state root {state_root}");
+    let mut update = shard_tries.store_update();
+    store_helper::remove_all_flat_state_values(&mut update, shard_uid);
+    update.commit()?;
+
+    let trie = shard_tries.get_trie_for_shard(shard_uid, state_root);
+    let mut update = shard_tries.store_update();
+    let mut num_values = 0;
+    for item in trie.iter()? {
+        let (key, value) = item?;
+        store_helper::set_flat_state_value(
+            &mut update,
+            shard_uid,
+            key,
+            Some(FlatStateValue::on_disk(&value)),
+        );
+        num_values += 1;
+        if num_values % BATCH_SIZE == 0 {
+            std::mem::replace(&mut update, shard_tries.store_update()).commit()?;
+            println!("Wrote {num_values} flat state values of shard {shard_uid}");
+        }
+    }
+    update.commit()?;
+    println!("Wrote {num_values} flat state values of shard {shard_uid}");
+    Ok(())
+}
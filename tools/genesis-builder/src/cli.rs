@@ -0,0 +1,46 @@
+use crate::{build_genesis, GenesisSpec};
+use std::path::{Path, PathBuf};
+
+#[derive(clap::Parser)]
+pub struct GenesisCommand {
+    #[clap(subcommand)]
+    subcmd: GenesisSubCommand,
+}
+
+#[derive(clap::Parser)]
+enum GenesisSubCommand {
+    /// Builds genesis.json and the keys of the validators and accounts from a
+    /// TOML spec.
+    Build(BuildCmd),
+}
+
+#[derive(clap::Parser)]
+struct BuildCmd {
+    /// Path to the genesis spec.
+    #[clap(long)]
+    spec: PathBuf,
+    /// Directory to write genesis.json and the generated keys to.
+    #[clap(long, default_value = ".")]
+    output_dir: PathBuf,
+    /// Write the records to this file in the output directory instead of
+    /// including them in genesis.json.
+    #[clap(long)]
+    records_file: Option<PathBuf>,
+}
+
+impl GenesisCommand {
+    pub fn run(self) -> anyhow::Result<()> {
+        match self.subcmd {
+            GenesisSubCommand::Build(cmd) => cmd.run(),
+        }
+    }
+}
+
+impl BuildCmd {
+    fn run(self) -> anyhow::Result<()> {
+        let spec = GenesisSpec::from_file(&self.spec)?;
+        let spec_dir = self.spec.parent().unwrap_or(Path::new("."));
+        let built = build_genesis(spec, spec_dir)?;
+        built.write(&self.output_dir, self.records_file.as_deref())
+    }
+}
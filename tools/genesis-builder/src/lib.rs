@@ -0,0 +1,512 @@
+use anyhow::Context;
+use near_chain_configs::{get_initial_supply, Genesis, GenesisConfig, GenesisRecords};
+use near_crypto::{InMemorySigner, KeyType, PublicKey, SecretKey, Signer};
+use near_primitives::account::{AccessKey, AccessKeyPermission, Account, FunctionCallPermission};
+use near_primitives::hash::CryptoHash;
+use near_primitives::serialize::dec_format;
+use near_primitives::shard_layout::{ShardLayout, ShardVersion};
+use near_primitives::state_record::StateRecord;
+use near_primitives::types::{AccountId, AccountInfo, Balance, BlockHeightDelta, NumSeats};
+use near_primitives::utils::get_num_seats_per_shard;
+use near_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
+use nearcore::config::{
+    BLOCK_PRODUCER_KICKOUT_THRESHOLD, CHUNK_PRODUCER_KICKOUT_THRESHOLD, EXPECTED_EPOCH_LENGTH,
+    FISHERMEN_THRESHOLD, GAS_PRICE_ADJUSTMENT_RATE, GENESIS_CONFIG_FILENAME, INITIAL_GAS_LIMIT,
+    MAX_INFLATION_RATE, MIN_GAS_PRICE, NODE_KEY_FILE, NUM_BLOCKS_PER_YEAR,
+    NUM_BLOCK_PRODUCER_SEATS, PROTOCOL_REWARD_RATE, PROTOCOL_UPGRADE_STAKE_THRESHOLD,
+    TRANSACTION_VALIDITY_PERIOD, VALIDATOR_KEY_FILE,
+};
+use num_rational::Rational32;
+use std::path::{Path, PathBuf};
+
+pub mod cli;
+
+/// Declarative description of a genesis, read from a TOML file.
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisSpec {
+    pub chain_id: String,
+    /// Defaults to the latest protocol version.
+    pub protocol_version: Option<ProtocolVersion>,
+    /// Defaults to the epoch length of `neard localnet`.
+    pub epoch_length: Option<BlockHeightDelta>,
+    /// Defaults to the number of seats of `neard localnet`.
+    pub num_block_producer_seats: Option<NumSeats>,
+    /// Defaults to a single shard.
+    #[serde(default)]
+    pub shard_layout: ShardLayoutSpec,
+    #[serde(default)]
+    pub validators: Vec<ValidatorSpec>,
+    #[serde(default)]
+    pub accounts: Vec<AccountSpec>,
+    /// Values of the genesis config fields, in the format of `genesis.json`,
+    /// overriding the ones derived from the rest of the spec.
+    #[serde(default)]
+    pub config: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ShardLayoutSpec {
+    /// Accounts which separate the shards. Without them there is a single shard.
+    #[serde(default)]
+    pub boundary_accounts: Vec<AccountId>,
+    /// Defaults to 1.
+    pub version: Option<ShardVersion>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorSpec {
+    pub account_id: AccountId,
+    #[serde(with = "dec_format")]
+    pub stake: Balance,
+    /// Liquid balance of the validator account.
+    #[serde(default, with = "dec_format")]
+    pub balance: Balance,
+    /// Validator key. If not given, a new key is generated and saved to
+    /// `<account_id>/validator_key.json` in the output directory.
+    pub public_key: Option<PublicKey>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AccountSpec {
+    pub account_id: AccountId,
+    #[serde(with = "dec_format")]
+    pub balance: Balance,
+    /// Path to the contract to deploy, relative to the spec file.
+    pub contract: Option<PathBuf>,
+    /// If no keys are given, a new full access key is generated and saved to
+    /// `keys/<account_id>.json` in the output directory.
+    #[serde(default)]
+    pub access_keys: Vec<AccessKeySpec>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AccessKeySpec {
+    pub public_key: PublicKey,
+    /// Account whose methods the key can call. Without it the key has full access.
+    pub receiver_id: Option<AccountId>,
+    /// Methods the key can call, any method if empty.
+    #[serde(default)]
+    pub method_names: Vec<String>,
+    /// Unlimited if not given.
+    #[serde(default, with = "dec_format")]
+    pub allowance: Option<Balance>,
+}
+
+impl AccessKeySpec {
+    fn access_key(&self) -> AccessKey {
+        let permission = match &self.receiver_id {
+            None => AccessKeyPermission::FullAccess,
+            Some(receiver_id) => AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                allowance: self.allowance,
+                receiver_id: receiver_id.to_string(),
+                method_names: self.method_names.clone(),
+            }),
+        };
+        AccessKey { nonce: 0, permission }
+    }
+}
+
+impl GenesisSpec {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&data).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+}
+
+/// Genesis built from a spec, together with the keys generated for it.
+pub struct BuiltGenesis {
+    pub genesis: Genesis,
+    /// Validator keys which weren't given in the spec.
+    pub validator_keys: Vec<InMemorySigner>,
+    /// Keys of the accounts which had no access keys in the spec.
+    pub account_keys: Vec<InMemorySigner>,
+}
+
+fn generate_key(account_id: &AccountId) -> InMemorySigner {
+    InMemorySigner::from_secret_key(account_id.clone(), SecretKey::from_random(KeyType::ED25519))
+}
+
+fn add_access_key(
+    records: &mut Vec<StateRecord>,
+    account_id: &AccountId,
+    access_key_spec: AccessKeySpec,
+) {
+    records.push(StateRecord::AccessKey {
+        account_id: account_id.clone(),
+        access_key: access_key_spec.access_key(),
+        public_key: access_key_spec.public_key,
+    });
+}
+
+/// Builds the genesis described by the spec and validates it.
+/// Contract paths are resolved relative to `spec_dir`.
+pub fn build_genesis(spec: GenesisSpec, spec_dir: &Path) -> anyhow::Result<BuiltGenesis> {
+    let mut records = vec![];
+    let mut validators = vec![];
+    let mut validator_keys = vec![];
+    let mut account_keys = vec![];
+
+    for validator in spec.validators {
+        let public_key = match validator.public_key {
+            Some(public_key) => public_key,
+            None => {
+                let signer = generate_key(&validator.account_id);
+                let public_key = signer.public_key();
+                validator_keys.push(signer);
+                public_key
+            }
+        };
+        records.push(StateRecord::Account {
+            account_id: validator.account_id.clone(),
+            account: Account::new(validator.balance, validator.stake, CryptoHash::default(), 0),
+        });
+        records.push(StateRecord::AccessKey {
+            account_id: validator.account_id.clone(),
+            public_key: public_key.clone(),
+            access_key: AccessKey::full_access(),
+        });
+        validators.push(AccountInfo {
+            account_id: validator.account_id,
+            public_key,
+            amount: validator.stake,
+        });
+    }
+
+    for account in spec.accounts {
+        let code = match &account.contract {
+            Some(contract) => {
+                let path = spec_dir.join(contract);
+                Some(
+                    std::fs::read(&path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?,
+                )
+            }
+            None => None,
+        };
+        let code_hash = code.as_deref().map_or_else(CryptoHash::default, CryptoHash::hash_bytes);
+        records.push(StateRecord::Account {
+            account_id: account.account_id.clone(),
+            account: Account::new(account.balance, 0, code_hash, 0),
+        });
+        if let Some(code) = code {
+            records.push(StateRecord::Contract { account_id: account.account_id.clone(), code });
+        }
+        if account.access_keys.is_empty() {
+            let signer = generate_key(&account.account_id);
+            records.push(StateRecord::AccessKey {
+                account_id: account.account_id.clone(),
+                public_key: signer.public_key(),
+                access_key: AccessKey::full_access(),
+            });
+            account_keys.push(signer);
+        }
+        for access_key in account.access_keys {
+            add_access_key(&mut records, &account.account_id, access_key);
+        }
+    }
+
+    let shard_layout = if spec.shard_layout.boundary_accounts.is_empty() {
+        ShardLayout::v0_single_shard()
+    } else {
+        ShardLayout::v1(
+            spec.shard_layout.boundary_accounts,
+            None,
+            spec.shard_layout.version.unwrap_or(1),
+        )
+    };
+    let num_shards = shard_layout.num_shards();
+    let num_block_producer_seats =
+        spec.num_block_producer_seats.unwrap_or(NUM_BLOCK_PRODUCER_SEATS);
+    let protocol_treasury_account = match validators.first() {
+        Some(validator) => validator.account_id.clone(),
+        None => anyhow::bail!("The spec must have at least one validator"),
+    };
+    let genesis_config = GenesisConfig {
+        protocol_version: spec.protocol_version.unwrap_or(PROTOCOL_VERSION),
+        chain_id: spec.chain_id,
+        genesis_height: 0,
+        num_block_producer_seats,
+        num_block_producer_seats_per_shard: get_num_seats_per_shard(
+            num_shards,
+            num_block_producer_seats,
+        ),
+        avg_hidden_validator_seats_per_shard: vec![0; num_shards as usize],
+        dynamic_resharding: false,
+        protocol_upgrade_stake_threshold: PROTOCOL_UPGRADE_STAKE_THRESHOLD,
+        epoch_length: spec.epoch_length.unwrap_or(EXPECTED_EPOCH_LENGTH),
+        gas_limit: INITIAL_GAS_LIMIT,
+        gas_price_adjustment_rate: GAS_PRICE_ADJUSTMENT_RATE,
+        block_producer_kickout_threshold: BLOCK_PRODUCER_KICKOUT_THRESHOLD,
+        chunk_producer_kickout_threshold: CHUNK_PRODUCER_KICKOUT_THRESHOLD,
+        online_max_threshold: Rational32::new(99, 100),
+        online_min_threshold: Rational32::new(BLOCK_PRODUCER_KICKOUT_THRESHOLD as i32, 100),
+        validators,
+        transaction_validity_period: TRANSACTION_VALIDITY_PERIOD,
+        protocol_reward_rate: PROTOCOL_REWARD_RATE,
+        max_inflation_rate: MAX_INFLATION_RATE,
+        total_supply: get_initial_supply(&records),
+        num_blocks_per_year: NUM_BLOCKS_PER_YEAR,
+        protocol_treasury_account,
+        fishermen_threshold: FISHERMEN_THRESHOLD,
+        shard_layout,
+        min_gas_price: MIN_GAS_PRICE,
+        ..Default::default()
+    };
+    let genesis_config = override_config(genesis_config, spec.config)?;
+    let genesis = Genesis::new(genesis_config, GenesisRecords(records))?;
+    Ok(BuiltGenesis { genesis, validator_keys, account_keys })
+}
+
+/// Genesis config fields which can't be overridden, because other fields are derived from them,
+/// and the spec fields to set instead.
+const DERIVED_FROM: &[(&str, &str)] =
+    &[("shard_layout", "shard_layout"), ("num_block_producer_seats", "num_block_producer_seats")];
+
+/// Replaces the fields of the genesis config with the given values.
+fn override_config(
+    genesis_config: GenesisConfig,
+    overrides: serde_json::Map<String, serde_json::Value>,
+) -> anyhow::Result<GenesisConfig> {
+    let mut value = serde_json::to_value(genesis_config)?;
+    let fields = value.as_object_mut().unwrap();
+    for (field, field_value) in overrides {
+        if let Some((_, spec_field)) = DERIVED_FROM.iter().find(|(name, _)| *name == field) {
+            anyhow::bail!(
+                "Genesis config field {field} can't be overridden, set {spec_field} in the spec instead"
+            );
+        }
+        match fields.get_mut(&field) {
+            Some(old_value) => *old_value = field_value,
+            None => anyhow::bail!("Unknown genesis config field {field}"),
+        }
+    }
+    serde_json::from_value(value).context("Invalid genesis config overrides")
+}
+
+impl BuiltGenesis {
+    /// Writes `genesis.json`, the generated keys and, for every validator, a
+    /// node key to `output_dir`. The records are written to `records_file` in
+    /// `output_dir` if given, otherwise they are included in `genesis.json`.
+    /// Existing files are never overwritten.
+    pub fn write(&self, output_dir: &Path, records_file: Option<&Path>) -> anyhow::Result<()> {
+        let genesis_file = output_dir.join(GENESIS_CONFIG_FILENAME);
+        let records_file = records_file.map(|records_file| output_dir.join(records_file));
+        let keys_dir = output_dir.join("keys");
+        let mut output_files: Vec<_> =
+            std::iter::once(genesis_file.clone()).chain(records_file.clone()).collect();
+        for validator in &self.genesis.config.validators {
+            let node_dir = output_dir.join(validator.account_id.as_str());
+            output_files.push(node_dir.join(NODE_KEY_FILE));
+            if self.validator_keys.iter().any(|signer| signer.account_id == validator.account_id) {
+                output_files.push(node_dir.join(VALIDATOR_KEY_FILE));
+            }
+        }
+        for signer in &self.account_keys {
+            output_files.push(keys_dir.join(format!("{}.json", signer.account_id)));
+        }
+        for file in &output_files {
+            if file.exists() {
+                anyhow::bail!("{} already exists", file.display());
+            }
+        }
+        std::fs::create_dir_all(output_dir)?;
+        match records_file {
+            Some(records_file) => {
+                self.genesis.config.to_file(&genesis_file);
+                let mut records = vec![];
+                self.genesis.for_each_record(|record| records.push(record.clone()));
+                GenesisRecords(records).to_file(&records_file);
+                tracing::info!(target: "genesis", "Wrote {} and {}", genesis_file.display(), records_file.display());
+            }
+            None => {
+                self.genesis.to_file(&genesis_file);
+                tracing::info!(target: "genesis", "Wrote {}", genesis_file.display());
+            }
+        }
+
+        for validator in &self.genesis.config.validators {
+            let node_dir = output_dir.join(validator.account_id.as_str());
+            std::fs::create_dir_all(&node_dir)?;
+            let node_signer = generate_key(&"node".parse().unwrap());
+            node_signer.write_to_file(&node_dir.join(NODE_KEY_FILE))?;
+            if let Some(signer) =
+                self.validator_keys.iter().find(|signer| signer.account_id == validator.account_id)
+            {
+                signer.write_to_file(&node_dir.join(VALIDATOR_KEY_FILE))?;
+            }
+            tracing::info!(target: "genesis", "Wrote the keys of {} to {}", validator.account_id, node_dir.display());
+        }
+
+        if !self.account_keys.is_empty() {
+            std::fs::create_dir_all(&keys_dir)?;
+            for signer in &self.account_keys {
+                signer.write_to_file(&keys_dir.join(format!("{}.json", signer.account_id)))?;
+            }
+            tracing::info!(target: "genesis", "Wrote the keys of {} accounts to {}", self.account_keys.len(), keys_dir.display());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_chain_configs::GenesisValidationMode;
+
+    const SPEC: &str = r#"
+chain_id = "spec-test"
+epoch_length = 20
+
+[shard_layout]
+boundary_accounts = ["bob.test"]
+
+[[validators]]
+account_id = "node0.test"
+stake = "50000000000000000000000000000000"
+balance = "1000000000000000000000000000"
+
+[[accounts]]
+account_id = "alice.test"
+balance = "1000000000000000000000000000"
+contract = "contract.wasm"
+
+[[accounts]]
+account_id = "carol.test"
+balance = "1000"
+
+[[accounts.access_keys]]
+public_key = "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp"
+receiver_id = "alice.test"
+method_names = ["set_status"]
+
+[config]
+gas_limit = 2000000000000000
+"#;
+
+    #[test]
+    fn test_build_genesis() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("contract.wasm"), b"code").unwrap();
+        let spec: GenesisSpec = toml::from_str(SPEC).unwrap();
+        let built = build_genesis(spec, dir.path()).unwrap();
+
+        let config = &built.genesis.config;
+        assert_eq!(config.chain_id, "spec-test");
+        assert_eq!(config.epoch_length, 20);
+        assert_eq!(config.gas_limit, 2_000_000_000_000_000);
+        assert_eq!(config.shard_layout.num_shards(), 2);
+        assert_eq!(config.num_block_producer_seats_per_shard.len(), 2);
+        assert_eq!(config.validators.len(), 1);
+        assert_eq!(built.validator_keys.len(), 1);
+        assert_eq!(built.validator_keys[0].public_key, config.validators[0].public_key);
+        // Only alice has no access keys in the spec.
+        assert_eq!(built.account_keys.len(), 1);
+        assert_eq!(built.account_keys[0].account_id.as_str(), "alice.test");
+
+        let mut contracts = vec![];
+        built.genesis.for_each_record(|record| {
+            if let StateRecord::Contract { account_id, code } = record {
+                contracts.push((account_id.clone(), code.clone()));
+            }
+        });
+        assert_eq!(contracts, vec![("alice.test".parse().unwrap(), b"code".to_vec())]);
+
+        let output_dir = dir.path().join("output");
+        built.write(&output_dir, Some(Path::new("records.json"))).unwrap();
+        let genesis = Genesis::from_files(
+            output_dir.join(GENESIS_CONFIG_FILENAME),
+            output_dir.join("records.json"),
+            GenesisValidationMode::Full,
+        )
+        .unwrap();
+        assert_eq!(genesis.config.total_supply, config.total_supply);
+        assert!(output_dir.join("node0.test").join(NODE_KEY_FILE).exists());
+        assert!(output_dir.join("node0.test").join(VALIDATOR_KEY_FILE).exists());
+        assert!(output_dir.join("keys").join("alice.test.json").exists());
+        // The output isn't overwritten.
+        assert!(built.write(&output_dir, None).is_err());
+
+        // Neither are the keys of the nodes.
+        let node_key = std::fs::read(output_dir.join("node0.test").join(NODE_KEY_FILE)).unwrap();
+        std::fs::remove_file(output_dir.join(GENESIS_CONFIG_FILENAME)).unwrap();
+        let err = built.write(&output_dir, None).unwrap_err();
+        assert!(err.to_string().contains(NODE_KEY_FILE), "{err}");
+        assert!(!output_dir.join(GENESIS_CONFIG_FILENAME).exists());
+        assert_eq!(
+            node_key,
+            std::fs::read(output_dir.join("node0.test").join(NODE_KEY_FILE)).unwrap()
+        );
+
+        // Nor the records and the keys of the accounts.
+        std::fs::remove_dir_all(output_dir.join("node0.test")).unwrap();
+        let err = built.write(&output_dir, Some(Path::new("records.json"))).unwrap_err();
+        assert!(err.to_string().contains("records.json"), "{err}");
+        std::fs::remove_file(output_dir.join("records.json")).unwrap();
+        let alice_key = std::fs::read(output_dir.join("keys").join("alice.test.json")).unwrap();
+        let err = built.write(&output_dir, Some(Path::new("records.json"))).unwrap_err();
+        assert!(err.to_string().contains("alice.test.json"), "{err}");
+        assert!(!output_dir.join(GENESIS_CONFIG_FILENAME).exists());
+        assert!(!output_dir.join("records.json").exists());
+        assert!(!output_dir.join("node0.test").exists());
+        assert_eq!(
+            alice_key,
+            std::fs::read(output_dir.join("keys").join("alice.test.json")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_spec() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec: GenesisSpec = toml::from_str(
+            r#"
+chain_id = "spec-test"
+[config]
+no_such_field = 1
+[[validators]]
+account_id = "node0.test"
+stake = "50000000000000000000000000000000"
+"#,
+        )
+        .unwrap();
+        let err = build_genesis(spec, dir.path()).err().unwrap();
+        assert!(err.to_string().contains("no_such_field"), "{err}");
+
+        // Fields which other fields are derived from are set by the spec only.
+        for field in ["shard_layout", "num_block_producer_seats"] {
+            let spec: GenesisSpec = toml::from_str(&format!(
+                r#"
+chain_id = "spec-test"
+[config]
+{field} = 10
+[[validators]]
+account_id = "node0.test"
+stake = "50000000000000000000000000000000"
+"#
+            ))
+            .unwrap();
+            let err = build_genesis(spec, dir.path()).err().unwrap();
+            assert!(err.to_string().contains("can't be overridden"), "{err}");
+        }
+
+        // The genesis is validated: the validator's stake doesn't match its account.
+        let spec: GenesisSpec = toml::from_str(
+            r#"
+chain_id = "spec-test"
+[[validators]]
+account_id = "node0.test"
+stake = "50000000000000000000000000000000"
+[[accounts]]
+account_id = "node0.test"
+balance = "1"
+"#,
+        )
+        .unwrap();
+        assert!(build_genesis(spec, dir.path()).is_err());
+    }
+}
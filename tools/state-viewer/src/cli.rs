@@ -594,9 +594,18 @@ pub struct StatePartsCmd {
     /// Store state parts in an S3 bucket.
     #[clap(long)]
     s3_region: Option<String>,
+    /// URL of an S3-compatible service, for example MinIO, to use instead of AWS.
+    #[clap(long)]
+    s3_endpoint: Option<String>,
+    /// Address the S3 bucket by path instead of by subdomain.
+    #[clap(long)]
+    s3_path_style: bool,
     /// Store state parts in an GCS bucket.
     #[clap(long)]
     gcs_bucket: Option<String>,
+    /// Read state parts from a static HTTP(S) file server. Can't be used to dump state parts.
+    #[clap(long)]
+    http_base_url: Option<String>,
     /// Dump or Apply state parts.
     #[clap(subcommand)]
     command: crate::state_parts::StatePartsSubCommand,
@@ -609,7 +618,10 @@ impl StatePartsCmd {
             self.root_dir,
             self.s3_bucket,
             self.s3_region,
+            self.s3_endpoint,
+            self.s3_path_style,
             self.gcs_bucket,
+            self.http_base_url,
             home_dir,
             near_config,
             store,
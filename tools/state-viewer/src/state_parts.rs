@@ -92,7 +92,10 @@ impl StatePartsSubCommand {
         root_dir: Option<PathBuf>,
         s3_bucket: Option<String>,
         s3_region: Option<String>,
+        s3_endpoint: Option<String>,
+        s3_path_style: bool,
         gcs_bucket: Option<String>,
+        http_base_url: Option<String>,
         home_dir: &Path,
         near_config: NearConfig,
         store: Store,
@@ -134,7 +137,10 @@ impl StatePartsSubCommand {
                         root_dir,
                         s3_bucket,
                         s3_region,
+                        s3_endpoint,
+                        s3_path_style,
                         gcs_bucket,
+                        http_base_url,
                         None,
                         Mode::Readonly,
                     );
@@ -162,7 +168,10 @@ impl StatePartsSubCommand {
                         root_dir,
                         s3_bucket,
                         s3_region,
+                        s3_endpoint,
+                        s3_path_style,
                         gcs_bucket,
+                        http_base_url,
                         credentials_file,
                         Mode::Readwrite,
                     );
@@ -199,7 +208,10 @@ fn create_external_connection(
     root_dir: Option<PathBuf>,
     bucket: Option<String>,
     region: Option<String>,
+    endpoint: Option<String>,
+    path_style: bool,
     gcs_bucket: Option<String>,
+    http_base_url: Option<String>,
     credentials_file: Option<PathBuf>,
     mode: Mode,
 ) -> ExternalConnection {
@@ -207,10 +219,21 @@ fn create_external_connection(
         ExternalConnection::Filesystem { root_dir }
     } else if let (Some(bucket), Some(region)) = (bucket, region) {
         let bucket = match mode {
-            Mode::Readonly => create_bucket_readonly(&bucket, &region, Duration::from_secs(5)),
-            Mode::Readwrite => {
-                create_bucket_readwrite(&bucket, &region, Duration::from_secs(5), credentials_file)
-            }
+            Mode::Readonly => create_bucket_readonly(
+                &bucket,
+                &region,
+                endpoint.as_deref(),
+                path_style,
+                Duration::from_secs(5),
+            ),
+            Mode::Readwrite => create_bucket_readwrite(
+                &bucket,
+                &region,
+                endpoint.as_deref(),
+                path_style,
+                Duration::from_secs(5),
+                credentials_file,
+            ),
         }
         .expect("Failed to create an S3 bucket");
         ExternalConnection::S3 { bucket: Arc::new(bucket) }
@@ -223,9 +246,14 @@ fn create_external_connection(
             reqwest_client: Arc::new(reqwest::Client::default()),
             bucket,
         }
+    } else if let Some(base_url) = http_base_url {
+        if let Mode::Readwrite = mode {
+            panic!("--http-base-url is read-only and can't be used to dump state parts");
+        }
+        ExternalConnection::Http { reqwest_client: Arc::new(reqwest::Client::default()), base_url }
     } else {
         panic!(
-            "Please provide --root-dir, or both of --s3-bucket and --s3-region, or --gcs-bucket"
+            "Please provide --root-dir, or both of --s3-bucket and --s3-region, or --gcs-bucket, or --http-base-url"
         );
     }
 }